@@ -19,7 +19,7 @@ const BYTES_MIDEN_WIDTH: u32 = 2;
 const ARRAY_MIDEN_WIDTH: u32 = 3;
 const MAP_MIDEN_WIDTH: u32 = ARRAY_MIDEN_WIDTH * 2;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StdVersion {
     #[serde(rename = "0.5.0")]
     V0_5_0,
@@ -29,6 +29,16 @@ pub enum StdVersion {
     V0_7_0,
 }
 
+impl std::fmt::Display for StdVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            StdVersion::V0_5_0 => "0.5.0",
+            StdVersion::V0_6_1 => "0.6.1",
+            StdVersion::V0_7_0 => "0.7.0",
+        })
+    }
+}
+
 /// An array of record hashes.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RecordHashes {
@@ -43,12 +53,59 @@ pub struct Abi {
     pub result_addr: Option<u32>,
     pub result_type: Option<Type>,
     pub param_types: Vec<Type>,
+    /// Names of the parameters, in the same order as `param_types`.
+    #[serde(default)]
+    pub param_names: Vec<String>,
+    /// Whether the last parameter is a rest parameter,
+    /// which collects the remaining arguments into an array.
+    #[serde(default)]
+    pub variadic: bool,
     pub other_records: Vec<RecordHashes>,
     pub other_contract_types: Vec<Type>,
     pub dependent_fields: Vec<(String, Type)>,
+    /// The fields of `this` the function assigns to.
+    /// Fields that are neither dependent nor written keep their old value.
+    /// `None` for ABIs from compilers that didn't record them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub written_fields: Option<Vec<String>>,
+    /// Statements marked with a `// @breakpoint` comment,
+    /// only recorded when the program is compiled with its source.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub breakpoints: Vec<Breakpoint>,
+    /// The `@read` fields of a `.readAuth` program, in the order their results are on the output stack.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub read_fields: Vec<String>,
+}
+
+/// A statement marked with a `// @breakpoint` comment on the line before it.
+///
+/// The compiled statement is put in its own masm procedure, which the program `exec`s
+/// in its place, so that a VM trace shows when it runs by the procedure
+/// its operations belong to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Breakpoint {
+    /// The 1-based line the statement starts on.
+    pub line: usize,
+    /// The name of the procedure the statement is compiled into.
+    pub procedure: String,
 }
 
 impl Abi {
+    /// Gives an ABI without a `this` type the empty `this` that the compiler adds
+    /// to functions outside of a contract.
+    ///
+    /// ABIs from older compilers leave `this_type` unset for those functions,
+    /// call this on every ABI received from outside before running it.
+    pub fn normalize(&mut self) {
+        if self.this_type.is_none() {
+            self.this_type = Some(Type::Struct(Struct {
+                name: "Empty".to_owned(),
+                fields: vec![],
+            }));
+            self.this_addr = Some(0);
+        }
+    }
+
     pub fn default_this_value(&self) -> Result<Value, Box<dyn std::error::Error>> {
         let Some(ref this_type) = self.this_type else {
             return Err("Missing this type".into());
@@ -56,6 +113,91 @@ impl Abi {
 
         Ok(this_type.default_value())
     }
+
+    /// Parses `this` from a JSON object that may be missing some fields,
+    /// filling them in according to `missing_fields`.
+    pub fn this_value_from_json(
+        &self,
+        this_json: &serde_json::Value,
+        missing_fields: MissingFields,
+    ) -> Result<Value> {
+        let Some(ref this_type) = self.this_type else {
+            return Err(Error::simple("Missing this type"));
+        };
+        let Type::Struct(struct_) = this_type else {
+            return Err(Error::simple("This type is not a struct"));
+        };
+
+        struct_.parse_partial(this_json, missing_fields)
+    }
+
+    /// A JSON Schema (draft 7) for an object with the `this` and `args`
+    /// accepted by the function, for generating input forms.
+    pub fn to_json_schema(&self) -> serde_json::Value {
+        let mut params = self.param_types.iter().collect::<Vec<_>>();
+        // The rest parameter's array is made of all the remaining arguments.
+        let rest = if self.variadic {
+            params.pop().map(|t| match t {
+                Type::Array(element) => element.to_json_schema(),
+                t => t.to_json_schema(),
+            })
+        } else {
+            None
+        };
+
+        let mut args = serde_json::json!({
+            "type": "array",
+            "items": params.iter().map(|t| t.to_json_schema()).collect::<Vec<_>>(),
+            "minItems": params.len(),
+        });
+        match rest {
+            Some(rest) => args["additionalItems"] = rest,
+            None => {
+                args["additionalItems"] = false.into();
+                args["maxItems"] = params.len().into();
+            }
+        }
+
+        let mut properties = serde_json::Map::new();
+        let mut required = vec![];
+        if let Some(this_type) = &self.this_type {
+            properties.insert("this".to_owned(), this_type.to_json_schema());
+            required.push("this");
+        }
+        properties.insert("args".to_owned(), args);
+        required.push("args");
+
+        serde_json::json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        })
+    }
+}
+
+/// How fields that are absent from a JSON object are filled in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissingFields {
+    /// Absent nullable fields are null, other absent fields are an error.
+    /// An empty object is filled entirely with default values.
+    #[default]
+    Strict,
+    /// Absent optional (nullable) fields are filled with their type's default value,
+    /// absent required fields are an error, even in an empty object.
+    Default,
+}
+
+/// How floats that JSON has no numbers for, `NaN` and the infinities, are converted to JSON.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NonFiniteFloats {
+    /// They become null, like in JavaScript's `JSON.stringify`.
+    #[default]
+    Null,
+    /// They become the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+    String,
+    /// They are an error.
+    Error,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -86,6 +228,8 @@ impl PrimitiveType {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Struct {
     pub name: String,
+    /// In declaration order. Values are read, written and hashed field by field in this order,
+    /// so the compiler must never reorder them.
     pub fields: Vec<(String, Type)>,
 }
 
@@ -110,6 +254,75 @@ pub enum Type {
 }
 
 impl Type {
+    /// A JSON Schema for the JSON values of this type, in the shape [`Parser`] accepts them.
+    pub fn to_json_schema(&self) -> serde_json::Value {
+        let string_fields = |names: &[&str]| {
+            serde_json::json!({
+                "type": "object",
+                "properties": names
+                    .iter()
+                    .map(|name| (name.to_string(), serde_json::json!({ "type": "string" })))
+                    .collect::<serde_json::Map<_, _>>(),
+                "required": names,
+            })
+        };
+
+        match self {
+            Type::Nullable(t) => {
+                serde_json::json!({ "anyOf": [t.to_json_schema(), { "type": "null" }] })
+            }
+            Type::PrimitiveType(PrimitiveType::Boolean) => serde_json::json!({ "type": "boolean" }),
+            Type::PrimitiveType(PrimitiveType::UInt32) => {
+                serde_json::json!({ "type": "integer", "minimum": 0, "maximum": u32::MAX })
+            }
+            Type::PrimitiveType(PrimitiveType::UInt64) => {
+                serde_json::json!({ "type": "integer", "minimum": 0, "maximum": u64::MAX })
+            }
+            Type::PrimitiveType(PrimitiveType::Int32) => {
+                serde_json::json!({ "type": "integer", "minimum": i32::MIN, "maximum": i32::MAX })
+            }
+            Type::PrimitiveType(PrimitiveType::Int64) => {
+                serde_json::json!({ "type": "integer", "minimum": i64::MIN, "maximum": i64::MAX })
+            }
+            Type::PrimitiveType(PrimitiveType::Float32 | PrimitiveType::Float64) => {
+                serde_json::json!({ "type": "number" })
+            }
+            Type::String => serde_json::json!({ "type": "string" }),
+            // Comma separated byte values
+            Type::Bytes => serde_json::json!({ "type": "string" }),
+            Type::Hash | Type::Hash8 => {
+                serde_json::json!({ "type": "string", "pattern": "^(0x)?[0-9a-fA-F]*$" })
+            }
+            Type::ContractReference { contract } => {
+                let mut schema = string_fields(&["id"]);
+                schema["title"] = contract.clone().into();
+                schema
+            }
+            Type::Array(t) => serde_json::json!({ "type": "array", "items": t.to_json_schema() }),
+            // JSON object keys are always strings, they are parsed as the key type.
+            Type::Map(_, v) => {
+                serde_json::json!({ "type": "object", "additionalProperties": v.to_json_schema() })
+            }
+            Type::PublicKey => string_fields(&["kty", "crv", "alg", "use", "x", "y"]),
+            Type::Struct(s) => serde_json::json!({
+                "type": "object",
+                "title": s.name,
+                "properties": s
+                    .fields
+                    .iter()
+                    .map(|(name, t)| (name.clone(), t.to_json_schema()))
+                    .collect::<serde_json::Map<_, _>>(),
+                // optional fields can be left out
+                "required": s
+                    .fields
+                    .iter()
+                    .filter(|(_, t)| !matches!(t, Type::Nullable(_)))
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            }),
+        }
+    }
+
     pub fn miden_width(&self) -> u32 {
         match self {
             Type::Nullable(t) => 1 + t.miden_width(),
@@ -126,6 +339,40 @@ impl Type {
         }
     }
 
+    /// Whether a field of this type can be used in an `@index`.
+    /// Arrays, maps and structs can't be, optional fields are indexable if their value type is.
+    pub fn is_indexable(&self) -> bool {
+        match self {
+            Type::Nullable(t) => t.is_indexable(),
+            Type::PrimitiveType(_)
+            | Type::String
+            | Type::Bytes
+            | Type::ContractReference { .. }
+            | Type::Hash
+            | Type::Hash8
+            | Type::PublicKey => true,
+            Type::Array(_) | Type::Map(_, _) | Type::Struct(_) => false,
+        }
+    }
+
+    /// Calls `visitor` on this type and then on every type nested in it, depth first.
+    pub fn visit(&self, visitor: &mut impl FnMut(&Type)) {
+        visitor(self);
+        match self {
+            Type::Nullable(t) | Type::Array(t) => t.visit(visitor),
+            Type::Map(k, v) => {
+                k.visit(visitor);
+                v.visit(visitor);
+            }
+            Type::Struct(struct_) => {
+                for (_, t) in &struct_.fields {
+                    t.visit(visitor);
+                }
+            }
+            _ => {}
+        }
+    }
+
     pub fn default_value(&self) -> Value {
         match &self {
             Type::Nullable(_) => Value::Nullable(None),
@@ -156,10 +403,82 @@ impl Type {
 
 type MemoryReader<'a> = dyn Fn(u64) -> Option<[u64; 4]> + 'a;
 
+/// Limits on what a `TypeReader` reads, so that a corrupted or malicious memory
+/// image can't make it loop (almost) forever or run out of memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadLimits {
+    /// Maximum length of a single string, bytes, contract reference, array or map.
+    pub max_length: u64,
+    /// Maximum sum of the lengths of all values read, including nested ones.
+    pub max_total_length: u64,
+}
+
+impl Default for ReadLimits {
+    fn default() -> Self {
+        Self {
+            max_length: 1 << 20,
+            max_total_length: 1 << 24,
+        }
+    }
+}
+
+/// What is left of the `ReadLimits` while reading a value.
+#[derive(Debug)]
+pub struct ReadBudget {
+    limits: ReadLimits,
+    remaining: u64,
+}
+
+impl ReadBudget {
+    pub fn new(limits: ReadLimits) -> Self {
+        Self {
+            limits,
+            remaining: limits.max_total_length,
+        }
+    }
+
+    fn take(&mut self, type_name: &'static str, length: u64) -> Result<()> {
+        snafu::ensure!(
+            length <= self.limits.max_length,
+            TooLongSnafu {
+                type_name,
+                length,
+                limit: "maximum length",
+                max: self.limits.max_length,
+            }
+        );
+        snafu::ensure!(
+            length <= self.remaining,
+            TooLongSnafu {
+                type_name,
+                length,
+                limit: "remaining read budget",
+                max: self.remaining,
+            }
+        );
+
+        self.remaining -= length;
+        Ok(())
+    }
+}
+
 pub trait TypeReader {
-    fn read(&self, reader: &MemoryReader, addr: u64) -> Result<Value>;
+    /// Reads with the default `ReadLimits`.
+    fn read(&self, reader: &MemoryReader, addr: u64) -> Result<Value> {
+        self.read_with_budget(reader, addr, &mut ReadBudget::new(ReadLimits::default()))
+    }
+
+    fn read_with_budget(
+        &self,
+        reader: &MemoryReader,
+        addr: u64,
+        budget: &mut ReadBudget,
+    ) -> Result<Value>;
 }
 
+/// `PartialEq` is derived, so maps are only equal if their entries are in the same order,
+/// and floats compare like `f32` and `f64` do, so `NaN` is not equal to itself.
+/// [`Value::structural_eq`] compares values regardless of either.
 #[derive(Debug, PartialEq, Clone)]
 pub enum Value {
     Nullable(Option<Box<Value>>),
@@ -182,6 +501,41 @@ pub enum Value {
 }
 
 impl Value {
+    /// Like `==`, but maps are compared regardless of the order of their entries,
+    /// and floats by their bits, so `NaN` equals itself and `0.0` doesn't equal `-0.0`.
+    pub fn structural_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Float32(a), Value::Float32(b)) => a.to_bits() == b.to_bits(),
+            (Value::Float64(a), Value::Float64(b)) => a.to_bits() == b.to_bits(),
+            (Value::Nullable(Some(a)), Value::Nullable(Some(b))) => a.structural_eq(b),
+            (Value::Array(a), Value::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.structural_eq(b))
+            }
+            (Value::Map(a), Value::Map(b)) => {
+                // Every entry of `a` has to match a different entry of `b`.
+                let mut unmatched = b.iter().collect::<Vec<_>>();
+                a.len() == b.len()
+                    && a.iter().all(|(key, value)| {
+                        let Some(i) = unmatched
+                            .iter()
+                            .position(|(k, v)| key.structural_eq(k) && value.structural_eq(v))
+                        else {
+                            return false;
+                        };
+                        unmatched.swap_remove(i);
+                        true
+                    })
+            }
+            (Value::StructValue(a), Value::StructValue(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .zip(b)
+                        .all(|((a_name, a), (b_name, b))| a_name == b_name && a.structural_eq(b))
+            }
+            _ => self == other,
+        }
+    }
+
     pub fn visit<E>(&self, visitor: &mut impl FnMut(&Value) -> Result<(), E>) -> Result<(), E> {
         visitor(self)?;
         match self {
@@ -211,19 +565,38 @@ impl Value {
     }
 }
 
-impl TryInto<serde_json::Value> for Value {
-    type Error = Error;
-    fn try_into(self) -> Result<serde_json::Value> {
+fn non_finite_float_to_json(x: f64, non_finite: NonFiniteFloats) -> Result<serde_json::Value> {
+    match non_finite {
+        NonFiniteFloats::Null => Ok(serde_json::Value::Null),
+        NonFiniteFloats::String => Ok(serde_json::Value::String(
+            if x.is_nan() {
+                "NaN"
+            } else if x > 0.0 {
+                "Infinity"
+            } else {
+                "-Infinity"
+            }
+            .to_owned(),
+        )),
+        NonFiniteFloats::Error => Err(Error::simple(format!("{x} can't be represented in JSON"))),
+    }
+}
+
+impl Value {
+    /// Converts the value to JSON, with `non_finite` deciding what `NaN` and the infinities become.
+    pub fn to_json(self, non_finite: NonFiniteFloats) -> Result<serde_json::Value> {
         Ok(match self {
             Value::Nullable(opt) => match opt {
                 None => serde_json::Value::Null,
-                Some(v) => (*v).try_into()?,
+                Some(v) => v.to_json(non_finite)?,
             },
             Value::Boolean(b) => serde_json::Value::Bool(b),
             Value::UInt32(x) => serde_json::Value::Number(x.into()),
             Value::UInt64(x) => serde_json::Value::Number(x.into()),
             Value::Int32(x) => serde_json::Value::Number(x.into()),
             Value::Int64(x) => serde_json::Value::Number(x.into()),
+            Value::Float32(x) if !x.is_finite() => non_finite_float_to_json(x.into(), non_finite)?,
+            Value::Float64(x) if !x.is_finite() => non_finite_float_to_json(x, non_finite)?,
             Value::Float32(x) => {
                 serde_json::Value::Number(serde_json::Number::from_str(&x.to_string()).wrap_err()?)
             }
@@ -265,7 +638,7 @@ impl TryInto<serde_json::Value> for Value {
             Value::Array(a) => {
                 let mut array = Vec::new();
                 for value in a {
-                    array.push(value.try_into()?);
+                    array.push(value.to_json(non_finite)?);
                 }
                 serde_json::Value::Array(array)
             }
@@ -275,7 +648,7 @@ impl TryInto<serde_json::Value> for Value {
                     .into_iter()
                     .filter_map(|(k, v)| Some((k.maybe_to_string()?, v)))
                 {
-                    map.insert(key, value.try_into()?);
+                    map.insert(key, value.to_json(non_finite)?);
                 }
                 serde_json::Value::Object(map)
             }
@@ -283,7 +656,7 @@ impl TryInto<serde_json::Value> for Value {
             Value::StructValue(sv) => {
                 let mut map = serde_json::Map::new();
                 for (name, value) in sv {
-                    map.insert(name, value.try_into()?);
+                    map.insert(name, value.to_json(non_finite)?);
                 }
                 serde_json::Value::Object(map)
             }
@@ -291,8 +664,22 @@ impl TryInto<serde_json::Value> for Value {
     }
 }
 
+impl TryInto<serde_json::Value> for Value {
+    type Error = Error;
+
+    /// Converts `NaN` and the infinities to null, see [`Value::to_json`].
+    fn try_into(self) -> Result<serde_json::Value> {
+        self.to_json(NonFiniteFloats::default())
+    }
+}
+
 impl TypeReader for PrimitiveType {
-    fn read(&self, reader: &MemoryReader, addr: u64) -> Result<Value> {
+    fn read_with_budget(
+        &self,
+        reader: &MemoryReader,
+        addr: u64,
+        _budget: &mut ReadBudget,
+    ) -> Result<Value> {
         Ok(match self {
             PrimitiveType::Boolean => {
                 let [b, _, _, _] = reader(addr).context(InvalidAddressSnafu {
@@ -368,12 +755,17 @@ impl TypeReader for PrimitiveType {
 }
 
 impl TypeReader for Struct {
-    fn read(&self, reader: &MemoryReader, addr: u64) -> Result<Value> {
+    fn read_with_budget(
+        &self,
+        reader: &MemoryReader,
+        addr: u64,
+        budget: &mut ReadBudget,
+    ) -> Result<Value> {
         let mut fields = Vec::new();
         let mut current_addr = addr;
         for (name, type_) in &self.fields {
             let value = type_
-                .read(reader, current_addr)
+                .read_with_budget(reader, current_addr, budget)
                 .nest_err(|| format!("invalid read of field {name}"))?;
             fields.push((name.clone(), value));
             current_addr += u64::from(type_.miden_width());
@@ -383,7 +775,12 @@ impl TypeReader for Struct {
 }
 
 impl TypeReader for Type {
-    fn read(&self, reader: &MemoryReader, addr: u64) -> Result<Value> {
+    fn read_with_budget(
+        &self,
+        reader: &MemoryReader,
+        addr: u64,
+        budget: &mut ReadBudget,
+    ) -> Result<Value> {
         match self {
             Type::Nullable(t) => {
                 let [is_null, _, _, _] = reader(addr).context(InvalidAddressSnafu {
@@ -393,11 +790,15 @@ impl TypeReader for Type {
                 if is_null == 0 {
                     Ok(Value::Nullable(None))
                 } else {
-                    Ok(Value::Nullable(Some(Box::new(t.read(reader, addr + 1)?))))
+                    Ok(Value::Nullable(Some(Box::new(t.read_with_budget(
+                        reader,
+                        addr + 1,
+                        budget,
+                    )?))))
                 }
             }
-            Type::PrimitiveType(pt) => pt.read(reader, addr),
-            Type::Struct(s) => s.read(reader, addr),
+            Type::PrimitiveType(pt) => pt.read_with_budget(reader, addr, budget),
+            Type::Struct(s) => s.read_with_budget(reader, addr, budget),
             Type::Hash => reader(addr)
                 .map(Value::Hash)
                 .context(InvalidAddressSnafu {
@@ -429,6 +830,7 @@ impl TypeReader for Type {
                     addr,
                     type_name: "string length",
                 })?[0];
+                budget.take("string", length)?;
                 let data_ptr = reader(addr + 1).context(InvalidAddressSnafu {
                     addr,
                     type_name: "string data ptr",
@@ -452,6 +854,7 @@ impl TypeReader for Type {
                     addr,
                     type_name: "bytes length",
                 })?[0];
+                budget.take("bytes", length)?;
                 let data_ptr = reader(addr + 1).context(InvalidAddressSnafu {
                     addr,
                     type_name: "bytes data ptr",
@@ -473,6 +876,7 @@ impl TypeReader for Type {
                     addr,
                     type_name: "contract reference length",
                 })?[0];
+                budget.take("contract reference", length)?;
                 let data_ptr = reader(addr + 1).context(InvalidAddressSnafu {
                     addr,
                     type_name: "contract reference data ptr",
@@ -494,12 +898,14 @@ impl TypeReader for Type {
                     addr,
                     type_name: "array length",
                 })?[0];
+                budget.take("array", length)?;
                 let data_ptr = reader(addr + 2).context(InvalidAddressSnafu {
                     addr,
                     type_name: "array data ptr",
                 })?[0];
                 for i in 0..length {
-                    let value = t.read(reader, data_ptr + i * t.miden_width() as u64)?;
+                    let value =
+                        t.read_with_budget(reader, data_ptr + i * t.miden_width() as u64, budget)?;
                     values.push(value);
                 }
 
@@ -508,22 +914,31 @@ impl TypeReader for Type {
             Type::Map(k, v) => {
                 let mut key_values = Vec::new();
 
-                let key_array_data_start_ptr = reader(addr + 2).unwrap()[0];
-                let value_array_data_start_ptr =
-                    reader(addr + ARRAY_MIDEN_WIDTH as u64 + 2).unwrap()[0];
+                let key_array_data_start_ptr = reader(addr + 2).context(InvalidAddressSnafu {
+                    addr,
+                    type_name: "map keys data ptr",
+                })?[0];
+                let value_array_data_start_ptr = reader(addr + ARRAY_MIDEN_WIDTH as u64 + 2)
+                    .context(InvalidAddressSnafu {
+                        addr,
+                        type_name: "map values data ptr",
+                    })?[0];
                 let length = reader(addr + 1).context(InvalidAddressSnafu {
                     addr,
                     type_name: "map keys length",
                 })?[0];
+                budget.take("map", length)?;
 
                 for i in 0..length {
-                    let key = k.read(
+                    let key = k.read_with_budget(
                         reader,
                         key_array_data_start_ptr + i * k.miden_width() as u64,
+                        budget,
                     )?;
-                    let value = v.read(
+                    let value = v.read_with_budget(
                         reader,
                         value_array_data_start_ptr + i * v.miden_width() as u64,
+                        budget,
                     )?;
 
                     key_values.push((key, value));
@@ -661,23 +1076,118 @@ impl Parser<serde_json::Value> for PrimitiveType {
     }
 }
 
+/// The characters of `value` outside of quoted strings, with their byte index
+/// and the depth of `{...}` and `[...]` groups after them.
+/// A quoted string starts and ends with `"`, and `\` escapes the character after it.
+fn top_level_chars(value: &str) -> impl Iterator<Item = (usize, char, usize)> + '_ {
+    let mut depth = 0usize;
+    let mut in_quotes = false;
+    let mut escaped = false;
+    value.char_indices().filter_map(move |(i, c)| {
+        if in_quotes {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_quotes = false,
+                _ => {}
+            }
+            return None;
+        }
+
+        match c {
+            '"' => {
+                in_quotes = true;
+                return None;
+            }
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        Some((i, c, depth))
+    })
+}
+
+/// Splits `value` on `separator`, except inside `{...}` and `[...]` groups and quoted strings,
+/// so that a struct or array nested in another one can be written as a single element.
+fn split_top_level(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    for (i, c, depth) in top_level_chars(value) {
+        if c == separator && depth == 0 {
+            parts.push(&value[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+/// Removes the `open` and `close` delimiters around `value`, if they enclose all of it,
+/// so `[1;2]` becomes `1;2` but `[1];[2]` is left as it is.
+fn strip_group(value: &str, open: char, close: char) -> &str {
+    if !value.starts_with(open) {
+        return value;
+    }
+
+    match top_level_chars(value).find(|&(_, _, depth)| depth == 0) {
+        Some((i, c, _)) if c == close && i + c.len_utf8() == value.len() => {
+            &value[open.len_utf8()..i]
+        }
+        _ => value,
+    }
+}
+
+/// Removes the quotes around a quoted string, along with the `\` before escaped characters.
+/// Strings without quotes are returned as they are.
+fn unquote(value: &str) -> String {
+    let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+        return value.to_owned();
+    };
+
+    let mut unquoted = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unquoted.extend(chars.next()),
+            c => unquoted.push(c),
+        }
+    }
+    unquoted
+}
+
+/// Parses a field of a struct or an element of an array or map,
+/// where a string can be quoted, as in `"a;b"`, to contain separators or delimiters.
+/// Top-level strings are taken as they are, quotes included.
+fn parse_element(type_: &Type, value: &str) -> Result<Value> {
+    match type_ {
+        Type::String => Ok(Value::String(unquote(value))),
+        Type::Nullable(t) if value != "null" => {
+            Ok(Value::Nullable(Some(Box::new(parse_element(t, value)?))))
+        }
+        _ => type_.parse(value),
+    }
+}
+
+/// Fields are given in declaration order, separated by `,`, e.g. `1,2`.
+/// The struct can be wrapped in `{}`, as in `{1,2};{3,4}` for an array of structs.
 impl Parser<str> for Struct {
     fn parse(&self, value: &str) -> Result<Value> {
         let mut fields = Vec::new();
-        let mut value = value;
+        let mut parts = split_top_level(strip_group(value, '{', '}'), ',').into_iter();
         for (name, type_) in &self.fields {
-            let (field_value, rest) = value
-                .split_once(',')
-                .parse_err("missing field comma", "struct", value)
+            let field_value = parts
+                .next()
+                .parse_err("missing field", "struct", value)
                 .nest_err(|| format!("field {name}"))?;
             fields.push((
                 name.clone(),
-                type_
-                    .parse(field_value)
-                    .nest_err(|| format!("field {name}"))?,
+                parse_element(type_, field_value).nest_err(|| format!("field {name}"))?,
             ));
-            value = rest;
         }
+        // A trailing `,` is allowed, as in `1,2,`.
+        matches!(parts.collect::<Vec<_>>().as_slice(), [] | [""])
+            .then_some(())
+            .parse_err("too many fields", "struct", value)?;
         Ok(Value::StructValue(fields))
     }
 }
@@ -686,8 +1196,36 @@ impl Parser<serde_json::Value> for Struct {
     fn parse(&self, value: &serde_json::Value) -> Result<Value> {
         let mut fields = Vec::new();
         for (name, type_) in &self.fields {
-            let field_value = value.get(name).parse_err("missing", "field", name)?;
-            fields.push((name.clone(), type_.parse(field_value)?));
+            let field_value = match value.get(name) {
+                // optional fields can be left out
+                None if matches!(type_, Type::Nullable(_)) => Value::Nullable(None),
+                field_value => type_.parse(field_value.parse_err("missing", "field", name)?)?,
+            };
+            fields.push((name.clone(), field_value));
+        }
+        Ok(Value::StructValue(fields))
+    }
+}
+
+impl Struct {
+    /// Like `parse`, but fields absent from `value` are filled in according to `missing_fields`.
+    pub fn parse_partial(
+        &self,
+        value: &serde_json::Value,
+        missing_fields: MissingFields,
+    ) -> Result<Value> {
+        let default_all = missing_fields == MissingFields::Strict
+            && value.as_object().map(|o| o.is_empty()).unwrap_or(false);
+
+        let mut fields = Vec::new();
+        for (name, type_) in &self.fields {
+            let field_value = match value.get(name) {
+                Some(value) => type_.parse(value)?,
+                None if default_all => type_.default_value(),
+                None if matches!(type_, Type::Nullable(_)) => type_.default_value(),
+                None => return Err(Error::simple(format!("missing value for field `{}`", name))),
+            };
+            fields.push((name.clone(), field_value));
         }
         Ok(Value::StructValue(fields))
     }
@@ -746,11 +1284,13 @@ impl Parser<str> for Type {
                 }
                 Ok(Value::ContractReference(bytes))
             }
+            // Elements are separated by `;`, the array can be wrapped in `[]`.
             Type::Array(t) => {
+                let value = strip_group(value, '[', ']');
                 let mut values = vec![];
                 if !value.is_empty() {
-                    for value in value.split(';') {
-                        values.push(t.parse(value)?);
+                    for value in split_top_level(value, ';') {
+                        values.push(parse_element(t, value)?);
                     }
                 }
                 Ok(Value::Array(values))
@@ -758,7 +1298,7 @@ impl Parser<str> for Type {
             Type::Map(k, v) => {
                 let mut key_values = vec![];
                 if !value.is_empty() {
-                    let mut parts = value.split(';');
+                    let mut parts = split_top_level(value, ';').into_iter();
                     loop {
                         let Some(key) = parts.next() else {
                             break;
@@ -768,7 +1308,7 @@ impl Parser<str> for Type {
                             .next()
                             .ok_or_else(|| Error::simple("missing value in map"))?;
 
-                        key_values.push((k.parse(key)?, v.parse(value)?));
+                        key_values.push((parse_element(k, key)?, parse_element(v, value)?));
                     }
                 }
                 Ok(Value::Map(key_values))
@@ -1010,7 +1550,7 @@ impl Value {
             Value::UInt32(x) => vec![u64::from(*x)],
             Value::UInt64(x) => vec![*x >> 32, *x & 0xffffffff],
             Value::Int32(x) => vec![*x as u32 as u64],
-            Value::Int64(x) => vec![(*x >> 32) as u64, *x as u64],
+            Value::Int64(x) => vec![(*x as u64) >> 32, *x as u64 & 0xffffffff],
             Value::Float32(x) => vec![x.to_bits() as u64],
             Value::Float64(x) => vec![(x.to_bits() >> 32), (x.to_bits() & 0xffffffff)],
             Value::Hash(h) => h.to_vec(),
@@ -1027,7 +1567,8 @@ impl Value {
                 .into_iter()
                 .chain(values.iter().flat_map(|v| v.serialize()))
                 .collect(),
-            // Map is serialized as [keys_arr..., values_arr...] so that we can reuse read_advice_array
+            // Map is serialized as [keys_arr..., values_arr...] so that we can reuse read_advice_array.
+            // Entries keep their order, which is the order compiled programs hash them in.
             Value::Map(key_values) => []
                 .into_iter()
                 .chain([key_values.len() as u64])
@@ -1056,6 +1597,29 @@ impl Value {
         }
     }
 
+    /// The number of elements [`Value::serialize`] would produce, without allocating them.
+    pub fn serialized_len(&self) -> usize {
+        match self {
+            Value::Nullable(None) => 1,
+            Value::Nullable(Some(v)) => 1 + v.serialized_len(),
+            Value::Boolean(_) | Value::UInt32(_) | Value::Int32(_) | Value::Float32(_) => 1,
+            Value::UInt64(_) | Value::Int64(_) | Value::Float64(_) => 2,
+            Value::Hash(h) => h.len(),
+            Value::Hash8(h) => h.len(),
+            Value::String(s) => 1 + s.len(),
+            Value::Bytes(b) | Value::ContractReference(b) => 1 + b.len(),
+            Value::Array(values) => 1 + values.iter().map(Value::serialized_len).sum::<usize>(),
+            Value::Map(key_values) => {
+                2 + key_values
+                    .iter()
+                    .map(|(k, v)| k.serialized_len() + v.serialized_len())
+                    .sum::<usize>()
+            }
+            Value::PublicKey(k) => 4 + k.x.len() + k.y.len(),
+            Value::StructValue(sv) => sv.iter().map(|(_, v)| v.serialized_len()).sum(),
+        }
+    }
+
     fn maybe_to_string(self) -> Option<String> {
         match self {
             Value::Nullable(_) => None,
@@ -1079,3 +1643,847 @@ impl Value {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use proptest::test_runner::{Config, RngAlgorithm, TestRng, TestRunner};
+    use std::collections::HashMap;
+
+    /// Order of the Goldilocks field, advice values must be below it.
+    const FIELD_MODULUS: u64 = 0xffff_ffff_0000_0001;
+
+    /// Memory laid out the way the compiled `readAdvice*` functions do it,
+    /// values are read from the serialized advice tape.
+    struct Memory {
+        words: HashMap<u64, [u64; 4]>,
+        next_alloc: u64,
+    }
+
+    impl Memory {
+        fn alloc(&mut self, size: u64) -> u64 {
+            let addr = self.next_alloc;
+            self.next_alloc += size;
+            addr
+        }
+
+        fn store(&mut self, addr: u64, value: u64) {
+            self.words.insert(addr, [value, 0, 0, 0]);
+        }
+
+        fn load(&mut self, type_: &Type, tape: &mut impl Iterator<Item = u64>, addr: u64) {
+            let mut next = || {
+                let word = tape.next().expect("advice tape is too short");
+                assert!(word < FIELD_MODULUS, "{word} is not a field element");
+                word
+            };
+
+            match type_ {
+                Type::Nullable(t) => {
+                    let is_not_null = next();
+                    self.store(addr, is_not_null);
+                    if is_not_null == 1 {
+                        self.load(t, tape, addr + 1);
+                    }
+                }
+                Type::PrimitiveType(pt) => {
+                    for i in 0..u64::from(pt.miden_width()) {
+                        self.store(addr + i, next());
+                    }
+                }
+                Type::String | Type::Bytes | Type::ContractReference { .. } => {
+                    let len = next();
+                    let data_ptr = self.alloc(len);
+                    for i in 0..len {
+                        let byte = next();
+                        self.store(data_ptr + i, byte);
+                    }
+                    self.store(addr, len);
+                    self.store(addr + 1, data_ptr);
+                }
+                Type::Array(t) => {
+                    let len = next();
+                    let width = u64::from(t.miden_width());
+                    let data_ptr = self.alloc(len * 2 * width);
+                    for i in 0..len {
+                        self.load(t, tape, data_ptr + i * width);
+                    }
+                    self.store(addr, len * 2);
+                    self.store(addr + 1, len);
+                    self.store(addr + 2, data_ptr);
+                }
+                Type::Map(k, v) => {
+                    self.load(&Type::Array(k.clone()), tape, addr);
+                    self.load(&Type::Array(v.clone()), tape, addr + 3);
+                }
+                Type::Hash => {
+                    let word = [next(), next(), next(), next()];
+                    self.words.insert(addr, word);
+                }
+                Type::Hash8 => {
+                    for i in 0..2 {
+                        let word = [next(), next(), next(), next()];
+                        self.words.insert(addr + i * 4, word);
+                    }
+                }
+                Type::PublicKey => {
+                    for i in 0..4 {
+                        self.store(addr + i, next());
+                    }
+                    let extra_ptr = self.alloc(64);
+                    for i in 0..64 {
+                        let byte = next();
+                        self.store(extra_ptr + i, byte);
+                    }
+                    self.store(addr + 4, extra_ptr);
+                }
+                Type::Struct(s) => {
+                    let mut field_addr = addr;
+                    for (_, t) in &s.fields {
+                        self.load(t, tape, field_addr);
+                        field_addr += u64::from(t.miden_width());
+                    }
+                }
+            }
+        }
+    }
+
+    fn arb_type() -> impl Strategy<Value = Type> {
+        let leaf = prop_oneof![
+            Just(Type::PrimitiveType(PrimitiveType::Boolean)),
+            Just(Type::PrimitiveType(PrimitiveType::UInt32)),
+            Just(Type::PrimitiveType(PrimitiveType::UInt64)),
+            Just(Type::PrimitiveType(PrimitiveType::Int32)),
+            Just(Type::PrimitiveType(PrimitiveType::Int64)),
+            Just(Type::PrimitiveType(PrimitiveType::Float32)),
+            Just(Type::PrimitiveType(PrimitiveType::Float64)),
+            Just(Type::String),
+            Just(Type::Bytes),
+            Just(Type::ContractReference {
+                contract: "Account".to_owned(),
+            }),
+            Just(Type::Hash),
+            Just(Type::Hash8),
+            Just(Type::PublicKey),
+        ];
+
+        leaf.prop_recursive(3, 16, 4, |inner| {
+            prop_oneof![
+                inner.clone().prop_map(|t| Type::Nullable(Box::new(t))),
+                inner.clone().prop_map(|t| Type::Array(Box::new(t))),
+                (inner.clone(), inner.clone())
+                    .prop_map(|(k, v)| Type::Map(Box::new(k), Box::new(v))),
+                prop::collection::vec(inner, 0..4).prop_map(|types| {
+                    Type::Struct(Struct {
+                        name: "Test".to_owned(),
+                        fields: types
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, t)| (format!("field{i}"), t))
+                            .collect(),
+                    })
+                }),
+            ]
+        })
+    }
+
+    fn arb_value(type_: &Type) -> BoxedStrategy<Value> {
+        match type_ {
+            Type::Nullable(t) => prop::option::of(arb_value(t))
+                .prop_map(|v| Value::Nullable(v.map(Box::new)))
+                .boxed(),
+            Type::PrimitiveType(PrimitiveType::Boolean) => {
+                any::<bool>().prop_map(Value::Boolean).boxed()
+            }
+            Type::PrimitiveType(PrimitiveType::UInt32) => {
+                any::<u32>().prop_map(Value::UInt32).boxed()
+            }
+            Type::PrimitiveType(PrimitiveType::UInt64) => {
+                any::<u64>().prop_map(Value::UInt64).boxed()
+            }
+            Type::PrimitiveType(PrimitiveType::Int32) => {
+                any::<i32>().prop_map(Value::Int32).boxed()
+            }
+            Type::PrimitiveType(PrimitiveType::Int64) => {
+                any::<i64>().prop_map(Value::Int64).boxed()
+            }
+            // NaN is never equal to itself
+            Type::PrimitiveType(PrimitiveType::Float32) => any::<f32>()
+                .prop_filter("NaN", |x| !x.is_nan())
+                .prop_map(Value::Float32)
+                .boxed(),
+            Type::PrimitiveType(PrimitiveType::Float64) => any::<f64>()
+                .prop_filter("NaN", |x| !x.is_nan())
+                .prop_map(Value::Float64)
+                .boxed(),
+            Type::String => ".{0,8}".prop_map(Value::String).boxed(),
+            Type::Bytes => prop::collection::vec(any::<u8>(), 0..8)
+                .prop_map(Value::Bytes)
+                .boxed(),
+            Type::ContractReference { .. } => "[a-z0-9]{0,8}"
+                .prop_map(|id| Value::ContractReference(id.into_bytes()))
+                .boxed(),
+            Type::Hash => prop::array::uniform4(0..FIELD_MODULUS)
+                .prop_map(Value::Hash)
+                .boxed(),
+            Type::Hash8 => prop::array::uniform8(0..FIELD_MODULUS)
+                .prop_map(Value::Hash8)
+                .boxed(),
+            Type::PublicKey => (any::<[u8; 32]>(), any::<[u8; 32]>())
+                .prop_map(|(x, y)| {
+                    Value::PublicKey(publickey::Key {
+                        x,
+                        y,
+                        ..Default::default()
+                    })
+                })
+                .boxed(),
+            Type::Array(t) => prop::collection::vec(arb_value(t), 0..4)
+                .prop_map(Value::Array)
+                .boxed(),
+            Type::Map(k, v) => prop::collection::vec((arb_value(k), arb_value(v)), 0..4)
+                .prop_map(Value::Map)
+                .boxed(),
+            Type::Struct(s) => {
+                let names = s.fields.iter().map(|(n, _)| n.clone()).collect::<Vec<_>>();
+                s.fields
+                    .iter()
+                    .map(|(_, t)| arb_value(t))
+                    .collect::<Vec<_>>()
+                    .prop_map(move |values| {
+                        Value::StructValue(names.iter().cloned().zip(values).collect())
+                    })
+                    .boxed()
+            }
+        }
+    }
+
+    #[test]
+    fn serialize_read_roundtrip() {
+        let config = Config {
+            cases: 512,
+            failure_persistence: None,
+            ..Config::default()
+        };
+        let mut runner =
+            TestRunner::new_with_rng(config, TestRng::deterministic_rng(RngAlgorithm::ChaCha));
+
+        let strategy = arb_type().prop_flat_map(|t| (Just(t.clone()), arb_value(&t)));
+        runner
+            .run(&strategy, |(type_, value)| {
+                let mut memory = Memory {
+                    words: HashMap::new(),
+                    next_alloc: u64::from(type_.miden_width()),
+                };
+
+                let mut tape = value.serialize().into_iter();
+                memory.load(&type_, &mut tape, 0);
+                prop_assert_eq!(tape.next(), None, "unread advice for {:?}", type_);
+
+                let read = type_
+                    .read(
+                        &|addr| Some(memory.words.get(&addr).copied().unwrap_or_default()),
+                        0,
+                    )
+                    .unwrap();
+                prop_assert_eq!(read, value);
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn serialized_len_matches_serialize() {
+        let config = Config {
+            cases: 512,
+            failure_persistence: None,
+            ..Config::default()
+        };
+        let mut runner =
+            TestRunner::new_with_rng(config, TestRng::deterministic_rng(RngAlgorithm::ChaCha));
+
+        let strategy = arb_type().prop_flat_map(|t| arb_value(&t));
+        runner
+            .run(&strategy, |value| {
+                prop_assert_eq!(value.serialized_len(), value.serialize().len());
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn serialized_len_of_nested_values() {
+        let values = [
+            Value::Nullable(None),
+            Value::Nullable(Some(Box::new(Value::UInt64(1)))),
+            Value::Array(vec![]),
+            Value::Array(vec![Value::String("ab".to_owned()), Value::Bytes(vec![1])]),
+            Value::Map(vec![(
+                Value::String("a".to_owned()),
+                Value::Array(vec![Value::Int32(-1), Value::Int32(2)]),
+            )]),
+            Value::StructValue(vec![
+                ("id".to_owned(), Value::ContractReference(b"abc".to_vec())),
+                (
+                    "inner".to_owned(),
+                    Value::StructValue(vec![
+                        ("hash".to_owned(), Value::Hash8([0; 8])),
+                        ("key".to_owned(), Value::PublicKey(Default::default())),
+                    ]),
+                ),
+            ]),
+        ];
+
+        for value in values {
+            assert_eq!(value.serialized_len(), value.serialize().len(), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn parse_struct_with_absent_optional_field() {
+        let struct_ = Struct {
+            name: "Person".to_owned(),
+            fields: vec![
+                ("name".to_owned(), Type::String),
+                (
+                    "age".to_owned(),
+                    Type::Nullable(Box::new(Type::PrimitiveType(PrimitiveType::UInt32))),
+                ),
+            ],
+        };
+
+        assert_eq!(
+            struct_
+                .parse(&serde_json::json!({ "name": "alice" }))
+                .unwrap(),
+            Value::StructValue(vec![
+                ("name".to_owned(), Value::String("alice".to_owned())),
+                ("age".to_owned(), Value::Nullable(None)),
+            ])
+        );
+        assert!(struct_.parse(&serde_json::json!({ "age": 30 })).is_err());
+    }
+
+    #[test]
+    fn parse_array_of_structs_from_str() {
+        let point = Struct {
+            name: "Point".to_owned(),
+            fields: vec![
+                ("a".to_owned(), Type::PrimitiveType(PrimitiveType::UInt32)),
+                ("b".to_owned(), Type::PrimitiveType(PrimitiveType::UInt32)),
+            ],
+        };
+        let type_ = Type::Array(Box::new(Type::Struct(point)));
+        let point = |a, b| {
+            Value::StructValue(vec![
+                ("a".to_owned(), Value::UInt32(a)),
+                ("b".to_owned(), Value::UInt32(b)),
+            ])
+        };
+        let expected = Value::Array(vec![point(1, 2), point(3, 4)]);
+
+        for input in ["{1,2};{3,4}", "[{1,2};{3,4}]", "1,2,;3,4,", "1,2;3,4"] {
+            assert_eq!(type_.parse(input).unwrap(), expected, "{input}");
+        }
+        assert_eq!(type_.parse("").unwrap(), Value::Array(vec![]));
+        assert_eq!(
+            type_.parse("{1,2};{3}").unwrap_err().to_string(),
+            "field b >> cannot parse struct from {3} (missing field)"
+        );
+        assert_eq!(
+            type_.parse("{1,2,3}").unwrap_err().to_string(),
+            "cannot parse struct from {1,2,3} (too many fields)"
+        );
+        assert_eq!(
+            type_.parse("1,2,3").unwrap_err().to_string(),
+            "cannot parse struct from 1,2,3 (too many fields)"
+        );
+    }
+
+    #[test]
+    fn parse_nested_structs_and_arrays_from_str() {
+        let type_ = Type::Array(Box::new(Type::Struct(Struct {
+            name: "Tagged".to_owned(),
+            fields: vec![
+                ("id".to_owned(), Type::String),
+                ("tags".to_owned(), Type::Array(Box::new(Type::String))),
+                (
+                    "inner".to_owned(),
+                    Type::Struct(Struct {
+                        name: "Inner".to_owned(),
+                        fields: vec![
+                            ("x".to_owned(), Type::PrimitiveType(PrimitiveType::Int32)),
+                            ("y".to_owned(), Type::PrimitiveType(PrimitiveType::Int32)),
+                        ],
+                    }),
+                ),
+            ],
+        })));
+        let tagged = |id: &str, tags: &[&str], x, y| {
+            Value::StructValue(vec![
+                ("id".to_owned(), Value::String(id.to_owned())),
+                (
+                    "tags".to_owned(),
+                    Value::Array(
+                        tags.iter()
+                            .map(|t| Value::String((*t).to_owned()))
+                            .collect(),
+                    ),
+                ),
+                (
+                    "inner".to_owned(),
+                    Value::StructValue(vec![
+                        ("x".to_owned(), Value::Int32(x)),
+                        ("y".to_owned(), Value::Int32(y)),
+                    ]),
+                ),
+            ])
+        };
+
+        assert_eq!(
+            type_.parse("{a,[x;y],{1,-2}};{b,[],{3,4}}").unwrap(),
+            Value::Array(vec![
+                tagged("a", &["x", "y"], 1, -2),
+                tagged("b", &[], 3, 4),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_quoted_strings_from_str() {
+        let type_ = Type::Array(Box::new(Type::Struct(Struct {
+            name: "Named".to_owned(),
+            fields: vec![
+                ("name".to_owned(), Type::String),
+                ("n".to_owned(), Type::PrimitiveType(PrimitiveType::UInt32)),
+            ],
+        })));
+        let named = |name: &str, n| {
+            Value::StructValue(vec![
+                ("name".to_owned(), Value::String(name.to_owned())),
+                ("n".to_owned(), Value::UInt32(n)),
+            ])
+        };
+
+        assert_eq!(
+            type_
+                .parse(r#"{"a,b;c",1};{"{[}]",2};{"say \"hi\" \\o/",3};{plain,4}"#)
+                .unwrap(),
+            Value::Array(vec![
+                named("a,b;c", 1),
+                named("{[}]", 2),
+                named(r#"say "hi" \o/"#, 3),
+                named("plain", 4),
+            ])
+        );
+        assert_eq!(
+            Type::String.parse(r#"""#).unwrap(),
+            Value::String(r#"""#.to_owned())
+        );
+    }
+
+    #[test]
+    fn parse_plain_string_from_str() {
+        for input in [r#""quoted""#, r#"a\b"#, "{a,b}", "[a;b]"] {
+            assert_eq!(
+                Type::String.parse(input).unwrap(),
+                Value::String(input.to_owned()),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            Type::Nullable(Box::new(Type::String))
+                .parse(r#""quoted""#)
+                .unwrap(),
+            Value::Nullable(Some(Box::new(Value::String(r#""quoted""#.to_owned()))))
+        );
+    }
+
+    #[test]
+    fn parse_array_of_arrays_from_str() {
+        let type_ = Type::Array(Box::new(Type::Array(Box::new(Type::PrimitiveType(
+            PrimitiveType::UInt32,
+        )))));
+        let array =
+            |values: &[u32]| Value::Array(values.iter().copied().map(Value::UInt32).collect());
+
+        // The outer brackets are only removed when they enclose the whole array.
+        for input in ["[1;2];[3]", "[[1;2];[3]]"] {
+            assert_eq!(
+                type_.parse(input).unwrap(),
+                Value::Array(vec![array(&[1, 2]), array(&[3])]),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn json_schema_validates_this_and_args() {
+        let abi = Abi {
+            this_type: Some(Type::Struct(Struct {
+                name: "Account".to_owned(),
+                fields: vec![
+                    ("id".to_owned(), Type::String),
+                    (
+                        "balance".to_owned(),
+                        Type::PrimitiveType(PrimitiveType::UInt32),
+                    ),
+                    (
+                        "owner".to_owned(),
+                        Type::Nullable(Box::new(Type::ContractReference {
+                            contract: "User".to_owned(),
+                        })),
+                    ),
+                    ("tags".to_owned(), Type::Array(Box::new(Type::String))),
+                    ("pk".to_owned(), Type::PublicKey),
+                ],
+            })),
+            param_types: vec![
+                Type::PrimitiveType(PrimitiveType::Int64),
+                Type::Map(
+                    Box::new(Type::String),
+                    Box::new(Type::PrimitiveType(PrimitiveType::Boolean)),
+                ),
+            ],
+            ..Default::default()
+        };
+        let schema = jsonschema::JSONSchema::compile(&abi.to_json_schema()).unwrap();
+        let pk = serde_json::json!({
+            "kty": "EC",
+            "crv": "secp256k1",
+            "alg": "ES256K",
+            "use": "sig",
+            "x": "nnzHFO4bZ239bIuAo8t0wQwXH3fPwbKQnpWPzOptv0Q=",
+            "y": "Z1-oY62A6q5kCRGfBuk6E3IrSUjPCK2F6_EwVhW22lY="
+        });
+        let this = serde_json::json!({
+            "id": "a",
+            "balance": 10,
+            "owner": { "id": "user1" },
+            "tags": ["x", "y"],
+            "pk": pk,
+        });
+        let args = serde_json::json!([-5, { "a": true }]);
+
+        let valid = [
+            serde_json::json!({ "this": this, "args": args }),
+            // optional fields can be left out or null
+            serde_json::json!({
+                "this": { "id": "a", "balance": 0, "tags": [], "pk": pk },
+                "args": args,
+            }),
+            serde_json::json!({
+                "this": { "id": "a", "balance": 0, "owner": null, "tags": [], "pk": pk },
+                "args": args,
+            }),
+        ];
+        for input in valid {
+            assert!(schema.is_valid(&input), "{input}");
+            abi.this_value_from_json(&input["this"], MissingFields::Strict)
+                .unwrap();
+        }
+
+        let mut malformed = vec![];
+        for (field, value) in [
+            ("id", serde_json::json!(1)),
+            ("balance", serde_json::json!(-1)),
+            ("balance", serde_json::json!(1.5)),
+            ("owner", serde_json::json!("user1")),
+            ("tags", serde_json::json!("x")),
+            ("pk", serde_json::json!({ "kty": "EC" })),
+        ] {
+            let mut this = this.clone();
+            this[field] = value;
+            malformed.push(serde_json::json!({ "this": this, "args": args }));
+        }
+        let mut missing_id = this.clone();
+        missing_id.as_object_mut().unwrap().remove("id");
+        malformed.extend([
+            serde_json::json!({ "this": missing_id, "args": args }),
+            serde_json::json!({ "this": this, "args": [-5] }),
+            serde_json::json!({ "this": this, "args": [-5, { "a": 1 }] }),
+            serde_json::json!({ "this": this, "args": [-5, {}, 1] }),
+            serde_json::json!({ "args": args }),
+        ]);
+        for input in malformed {
+            assert!(!schema.is_valid(&input), "{input}");
+        }
+    }
+
+    #[test]
+    fn json_schema_for_rest_parameter() {
+        let abi = Abi {
+            param_types: vec![
+                Type::String,
+                Type::Array(Box::new(Type::PrimitiveType(PrimitiveType::UInt32))),
+            ],
+            variadic: true,
+            ..Default::default()
+        };
+        let schema = jsonschema::JSONSchema::compile(&abi.to_json_schema()).unwrap();
+
+        assert!(schema.is_valid(&serde_json::json!({ "args": ["a"] })));
+        assert!(schema.is_valid(&serde_json::json!({ "args": ["a", 1, 2] })));
+        assert!(!schema.is_valid(&serde_json::json!({ "args": [] })));
+        assert!(!schema.is_valid(&serde_json::json!({ "args": ["a", "b"] })));
+    }
+
+    #[test]
+    fn oversized_length() {
+        let memory = |addr| match addr {
+            0 => Some([u64::from(u32::MAX), 0, 0, 0]),
+            _ => Some([0; 4]),
+        };
+
+        let err = Type::String.read(&memory, 0).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "string length 4294967295 exceeds the maximum length of 1048576"
+        );
+    }
+
+    #[test]
+    fn read_budget() {
+        // an array of three strings, each "abc"
+        let memory = |addr| match addr {
+            0 => Some([6, 0, 0, 0]),
+            1 => Some([3, 0, 0, 0]),
+            2 => Some([10, 0, 0, 0]),
+            10 | 12 | 14 => Some([3, 0, 0, 0]),
+            11 | 13 | 15 => Some([100, 0, 0, 0]),
+            100..=102 => Some([u64::from(b'a') + addr - 100, 0, 0, 0]),
+            _ => None,
+        };
+        let type_ = Type::Array(Box::new(Type::String));
+
+        let mut budget = ReadBudget::new(ReadLimits {
+            max_length: 10,
+            max_total_length: 12,
+        });
+        assert_eq!(
+            type_.read_with_budget(&memory, 0, &mut budget).unwrap(),
+            Value::Array(vec![Value::String("abc".to_owned()); 3])
+        );
+
+        let mut budget = ReadBudget::new(ReadLimits {
+            max_length: 10,
+            max_total_length: 8,
+        });
+        let err = type_.read_with_budget(&memory, 0, &mut budget).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "string length 3 exceeds the remaining read budget of 2"
+        );
+    }
+
+    #[test]
+    fn is_indexable() {
+        let string = || Box::new(Type::String);
+
+        assert!(Type::PrimitiveType(PrimitiveType::UInt64).is_indexable());
+        assert!(Type::String.is_indexable());
+        assert!(Type::PublicKey.is_indexable());
+        assert!(Type::Nullable(string()).is_indexable());
+
+        assert!(!Type::Array(string()).is_indexable());
+        assert!(!Type::Map(string(), string()).is_indexable());
+        assert!(!Type::Struct(Struct {
+            name: "Person".to_owned(),
+            fields: vec![("name".to_owned(), Type::String)],
+        })
+        .is_indexable());
+        assert!(!Type::Nullable(Box::new(Type::Array(string()))).is_indexable());
+    }
+
+    #[test]
+    fn type_visit_collects_contract_references() {
+        let reference = |contract: &str| Type::ContractReference {
+            contract: contract.to_owned(),
+        };
+        let type_ = Type::Struct(Struct {
+            name: "Account".to_owned(),
+            fields: vec![
+                ("id".to_owned(), Type::String),
+                ("owner".to_owned(), reference("User")),
+                (
+                    "friends".to_owned(),
+                    Type::Array(Box::new(Type::Nullable(Box::new(reference("Friend"))))),
+                ),
+                (
+                    "balances".to_owned(),
+                    Type::Map(Box::new(Type::String), Box::new(reference("Token"))),
+                ),
+                (
+                    "meta".to_owned(),
+                    Type::Struct(Struct {
+                        name: "Meta".to_owned(),
+                        fields: vec![("creator".to_owned(), reference("User"))],
+                    }),
+                ),
+            ],
+        });
+
+        let mut contracts = vec![];
+        type_.visit(&mut |t| {
+            if let Type::ContractReference { contract } = t {
+                contracts.push(contract.clone());
+            }
+        });
+
+        assert_eq!(contracts, ["User", "Friend", "Token", "User"]);
+    }
+
+    #[test]
+    fn structural_eq_ignores_map_order() {
+        let a = Value::Map(vec![
+            (Value::String("a".to_owned()), Value::UInt32(1)),
+            (Value::String("b".to_owned()), Value::UInt32(2)),
+        ]);
+        let b = Value::Map(vec![
+            (Value::String("b".to_owned()), Value::UInt32(2)),
+            (Value::String("a".to_owned()), Value::UInt32(1)),
+        ]);
+
+        assert_ne!(a, b);
+        assert!(a.structural_eq(&b));
+
+        let c = Value::Map(vec![
+            (Value::String("a".to_owned()), Value::UInt32(1)),
+            (Value::String("a".to_owned()), Value::UInt32(1)),
+        ]);
+        assert!(!a.structural_eq(&c));
+        assert!(!c.structural_eq(&a));
+    }
+
+    #[test]
+    fn structural_eq_compares_floats_by_bits() {
+        let nan = Value::Array(vec![Value::Float64(f64::NAN)]);
+
+        assert_ne!(nan, nan.clone());
+        assert!(nan.structural_eq(&nan.clone()));
+
+        assert_eq!(Value::Float32(0.0), Value::Float32(-0.0));
+        assert!(!Value::Float32(0.0).structural_eq(&Value::Float32(-0.0)));
+    }
+
+    #[test]
+    fn non_finite_floats_to_json() {
+        let value = Value::Array(vec![
+            Value::Float64(f64::NAN),
+            Value::Float32(f32::INFINITY),
+            Value::Float64(f64::NEG_INFINITY),
+            Value::Float32(0.1),
+        ]);
+
+        let json: serde_json::Value = value.clone().try_into().unwrap();
+        assert_eq!(json, serde_json::json!([null, null, null, 0.1]));
+
+        assert_eq!(
+            value.clone().to_json(NonFiniteFloats::String).unwrap(),
+            serde_json::json!(["NaN", "Infinity", "-Infinity", 0.1])
+        );
+
+        assert_eq!(
+            value
+                .to_json(NonFiniteFloats::Error)
+                .unwrap_err()
+                .to_string(),
+            "NaN can't be represented in JSON"
+        );
+    }
+
+    #[test]
+    fn abi_without_param_names() {
+        let abi: Abi = serde_json::from_value(serde_json::json!({
+            "param_types": ["String"],
+            "other_records": [],
+            "other_contract_types": [],
+            "dependent_fields": [],
+        }))
+        .unwrap();
+
+        assert!(abi.param_names.is_empty());
+    }
+
+    #[test]
+    fn normalize_gives_old_abis_an_empty_this() {
+        let mut abi: Abi = serde_json::from_value(serde_json::json!({
+            "this_type": null,
+            "param_types": [],
+            "other_records": [],
+            "other_contract_types": [],
+            "dependent_fields": [],
+        }))
+        .unwrap();
+        assert!(abi.default_this_value().is_err());
+
+        abi.normalize();
+
+        assert_eq!(abi.this_addr, Some(0));
+        assert_eq!(
+            abi.default_this_value().unwrap(),
+            Value::StructValue(vec![])
+        );
+
+        let mut contract_abi = Abi {
+            this_addr: Some(4),
+            this_type: Some(Type::Struct(Struct {
+                name: "Account".to_owned(),
+                fields: vec![("id".to_owned(), Type::String)],
+            })),
+            ..Default::default()
+        };
+        let before = serde_json::to_value(&contract_abi).unwrap();
+        contract_abi.normalize();
+        assert_eq!(serde_json::to_value(&contract_abi).unwrap(), before);
+    }
+
+    #[test]
+    fn this_value_from_partial_json() {
+        let abi = Abi {
+            this_type: Some(Type::Struct(Struct {
+                name: "Account".to_owned(),
+                fields: vec![
+                    ("id".to_owned(), Type::String),
+                    (
+                        "balance".to_owned(),
+                        Type::PrimitiveType(PrimitiveType::UInt32),
+                    ),
+                    (
+                        "owner".to_owned(),
+                        Type::Nullable(Box::new(Type::PublicKey)),
+                    ),
+                ],
+            })),
+            ..Default::default()
+        };
+        let partial = serde_json::json!({ "id": "a", "balance": 5 });
+
+        for missing_fields in [MissingFields::Strict, MissingFields::Default] {
+            assert_eq!(
+                abi.this_value_from_json(&partial, missing_fields).unwrap(),
+                Value::StructValue(vec![
+                    ("id".to_owned(), Value::String("a".to_owned())),
+                    ("balance".to_owned(), Value::UInt32(5)),
+                    ("owner".to_owned(), Value::Nullable(None)),
+                ])
+            );
+
+            let err = abi
+                .this_value_from_json(&serde_json::json!({ "id": "a" }), missing_fields)
+                .unwrap_err();
+            assert_eq!(err.to_string(), "missing value for field `balance`");
+        }
+
+        assert_eq!(
+            abi.this_value_from_json(&serde_json::json!({}), MissingFields::Strict)
+                .unwrap(),
+            abi.default_this_value().unwrap()
+        );
+        let err = abi
+            .this_value_from_json(&serde_json::json!({}), MissingFields::Default)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "missing value for field `id`");
+    }
+}
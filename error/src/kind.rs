@@ -1,5 +1,42 @@
+use std::ops::RangeInclusive;
+
 use snafu::Snafu;
 
+/// The number of arguments a function takes, either exactly or within a range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arity {
+    min: usize,
+    max: usize,
+}
+
+impl From<usize> for Arity {
+    fn from(count: usize) -> Self {
+        Arity {
+            min: count,
+            max: count,
+        }
+    }
+}
+
+impl From<RangeInclusive<usize>> for Arity {
+    fn from(range: RangeInclusive<usize>) -> Self {
+        Arity {
+            min: *range.start(),
+            max: *range.end(),
+        }
+    }
+}
+
+impl std::fmt::Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.min == self.max {
+            write!(f, "{}", self.min)
+        } else {
+            write!(f, "{} to {}", self.min, self.max)
+        }
+    }
+}
+
 #[derive(Snafu, Debug)]
 #[snafu(visibility(pub))]
 pub enum ErrorKind {
@@ -13,6 +50,13 @@ pub enum ErrorKind {
         input: String,
         source: Box<dyn std::error::Error>,
     },
+    #[snafu(display("{type_name} length {length} exceeds the {limit} of {max}"))]
+    TooLong {
+        type_name: &'static str,
+        length: u64,
+        limit: &'static str,
+        max: u64,
+    },
     #[snafu(display("{type_name} {item} not found"))]
     NotFound {
         type_name: &'static str,
@@ -21,7 +65,7 @@ pub enum ErrorKind {
     #[snafu(display("type mismatch: {context}"))]
     TypeMismatch { context: String },
     #[snafu(display("incorrect number of arguments {found} but expected {expected}"))]
-    ArgumentsCount { found: usize, expected: usize },
+    ArgumentsCount { found: usize, expected: Arity },
     #[snafu(display(
         "stack depth is too small found {stack_len}{}",
         if let Some(expected) = expected {
@@ -45,4 +89,28 @@ pub enum ErrorKind {
     Io { source: std::io::Error },
     #[snafu(display("{context} is not implemented yet"))]
     NotImplemented { context: String },
+    #[snafu(display("contract {contract} has a cyclic extends chain"))]
+    CyclicInheritance { contract: String },
+}
+
+impl ErrorKind {
+    /// Name of the variant, for callers that need to tell errors apart
+    /// without matching on them, e.g. across the wasm boundary.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::Wrapped { .. } => "Wrapped",
+            ErrorKind::InvalidAddress { .. } => "InvalidAddress",
+            ErrorKind::Parse { .. } => "Parse",
+            ErrorKind::TooLong { .. } => "TooLong",
+            ErrorKind::NotFound { .. } => "NotFound",
+            ErrorKind::TypeMismatch { .. } => "TypeMismatch",
+            ErrorKind::ArgumentsCount { .. } => "ArgumentsCount",
+            ErrorKind::Stack { .. } => "Stack",
+            ErrorKind::Simple { .. } => "Simple",
+            ErrorKind::Nested { .. } => "Nested",
+            ErrorKind::Io { .. } => "Io",
+            ErrorKind::NotImplemented { .. } => "NotImplemented",
+            ErrorKind::CyclicInheritance { .. } => "CyclicInheritance",
+        }
+    }
 }
@@ -32,11 +32,19 @@ impl Error {
         }
     }
 
-    fn print_source(&self) -> impl std::fmt::Display + '_ {
-        if let Some((source, span)) = self.source_code.as_ref().zip(self.span.as_ref()) {
-            assert!(source.len() >= span.end);
-            assert!(span.end >= span.start);
+    pub fn span(&self) -> Option<span::Span> {
+        self.span
+    }
 
+    fn print_source(&self) -> impl std::fmt::Display + '_ {
+        // The span can be out of range if it points into a different source,
+        // e.g. code merged in from an import.
+        if let Some((source, span)) = self
+            .source_code
+            .as_ref()
+            .zip(self.span.as_ref())
+            .filter(|(source, span)| span.start <= span.end && span.end < source.len())
+        {
             let mut lines = source.lines();
             let mut char_count = 0;
             let (start_line_no, start_line_sym) = lines
@@ -11,6 +11,16 @@ impl Span {
     pub fn new(start: usize, end: usize) -> Self {
         Self { start, end }
     }
+
+    /// Returns the 1-based line and column of the start of the span in `source`.
+    pub fn start_line_col(&self, source: &str) -> (usize, usize) {
+        let before = &source[..self.start.min(source.len())];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (
+            before.matches('\n').count() + 1,
+            before.len() - line_start + 1,
+        )
+    }
 }
 
 #[macro_export]
@@ -40,6 +50,11 @@ pub(crate) fn get() -> Option<Span> {
     *CURRENT_SPAN.lock()
 }
 
+/// Returns the span of the code that is currently being compiled.
+pub fn current() -> Option<Span> {
+    get()
+}
+
 impl Drop for SpanGuard {
     fn drop(&mut self) {
         *CURRENT_SPAN.lock() = self.0;
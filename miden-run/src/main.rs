@@ -3,6 +3,7 @@ use error::prelude::*;
 use std::{
     collections::HashMap,
     io::{Read, Write},
+    time::{Duration, Instant},
 };
 
 struct Args {
@@ -14,6 +15,8 @@ struct Args {
     abi: Abi,
     ctx: Ctx,
     proof_output: Option<String>,
+    /// Number of times to run the program for `--bench`.
+    bench: Option<usize>,
 }
 
 #[derive(Default, serde::Deserialize)]
@@ -23,7 +26,7 @@ struct Ctx {
 }
 
 impl Args {
-    fn parse(args: std::env::Args, masm_code: &str) -> Result<Self, String> {
+    fn parse(args: impl Iterator<Item = String>, masm_code: &str) -> Result<Self, String> {
         let mut args = args.skip(1);
         let mut advice_tape_json = None;
         let mut abi = None;
@@ -32,6 +35,7 @@ impl Args {
         let mut other_records = HashMap::new();
         let mut ctx = None;
         let mut proof_output = None;
+        let mut bench = None;
 
         while let Some(arg) = args.next() {
             match arg.as_str() {
@@ -98,6 +102,23 @@ impl Args {
 
                     proof_output = Some(value);
                 }
+                "--bench" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("missing value for argument {}", arg))?;
+
+                    let iterations = value
+                        .parse::<usize>()
+                        .map_err(|e| format!("invalid value for argument {}: {}", arg, e))?;
+                    if iterations == 0 {
+                        return Err(format!(
+                            "invalid value for argument {}: must be at least 1",
+                            arg
+                        ));
+                    }
+
+                    bench = Some(iterations);
+                }
                 this_param if this_param.starts_with("--this.") => {
                     let field = this_param
                         .strip_prefix("--this.")
@@ -114,7 +135,7 @@ impl Args {
             }
         }
 
-        let abi = match abi {
+        let mut abi = match abi {
             None => {
                 let abi_comment = masm_code
                     .lines()
@@ -129,6 +150,7 @@ impl Args {
             }
             Some(abi) => abi,
         };
+        abi.normalize();
 
         for (contract, records) in other_records.iter_mut() {
             let col_struct = abi.other_contract_types.iter().find_map(|t| match t {
@@ -149,6 +171,7 @@ impl Args {
             other_records,
             ctx: ctx.unwrap_or_default(),
             proof_output,
+            bench,
         })
     }
 
@@ -194,36 +217,8 @@ impl Args {
             return Err(Error::simple("No JSON value for `this`"));
         };
 
-        let this_type = self
-            .abi
-            .this_type
-            .as_ref()
-            .ok_or_else(|| Error::simple("ABI does not specify a `this` type"))?;
-        let abi::Type::Struct(struct_) = this_type else {
-            return Err(Error::simple("This type is not a struct"));
-        };
-
-        let use_defaults = this_json.as_object().map(|o| o.is_empty()).unwrap_or(false);
-
-        let mut struct_values = Vec::new();
-        for (field_name, field_type) in &struct_.fields {
-            let field_value = match this_json.get(field_name) {
-                Some(value) => abi::Parser::parse(field_type, value)?,
-                None if use_defaults => field_type.default_value(),
-                None if matches!(field_type, abi::Type::Nullable(_)) => field_type.default_value(),
-                // FIXME: add a separate variant
-                None => {
-                    return Err(Error::simple(format!(
-                        "missing value for field `{}`",
-                        field_name
-                    )))
-                }
-            };
-
-            struct_values.push((field_name.clone(), field_value));
-        }
-
-        Ok(abi::Value::StructValue(struct_values))
+        self.abi
+            .this_value_from_json(this_json, abi::MissingFields::Strict)
     }
 
     fn inputs(
@@ -257,50 +252,83 @@ impl Args {
     }
 }
 
+/// Wall times of running a program repeatedly, see `--bench`.
+struct Bench {
+    /// Sorted from fastest to slowest.
+    durations: Vec<Duration>,
+    cycle_count: u32,
+}
+
+impl std::fmt::Display for Bench {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "iterations: {}, cycles: {}, min: {:?}, median: {:?}, max: {:?}",
+            self.durations.len(),
+            self.cycle_count,
+            self.durations[0],
+            self.durations[self.durations.len() / 2],
+            self.durations[self.durations.len() - 1],
+        )
+    }
+}
+
+/// Runs the program `iterations` times without proving.
+fn bench(
+    program: &polylang_prover::Program,
+    inputs: &polylang_prover::Inputs,
+    iterations: usize,
+) -> Result<Bench> {
+    let mut durations = Vec::with_capacity(iterations);
+    let mut cycle_count = 0;
+
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let output = polylang_prover::execute(program, inputs)?;
+        durations.push(start.elapsed());
+
+        // The program is deterministic, so every run takes the same number of cycles.
+        cycle_count = output.cycle_count;
+    }
+
+    durations.sort();
+
+    Ok(Bench {
+        durations,
+        cycle_count,
+    })
+}
+
 fn try_main() -> Result<(), Box<dyn std::error::Error>> {
     let mut masm_code = String::new();
     std::io::stdin()
         .read_to_string(&mut masm_code)
         .context(IoSnafu)?;
 
-    let mut args = Args::parse(std::env::args(), &masm_code).map_err(Error::simple)?;
-
-    let has_this_type = if args.abi.this_type.is_none() {
-        args.abi.this_type = Some(abi::Type::Struct(abi::Struct {
-            name: "Empty".to_string(),
-            fields: Vec::new(),
-        }));
-
-        false
-    } else {
-        true
-    };
+    let args = Args::parse(std::env::args(), &masm_code).map_err(Error::simple)?;
 
     let inputs = args.inputs(polylang_prover::hash_this)?;
 
     let program = polylang_prover::compile_program(&args.abi, &masm_code)
         .map_err(|e| e.add_source(masm_code))?;
 
+    if let Some(iterations) = args.bench {
+        println!("{}", bench(&program, &inputs, iterations)?);
+        return Ok(());
+    }
+
     let (output, prove) = polylang_prover::run(&program, &inputs)?;
 
     dbg!(&output);
-    dbg!(output.hashes());
+    dbg!(output.hashes()?);
     dbg!(output.logs());
     dbg!(output.cycle_count);
 
-    if has_this_type {
-        dbg!(output.self_destructed()?);
-        println!(
-            "this_json: {}",
-            TryInto::<serde_json::Value>::try_into(output.this(&args.abi)?)?
-        );
-    }
+    dbg!(output.self_destructed()?);
+    println!("this_json: {}", output.this_json(&args.abi)?);
 
     if args.abi.result_type.is_some() {
-        println!(
-            "result_json: {}",
-            TryInto::<serde_json::Value>::try_into(output.result(&args.abi)?)?
-        );
+        println!("result_json: {}", output.result_json(&args.abi)?);
     }
 
     if let Some(out) = args.proof_output {
@@ -320,3 +348,38 @@ fn main() {
         std::process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bench_reports_timings() {
+        let abi = Abi {
+            this_addr: Some(0),
+            this_type: Some(abi::Type::Struct(abi::Struct {
+                name: "Empty".to_string(),
+                fields: Vec::new(),
+            })),
+            ..Default::default()
+        };
+        let masm_code = "begin\n  push.1\n  drop\nend\n";
+        let args = Args::parse(
+            ["miden-run", "--bench", "3", "--abi"]
+                .into_iter()
+                .map(String::from)
+                .chain([serde_json::to_string(&abi).unwrap()]),
+            masm_code,
+        )
+        .unwrap();
+        assert_eq!(args.bench, Some(3));
+
+        let inputs = args.inputs(polylang_prover::hash_this).unwrap();
+        let program = polylang_prover::compile_program(&args.abi, masm_code).unwrap();
+        let report = bench(&program, &inputs, 3).unwrap().to_string();
+
+        for field in ["iterations: 3", "cycles: ", "min: ", "median: ", "max: "] {
+            assert!(report.contains(field), "{report:?} is missing {field:?}");
+        }
+    }
+}
@@ -3,21 +3,67 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Program {
     pub nodes: Vec<RootNode>,
+    /// Names of the declarations in `nodes` that were merged in from imported files.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub imported: Vec<String>,
+}
+
+impl Program {
+    /// Whether `function` belongs to a declaration merged in from an imported file.
+    pub fn is_imported(&self, function: &Function) -> bool {
+        self.nodes.iter().any(|node| match node {
+            RootNode::Contract(c) if self.imported.contains(&c.name) => c
+                .items
+                .iter()
+                .any(|item| matches!(item, ContractItem::Function(f) if std::ptr::eq(f, function))),
+            RootNode::Function(f) if self.imported.contains(&f.name) => std::ptr::eq(f, function),
+            _ => false,
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum RootNode {
     Contract(Contract),
     Function(Function),
+    Interface(Interface),
+    Import(Import),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Contract {
     pub name: String,
     pub decorators: Vec<Decorator>,
+    /// The contract whose fields and functions this one inherits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// The interfaces this contract must conform to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub implements: Vec<String>,
     pub items: Vec<ContractItem>,
 }
 
+/// `import { Name, ... } from "path";`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Import {
+    pub names: Vec<String>,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Interface {
+    pub name: String,
+    pub methods: Vec<MethodSignature>,
+}
+
+/// A function declaration without a body, as found in an interface.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MethodSignature {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub return_type: Option<Type>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ContractItem {
     Field(Field),
@@ -45,6 +91,8 @@ pub type Decorator = MaybeSpanned<DecoratorNode>;
 pub enum DecoratorArgument {
     Identifier(String),
     Literal(Literal),
+    String(String),
+    Number(f64),
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -93,6 +141,15 @@ pub struct Parameter {
     pub name: String,
     pub type_: ParameterType,
     pub required: bool,
+    /// The value used when a call omits this argument.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Expression>,
+    /// Whether this is a rest parameter (`...nums: u32[]`) that collects
+    /// the remaining arguments into an array.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub variadic: bool,
+    #[serde(skip)]
+    pub span: Option<Span>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -190,7 +247,7 @@ impl<T> std::ops::Deref for MaybeSpanned<T> {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum StatementKind {
     Break,
     If(If),
@@ -220,25 +277,36 @@ pub enum ExpressionKind {
     Primitive(Primitive),
     Ident(String),
     Boolean(bool),
+    /// Its type comes from the other side of an assignment or comparison.
+    Null,
     Object(Object),
     Array(Vec<Expression>),
+    /// `{ "a": 1, "b": 2 }`, entries with literal keys.
+    Map(Vec<(Expression, Expression)>),
     Assign(Box<Expression>, Box<Expression>),
     AssignSub(Box<Expression>, Box<Expression>),
     AssignAdd(Box<Expression>, Box<Expression>),
     Increment(Box<Expression>),
     Or(Box<Expression>, Box<Expression>),
     And(Box<Expression>, Box<Expression>),
+    /// `a ?? b`, the value of `a` if it's not null, otherwise `b`.
+    NullishCoalesce(Box<Expression>, Box<Expression>),
     Equal(Box<Expression>, Box<Expression>),
     NotEqual(Box<Expression>, Box<Expression>),
     LessThan(Box<Expression>, Box<Expression>),
     LessThanOrEqual(Box<Expression>, Box<Expression>),
     GreaterThan(Box<Expression>, Box<Expression>),
     GreaterThanOrEqual(Box<Expression>, Box<Expression>),
+    /// `x in arr` or `key in map`.
+    In(Box<Expression>, Box<Expression>),
     BitOr(Box<Expression>, Box<Expression>),
     BitXor(Box<Expression>, Box<Expression>),
     BitAnd(Box<Expression>, Box<Expression>),
     ShiftLeft(Box<Expression>, Box<Expression>),
+    /// Arithmetic (sign-extending) for signed integers.
     ShiftRight(Box<Expression>, Box<Expression>),
+    /// `a >>> b`, shifts in zeros regardless of the sign.
+    UnsignedShiftRight(Box<Expression>, Box<Expression>),
     Add(Box<Expression>, Box<Expression>),
     Subtract(Box<Expression>, Box<Expression>),
     Multiply(Box<Expression>, Box<Expression>),
@@ -251,35 +319,52 @@ pub enum ExpressionKind {
     Dot(Box<Expression>, String),
     Index(Box<Expression>, Box<Expression>),
     Call(Box<Expression>, Vec<Expression>),
+    ArrowFunction(ArrowFunction),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// `(x, i) => { ... }` or `x => x + 1`, only allowed as a callback argument.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct ArrowFunction {
+    pub parameters: Vec<String>,
+    pub body: ArrowFunctionBody,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub enum ArrowFunctionBody {
+    Statements(Vec<Statement>),
+    Expression(Box<Expression>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Let {
     pub identifier: String,
     pub type_: Option<Type>,
+    /// Whether this is declared as `let x?: T = ...`, which holds a nullable `T`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
     pub expression: Expression,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct If {
     pub condition: Expression,
     pub then_statements: Vec<Statement>,
     pub else_statements: Vec<Statement>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct While {
     pub condition: Expression,
     pub statements: Vec<Statement>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct For {
     pub for_kind: ForKind,
     pub statements: Vec<Statement>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ForKind {
     Basic {
         initial_statement: ForInitialStatement,
@@ -293,13 +378,13 @@ pub enum ForKind {
     },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ForInitialStatement {
     Let(Let),
     Expression(Expression),
 }
 
-#[derive(Debug, Serialize, Deserialize, derive_more::Display)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, derive_more::Display)]
 pub enum ForEachType {
     // for .. in ..
     #[display(fmt = "in")]
@@ -314,6 +399,8 @@ pub enum Primitive {
     // (value, has_decimal_point)
     Number(f64, bool),
     String(String),
+    /// `bytes("deadbeef")`, already decoded from hex.
+    Bytes(Vec<u8>),
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
@@ -12,6 +12,7 @@ pub enum Tok<'input> {
     Asc,
     True,
     False,
+    Null,
     Number,
     F32,
     F64,
@@ -39,6 +40,11 @@ pub enum Tok<'input> {
     Index,
     Collection,
     Contract,
+    Extends,
+    Interface,
+    Implements,
+    Import,
+    From,
     LBrace,
     RBrace,
     LBracket,
@@ -52,6 +58,7 @@ pub enum Tok<'input> {
     BangEqual,  // !=
     MinusEqual, // -=
     PlusEqual,  // +=
+    FatArrow,   // =>
     Comma,
     Colon,
     Semicolon,
@@ -64,6 +71,7 @@ pub enum Tok<'input> {
     Percent,
     Bang,
     Question,
+    QuestionQuestion, // ??
     Tilde,
     Ampersand,
     AmpersandAmpersand,
@@ -88,6 +96,7 @@ impl std::fmt::Display for Tok<'_> {
             Tok::Asc => write!(f, "asc"),
             Tok::True => write!(f, "true"),
             Tok::False => write!(f, "false"),
+            Tok::Null => write!(f, "null"),
             Tok::Number => write!(f, "number"),
             Tok::F32 => write!(f, "f32"),
             Tok::F64 => write!(f, "f64"),
@@ -115,6 +124,11 @@ impl std::fmt::Display for Tok<'_> {
             Tok::Index => write!(f, "index"),
             Tok::Collection => write!(f, "contract"),
             Tok::Contract => write!(f, "contract"),
+            Tok::Extends => write!(f, "extends"),
+            Tok::Interface => write!(f, "interface"),
+            Tok::Implements => write!(f, "implements"),
+            Tok::Import => write!(f, "import"),
+            Tok::From => write!(f, "from"),
             Tok::LBrace => write!(f, "{{"),
             Tok::RBrace => write!(f, "}}"),
             Tok::LBracket => write!(f, "["),
@@ -128,6 +142,7 @@ impl std::fmt::Display for Tok<'_> {
             Tok::BangEqual => write!(f, "!="),
             Tok::MinusEqual => write!(f, "-="),
             Tok::PlusEqual => write!(f, "+="),
+            Tok::FatArrow => write!(f, "=>"),
             Tok::Comma => write!(f, ","),
             Tok::Colon => write!(f, ":"),
             Tok::Semicolon => write!(f, ";"),
@@ -140,6 +155,7 @@ impl std::fmt::Display for Tok<'_> {
             Tok::Percent => write!(f, "%"),
             Tok::Bang => write!(f, "!"),
             Tok::Question => write!(f, "?"),
+            Tok::QuestionQuestion => write!(f, "??"),
             Tok::Tilde => write!(f, "~"),
             Tok::Ampersand => write!(f, "&"),
             Tok::AmpersandAmpersand => write!(f, "&&"),
@@ -211,6 +227,7 @@ const KEYWORDS: &[(Tok, &str)] = &[
     (Tok::Asc, "asc"),
     (Tok::True, "true"),
     (Tok::False, "false"),
+    (Tok::Null, "null"),
     (Tok::Number, "number"),
     (Tok::F32, "f32"),
     (Tok::F64, "f64"),
@@ -236,6 +253,11 @@ const KEYWORDS: &[(Tok, &str)] = &[
     (Tok::Index, "@index"),
     (Tok::Collection, "collection"),
     (Tok::Contract, "contract"),
+    (Tok::Extends, "extends"),
+    (Tok::Interface, "interface"),
+    (Tok::Implements, "implements"),
+    (Tok::Import, "import"),
+    (Tok::From, "from"),
     (Tok::PublicKey, "PublicKey"),
     (Tok::Bytes, "bytes"),
 ];
@@ -659,6 +681,10 @@ impl<'input> Iterator for Lexer<'input> {
                             self.next_char();
                             Some(Ok((i, Tok::EqualEqual, i + 2)))
                         }
+                        Some((_, '>')) => {
+                            self.next_char();
+                            Some(Ok((i, Tok::FatArrow, i + 2)))
+                        }
                         _ => Some(Ok((i, Tok::Equal, i + 1))),
                     }
                 }
@@ -720,7 +746,13 @@ impl<'input> Iterator for Lexer<'input> {
                 }
                 (i, '?') => {
                     self.next_char();
-                    Some(Ok((i, Tok::Question, i + 1)))
+                    match self.peek_char() {
+                        Some((_, '?')) => {
+                            self.next_char();
+                            Some(Ok((i, Tok::QuestionQuestion, i + 2)))
+                        }
+                        _ => Some(Ok((i, Tok::Question, i + 1))),
+                    }
                 }
                 (i, '~') => {
                     self.next_char();
@@ -941,6 +973,7 @@ mod tests {
             ("%", Tok::Percent),
             ("!", Tok::Bang),
             ("?", Tok::Question),
+            ("??", Tok::QuestionQuestion),
             ("~", Tok::Tilde),
             ("&", Tok::Ampersand),
             ("&&", Tok::AmpersandAmpersand),
@@ -953,6 +986,7 @@ mod tests {
             ("!=", Tok::BangEqual),
             ("-=", Tok::MinusEqual),
             ("+=", Tok::PlusEqual),
+            ("=>", Tok::FatArrow),
             (",", Tok::Comma),
             (":", Tok::Colon),
             (";", Tok::Semicolon),
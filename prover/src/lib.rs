@@ -1,11 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
 
-use abi::{publickey, Abi, Parser, Type, TypeReader, Value};
+use abi::{publickey, Abi, MissingFields, Parser, Type, TypeReader, Value};
 use error::prelude::*;
-use miden::{ExecutionProof, ProvingOptions};
-use miden_processor::{
-    math::Felt, utils::Serializable, Program, ProgramInfo, StackInputs, StackOutputs,
-};
+use miden::ExecutionProof;
+pub use miden::ProvingOptions;
+pub use miden_processor::Program;
+use miden_processor::{math::Felt, utils::Serializable, ProgramInfo, StackInputs, StackOutputs};
 use polylang::compiler;
 
 #[derive(Debug)]
@@ -13,6 +14,7 @@ enum MidenError {
     Assembly(miden::AssemblyError),
     Execution(miden::ExecutionError),
     Input(miden::InputError),
+    Verification(miden::VerificationError),
 }
 
 impl std::fmt::Display for MidenError {
@@ -21,6 +23,7 @@ impl std::fmt::Display for MidenError {
             MidenError::Assembly(e) => write!(f, "{}", e),
             MidenError::Execution(e) => write!(f, "{}", e),
             MidenError::Input(e) => write!(f, "{}", e),
+            MidenError::Verification(e) => write!(f, "{}", e),
         }
     }
 }
@@ -54,31 +57,18 @@ fn json_to_this_value(this_json: &serde_json::Value, this_type: &Type) -> Result
         return Err(Error::simple("This type is not a struct"));
     };
 
-    let use_defaults = this_json.as_object().map(|o| o.is_empty()).unwrap_or(false);
-
-    let mut struct_values = Vec::new();
-    for (field_name, field_type) in &struct_.fields {
-        let field_value = match this_json.get(field_name) {
-            Some(value) => Parser::parse(field_type, value)?,
-            None if use_defaults => field_type.default_value(),
-            None if matches!(field_type, Type::Nullable(_)) => field_type.default_value(),
-            None => {
-                return Err(Error::simple(format!(
-                    "missing value for field `{}`",
-                    field_name
-                )))
-            }
-        };
-
-        struct_values.push((field_name.clone(), field_value));
-    }
-
-    Ok(Value::StructValue(struct_values))
+    struct_.parse_partial(this_json, MissingFields::Strict)
 }
 
 pub fn hash_this(type_: Type, this: &Value, salts: Option<&[u32]>) -> Result<[u64; 4]> {
     let hasher_program = compiler::compile_hasher(type_, salts)?;
 
+    run_hasher(hasher_program, this)
+}
+
+/// Runs a hasher compiled by [`compiler::compile_hasher`] on `value`,
+/// which must be of the type the hasher was compiled for.
+pub fn run_hasher(hasher_program: String, value: &Value) -> Result<[u64; 4]> {
     let assembler = miden::Assembler::default()
         .with_library(&miden_stdlib::StdLibrary::default())
         .map_err(MidenError::Assembly)
@@ -91,7 +81,7 @@ pub fn hash_this(type_: Type, this: &Value, salts: Option<&[u32]>) -> Result<[u6
 
     let mem_advice_provider = miden::MemAdviceProvider::from(
         miden::AdviceInputs::default()
-            .with_stack_values(this.serialize().into_iter())
+            .with_stack_values(value.serialize().into_iter())
             .map_err(MidenError::Input)
             .wrap_err()?,
     );
@@ -114,12 +104,18 @@ pub fn compile_program(abi: &Abi, miden_code: &str) -> Result<Program> {
     let std_library = match &abi.std_version {
         None => miden_stdlib::StdLibrary::default(),
         Some(version) => match version {
-            abi::StdVersion::V0_5_0 => unimplemented!("Unsupported std version: 0.5.0"),
-            abi::StdVersion::V0_6_1 => unimplemented!("Unsupported std version: 0.6.1"),
+            abi::StdVersion::V0_5_0 | abi::StdVersion::V0_6_1 => {
+                return Err(Error::unimplemented(format!(
+                    "standard library version {version}"
+                )))
+            }
             abi::StdVersion::V0_7_0 => miden_stdlib::StdLibrary::default(),
         },
     };
+    // Debug mode records which procedure each operation belongs to,
+    // which `run_with_trace` needs to find breakpoints. It doesn't change how the program runs.
     let assembler = miden::Assembler::default()
+        .with_debug_mode(!abi.breakpoints.is_empty())
         .with_library(&std_library)
         .map_err(MidenError::Assembly)
         .wrap_err()?;
@@ -130,6 +126,194 @@ pub fn compile_program(abi: &Abi, miden_code: &str) -> Result<Program> {
         .wrap_err()
 }
 
+/// A least-recently-used cache of compiled programs, keyed by the ABI and the
+/// miden code they were compiled from.
+pub struct ProgramCache {
+    capacity: usize,
+    state: Mutex<ProgramCacheState>,
+}
+
+/// The ABI, serialized as JSON, and the miden code that a program is compiled from.
+type ProgramKey = (String, String);
+
+#[derive(Default)]
+struct ProgramCacheState {
+    programs: HashMap<Arc<ProgramKey>, Arc<Program>>,
+    /// Keys of `programs`, least recently used first.
+    order: VecDeque<Arc<ProgramKey>>,
+    hits: u64,
+}
+
+impl ProgramCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::new(ProgramCacheState::default()),
+        }
+    }
+
+    /// Number of compilations that were served from the cache.
+    pub fn hits(&self) -> u64 {
+        self.state.lock().unwrap().hits
+    }
+
+    fn key(abi: &Abi, miden_code: &str) -> Result<ProgramKey> {
+        Ok((
+            serde_json::to_string(abi).wrap_err()?,
+            miden_code.to_owned(),
+        ))
+    }
+}
+
+/// Like [`compile_program`], but returns the cached program if `cache` already has one
+/// for the same `abi` and `miden_code`.
+pub fn compile_program_cached(
+    cache: &ProgramCache,
+    abi: &Abi,
+    miden_code: &str,
+) -> Result<Arc<Program>> {
+    let key = ProgramCache::key(abi, miden_code)?;
+
+    {
+        let mut state = cache.state.lock().unwrap();
+        if let Some((cached_key, program)) = state
+            .programs
+            .get_key_value(&key)
+            .map(|(k, program)| (Arc::clone(k), Arc::clone(program)))
+        {
+            state.hits += 1;
+            state.order.retain(|k| !Arc::ptr_eq(k, &cached_key));
+            state.order.push_back(cached_key);
+            return Ok(program);
+        }
+    }
+
+    // Compile without holding the lock, so that other programs can be looked up meanwhile.
+    let program = Arc::new(compile_program(abi, miden_code)?);
+
+    let mut state = cache.state.lock().unwrap();
+    if cache.capacity > 0 && !state.programs.contains_key(&key) {
+        while state.programs.len() >= cache.capacity {
+            let Some(oldest) = state.order.pop_front() else {
+                break;
+            };
+            state.programs.remove(&oldest);
+        }
+        let key = Arc::new(key);
+        state
+            .programs
+            .insert(Arc::clone(&key), Arc::clone(&program));
+        state.order.push_back(key);
+    }
+
+    Ok(program)
+}
+
+/// Number of iterations assumed for every `while.true` loop,
+/// their real iteration count is only known at runtime.
+const ESTIMATED_LOOP_ITERATIONS: u64 = 4;
+
+fn instruction_cycle_estimate(instruction: &str) -> u64 {
+    let (op, immediate) = instruction
+        .split_once('.')
+        .map(|(op, imm)| (op, Some(imm)))
+        .unwrap_or((instruction, None));
+
+    match (op, immediate) {
+        ("exec", Some(procedure)) => match procedure {
+            "sha256::hash_2to1" => 3000,
+            "blake3::hash_2to1" => 400,
+            "u64::checked_div" | "u64::checked_mod" => 80,
+            "u64::checked_mul" | "u64::wrapping_mul" => 40,
+            _ => 20,
+        },
+        ("adv_push", Some(n)) => n.parse().unwrap_or(1),
+        ("adv", _) => 0,
+        ("mem_load" | "mem_store" | "push" | "dup" | "movup" | "movdn", Some(_)) => 2,
+        (op, _) if op.starts_with("u32checked_") => 4,
+        (op, _) if op.starts_with("u32") => 2,
+        _ => 1,
+    }
+}
+
+/// Statically estimates the number of cycles it takes to run `miden_code`.
+///
+/// Every instruction is weighted by its approximate cost, `if` blocks count
+/// their more expensive branch, and `while` loops are assumed to run a fixed
+/// number of times. The result is not exact, but correlates with
+/// [`RunOutput::cycle_count`].
+pub fn cycle_estimate(miden_code: &str) -> u64 {
+    enum Block {
+        Begin,
+        If { then: u64, in_else: bool },
+        While,
+        Repeat(u64),
+    }
+
+    // the cost of each open block, the else branch cost is tracked separately
+    let mut blocks: Vec<(Block, u64)> = vec![];
+    let mut total = 0;
+
+    for line in miden_code.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("use.") {
+            continue;
+        }
+
+        let cost = match line {
+            "begin" => {
+                blocks.push((Block::Begin, 0));
+                continue;
+            }
+            "if.true" => {
+                blocks.push((
+                    Block::If {
+                        then: 0,
+                        in_else: false,
+                    },
+                    0,
+                ));
+                continue;
+            }
+            "while.true" => {
+                blocks.push((Block::While, 0));
+                continue;
+            }
+            "else" => {
+                if let Some((Block::If { then, in_else }, cost)) = blocks.last_mut() {
+                    *then = *cost;
+                    *in_else = true;
+                    *cost = 0;
+                }
+                continue;
+            }
+            "end" => match blocks.pop() {
+                Some((Block::Begin, cost)) => cost,
+                Some((Block::If { then, in_else }, cost)) => {
+                    let (then, else_) = if in_else { (then, cost) } else { (cost, 0) };
+                    1 + then.max(else_)
+                }
+                Some((Block::While, cost)) => 1 + ESTIMATED_LOOP_ITERATIONS * (cost + 1),
+                Some((Block::Repeat(n), cost)) => n * cost,
+                None => 0,
+            },
+            line if line.starts_with("repeat.") => {
+                let n = line.trim_start_matches("repeat.").parse().unwrap_or(1);
+                blocks.push((Block::Repeat(n), 0));
+                continue;
+            }
+            instruction => instruction_cycle_estimate(instruction),
+        };
+
+        match blocks.last_mut() {
+            Some((_, block_cost)) => *block_cost += cost,
+            None => total += cost,
+        }
+    }
+
+    total
+}
+
 type OtherRecordsType = HashMap<String, Vec<(Type, Value, Value, Vec<u32>)>>;
 
 #[derive(Clone)]
@@ -195,25 +379,21 @@ impl Inputs {
         })
     }
 
-    pub fn stack_values(&self, other_records: &OtherRecordsType) -> Vec<u64> {
+    pub fn stack_values(&self, other_records: &OtherRecordsType) -> Result<Vec<u64>> {
         let mut other_record_hashes = vec![];
         for or in &self.abi.other_records {
-            let records = other_records.get(&or.contract).unwrap();
-            let struct_ = self
-                .abi
-                .other_contract_types
-                .iter()
-                .find_map(|t| match t {
-                    Type::Struct(s) if s.name == or.contract => Some(s),
-                    _ => None,
-                })
-                .unwrap();
+            let records = other_records
+                .get(&or.contract)
+                .not_found("records of contract", &or.contract)?;
+            let struct_ = self.other_contract_type(&or.contract)?;
 
             let mut record_hashes = vec![];
             for (_, _, record, salts) in records {
-                record_hashes.push(Value::Hash(
-                    hash_this(Type::Struct(struct_.clone()), record, Some(salts)).unwrap(),
-                ));
+                record_hashes.push(Value::Hash(hash_this(
+                    Type::Struct(struct_.clone()),
+                    record,
+                    Some(salts),
+                )?));
             }
 
             other_record_hashes.push(Value::Array(record_hashes));
@@ -229,7 +409,7 @@ impl Inputs {
             vec![]
         };
 
-        [
+        Ok([
             self.this_field_hashes
                 .iter()
                 .enumerate()
@@ -244,15 +424,75 @@ impl Inputs {
         .into_iter()
         .flatten()
         .rev()
-        .collect::<Vec<_>>()
+        .collect::<Vec<_>>())
     }
 
     pub fn stack(&self, other_records: &OtherRecordsType) -> Result<StackInputs> {
-        StackInputs::try_from_values(self.stack_values(other_records))
+        StackInputs::try_from_values(self.stack_values(other_records)?)
             .map_err(MidenError::Input)
             .wrap_err()
     }
 
+    /// Checks that the arguments match the parameters of the function,
+    /// so that a bad call fails before the program runs.
+    pub fn validate(&self) -> Result<()> {
+        self.parse_args().map(|_| ())
+    }
+
+    fn parse_args(&self) -> Result<Vec<Value>> {
+        let params = &self.abi.param_types;
+        if self.abi.variadic {
+            // The last parameter is the rest parameter, the ones before it are required.
+            let required = params
+                .len()
+                .checked_sub(1)
+                .ok_or_else(|| Error::simple("variadic function without a rest parameter"))?;
+            if self.args.len() < required {
+                return Err(Error::simple(format!(
+                    "incorrect number of arguments {} but expected at least {required}",
+                    self.args.len()
+                )));
+            }
+        } else {
+            ensure!(
+                self.args.len() == params.len(),
+                ArgumentsCountSnafu {
+                    found: self.args.len(),
+                    expected: params.len(),
+                }
+            );
+        }
+
+        params
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                let value = if self.abi.variadic && i == params.len() - 1 {
+                    // The rest parameter takes all the remaining arguments as an array.
+                    t.parse(&serde_json::Value::Array(self.args[i..].to_vec()))
+                } else {
+                    t.parse(&self.args[i])
+                };
+
+                value.nest_err(|| match self.abi.param_names.get(i) {
+                    Some(name) => format!("invalid value for parameter '{name}'"),
+                    None => format!("argument {i}"),
+                })
+            })
+            .collect()
+    }
+
+    fn other_contract_type(&self, contract: &str) -> Result<&abi::Struct> {
+        self.abi
+            .other_contract_types
+            .iter()
+            .find_map(|t| match t {
+                Type::Struct(s) if s.name == contract => Some(s),
+                _ => None,
+            })
+            .not_found("type of contract", contract)
+    }
+
     fn this_value(&self) -> Result<Value> {
         let Some(this_type) = &self.abi.this_type else {
             return Err(Error::simple("Missing this type"));
@@ -267,37 +507,29 @@ impl Inputs {
 
         for x in &self.abi.other_records {
             let records = self.other_records.get(&x.contract);
-            let struct_ = self
-                .abi
-                .other_contract_types
-                .iter()
-                .find_map(|t| match t {
-                    Type::Struct(s) if s.name == x.contract => Some(s),
-                    _ => None,
-                })
-                .unwrap();
+            let struct_ = self.other_contract_type(&x.contract)?;
 
             let mut contract_records = Vec::new();
             for (record, salts) in records.iter().flat_map(|r| r.iter()) {
                 let record = json_to_this_value(record, &Type::Struct(struct_.clone()))?;
 
-                contract_records.push((
-                    struct_
-                        .fields
-                        .iter()
-                        .find_map(|(k, t)| if k == "id" { Some(t.clone()) } else { None })
-                        .unwrap(),
-                    match &record {
-                        Value::StructValue(fields) => fields
-                            .iter()
-                            .find_map(|(k, v)| if k == "id" { Some(v) } else { None })
-                            .unwrap()
-                            .clone(),
-                        _ => unreachable!(),
-                    },
-                    record,
-                    salts.clone(),
-                ));
+                let id_type = struct_
+                    .fields
+                    .iter()
+                    .find_map(|(k, t)| if k == "id" { Some(t.clone()) } else { None })
+                    .not_found("id field of contract", &x.contract)?;
+                let Value::StructValue(fields) = &record else {
+                    return Err(Error::simple(format!(
+                        "record of contract {} is not a struct",
+                        x.contract
+                    )));
+                };
+                let id = fields
+                    .iter()
+                    .find_map(|(k, v)| if k == "id" { Some(v.clone()) } else { None })
+                    .not_found("id of a record of contract", &x.contract)?;
+
+                contract_records.push((id_type, id, record, salts.clone()));
             }
 
             result.insert(x.contract.clone(), contract_records);
@@ -331,10 +563,30 @@ impl Inputs {
         Ok(result)
     }
 
-    fn advice_provider(
-        &self,
-        other_records: &OtherRecordsType,
-    ) -> Result<miden::MemAdviceProvider> {
+    /// The number of values the program will read from the advice tape, without running it.
+    /// `this` and other records are passed in the advice map, and aren't counted.
+    pub fn advice_tape_len(&self) -> Result<usize> {
+        let ctx_public_key_len = Value::Nullable(
+            self.ctx_public_key
+                .clone()
+                .map(|pk| Box::new(Value::PublicKey(pk))),
+        )
+        .serialized_len();
+        let salts_len = match &self.abi.this_type {
+            Some(Type::Struct(this_struct)) => this_struct.fields.len(),
+            _ => 0,
+        };
+        let args_len = self
+            .parse_args()?
+            .iter()
+            .map(Value::serialized_len)
+            .sum::<usize>();
+
+        Ok(ctx_public_key_len + salts_len + args_len)
+    }
+
+    /// The context public key, the salts of `this` and the arguments, in the order the program reads them.
+    fn advice_tape(&self) -> Result<Vec<u64>> {
         let mut advice_tape = vec![];
         advice_tape.extend(
             // This should probably be on the stack
@@ -352,10 +604,18 @@ impl Inputs {
             }
         }
 
-        for (i, t) in self.abi.param_types.iter().enumerate() {
-            advice_tape.extend_from_slice(&t.parse(&self.args[i])?.serialize());
+        for value in self.parse_args()? {
+            advice_tape.extend_from_slice(&value.serialize());
         }
 
+        Ok(advice_tape)
+    }
+
+    fn advice_provider(
+        &self,
+        other_records: &OtherRecordsType,
+    ) -> Result<miden::MemAdviceProvider> {
+        let advice_tape = self.advice_tape()?;
         let mut advice_map = Vec::<([u8; 32], _)>::new();
 
         let Value::StructValue(this_value) = self.this_value()? else {
@@ -454,12 +714,21 @@ impl Inputs {
 }
 
 pub fn prove(program: &Program, inputs: &Inputs) -> Result<Output> {
-    let (output, prove) = run(program, inputs)?;
+    prove_with_options(program, inputs, ProvingOptions::default())
+}
+
+/// Like [`prove`], but with `options` controlling the trade-off between proof size and security.
+pub fn prove_with_options(
+    program: &Program,
+    inputs: &Inputs,
+    options: ProvingOptions,
+) -> Result<Output> {
+    let (output, prove) = run_with_options(program, inputs, options)?;
     let proof = prove()?;
 
     Ok(Output {
         new_this: output.this(&inputs.abi)?,
-        new_hashes: output.hashes(),
+        new_hashes: output.hashes()?,
         proof: proof.0.to_bytes(),
         overflow_addrs: proof.1.overflow_addrs().to_vec(),
         stack: output.stack.clone(),
@@ -468,29 +737,114 @@ pub fn prove(program: &Program, inputs: &Inputs) -> Result<Output> {
     })
 }
 
+/// The output stack of a program, decoded according to its ABI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputStack {
+    pub self_destructed: bool,
+    pub field_hashes: Vec<[u64; 4]>,
+    pub result_hash: Option<[u64; 4]>,
+    pub read_auth: bool,
+    pub read_fields: HashMap<String, bool>,
+}
+
+/// A snapshot of the VM after one cycle, recorded by [`run_with_trace`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    pub clk: u32,
+    /// The operation executed in this cycle, if any.
+    pub op: Option<String>,
+    /// The top 16 elements of the stack after the operation.
+    pub stack_top: Vec<u64>,
+}
+
+/// Execution reaching an [`abi::Breakpoint`], recorded by [`run_with_trace`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BreakpointHit {
+    pub line: usize,
+    /// The cycle the statement started at.
+    pub start_clk: u32,
+    /// The cycle after the statement finished, `None` if the execution stopped before that.
+    pub end_clk: Option<u32>,
+}
+
 #[derive(Debug)]
 pub struct RunOutput {
     abi: Abi,
+    /// The `this` the program was run with, for the fields it neither loads nor writes.
+    old_this: Value,
     memory: HashMap<u64, [u64; 4]>,
     pub cycle_count: u32,
     pub stack: Vec<u64>,
     pub input_stack: Vec<u64>,
     pub stack_inputs: StackInputs,
+    /// The last cycles of the execution, only recorded by [`run_with_trace`].
+    pub trace: Option<VecDeque<TraceStep>>,
+    /// Every time a breakpoint was reached, in order. Only recorded by [`run_with_trace`].
+    pub breakpoint_hits: Vec<BreakpointHit>,
+}
+
+/// Where each part of a program's output stack starts, see [`RunOutput::layout`].
+struct OutputLayout {
+    self_destructed: usize,
+    field_hashes: usize,
+    /// Only present if the function returns a value.
+    result_hash: Option<usize>,
+    /// Only present for `.readAuth` programs, which never return a value.
+    /// The read field results follow it.
+    read_auth: Option<usize>,
 }
 
 impl RunOutput {
-    pub fn hashes(&self) -> Vec<[u64; 4]> {
-        let mut hashes = Vec::new();
+    /// The output stack is laid out as:
+    /// [self_destructed, ...dependent field hashes, ...result hash or (read_auth, ...read field results)]
+    fn layout(&self) -> OutputLayout {
+        let self_destructed = 0;
+        let field_hashes = self_destructed + 1;
+        let after_hashes = field_hashes + self.abi.dependent_fields.len() * 4;
+        let returns = self.abi.result_type.is_some();
+
+        OutputLayout {
+            self_destructed,
+            field_hashes,
+            result_hash: returns.then_some(after_hashes),
+            read_auth: (!returns).then_some(after_hashes),
+        }
+    }
 
-        let hashes_offset = 1;
-        for (i, _) in self.abi.dependent_fields.iter().enumerate() {
-            let offset = hashes_offset + i * 4;
-            let field_hash = &self.stack[offset..offset + 4];
+    fn stack_at(&self, offset: usize) -> Result<u64> {
+        self.stack.get(offset).copied().ok_or_else(|| {
+            Error::simple(format!(
+                "Output stack is too short: expected an element at {offset}, got {} elements",
+                self.stack.len()
+            ))
+        })
+    }
 
-            hashes.push(field_hash.try_into().unwrap());
-        }
+    fn word_at(&self, offset: usize) -> Result<[u64; 4]> {
+        Ok([
+            self.stack_at(offset)?,
+            self.stack_at(offset + 1)?,
+            self.stack_at(offset + 2)?,
+            self.stack_at(offset + 3)?,
+        ])
+    }
+
+    /// Decodes the whole output stack at once.
+    pub fn stack_typed(&self) -> Result<OutputStack> {
+        Ok(OutputStack {
+            self_destructed: self.self_destructed()?,
+            field_hashes: self.hashes()?,
+            result_hash: self.result_hash(&self.abi)?,
+            read_auth: self.read_auth()?,
+            read_fields: self.read_fields()?,
+        })
+    }
 
-        hashes
+    pub fn hashes(&self) -> Result<Vec<[u64; 4]>> {
+        let offset = self.layout().field_hashes;
+        (0..self.abi.dependent_fields.len())
+            .map(|i| self.word_at(offset + i * 4))
+            .collect()
     }
 
     pub fn logs(&self) -> Vec<String> {
@@ -538,7 +892,7 @@ impl RunOutput {
             return Err(Error::simple("Missing this addr"));
         };
 
-        this_type.read(
+        let this = this_type.read(
             &|addr| {
                 Some(
                     self.memory
@@ -548,7 +902,32 @@ impl RunOutput {
                 )
             },
             this_addr as u64,
-        )
+        )?;
+
+        // Only the dependent fields are loaded into memory, the others read back as default values
+        // unless the function wrote them, so they keep their old values.
+        let Some(written_fields) = &abi.written_fields else {
+            return Ok(this);
+        };
+        let (Value::StructValue(fields), Value::StructValue(old_fields)) = (this, &self.old_this)
+        else {
+            return Err(Error::simple("This value is not a struct"));
+        };
+
+        Ok(Value::StructValue(
+            fields
+                .into_iter()
+                .map(|(name, value)| {
+                    let untouched = !abi.dependent_fields.iter().any(|(f, _)| *f == name)
+                        && !written_fields.contains(&name);
+                    let old_value = old_fields.iter().find(|(f, _)| *f == name);
+                    match old_value {
+                        Some((_, old_value)) if untouched => (name, old_value.clone()),
+                        _ => (name, value),
+                    }
+                })
+                .collect(),
+        ))
     }
 
     pub fn result(&self, abi: &Abi) -> Result<Value> {
@@ -573,22 +952,91 @@ impl RunOutput {
         )
     }
 
-    pub fn result_hash(&self, abi: &Abi) -> Option<[u64; 4]> {
-        abi.result_type.as_ref()?;
+    /// The fields of [`Self::this`] whose values differ from `old`, the `this` the program was run with.
+    pub fn this_diff(&self, abi: &Abi, old: &Value) -> Result<Vec<(String, Value)>> {
+        let Value::StructValue(new_fields) = self.this(abi)? else {
+            return Err(Error::simple("This value is not a struct"));
+        };
+        let Value::StructValue(old_fields) = old else {
+            return Err(Error::simple("Old this value is not a struct"));
+        };
 
-        let offset = self.abi.dependent_fields.len() * 4 + 1; // + 1 for self_destructed
-        let result_hash = [
-            self.stack[offset],
-            self.stack[offset + 1],
-            self.stack[offset + 2],
-            self.stack[offset + 3],
-        ];
+        Ok(changed_fields(new_fields, old_fields))
+    }
 
-        Some(result_hash)
+    /// [`Self::this`] as the caller of a `.readAuth` program is allowed to see it,
+    /// with every field wrapped in a nullable, see [`Self::redacted_this_type`].
+    /// If the caller isn't authorized to read the record, according to [`Self::read_auth`], every field is null.
+    /// Otherwise only the `@read` fields that don't authorize the caller, according to [`Self::read_fields`], are.
+    pub fn this_redacted(&self, abi: &Abi) -> Result<Value> {
+        let Value::StructValue(fields) = self.this(abi)? else {
+            return Err(Error::simple("This value is not a struct"));
+        };
+        let read_auth = self.read_auth()?;
+        let read_fields = self.read_fields()?;
+
+        Ok(Value::StructValue(
+            fields
+                .into_iter()
+                .map(|(name, value)| {
+                    let value = match value {
+                        _ if !read_auth || read_fields.get(&name) == Some(&false) => {
+                            Value::Nullable(None)
+                        }
+                        Value::Nullable(_) => value,
+                        _ => Value::Nullable(Some(Box::new(value))),
+                    };
+                    (name, value)
+                })
+                .collect(),
+        ))
+    }
+
+    /// The type of [`Self::this_redacted`]: the `this` struct, with the fields that aren't nullable made nullable.
+    pub fn redacted_this_type(abi: &Abi) -> Result<Type> {
+        let Some(Type::Struct(this_struct)) = &abi.this_type else {
+            return Err(Error::simple("This type is not a struct"));
+        };
+
+        Ok(Type::Struct(abi::Struct {
+            name: this_struct.name.clone(),
+            fields: this_struct
+                .fields
+                .iter()
+                .map(|(name, type_)| {
+                    let type_ = match type_ {
+                        Type::Nullable(_) => type_.clone(),
+                        _ => Type::Nullable(Box::new(type_.clone())),
+                    };
+                    (name.clone(), type_)
+                })
+                .collect(),
+        }))
+    }
+
+    /// [`Self::this`] converted to JSON.
+    pub fn this_json(&self, abi: &Abi) -> Result<serde_json::Value> {
+        self.this(abi)?.try_into()
+    }
+
+    /// [`Self::result`] converted to JSON.
+    pub fn result_json(&self, abi: &Abi) -> Result<serde_json::Value> {
+        self.result(abi)?.try_into()
+    }
+
+    pub fn result_hash(&self, abi: &Abi) -> Result<Option<[u64; 4]>> {
+        if abi.result_type.is_none() {
+            return Ok(None);
+        }
+        let Some(offset) = self.layout().result_hash else {
+            return Ok(None);
+        };
+
+        self.word_at(offset).map(Some)
     }
 
     pub fn self_destructed(&self) -> Result<bool> {
-        let self_destructed = self.stack[0];
+        let self_destructed = self.stack_at(self.layout().self_destructed)?;
         if self_destructed == 0 {
             Ok(false)
         } else if self_destructed == 1 {
@@ -601,10 +1049,56 @@ impl RunOutput {
         }
     }
 
-    pub fn read_auth(&self) -> bool {
-        let offset = self.abi.dependent_fields.len() * 4 + 1; // + 1 for self_destructed
-        self.stack[offset] == 1
+    /// Whether the caller is authorized to read the record.
+    /// Always false unless this is a `.readAuth` program.
+    pub fn read_auth(&self) -> Result<bool> {
+        match self.layout().read_auth {
+            Some(offset) => Ok(self.stack_at(offset)? == 1),
+            None => Ok(false),
+        }
     }
+
+    /// Whether each `@read` field on its own authorizes the caller, by field name.
+    /// Empty unless this is a `.readAuth` program.
+    pub fn read_fields(&self) -> Result<HashMap<String, bool>> {
+        let Some(read_auth_offset) = self.layout().read_auth else {
+            return Ok(HashMap::new());
+        };
+        let offset = read_auth_offset + 1;
+        self.abi
+            .read_fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| Ok((field.clone(), self.stack_at(offset + i)? == 1)))
+            .collect()
+    }
+}
+
+/// The fields of `new_fields` that are missing from `old_fields` or have a different value there.
+/// Values are compared with [`Value::structural_eq`], so an unchanged `NaN` or map isn't reported.
+fn changed_fields(
+    new_fields: Vec<(String, Value)>,
+    old_fields: &[(String, Value)],
+) -> Vec<(String, Value)> {
+    new_fields
+        .into_iter()
+        .filter(|(name, value)| {
+            !old_fields
+                .iter()
+                .find(|(old_name, _)| old_name == name)
+                .is_some_and(|(_, old_value)| old_value.structural_eq(value))
+        })
+        .collect()
+}
+
+/// Runs the program without generating a proof.
+///
+/// This is the fast path for tests and CI: it only executes the program
+/// and never builds a prover.
+pub fn execute(program: &Program, inputs: &Inputs) -> Result<RunOutput> {
+    let (output, _) = run(program, inputs)?;
+
+    Ok(output)
 }
 
 pub fn run<'a>(
@@ -614,18 +1108,143 @@ pub fn run<'a>(
     RunOutput,
     impl FnOnce() -> Result<(ExecutionProof, StackOutputs)> + 'a,
 )> {
+    run_with_options(program, inputs, ProvingOptions::default())
+}
+
+/// Like [`run`], but the returned closure proves with `options`.
+pub fn run_with_options<'a>(
+    program: &'a Program,
+    inputs: &Inputs,
+    options: ProvingOptions,
+) -> Result<(
+    RunOutput,
+    impl FnOnce() -> Result<(ExecutionProof, StackOutputs)> + 'a,
+)> {
+    run_inner(program, inputs, options, None)
+}
+
+/// Like [`run_with_options`], but also records the last `capacity` cycles in
+/// [`RunOutput::trace`], so that the execution can be stepped through, and the breakpoints
+/// that were reached in [`RunOutput::breakpoint_hits`].
+/// Snapshots are only taken here, [`run`] doesn't pay for them.
+pub fn run_with_trace<'a>(
+    program: &'a Program,
+    inputs: &Inputs,
+    options: ProvingOptions,
+    capacity: usize,
+) -> Result<(
+    RunOutput,
+    impl FnOnce() -> Result<(ExecutionProof, StackOutputs)> + 'a,
+)> {
+    run_inner(program, inputs, options, Some(capacity))
+}
+
+/// Records the breakpoint hits for an operation of the procedure `context` run at `clk`.
+///
+/// `running` holds the procedures of the breakpoints whose statements are running,
+/// innermost last, with the index of their hit in `hits`.
+/// Operations of library procedures, like `std::math::u64::checked_add`,
+/// run inside the procedure that called them.
+fn track_breakpoints<'b>(
+    breakpoints: &'b [abi::Breakpoint],
+    context: &str,
+    clk: u32,
+    hits: &mut Vec<BreakpointHit>,
+    running: &mut Vec<(&'b str, usize)>,
+) {
+    if context.starts_with("std::") {
+        return;
+    }
+
+    // Local procedures can be named with the path of their module.
+    let is_procedure = |procedure: &str| {
+        context
+            .strip_suffix(procedure)
+            .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with("::"))
+    };
+
+    let position = running
+        .iter()
+        .rposition(|(procedure, _)| is_procedure(procedure));
+    let breakpoint = breakpoints.iter().find(|b| is_procedure(&b.procedure));
+    let still_running = match (position, breakpoint) {
+        // Back in a running statement, the ones nested in it have finished.
+        (Some(i), _) => i + 1,
+        // A statement nested in the running ones is starting.
+        (None, Some(_)) => running.len(),
+        // The code outside of every breakpoint is running.
+        (None, None) => 0,
+    };
+    for (_, hit) in running.drain(still_running..) {
+        hits[hit].end_clk = Some(clk);
+    }
+
+    if let (None, Some(breakpoint)) = (position, breakpoint) {
+        running.push((breakpoint.procedure.as_str(), hits.len()));
+        hits.push(BreakpointHit {
+            line: breakpoint.line,
+            start_clk: clk,
+            end_clk: None,
+        });
+    }
+}
+
+fn run_inner<'a>(
+    program: &'a Program,
+    inputs: &Inputs,
+    options: ProvingOptions,
+    trace_capacity: Option<usize>,
+) -> Result<(
+    RunOutput,
+    impl FnOnce() -> Result<(ExecutionProof, StackOutputs)> + 'a,
+)> {
+    inputs.validate()?;
+
     let other_records = inputs.other_records()?;
     let input_stack = inputs.stack(&other_records)?;
     let advice_tape = inputs.advice_provider(&other_records)?;
 
     let mut last_ok_state = None;
     let mut err = None;
+    let mut trace = trace_capacity.map(VecDeque::with_capacity);
+    let mut breakpoint_hits = Vec::<BreakpointHit>::new();
+    let mut running_breakpoints = vec![];
 
     let host = miden::DefaultHost::new(advice_tape.clone());
 
     for state in miden_processor::execute_iter(program, input_stack.clone(), host) {
         match state {
             Ok(state) => {
+                if let (Some(trace), Some(capacity)) = (trace.as_mut(), trace_capacity) {
+                    // Operations only know the procedure they belong to
+                    // in programs assembled in debug mode, see `compile_program`.
+                    if let Some(asmop) = &state.asmop {
+                        track_breakpoints(
+                            &inputs.abi.breakpoints,
+                            asmop.context_name(),
+                            state.clk,
+                            &mut breakpoint_hits,
+                            &mut running_breakpoints,
+                        );
+                    }
+
+                    if capacity > 0 {
+                        if trace.len() == capacity {
+                            trace.pop_front();
+                        }
+                        trace.push_back(TraceStep {
+                            clk: state.clk,
+                            op: state.op.map(|op| op.to_string()),
+                            stack_top: state
+                                .stack
+                                .iter()
+                                .take(16)
+                                .map(|x| mont_red_cst(x.inner() as _))
+                                .collect(),
+                        });
+                    }
+                }
+
                 last_ok_state = Some(state);
             }
             Err(e) => {
@@ -701,25 +1320,42 @@ pub fn run<'a>(
     Ok((
         RunOutput {
             abi: inputs.abi.clone(),
+            old_this: inputs.this_value()?,
             stack: output_stack,
             cycle_count: last_ok_state.clk,
             input_stack: input_stack_values,
             stack_inputs: input_stack.clone(),
             memory,
+            trace,
+            breakpoint_hits,
         },
         move || {
             let host = miden::DefaultHost::new(advice_tape);
 
-            let (stack_outputs, proof) =
-                miden_prover::prove(program, input_stack, host, ProvingOptions::default())
-                    .map_err(MidenError::Execution)
-                    .wrap_err()?;
+            let (stack_outputs, proof) = miden_prover::prove(program, input_stack, host, options)
+                .map_err(MidenError::Execution)
+                .wrap_err()?;
 
             Ok((proof, stack_outputs))
         },
     ))
 }
 
+/// Checks that `proof` proves that running the program described by `program_info`
+/// on `stack_inputs` produces `stack_outputs`.
+pub fn verify(
+    program_info: ProgramInfo,
+    stack_inputs: StackInputs,
+    stack_outputs: StackOutputs,
+    proof: ExecutionProof,
+) -> Result<()> {
+    miden::verify(program_info, stack_inputs, stack_outputs, proof)
+        .map_err(MidenError::Verification)
+        .wrap_err()?;
+
+    Ok(())
+}
+
 pub trait ProgramExt {
     fn to_program_info_bytes(self) -> Vec<u8>;
 }
@@ -729,3 +1365,710 @@ impl ProgramExt for Program {
         ProgramInfo::from(self).to_bytes()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use abi::{PrimitiveType, RecordHashes, Struct};
+    use expect_test::expect;
+
+    fn inputs_with_other_records(
+        other_contract_types: Vec<Type>,
+        other_records: HashMap<String, Vec<(serde_json::Value, Vec<u32>)>>,
+    ) -> Inputs {
+        Inputs::new(
+            Abi {
+                other_records: vec![RecordHashes {
+                    contract: "User".to_owned(),
+                }],
+                other_contract_types,
+                ..Default::default()
+            },
+            None,
+            vec![],
+            serde_json::json!({}),
+            vec![],
+            other_records,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn other_records_of_unknown_contract() {
+        let inputs = inputs_with_other_records(
+            vec![],
+            HashMap::from([(
+                "User".to_owned(),
+                vec![(serde_json::json!({ "id": "u1" }), vec![0])],
+            )]),
+        );
+
+        assert_eq!(
+            inputs.other_records().unwrap_err().to_string(),
+            "type of contract User not found"
+        );
+        assert_eq!(
+            inputs
+                .stack_values(&HashMap::new())
+                .unwrap_err()
+                .to_string(),
+            "records of contract User not found"
+        );
+    }
+
+    #[test]
+    fn other_records_without_id() {
+        let user = Type::Struct(Struct {
+            name: "User".to_owned(),
+            fields: vec![("name".to_owned(), Type::String)],
+        });
+        let inputs = inputs_with_other_records(
+            vec![user],
+            HashMap::from([(
+                "User".to_owned(),
+                vec![(serde_json::json!({ "name": "a" }), vec![0])],
+            )]),
+        );
+
+        assert_eq!(
+            inputs.other_records().unwrap_err().to_string(),
+            "id field of contract User not found"
+        );
+    }
+
+    fn inputs_with_args(args: Vec<serde_json::Value>) -> Inputs {
+        Inputs::new(
+            Abi {
+                param_types: vec![Type::PrimitiveType(PrimitiveType::UInt32), Type::String],
+                ..Default::default()
+            },
+            None,
+            vec![],
+            serde_json::json!({}),
+            args,
+            HashMap::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn validate_args() {
+        inputs_with_args(vec![serde_json::json!(1), serde_json::json!("a")])
+            .validate()
+            .unwrap();
+    }
+
+    #[test]
+    fn validate_args_wrong_arity() {
+        assert_eq!(
+            inputs_with_args(vec![serde_json::json!(1)])
+                .validate()
+                .unwrap_err()
+                .to_string(),
+            "incorrect number of arguments 1 but expected 2"
+        );
+    }
+
+    #[test]
+    fn validate_args_type_mismatch() {
+        let err = inputs_with_args(vec![serde_json::json!(1), serde_json::json!(2)])
+            .validate()
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.starts_with("argument 1 >> "), "{err}");
+    }
+
+    #[test]
+    fn validate_args_names_the_parameter() {
+        let mut inputs = inputs_with_args(vec![serde_json::json!("a"), serde_json::json!("b")]);
+        inputs.abi.param_names = vec!["amount".to_owned(), "memo".to_owned()];
+
+        let err = inputs.validate().unwrap_err().to_string();
+        assert!(
+            err.starts_with("invalid value for parameter 'amount' >> "),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn validate_args_variadic_without_parameters() {
+        let mut inputs = inputs_with_args(vec![]);
+        inputs.abi.param_types = vec![];
+        inputs.abi.variadic = true;
+
+        assert_eq!(
+            inputs.validate().unwrap_err().to_string(),
+            "variadic function without a rest parameter"
+        );
+    }
+
+    // These pin the hashes that records are committed to.
+    // If the scheme changes, the hashes of existing records no longer match,
+    // so they should only change together with a migration.
+    // The digests are the ones the integration tests observe for the same values.
+
+    fn u32_type() -> Type {
+        Type::PrimitiveType(PrimitiveType::UInt32)
+    }
+
+    #[test]
+    fn hash_this_salted_u32() {
+        expect![[r#"
+            [
+                10272219061387384304,
+                13401779264242975131,
+                10013658661959349609,
+                9575923678792186484,
+            ]
+        "#]]
+        .assert_debug_eq(&hash_this(u32_type(), &Value::UInt32(10), Some(&[0])).unwrap());
+    }
+
+    #[test]
+    fn hash_this_salted_string() {
+        expect![[r#"
+            [
+                13624894021325080686,
+                17508316994659426056,
+                2620902411312551361,
+                8347809021004383550,
+            ]
+        "#]]
+        .assert_debug_eq(
+            &hash_this(Type::String, &Value::String("John".to_owned()), Some(&[0])).unwrap(),
+        );
+    }
+
+    #[test]
+    fn hash_this_unsalted_string() {
+        expect![[r#"
+            [
+                3850899504691128854,
+                5609950875147406075,
+                16200726738043745980,
+                18156263845020900466,
+            ]
+        "#]]
+        .assert_debug_eq(
+            &hash_this(Type::String, &Value::String("John".to_owned()), None).unwrap(),
+        );
+    }
+
+    fn account_type() -> Type {
+        Type::Struct(Struct {
+            name: "Account".to_owned(),
+            fields: vec![
+                ("id".to_owned(), Type::String),
+                ("balance".to_owned(), u32_type()),
+            ],
+        })
+    }
+
+    fn account() -> Value {
+        Value::StructValue(vec![
+            ("id".to_owned(), Value::String("id1".to_owned())),
+            ("balance".to_owned(), Value::UInt32(100)),
+        ])
+    }
+
+    #[test]
+    fn hash_this_struct_salts_are_per_field() {
+        let hash = hash_this(account_type(), &account(), Some(&[1, 2])).unwrap();
+
+        assert_ne!(
+            hash,
+            hash_this(account_type(), &account(), Some(&[2, 1])).unwrap()
+        );
+        assert_ne!(
+            hash,
+            hash_this(account_type(), &account(), Some(&[1, 3])).unwrap()
+        );
+    }
+
+    // A program keeps a map's entries in the order of the input and appends new keys,
+    // `hash_this` hashes the same entries in the same order.
+    #[test]
+    fn program_hashes_maps_in_entry_order() {
+        let code = r#"
+            contract Account {
+                id: string;
+                counts: map<string, u32>;
+
+                set(key: string, value: u32) {
+                    this.counts[key] = value;
+                }
+            }
+        "#;
+        let program = polylang::parse_program(code).unwrap();
+        let (miden_code, abi) = compiler::compile(program, Some("Account"), "set").unwrap();
+        let program = compile_program(&abi, &miden_code).unwrap();
+        let counts_type = Type::Map(
+            Box::new(Type::String),
+            Box::new(Type::PrimitiveType(PrimitiveType::UInt32)),
+        );
+        let counts_index = abi
+            .dependent_fields
+            .iter()
+            .position(|(name, _)| name == "counts")
+            .unwrap();
+
+        let program_hash = |key: &str| {
+            let inputs = Inputs::new(
+                abi.clone(),
+                None,
+                vec![0, 0],
+                serde_json::json!({ "id": "", "counts": { "b": 2 } }),
+                vec![serde_json::json!(key), serde_json::json!(1)],
+                HashMap::new(),
+            )
+            .unwrap();
+            execute(&program, &inputs).unwrap().hashes().unwrap()[counts_index]
+        };
+        let host_hash = |entries: &[(&str, u32)]| {
+            let map = Value::Map(
+                entries
+                    .iter()
+                    .map(|(k, v)| (Value::String((*k).to_owned()), Value::UInt32(*v)))
+                    .collect(),
+            );
+            hash_this(counts_type.clone(), &map, Some(&[0])).unwrap()
+        };
+
+        assert_eq!(program_hash("c"), host_hash(&[("b", 2), ("c", 1)]));
+        assert_eq!(program_hash("a"), host_hash(&[("b", 2), ("a", 1)]));
+    }
+
+    /// The compiled `deposit` function of an `Account` contract,
+    /// with inputs that deposit 2 into a balance of 1.
+    fn account_deposit_inputs() -> (Program, Inputs) {
+        let code = r#"
+            contract Account {
+                id: string;
+                balance: u32;
+
+                deposit(amount: u32) {
+                    this.balance = this.balance + amount;
+                }
+            }
+        "#;
+        let program = polylang::parse_program(code).unwrap();
+        let (miden_code, abi) = compiler::compile(program, Some("Account"), "deposit").unwrap();
+        let program = compile_program(&abi, &miden_code).unwrap();
+        let inputs = Inputs::new(
+            abi,
+            None,
+            vec![0, 0],
+            serde_json::json!({ "id": "a", "balance": 1 }),
+            vec![serde_json::json!(2)],
+            HashMap::new(),
+        )
+        .unwrap();
+
+        (program, inputs)
+    }
+
+    #[test]
+    fn prove_with_128_bit_security() {
+        let (program, inputs) = account_deposit_inputs();
+
+        let verify = |options| {
+            let (output, prove) = run_with_options(&program, &inputs, options).unwrap();
+            let (proof, stack_outputs) = prove().unwrap();
+
+            miden::verify(
+                ProgramInfo::from(program.clone()),
+                output.stack_inputs,
+                stack_outputs,
+                proof,
+            )
+            .unwrap()
+        };
+
+        let default_security = verify(ProvingOptions::default());
+        let high_security = verify(ProvingOptions::with_128_bit_security(false));
+        assert!(
+            high_security > default_security,
+            "{high_security} <= {default_security}"
+        );
+    }
+
+    #[test]
+    fn run_with_trace_proves_with_options() {
+        let (program, inputs) = account_deposit_inputs();
+
+        let security = |traced: bool| {
+            let options = ProvingOptions::with_128_bit_security(false);
+            let (output, (proof, stack_outputs)) = if traced {
+                let (output, prove) = run_with_trace(&program, &inputs, options, 0).unwrap();
+                (output, prove().unwrap())
+            } else {
+                let (output, prove) = run_with_options(&program, &inputs, options).unwrap();
+                (output, prove().unwrap())
+            };
+
+            miden::verify(
+                ProgramInfo::from(program.clone()),
+                output.stack_inputs,
+                stack_outputs,
+                proof,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(security(true), security(false));
+    }
+
+    #[test]
+    fn output_stack_too_short() {
+        let abi = Abi {
+            result_type: Some(Type::PrimitiveType(PrimitiveType::UInt32)),
+            dependent_fields: vec![(
+                "balance".to_owned(),
+                Type::PrimitiveType(PrimitiveType::UInt32),
+            )],
+            ..Default::default()
+        };
+        let output = RunOutput {
+            abi: abi.clone(),
+            old_this: Value::StructValue(vec![]),
+            memory: HashMap::new(),
+            cycle_count: 0,
+            // self_destructed and the field hash, but no result hash
+            stack: vec![0, 1, 2, 3, 4],
+            input_stack: vec![],
+            stack_inputs: StackInputs::default(),
+            trace: None,
+            breakpoint_hits: vec![],
+        };
+
+        assert!(!output.self_destructed().unwrap());
+        assert_eq!(output.hashes().unwrap(), vec![[1, 2, 3, 4]]);
+        assert!(output.result_hash(&abi).is_err());
+        assert!(output.stack_typed().is_err());
+        assert!(!output.read_auth().unwrap());
+    }
+
+    #[test]
+    fn this_diff_has_only_changed_fields() {
+        let (program, inputs) = account_deposit_inputs();
+
+        let output = execute(&program, &inputs).unwrap();
+        let old = inputs.this_value().unwrap();
+
+        assert_eq!(
+            output.this_diff(&inputs.abi, &old).unwrap(),
+            vec![("balance".to_owned(), Value::UInt32(3))]
+        );
+    }
+
+    #[test]
+    fn changed_fields_compares_structurally() {
+        let old = vec![
+            ("nan".to_owned(), Value::Float32(f32::NAN)),
+            (
+                "map".to_owned(),
+                Value::Map(vec![
+                    (Value::String("a".to_owned()), Value::UInt32(1)),
+                    (Value::String("b".to_owned()), Value::UInt32(2)),
+                ]),
+            ),
+            ("zero".to_owned(), Value::Float32(0.0)),
+        ];
+        let new = vec![
+            ("nan".to_owned(), Value::Float32(f32::NAN)),
+            (
+                "map".to_owned(),
+                Value::Map(vec![
+                    (Value::String("b".to_owned()), Value::UInt32(2)),
+                    (Value::String("a".to_owned()), Value::UInt32(1)),
+                ]),
+            ),
+            ("zero".to_owned(), Value::Float32(-0.0)),
+            ("added".to_owned(), Value::Boolean(true)),
+        ];
+
+        assert_eq!(
+            changed_fields(new, &old),
+            vec![
+                ("zero".to_owned(), Value::Float32(-0.0)),
+                ("added".to_owned(), Value::Boolean(true)),
+            ]
+        );
+    }
+
+    #[test]
+    fn run_with_trace_keeps_last_cycles() {
+        let (program, inputs) = account_deposit_inputs();
+
+        let (untraced, _) = run(&program, &inputs).unwrap();
+        let (traced, _) = run_with_trace(&program, &inputs, ProvingOptions::default(), 8).unwrap();
+        assert!(untraced.trace.is_none());
+
+        let trace = traced.trace.as_ref().unwrap();
+        assert_eq!(trace.len(), 8);
+        assert!(trace
+            .iter()
+            .zip(trace.iter().skip(1))
+            .all(|(a, b)| a.clk + 1 == b.clk));
+
+        let last = trace.back().unwrap();
+        assert_eq!(last.clk, untraced.cycle_count);
+        assert_eq!(last.stack_top, untraced.stack[..16]);
+        assert_eq!(traced.stack, untraced.stack);
+        assert_eq!(traced.cycle_count, untraced.cycle_count);
+    }
+
+    #[test]
+    fn run_with_trace_reports_breakpoints() {
+        let code = "contract Account {
+    id: string;
+    balance: u32;
+
+    deposit(amount: u32, step: u32) {
+        for (let i: u32 = 0; i < amount; i++) {
+            // @breakpoint
+            this.balance = this.balance + step;
+        }
+    }
+}
+";
+        let program = polylang::parse_program(code).unwrap();
+        let output = compiler::compile_with_options(
+            program,
+            Some("Account"),
+            "deposit",
+            compiler::CompileOptions {
+                source: Some(code),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let (miden_code, abi) = (output.miden_code, output.abi);
+        assert_eq!(
+            abi.breakpoints.iter().map(|b| b.line).collect::<Vec<_>>(),
+            vec![8]
+        );
+
+        let program = compile_program(&abi, &miden_code).unwrap();
+        let inputs = Inputs::new(
+            abi,
+            None,
+            vec![0, 0],
+            serde_json::json!({ "id": "a", "balance": 0 }),
+            vec![serde_json::json!(3), serde_json::json!(2)],
+            HashMap::new(),
+        )
+        .unwrap();
+
+        let (untraced, _) = run(&program, &inputs).unwrap();
+        assert!(untraced.breakpoint_hits.is_empty());
+
+        let (traced, _) = run_with_trace(&program, &inputs, ProvingOptions::default(), 0).unwrap();
+        assert_eq!(traced.breakpoint_hits.len(), 3);
+        let mut previous_end = 0;
+        for hit in &traced.breakpoint_hits {
+            assert_eq!(hit.line, 8);
+            let end = hit.end_clk.unwrap();
+            assert!(
+                previous_end < hit.start_clk && hit.start_clk < end,
+                "{hit:?}"
+            );
+            previous_end = end;
+        }
+        assert!(previous_end < traced.cycle_count);
+        assert_eq!(traced.stack, untraced.stack);
+    }
+
+    #[test]
+    fn track_breakpoints_nests_statements() {
+        let breakpoints = [
+            abi::Breakpoint {
+                line: 2,
+                procedure: "breakpoint_0".to_owned(),
+            },
+            abi::Breakpoint {
+                line: 3,
+                procedure: "breakpoint_1".to_owned(),
+            },
+        ];
+
+        let mut hits = vec![];
+        let mut running = vec![];
+        for (clk, context) in [
+            "#main",
+            "breakpoint_0",
+            "std::math::u64::checked_add",
+            "breakpoint_1",
+            "breakpoint_0",
+            "#main",
+            "breakpoint_0",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            track_breakpoints(&breakpoints, context, clk as u32, &mut hits, &mut running);
+        }
+
+        let hit = |line, start_clk, end_clk| BreakpointHit {
+            line,
+            start_clk,
+            end_clk,
+        };
+        assert_eq!(
+            hits,
+            vec![hit(2, 1, Some(5)), hit(3, 3, Some(4)), hit(2, 6, None)]
+        );
+    }
+
+    #[test]
+    fn advice_tape_len_without_running() {
+        let code = r#"
+            contract Account {
+                id: string;
+                values: u32[];
+
+                setValues(values: u32[]) {
+                    this.values = values;
+                }
+            }
+        "#;
+        let program = polylang::parse_program(code).unwrap();
+        let (_, abi) = compiler::compile(program, Some("Account"), "setValues").unwrap();
+        let inputs = Inputs::new(
+            abi,
+            None,
+            vec![0, 0],
+            serde_json::json!({ "id": "a", "values": [1, 2] }),
+            vec![serde_json::json!([1, 2, 3])],
+            HashMap::new(),
+        )
+        .unwrap();
+
+        // No public key, two field salts, and the array length followed by its elements.
+        assert_eq!(inputs.advice_tape_len().unwrap(), 7);
+    }
+
+    #[test]
+    fn advice_tape_len_of_arguments() {
+        let code = r#"
+            contract Account {
+                id: string;
+                name: string;
+                tags: string[];
+                balances: map<string, u32>;
+
+                setName(name: string) {
+                    this.name = name;
+                }
+
+                setTags(tags: string[]) {
+                    this.tags = tags;
+                }
+
+                setBalances(balances: map<string, u32>, name: string) {
+                    this.balances = balances;
+                    this.name = name;
+                }
+            }
+        "#;
+        let this = serde_json::json!({ "id": "a", "name": "", "tags": [], "balances": {} });
+
+        for (function, args, expected_len) in [
+            // No public key, four field salts, then the arguments.
+            // The string length followed by its bytes.
+            ("setName", vec![serde_json::json!("abc")], 9),
+            (
+                "setTags",
+                vec![serde_json::json!(["x", "yz"])],
+                // The array length, then each string.
+                11,
+            ),
+            (
+                "setBalances",
+                vec![
+                    serde_json::json!({ "b": 2, "a": 1 }),
+                    serde_json::json!("n"),
+                ],
+                // The map's key and value array lengths, its entries, then the string.
+                15,
+            ),
+        ] {
+            let program = polylang::parse_program(code).unwrap();
+            let (_, abi) = compiler::compile(program, Some("Account"), function).unwrap();
+            let inputs =
+                Inputs::new(abi, None, vec![0; 4], this.clone(), args, HashMap::new()).unwrap();
+
+            assert_eq!(
+                inputs.advice_tape_len().unwrap(),
+                expected_len,
+                "{function}"
+            );
+        }
+    }
+
+    #[test]
+    fn verify_proof() {
+        let (program, inputs) = account_deposit_inputs();
+
+        let output = prove(&program, &inputs).unwrap();
+        let stack_outputs =
+            StackOutputs::new(output.stack.clone(), output.overflow_addrs.clone()).unwrap();
+        let proof = || ExecutionProof::from_bytes(&output.proof).unwrap();
+
+        verify(
+            ProgramInfo::from(program.clone()),
+            output.run_output.stack_inputs.clone(),
+            stack_outputs,
+            proof(),
+        )
+        .unwrap();
+
+        let mut tampered_stack = output.stack.clone();
+        tampered_stack[0] += 1;
+        let tampered_outputs =
+            StackOutputs::new(tampered_stack, output.overflow_addrs.clone()).unwrap();
+        assert!(verify(
+            ProgramInfo::from(program),
+            output.run_output.stack_inputs.clone(),
+            tampered_outputs,
+            proof(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn compile_program_cached_hits() {
+        let abi = Abi::default();
+        let cache = ProgramCache::new(1);
+
+        let first = compile_program_cached(&cache, &abi, "begin push.1 drop end").unwrap();
+        assert_eq!(cache.hits(), 0);
+
+        let second = compile_program_cached(&cache, &abi, "begin push.1 drop end").unwrap();
+        assert_eq!(cache.hits(), 1);
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // A different program evicts the first one, as the capacity is 1.
+        compile_program_cached(&cache, &abi, "begin push.2 drop end").unwrap();
+        let third = compile_program_cached(&cache, &abi, "begin push.1 drop end").unwrap();
+        assert_eq!(cache.hits(), 1);
+        assert!(!Arc::ptr_eq(&first, &third));
+    }
+
+    #[test]
+    fn compile_program_cached_compares_the_abi() {
+        let cache = ProgramCache::new(2);
+        let with_std_version = Abi {
+            std_version: Some(abi::StdVersion::V0_7_0),
+            ..Default::default()
+        };
+
+        compile_program_cached(&cache, &Abi::default(), "begin push.1 drop end").unwrap();
+        compile_program_cached(&cache, &with_std_version, "begin push.1 drop end").unwrap();
+        assert_eq!(cache.hits(), 0);
+
+        compile_program_cached(&cache, &with_std_version, "begin push.1 drop end").unwrap();
+        assert_eq!(cache.hits(), 1);
+    }
+}
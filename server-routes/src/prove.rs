@@ -1,10 +1,18 @@
 use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Instant;
 
 use base64::Engine;
 use error::prelude::*;
-use polylang_prover::{compile_program, Inputs, ProgramExt};
+use polylang_prover::{compile_program_cached, Inputs, ProgramCache, ProgramExt, ProvingOptions};
 use serde::Deserialize;
 
+/// Number of compiled programs kept around, so that proving the same function again
+/// doesn't need to compile it again.
+const PROGRAM_CACHE_CAPACITY: usize = 64;
+
+static PROGRAM_CACHE: OnceLock<ProgramCache> = OnceLock::new();
+
 type OtherRecordsType = HashMap<String, Vec<(serde_json::Value, Vec<u32>)>>;
 
 #[derive(Debug, Deserialize)]
@@ -17,25 +25,133 @@ pub struct ProveRequest {
     pub this_salts: Option<Vec<u32>>,
     pub args: Vec<serde_json::Value>,
     pub other_records: Option<OtherRecordsType>,
+    /// 96 (the default) or 128. Higher security makes the proof larger and slower to generate.
+    pub security_bits: Option<u32>,
 }
 
-pub async fn prove(mut req: ProveRequest) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-    let program = compile_program(&req.abi, &req.miden_code)?;
-
-    let has_this = req.abi.this_type.is_some();
-    let this = req.this.clone().unwrap_or(if has_this {
-        req.abi.default_this_value()?.try_into()?
-    } else {
-        serde_json::Value::Null
-    });
-
-    if !has_this {
-        req.abi.this_type = Some(abi::Type::Struct(abi::Struct {
-            name: "Empty".to_string(),
-            fields: Vec::new(),
-        }));
-        req.abi.this_addr = Some(0);
+/// Bounds on the size of a [`ProveRequest`], checked before anything is compiled.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// Maximum length of `miden_code`, in bytes.
+    pub max_code_len: usize,
+    pub max_args: usize,
+    /// Maximum nesting depth of `this`, the arguments and the other records.
+    pub max_value_depth: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_code_len: 1024 * 1024,
+            max_args: 256,
+            max_value_depth: 32,
+        }
+    }
+}
+
+/// Why a [`ProveRequest`] was rejected by [`ProveRequest::validate`].
+#[derive(Debug)]
+pub enum InvalidRequest {
+    TooLarge(String),
+    TooDeep(String),
+    UnsupportedSecurityLevel(u32),
+}
+
+impl std::fmt::Display for InvalidRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidRequest::TooLarge(message) | InvalidRequest::TooDeep(message) => {
+                write!(f, "{message}")
+            }
+            InvalidRequest::UnsupportedSecurityLevel(bits) => {
+                write!(f, "unsupported security level {bits}, expected 96 or 128")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvalidRequest {}
+
+/// Nesting depth of a JSON value, scalars have a depth of 0.
+fn value_depth(value: &serde_json::Value) -> usize {
+    match value {
+        serde_json::Value::Array(values) => 1 + values.iter().map(value_depth).max().unwrap_or(0),
+        serde_json::Value::Object(fields) => {
+            1 + fields.values().map(value_depth).max().unwrap_or(0)
+        }
+        _ => 0,
+    }
+}
+
+/// The proving options for a request's `security_bits`.
+fn proving_options(security_bits: Option<u32>) -> Result<ProvingOptions, InvalidRequest> {
+    match security_bits {
+        None | Some(96) => Ok(ProvingOptions::with_96_bit_security(false)),
+        Some(128) => Ok(ProvingOptions::with_128_bit_security(false)),
+        Some(bits) => Err(InvalidRequest::UnsupportedSecurityLevel(bits)),
     }
+}
+
+impl ProveRequest {
+    pub fn validate(&self, limits: &Limits) -> Result<(), InvalidRequest> {
+        proving_options(self.security_bits)?;
+
+        if self.miden_code.len() > limits.max_code_len {
+            return Err(InvalidRequest::TooLarge(format!(
+                "miden code is {} bytes long, the limit is {}",
+                self.miden_code.len(),
+                limits.max_code_len
+            )));
+        }
+
+        if self.args.len() > limits.max_args {
+            return Err(InvalidRequest::TooLarge(format!(
+                "got {} arguments, the limit is {}",
+                self.args.len(),
+                limits.max_args
+            )));
+        }
+
+        let values = self
+            .this
+            .iter()
+            .map(|this| ("this", this))
+            .chain(self.args.iter().map(|arg| ("argument", arg)))
+            .chain(
+                self.other_records
+                    .iter()
+                    .flat_map(|records| records.values().flatten())
+                    .map(|(record, _)| ("other record", record)),
+            );
+        for (what, value) in values {
+            let depth = value_depth(value);
+            if depth > limits.max_value_depth {
+                return Err(InvalidRequest::TooDeep(format!(
+                    "{what} is nested {depth} levels deep, the limit is {}",
+                    limits.max_value_depth
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub async fn prove(mut req: ProveRequest) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    req.abi.normalize();
+
+    let proving_options = proving_options(req.security_bits)?;
+
+    let program = compile_program_cached(
+        PROGRAM_CACHE.get_or_init(|| ProgramCache::new(PROGRAM_CACHE_CAPACITY)),
+        &req.abi,
+        &req.miden_code,
+    )?;
+
+    let this = match req.this.clone() {
+        Some(this) => this,
+        None => req.abi.default_this_value()?.try_into()?,
+    };
 
     let this_salts = req.this_salts.unwrap_or(
         req.abi
@@ -58,18 +174,28 @@ pub async fn prove(mut req: ProveRequest) -> Result<serde_json::Value, Box<dyn s
         req.other_records.clone().unwrap_or_default(),
     )?;
 
-    let program_info = program.clone().to_program_info_bytes();
+    let program_info = (*program).clone().to_program_info_bytes();
+    let start = Instant::now();
     let output = tokio::task::spawn_blocking({
         let inputs = inputs.clone();
-        move || polylang_prover::prove(&program, &inputs).map_err(|e| e.to_string())
+        move || {
+            polylang_prover::prove_with_options(&program, &inputs, proving_options)
+                .map_err(|e| e.to_string())
+        }
     })
-    .await??;
+    .await
+    .map_err(|e| match e.try_into_panic() {
+        // Let the caller handle panics while proving like the ones in the rest of this function.
+        Ok(panic) => std::panic::resume_unwind(panic),
+        Err(e) => e,
+    })??;
+    let proving_duration_ms = start.elapsed().as_millis() as u64;
 
     let new_this = TryInto::<serde_json::Value>::try_into(output.new_this)?;
     let proof_len = output.proof.len();
     let result_hash = output
         .run_output
-        .result_hash(&req.abi)
+        .result_hash(&req.abi)?
         .map(|h| h.into_iter().map(|x| x.to_string()).collect::<Vec<_>>());
 
     Ok(serde_json::json!({
@@ -96,7 +222,7 @@ pub async fn prove(mut req: ProveRequest) -> Result<serde_json::Value, Box<dyn s
         },
         "result": if req.abi.result_type.is_some() {
             serde_json::json!({
-                "value": output.run_output.result(&req.abi).map(TryInto::<serde_json::Value>::try_into)??,
+                "value": output.run_output.result_json(&req.abi)?,
                 "hash": result_hash,
             })
         } else { serde_json::Value::Null },
@@ -106,8 +232,112 @@ pub async fn prove(mut req: ProveRequest) -> Result<serde_json::Value, Box<dyn s
             "logs": output.run_output.logs(),
         },
         "cycleCount": output.run_output.cycle_count,
+        "provingDurationMs": proving_duration_ms,
         "proofLength": proof_len, // raw unencoded length
         "logs": output.run_output.logs(),
-        "readAuth": output.run_output.read_auth(),
+        "readAuth": output.run_output.read_auth()?,
+        "readFields": output.run_output.read_fields()?,
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_with_security_bits(security_bits: Option<u32>) -> ProveRequest {
+        ProveRequest {
+            miden_code: String::new(),
+            abi: abi::Abi::default(),
+            ctx_public_key: None,
+            this: None,
+            this_salts: None,
+            args: vec![],
+            other_records: None,
+            security_bits,
+        }
+    }
+
+    #[test]
+    fn validate_security_bits() {
+        for bits in [None, Some(96), Some(128)] {
+            request_with_security_bits(bits)
+                .validate(&Limits::default())
+                .unwrap();
+        }
+
+        for bits in [0, 64, 97, 127, 256] {
+            let err = request_with_security_bits(Some(bits))
+                .validate(&Limits::default())
+                .unwrap_err();
+            assert!(
+                matches!(err, InvalidRequest::UnsupportedSecurityLevel(b) if b == bits),
+                "{err:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn prove_rejects_unsupported_security_bits() {
+        let err = prove(request_with_security_bits(Some(64)))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "unsupported security level 64, expected 96 or 128"
+        );
+    }
+
+    #[tokio::test]
+    async fn prove_with_salts_and_field_hashes() {
+        let code = r#"
+            contract Account {
+                id: string;
+                balance: u32;
+
+                addBalance(amount: u32) {
+                    this.balance = this.balance + amount;
+                }
+            }
+        "#;
+        let program = polylang::parse_program(code).unwrap();
+        let (miden_code, abi) =
+            polylang::compiler::compile(program, Some("Account"), "addBalance").unwrap();
+
+        let this = serde_json::json!({ "id": "acc", "balance": 5 });
+        let this_salts = vec![1, 2];
+        let expected_hashes = [
+            (abi::Type::String, abi::Value::String("acc".to_owned()), 1),
+            (
+                abi::Type::PrimitiveType(abi::PrimitiveType::UInt32),
+                abi::Value::UInt32(5),
+                2,
+            ),
+        ]
+        .into_iter()
+        .map(|(type_, value, salt)| {
+            polylang_prover::hash_this(type_, &value, Some(&[salt])).unwrap()
+        })
+        .collect::<Vec<_>>();
+
+        let output = prove(ProveRequest {
+            miden_code,
+            abi,
+            ctx_public_key: None,
+            this: Some(this),
+            this_salts: Some(this_salts),
+            args: vec![serde_json::json!(10)],
+            other_records: None,
+            security_bits: None,
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(output["old"]["hashes"], serde_json::json!(expected_hashes));
+        assert_eq!(
+            output["new"]["this"],
+            serde_json::json!({ "id": "acc", "balance": 15 })
+        );
+        assert_eq!(output["new"]["hashes"].as_array().unwrap().len(), 1);
+    }
+}
@@ -1,10 +1,140 @@
+use std::{
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
 use actix_cors::Cors;
 use actix_web::{web, App, HttpResponse, HttpServer, Responder};
+use futures_util::FutureExt;
+use server_routes::prove::{InvalidRequest, Limits};
+
+/// Identifies requests in the logs.
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(0);
 
 async fn prove(
+    limits: web::Data<Limits>,
     req: web::Json<server_routes::prove::ProveRequest>,
 ) -> Result<impl Responder, Box<dyn std::error::Error>> {
-    Ok(HttpResponse::Ok().json(server_routes::prove::prove(req.into_inner()).await?))
+    prove_with(limits, req, server_routes::prove::prove).await
+}
+
+/// Handles a prove request, proving it with `prove_fn`.
+async fn prove_with<F>(
+    limits: web::Data<Limits>,
+    req: web::Json<server_routes::prove::ProveRequest>,
+    prove_fn: impl FnOnce(server_routes::prove::ProveRequest) -> F,
+) -> Result<HttpResponse, Box<dyn std::error::Error>>
+where
+    F: Future<Output = Result<serde_json::Value, Box<dyn std::error::Error>>>,
+{
+    if let Err(e) = req.validate(&limits) {
+        let mut response = match e {
+            InvalidRequest::TooLarge(_) => HttpResponse::PayloadTooLarge(),
+            InvalidRequest::TooDeep(_) | InvalidRequest::UnsupportedSecurityLevel(_) => {
+                HttpResponse::BadRequest()
+            }
+        };
+        return Ok(response.body(e.to_string()));
+    }
+
+    let request_id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    // Stable across builds and machines, unlike `DefaultHasher`, so logs can be compared.
+    let code_hash = blake3::hash(req.miden_code.as_bytes()).to_hex();
+
+    let result = match catch_panic(prove_fn(req.into_inner())).await {
+        Ok(result) => result,
+        Err(message) => {
+            tracing::error!(
+                request_id,
+                %code_hash,
+                panic = message,
+                "panicked while proving",
+            );
+
+            // The panic message can contain internals, so it is only logged.
+            return Ok(HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "internal error while proving" })));
+        }
+    };
+
+    match &result {
+        Ok(output) => tracing::info!(
+            request_id,
+            %code_hash,
+            cycle_count = output["cycleCount"].as_u64(),
+            proving_duration_ms = output["provingDurationMs"].as_u64(),
+            "proved",
+        ),
+        Err(e) => tracing::warn!(
+            request_id,
+            %code_hash,
+            error = %e,
+            "failed to prove",
+        ),
+    }
+
+    Ok(HttpResponse::Ok().json(result?))
+}
+
+/// Runs `future`, returning the panic message if it panics.
+///
+/// Invalid inputs are reported as errors by the compiler and the prover;
+/// this is a last resort so that a bug in either does not take down the
+/// worker that handles the request.
+async fn catch_panic<T>(future: impl Future<Output = T>) -> Result<T, String> {
+    AssertUnwindSafe(future)
+        .catch_unwind()
+        .await
+        .map_err(|panic| {
+            panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string())
+        })
+}
+
+/// Room in a request body for everything but the miden code.
+const MAX_BODY_LEN_WITHOUT_CODE: usize = 1024 * 1024;
+
+/// The longest JSON escape of a single byte, `\u00XX`.
+const MAX_JSON_ESCAPE_LEN: usize = r"\u0000".len();
+
+/// Limits the size of JSON bodies so that code up to `limits.max_code_len` fits.
+///
+/// Without this, actix rejects bodies over 32 KiB before
+/// [`server_routes::prove::ProveRequest::validate`] checks the code length.
+/// A control character in the code is escaped as `\u00XX` in the body, so the
+/// code can take up to [`MAX_JSON_ESCAPE_LEN`] times its length.
+fn json_config(limits: &Limits) -> web::JsonConfig {
+    web::JsonConfig::default().limit(
+        limits
+            .max_code_len
+            .saturating_mul(MAX_JSON_ESCAPE_LEN)
+            .saturating_add(MAX_BODY_LEN_WITHOUT_CODE),
+    )
+}
+
+/// Reads the request limits from `PROVER_MAX_CODE_LEN`, `PROVER_MAX_ARGS` and
+/// `PROVER_MAX_VALUE_DEPTH`, using the defaults for the ones that are unset.
+fn limits_from_env() -> Limits {
+    let var = |name: &str, default: usize| {
+        std::env::var(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .unwrap_or_else(|e| panic!("invalid value for {name}: {e}"))
+            })
+            .unwrap_or(default)
+    };
+
+    let defaults = Limits::default();
+    Limits {
+        max_code_len: var("PROVER_MAX_CODE_LEN", defaults.max_code_len),
+        max_args: var("PROVER_MAX_ARGS", defaults.max_args),
+        max_value_depth: var("PROVER_MAX_VALUE_DEPTH", defaults.max_value_depth),
+    }
 }
 
 #[tokio::main]
@@ -12,10 +142,22 @@ async fn main() {
     let port = std::env::var("PORT").unwrap_or("8080".to_string());
     let listen_addr = std::env::var("PROVER_LADDR").unwrap_or(format!("0.0.0.0:{port}"));
 
-    let app = || {
+    // Logs one JSON object per line to stderr, for operators to monitor requests.
+    if std::env::var("PROVER_JSON_LOGS").is_ok() {
+        tracing_subscriber::fmt()
+            .json()
+            .with_writer(std::io::stderr)
+            .init();
+    }
+
+    let limits = web::Data::new(limits_from_env());
+
+    let app = move || {
         let cors = Cors::permissive();
         App::new()
             .wrap(cors)
+            .app_data(limits.clone())
+            .app_data(json_config(&limits))
             .service(web::resource("/prove").route(web::post().to(prove)))
     };
 
@@ -28,3 +170,221 @@ async fn main() {
         .await
         .unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test};
+    use std::sync::{Arc, Mutex};
+
+    /// Stands in for a bug in the compiler or the prover.
+    async fn panicking_prove(
+        _req: server_routes::prove::ProveRequest,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        panic!("bug while proving")
+    }
+
+    async fn prove_request(limits: Limits, body: String) -> actix_web::dev::ServiceResponse {
+        request(limits, "/prove", body).await
+    }
+
+    async fn request(limits: Limits, uri: &str, body: String) -> actix_web::dev::ServiceResponse {
+        let app = test::init_service(
+            App::new()
+                .app_data(json_config(&limits))
+                .app_data(web::Data::new(limits))
+                .service(web::resource("/prove").route(web::post().to(prove)))
+                .service(web::resource("/prove-panicking").route(web::post().to(
+                    |limits: web::Data<Limits>,
+                     req: web::Json<server_routes::prove::ProveRequest>| async move {
+                        prove_with(limits, req, panicking_prove).await
+                    },
+                ))),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri(uri)
+            .insert_header(("content-type", "application/json"))
+            .set_payload(body)
+            .to_request();
+
+        test::call_service(&app, req).await
+    }
+
+    async fn prove_status(limits: Limits, body: String) -> StatusCode {
+        prove_request(limits, body).await.status()
+    }
+
+    fn request_body(miden_code: &str, this: &str) -> String {
+        request_body_with_std_version(miden_code, this, "null")
+    }
+
+    fn request_body_with_std_version(miden_code: &str, this: &str, std_version: &str) -> String {
+        format!(
+            r#"{{
+                "midenCode": "{miden_code}",
+                "abi": {{
+                    "std_version": {std_version},
+                    "param_types": [],
+                    "other_records": [],
+                    "other_contract_types": [],
+                    "dependent_fields": []
+                }},
+                "this": {this},
+                "args": []
+            }}"#
+        )
+    }
+
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[actix_web::test]
+    async fn logs_proved_request() {
+        let logs = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_writer({
+                let logs = Arc::clone(&logs);
+                move || Buffer(Arc::clone(&logs))
+            })
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        assert_eq!(
+            prove_status(
+                Limits::default(),
+                request_body("begin\\n  push.1\\n  drop\\nend\\n", "null")
+            )
+            .await,
+            StatusCode::OK
+        );
+
+        let logs = String::from_utf8(logs.lock().unwrap().clone()).unwrap();
+        let line = logs
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .find(|line| line["fields"]["message"] == "proved")
+            .unwrap_or_else(|| panic!("no log line for the proved request in {logs:?}"));
+        let fields = &line["fields"];
+
+        assert!(fields["request_id"].is_u64(), "{fields}");
+        assert_eq!(
+            fields["code_hash"].as_str().map(str::len),
+            Some(64),
+            "{fields}"
+        );
+        assert!(fields["cycle_count"].as_u64().unwrap() > 0, "{fields}");
+        assert!(fields["proving_duration_ms"].is_u64(), "{fields}");
+    }
+
+    #[actix_web::test]
+    async fn rejects_code_over_the_limit() {
+        let limits = Limits {
+            max_code_len: 16,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            prove_status(limits, request_body(&"drop ".repeat(10), "{}")).await,
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+    }
+
+    #[actix_web::test]
+    async fn accepts_bodies_over_the_default_json_limit() {
+        let comment = "a".repeat(64 * 1024);
+        let body = request_body(
+            &format!("# {comment}\\nbegin\\n  push.1\\n  drop\\nend\\n"),
+            "null",
+        );
+        assert!(body.len() > 32 * 1024);
+
+        assert_eq!(prove_status(Limits::default(), body).await, StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn accepts_code_made_of_escaped_characters_up_to_the_limit() {
+        let limits = Limits {
+            max_code_len: 1024 * 1024,
+            ..Default::default()
+        };
+        let body = request_body(&"\\u0001".repeat(limits.max_code_len), "null");
+
+        // The code is not valid, but it gets past the body limit.
+        assert_ne!(
+            prove_status(limits, body).await,
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+    }
+
+    #[actix_web::test]
+    async fn rejects_this_nested_too_deep() {
+        let limits = Limits {
+            max_value_depth: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            prove_status(limits, request_body("begin end", r#"{"a": [[{"b": 1}]]}"#)).await,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[actix_web::test]
+    async fn unsupported_std_version_is_an_error() {
+        let response = prove_request(
+            Limits::default(),
+            request_body_with_std_version("begin end", "null", r#""0.5.0""#),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = test::read_body(response).await;
+        assert!(
+            String::from_utf8_lossy(&body).contains("standard library version 0.5.0"),
+            "{body:?}"
+        );
+    }
+
+    #[actix_web::test]
+    async fn panic_is_an_internal_error() {
+        let response = request(
+            Limits::default(),
+            "/prove-panicking",
+            request_body("begin\\n  push.1\\n  drop\\nend\\n", "null"),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body: serde_json::Value = test::read_body_json(response).await;
+        assert_eq!(
+            body,
+            serde_json::json!({ "error": "internal error while proving" })
+        );
+    }
+
+    #[actix_web::test]
+    async fn catch_panic_returns_the_message() {
+        assert_eq!(catch_panic(async { 1 }).await, Ok(1));
+        assert_eq!(
+            catch_panic(async { panic!("static message") }).await,
+            Err::<(), _>("static message".to_string())
+        );
+        let n = 2;
+        assert_eq!(
+            catch_panic(async { panic!("formatted message {n}") }).await,
+            Err::<(), _>("formatted message 2".to_string())
+        );
+    }
+}
@@ -4,6 +4,7 @@ use std::collections::HashMap;
 
 const CONTRACT: &str = r#"
     contract BinarySearch {
+        id: string;
         arr: i32[];
         found: boolean;
         foundPos: u32;
@@ -59,7 +60,7 @@ fn binary_search(arr: &Vec<i32>, elem: i32) -> Result<(), Box<dyn std::error::Er
     let args = Args {
         advice_tape_json: Some(format!("[{elem}]")),
         this_values: HashMap::new(),
-        this_json: Some(json!({"arr": arr, "found": false, "foundPos": 0 })),
+        this_json: Some(json!({"id": "", "arr": arr, "found": false, "foundPos": 0 })),
         other_records: HashMap::new(),
         abi,
         ctx: Ctx::default(),
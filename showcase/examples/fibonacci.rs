@@ -6,6 +6,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // specify your cpntract here
     let contract = r#"
     contract Fibonacci {
+        id: string;
+
         function main(p: u32, a: u32, b: u32): u32 {
             for (let i: u32 = 0; i < p; i++) {
                 let c = a.wrappingAdd(b);
@@ -30,7 +32,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args {
         advice_tape_json: Some("[8, 1, 1]".to_string()),
         this_values: HashMap::new(),
-        this_json: Some(json!([])),
+        this_json: Some(json!({"id": ""})),
         other_records: HashMap::new(),
         abi,
         ctx: Ctx::default(),
@@ -0,0 +1,42 @@
+use polylang_examples::{compile_contract, run_contract, Args, Ctx};
+use serde_json::json;
+use std::collections::HashMap;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // specify your contract here
+    let contract = r#"
+    contract Greeting {
+        id: string;
+        message: string;
+
+        greet(name: string, visits: u32) {
+            this.message = "Hello, " + name + "! This is visit #" + uint32ToString(visits) + ".";
+        }
+    }
+    "#;
+
+    // pass the name of `contract` here
+    let contract_name = Some("Greeting");
+    // pass the name of the function to be executed here
+    let function_name = "greet".to_string();
+    // pass the name of the proof file here
+    let proof_file_name = "greeting.proof";
+
+    let (miden_code, abi) = compile_contract(contract, contract_name, &function_name)?;
+
+    let args = Args {
+        advice_tape_json: Some(json!(["Polylang", 42]).to_string()),
+        this_values: HashMap::new(),
+        this_json: Some(json!({"id": "greeting", "message": ""})),
+        other_records: HashMap::new(),
+        abi,
+        ctx: Ctx::default(),
+        proof_output: Some(proof_file_name.to_string()),
+    };
+
+    // Run the contract. In addition to the output (if any), you should see the proof file
+    // generated in the same directory: `<proof_file_name>.proof`.
+    run_contract(miden_code, args)?;
+
+    Ok(())
+}
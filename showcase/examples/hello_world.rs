@@ -6,6 +6,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // specify your cpntract here
     let contract = r#"
     contract HelloWorld {
+        id: string;
+
         function add(a: i32, b: i32): i32 {
            return a + b;
         }
@@ -24,7 +26,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args {
         advice_tape_json: Some("[1, 2]".into()),
         this_values: HashMap::new(),
-        this_json: Some(json!({})),
+        this_json: Some(json!({"id": ""})),
         other_records: HashMap::new(),
         abi,
         ctx: Ctx::default(),
@@ -6,6 +6,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // specify your cpntract here
     let contract = r#"
       contract ReverseArray {
+          id: string;
           elements: number[];
 
           constructor (elements: number[]) {
@@ -41,7 +42,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args {
         advice_tape_json: None,
         this_values: HashMap::new(),
-        this_json: Some(json!({"elements": [1, 3, 4, 5, 7, 6, 2, 3]})),
+        this_json: Some(json!({"id": "", "elements": [1, 3, 4, 5, 7, 6, 2, 3]})),
         other_records: HashMap::new(),
         abi,
         ctx: Ctx::default(),
@@ -0,0 +1,49 @@
+use polylang_examples::{compile_contract, run_contract, Args, Ctx};
+use serde_json::json;
+use std::collections::HashMap;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // specify your contract here
+    let contract = r#"
+    contract WordCount {
+        id: string;
+        counts: map<string, number>;
+
+        count(words: string[]) {
+            for (let word of words) {
+                this.counts[word] = this.counts[word] + 1;
+            }
+        }
+    }
+    "#;
+
+    // pass the name of `contract` here
+    let contract_name = Some("WordCount");
+    // pass the name of the function to be executed here
+    let function_name = "count".to_string();
+    // pass the name of the proof file here
+    let proof_file_name = "word_count.proof";
+
+    let (miden_code, abi) = compile_contract(contract, contract_name, &function_name)?;
+
+    // Polylang has no `split` for strings yet, so the text is split into words here
+    // and the contract does the counting.
+    let text = "the quick fox jumps over the lazy dog the fox";
+    let words = text.split_whitespace().collect::<Vec<_>>();
+
+    let args = Args {
+        advice_tape_json: Some(json!([words]).to_string()),
+        this_values: HashMap::new(),
+        this_json: Some(json!({"id": "words", "counts": {}})),
+        other_records: HashMap::new(),
+        abi,
+        ctx: Ctx::default(),
+        proof_output: Some(proof_file_name.to_string()),
+    };
+
+    // Run the contract. In addition to the output (if any), you should see the proof file
+    // generated in the same directory: `<proof_file_name>.proof`.
+    run_contract(miden_code, args)?;
+
+    Ok(())
+}
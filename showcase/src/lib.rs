@@ -109,16 +109,7 @@ pub fn compile_contract(
 }
 
 pub fn run_contract(miden_code: String, mut args: Args) -> Result<(), Box<dyn std::error::Error>> {
-    let has_this_type = if args.abi.this_type.is_none() {
-        args.abi.this_type = Some(abi::Type::Struct(abi::Struct {
-            name: "Empty".to_string(),
-            fields: Vec::new(),
-        }));
-
-        false
-    } else {
-        true
-    };
+    args.abi.normalize();
 
     let inputs = args.inputs(|t, v, s| Ok(polylang_prover::hash_this(t, v, s)?))?;
 
@@ -127,18 +118,10 @@ pub fn run_contract(miden_code: String, mut args: Args) -> Result<(), Box<dyn st
 
     let (output, prove) = polylang_prover::run(&program, &inputs)?;
 
-    if has_this_type {
-        println!(
-            "this_json: {}",
-            TryInto::<serde_json::Value>::try_into(output.this(&args.abi)?)?
-        );
-    }
+    println!("this_json: {}", output.this_json(&args.abi)?);
 
     if args.abi.result_type.is_some() {
-        println!(
-            "result_json: {}",
-            TryInto::<serde_json::Value>::try_into(output.result(&args.abi)?)?
-        );
+        println!("result_json: {}", output.result_json(&args.abi)?);
     }
 
     if let Some(out) = args.proof_output {
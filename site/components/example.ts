@@ -38,12 +38,14 @@ export const EXAMPLES = [{
 }, {
   name: 'Hello, Contracts!',
   code: `contract HelloContracts {
+  id: string;
+
   function hello() {
     log("Hello, contracts!");
   }
 }`,
   inputs: `{
-  "init_params": "",
+  "init_params": { "id": "" },
   "params": [],
   "contract_name": "HelloContracts",
   "fn": "hello"
@@ -51,6 +53,7 @@ export const EXAMPLES = [{
 }, {
   name: 'Reverse Array',
   code: `contract ReverseArray {
+  id: string;
   elements: number[];
 
   constructor (elements: number[]) {
@@ -74,7 +77,7 @@ export const EXAMPLES = [{
 }
 `,
   inputs: `{
-  "init_params": { "elements": [1, 2, 3, 4, 5] },
+  "init_params": { "id": "", "elements": [1, 2, 3, 4, 5] },
   "params": [],
   "contract_name": "ReverseArray",
   "fn": "reverse"
@@ -82,6 +85,7 @@ export const EXAMPLES = [{
 }, {
   name: 'Binary Search',
   code: `contract BinarySearch {
+  id: string;
   arr: i32[];
   found: boolean; // indicates whether element was found
   foundPos: u32; // gives the position of the found element
@@ -116,7 +120,7 @@ export const EXAMPLES = [{
 }
 `,
   inputs: `{
-  "init_params" : { "arr": [1, 2, 3, 3, 5, 6, 11], "found": false, "foundPos": 0 },
+  "init_params" : { "id": "", "arr": [1, 2, 3, 3, 5, 6, 11], "found": false, "foundPos": 0 },
   "params": [5],
   "contract_name": "BinarySearch",
   "fn": "search"
@@ -6,24 +6,80 @@ fn main() {
 
     let mut contract_name = None;
     let mut function_name = "main".to_string();
+    let mut debug = false;
+    let mut warn_shadowing = false;
+    let mut max_loop_iterations = None;
 
     for arg in std::env::args().skip(1) {
         match arg.split_once(':') {
             Some((key, value)) => match key {
                 "contract" => contract_name = Some(value.to_string()),
                 "function" => function_name = value.to_string(),
+                "debug" => {
+                    debug = value
+                        .parse()
+                        .unwrap_or_else(|_| panic!("invalid value for debug: {}", value))
+                }
+                "warn_shadowing" => {
+                    warn_shadowing = value
+                        .parse()
+                        .unwrap_or_else(|_| panic!("invalid value for warn_shadowing: {}", value))
+                }
+                "max_loop_iterations" => {
+                    max_loop_iterations = Some(value.parse().unwrap_or_else(|_| {
+                        panic!("invalid value for max_loop_iterations: {}", value)
+                    }))
+                }
                 _ => panic!("unknown argument: {}", key),
             },
             None => panic!("invalid argument: {}", arg),
         }
     }
 
-    let program = polylang_parser::parse(&code).unwrap();
+    let output = polylang::compile(
+        &code,
+        contract_name.as_deref(),
+        &function_name,
+        polylang::compiler::CompileOptions {
+            source: Some(&code),
+            debug,
+            warn_shadowing,
+            max_loop_iterations,
+        },
+    )
+    .unwrap_or_else(|e| match e.span {
+        // Parse errors already say where they are.
+        Some(span) if e.kind != polylang::ErrorKind::Parse => {
+            let (line, col) = span.start_line_col(&code);
+            panic!("{:?} error at {}:{}: {}", e.kind, line, col, e.message)
+        }
+        _ => panic!("{:?} error: {}", e.kind, e.message),
+    });
+    for warning in &output.warnings {
+        match warning.span {
+            Some(span) => {
+                let (line, col) = span.start_line_col(&code);
+                eprint!("warning: {}:{}: {}", line, col, warning.message);
+                if let Some(related) = warning.related_span {
+                    let (line, col) = related.start_line_col(&code);
+                    eprint!(" (see {}:{})", line, col);
+                }
+                eprintln!();
+            }
+            None => eprintln!("warning: {}", warning.message),
+        }
+    }
 
-    let (miden_code, abi) =
-        polylang::compiler::compile(program, contract_name.as_deref(), &function_name)
-            .map_err(|e| e.add_source(code))
-            .unwrap_or_else(|e| panic!("{e}"));
-    println!("{}", miden_code);
-    eprintln!("ABI: {}", serde_json::to_string(&abi).unwrap());
+    println!("{}", output.miden_code);
+    eprintln!("ABI: {}", serde_json::to_string(&output.abi).unwrap());
+
+    if debug {
+        eprintln!("Symbols:");
+        for symbol in output.debug_symbols {
+            eprintln!(
+                "  {}: {} ({:?})",
+                symbol.memory_addr, symbol.name, symbol.type_
+            );
+        }
+    }
 }
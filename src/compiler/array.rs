@@ -40,6 +40,32 @@ pub(crate) fn new(compiler: &mut Compiler, len: u32, element_type: Type) -> (Sym
     (symbol, allocated_ptr)
 }
 
+/// Allocates an array holding copies of `elements`, which must all be of `element_type`.
+pub(crate) fn from_symbols(
+    compiler: &mut Compiler,
+    element_type: Type,
+    elements: &[Symbol],
+) -> Result<Symbol> {
+    for element in elements {
+        ensure_eq_type!(element, @&element_type);
+    }
+
+    let element_width = element_type.miden_width();
+    let (array, data_ptr) = new(compiler, elements.len() as u32, element_type);
+    for (i, element) in elements.iter().enumerate() {
+        compiler
+            .memory
+            .read(compiler.instructions, element.memory_addr, element_width);
+        compiler.memory.write(
+            compiler.instructions,
+            data_ptr + i as u32 * element_width,
+            &vec![ValueSource::Stack; element_width as usize],
+        );
+    }
+
+    Ok(array)
+}
+
 fn dynamic_new(compiler: &mut Compiler, element_type: Type, needed_len: Symbol) -> Result<Symbol> {
     let array = compiler
         .memory
@@ -832,6 +858,217 @@ pub(crate) fn includes(compiler: &mut Compiler, arr: &Symbol, el: &Symbol) -> Re
     Ok(result)
 }
 
+/// Adds up the elements of a numeric array, an empty array sums to zero.
+pub(crate) fn sum(compiler: &mut Compiler, arr: &Symbol) -> Result<Symbol> {
+    ensure_eq_type!(arr, Type::Array(_));
+    let element_type = element_type(&arr.type_);
+    ensure!(
+        matches!(
+            element_type,
+            Type::PrimitiveType(
+                PrimitiveType::UInt32
+                    | PrimitiveType::UInt64
+                    | PrimitiveType::Int32
+                    | PrimitiveType::Int64
+                    | PrimitiveType::Float32
+            )
+        ),
+        TypeMismatchSnafu {
+            context: format!("cannot sum an array of {:?}", element_type),
+        }
+    );
+
+    accumulate(compiler, arr, element_type.clone(), |_, element| {
+        element.clone()
+    })
+}
+
+/// Adds up the elements of `arr` into a `result_type` accumulator,
+/// converting each element with `convert` first.
+fn accumulate(
+    compiler: &mut Compiler,
+    arr: &Symbol,
+    result_type: Type,
+    convert: impl Fn(&mut Compiler, &Symbol) -> Symbol,
+) -> Result<Symbol> {
+    let element_type = element_type(&arr.type_);
+
+    // Zero bits are zero for all of the numeric types, including f32.
+    let result = compiler.memory.allocate_symbol(result_type);
+    compiler.memory.write(
+        compiler.instructions,
+        result.memory_addr,
+        &vec![ValueSource::Immediate(0); result.type_.miden_width() as usize],
+    );
+
+    let current_arr_element = compiler.memory.allocate_symbol(element_type.clone());
+    let add_insts = {
+        let mut insts = Vec::new();
+
+        std::mem::swap(compiler.instructions, &mut insts);
+        let element = convert(compiler, &current_arr_element);
+        let sum = super::compile_add(compiler, &result, &element)?;
+        compiler.memory.read(
+            compiler.instructions,
+            sum.memory_addr,
+            sum.type_.miden_width(),
+        );
+        compiler.memory.write(
+            compiler.instructions,
+            result.memory_addr,
+            &vec![ValueSource::Stack; result.type_.miden_width() as usize],
+        );
+        std::mem::swap(compiler.instructions, &mut insts);
+
+        insts
+    };
+
+    let current_index = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt32));
+    let finished = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Boolean));
+
+    iterate_array_elements(
+        compiler,
+        arr,
+        &current_index,
+        &current_arr_element,
+        &finished,
+        add_insts,
+    )?;
+
+    Ok(result)
+}
+
+/// The mean of a `u32`, `i32` or `f32` array as an `f32`.
+/// The elements are added up as `f32`s, so the total can't overflow the element type.
+/// The average of an empty array is NaN.
+pub(crate) fn average(compiler: &mut Compiler, arr: &Symbol) -> Result<Symbol> {
+    ensure_eq_type!(arr, Type::Array(_));
+    let convert: fn(&mut Compiler, &Symbol) -> Symbol = match element_type(&arr.type_) {
+        Type::PrimitiveType(PrimitiveType::UInt32) => float32::from_uint32,
+        Type::PrimitiveType(PrimitiveType::Int32) => float32::from_int32,
+        Type::PrimitiveType(PrimitiveType::Float32) => |_, x| x.clone(),
+        t => {
+            return TypeMismatchSnafu {
+                context: format!("cannot average an array of {:?}", t),
+            }
+            .fail()
+            .map_err(Into::into)
+        }
+    };
+    let total = accumulate(
+        compiler,
+        arr,
+        Type::PrimitiveType(PrimitiveType::Float32),
+        convert,
+    )?;
+    let len = float32::from_uint32(compiler, &length(arr));
+
+    Ok(float32::div(compiler, &total, &len))
+}
+
+/// The smallest element of a numeric or string array, or null if the array is empty.
+pub(crate) fn min(compiler: &mut Compiler, arr: &Symbol) -> Result<Symbol> {
+    extreme(compiler, arr, super::compile_lt)
+}
+
+/// The largest element of a numeric or string array, or null if the array is empty.
+pub(crate) fn max(compiler: &mut Compiler, arr: &Symbol) -> Result<Symbol> {
+    extreme(compiler, arr, super::compile_gt)
+}
+
+/// Folds the array into the element for which `replaces(element, result)` holds
+/// against every other element.
+fn extreme(
+    compiler: &mut Compiler,
+    arr: &Symbol,
+    replaces: fn(&mut Compiler, &Symbol, &Symbol) -> Symbol,
+) -> Result<Symbol> {
+    ensure_eq_type!(arr, Type::Array(_));
+    let element_type = element_type(&arr.type_);
+    ensure!(
+        matches!(
+            element_type,
+            Type::PrimitiveType(
+                PrimitiveType::UInt32
+                    | PrimitiveType::UInt64
+                    | PrimitiveType::Int32
+                    | PrimitiveType::Int64
+                    | PrimitiveType::Float32
+            ) | Type::String
+        ),
+        TypeMismatchSnafu {
+            context: format!(
+                "cannot compare the elements of an array of {:?}",
+                element_type
+            ),
+        }
+    );
+
+    let result = nullable::null(compiler, element_type);
+    let result_value = nullable::value(result.clone());
+    let width = element_type.miden_width();
+
+    let current_arr_element = compiler.memory.allocate_symbol(element_type.clone());
+    let (cmp_insts, cmp_result) = {
+        let mut insts = Vec::new();
+
+        std::mem::swap(compiler.instructions, &mut insts);
+        let result = replaces(compiler, &current_arr_element, &result_value);
+        std::mem::swap(compiler.instructions, &mut insts);
+
+        (insts, result)
+    };
+
+    let take_current = (0..width)
+        .flat_map(|i| {
+            [
+                Instruction::MemLoad(Some(current_arr_element.memory_addr + i)),
+                Instruction::MemStore(Some(result_value.memory_addr + i)),
+            ]
+        })
+        .chain([
+            Instruction::Push(1),
+            Instruction::MemStore(Some(nullable::is_not_null(&result).memory_addr)),
+        ])
+        .collect::<Vec<_>>();
+
+    let current_index = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt32));
+    let finished = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Boolean));
+
+    iterate_array_elements(
+        compiler,
+        arr,
+        &current_index,
+        &current_arr_element,
+        &finished,
+        // The comparison only runs once the result holds an element.
+        vec![Instruction::If {
+            condition: vec![Instruction::MemLoad(Some(
+                nullable::is_not_null(&result).memory_addr,
+            ))],
+            then: cmp_insts
+                .into_iter()
+                .chain([Instruction::If {
+                    condition: vec![Instruction::MemLoad(Some(cmp_result.memory_addr))],
+                    then: take_current.clone(),
+                    else_: vec![],
+                }])
+                .collect(),
+            else_: take_current,
+        }],
+    )?;
+
+    Ok(result)
+}
+
 pub(crate) fn splice(
     compiler: &mut Compiler,
     arr: &Symbol,
@@ -1193,6 +1430,211 @@ pub(crate) fn slice(
     Ok(new_arr)
 }
 
+/// Pushes `index` as an index into an array of `length` elements, like JS relative indices.
+/// Indices past the end are clamped to the length, and negative `i32` indices count back
+/// from the end, clamped to 0.
+fn relative_index_stack(compiler: &mut Compiler, index: &Symbol, length: &Symbol) -> Result<()> {
+    match &index.type_ {
+        Type::PrimitiveType(PrimitiveType::UInt32) => compiler.instructions.extend([
+            Instruction::MemLoad(Some(index.memory_addr)),
+            Instruction::MemLoad(Some(length.memory_addr)),
+            Instruction::U32CheckedMin,
+            // [min(index, length)]
+        ]),
+        Type::PrimitiveType(PrimitiveType::Int32) => compiler.instructions.extend([
+            Instruction::MemLoad(Some(index.memory_addr)),
+            Instruction::If {
+                condition: vec![
+                    Instruction::Dup(None),
+                    Instruction::Push(1 << 31),
+                    Instruction::U32CheckedGTE,
+                    // [index < 0, index]
+                ],
+                then: vec![
+                    Instruction::Push(0),
+                    Instruction::Swap,
+                    Instruction::U32WrappingSub,
+                    // [-index]
+                    Instruction::MemLoad(Some(length.memory_addr)),
+                    Instruction::U32CheckedMin,
+                    // [min(-index, length)]
+                    Instruction::MemLoad(Some(length.memory_addr)),
+                    Instruction::Swap,
+                    Instruction::U32CheckedSub,
+                    // [length - min(-index, length)]
+                ],
+                else_: vec![
+                    Instruction::MemLoad(Some(length.memory_addr)),
+                    Instruction::U32CheckedMin,
+                    // [min(index, length)]
+                ],
+            },
+        ]),
+        t => {
+            return TypeMismatchSnafu {
+                context: format!("expected u32 or i32 index, got {:?}", t),
+            }
+            .fail()
+            .map_err(Into::into)
+        }
+    }
+
+    Ok(())
+}
+
+/// Copies the elements in `start..end` to `target` within the same array, like JS `copyWithin`.
+/// The indices can be `u32` or `i32`, see [`relative_index_stack`], and the copy stops at the
+/// end of the array. Overlapping ranges are handled as if the source was copied to a temporary first.
+pub(crate) fn copy_within(
+    compiler: &mut Compiler,
+    arr: &Symbol,
+    target: &Symbol,
+    start: &Symbol,
+    end: Option<&Symbol>,
+) -> Result<Symbol> {
+    ensure_eq_type!(arr, Type::Array(_));
+
+    let element_width = element_type(&arr.type_).miden_width();
+    let length = length(arr);
+
+    let source_ptr = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt32));
+    let target_ptr = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt32));
+    let total_width = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt32));
+
+    for (index, ptr) in [(start, &source_ptr), (target, &target_ptr)] {
+        relative_index_stack(compiler, index, &length)?;
+        // [actual_index]
+        compiler.instructions.extend([
+            Instruction::Push(element_width),
+            Instruction::U32CheckedMul,
+            Instruction::MemLoad(Some(data_ptr(arr).memory_addr)),
+            Instruction::U32CheckedAdd,
+            // [data_ptr + actual_index * element_width]
+            Instruction::MemStore(Some(ptr.memory_addr)),
+        ]);
+    }
+
+    match end {
+        Some(end) => relative_index_stack(compiler, end, &length)?,
+        None => compiler
+            .instructions
+            .push(Instruction::MemLoad(Some(length.memory_addr))),
+    }
+    // [actual_end]
+
+    compiler.instructions.extend([
+        Instruction::Push(element_width),
+        Instruction::U32CheckedMul,
+        Instruction::MemLoad(Some(data_ptr(arr).memory_addr)),
+        Instruction::U32CheckedAdd,
+        // [source_end_ptr]
+        Instruction::MemLoad(Some(source_ptr.memory_addr)),
+        // [source_ptr, source_end_ptr]
+        Instruction::If {
+            condition: vec![
+                Instruction::Dup(Some(1)),
+                Instruction::Dup(Some(1)),
+                // [source_ptr, source_end_ptr, source_ptr, source_end_ptr]
+                Instruction::U32CheckedGT,
+                // [source_end_ptr > source_ptr, source_ptr, source_end_ptr]
+            ],
+            then: vec![Instruction::U32CheckedSub],
+            else_: vec![Instruction::Drop, Instruction::Drop, Instruction::Push(0)],
+        },
+        // [source_width]
+        Instruction::MemLoad(Some(data_ptr(arr).memory_addr)),
+        Instruction::MemLoad(Some(length.memory_addr)),
+        Instruction::Push(element_width),
+        Instruction::U32CheckedMul,
+        Instruction::U32CheckedAdd,
+        // [end_ptr, source_width]
+        Instruction::MemLoad(Some(target_ptr.memory_addr)),
+        Instruction::U32CheckedSub,
+        // [end_ptr - target_ptr, source_width]
+        Instruction::U32CheckedMin,
+        // [total_width]
+        Instruction::MemStore(Some(total_width.memory_addr)),
+    ]);
+
+    // [offset] -> []
+    let copy_word = [
+        Instruction::Dup(None),
+        Instruction::MemLoad(Some(source_ptr.memory_addr)),
+        Instruction::U32CheckedAdd,
+        Instruction::MemLoad(None),
+        // [value, offset]
+        Instruction::Swap,
+        Instruction::MemLoad(Some(target_ptr.memory_addr)),
+        Instruction::U32CheckedAdd,
+        // [target_ptr + offset, value]
+        Instruction::MemStore(None),
+    ];
+
+    // Copying front to back would overwrite source elements that haven't been copied yet
+    // when the target is after the source, so in that case copy back to front.
+    let backwards = vec![
+        Instruction::MemLoad(Some(total_width.memory_addr)),
+        // [offset = total_width]
+        Instruction::While {
+            condition: vec![
+                Instruction::Dup(None),
+                Instruction::Push(0),
+                Instruction::U32CheckedGT,
+            ],
+            body: [
+                vec![
+                    Instruction::Push(1),
+                    Instruction::U32CheckedSub,
+                    // [offset = offset - 1]
+                    Instruction::Dup(None),
+                ],
+                copy_word.to_vec(),
+            ]
+            .concat(),
+        },
+        Instruction::Drop,
+    ];
+    let forwards = vec![
+        Instruction::Push(0),
+        // [offset = 0]
+        Instruction::While {
+            condition: vec![
+                Instruction::Dup(None),
+                Instruction::MemLoad(Some(total_width.memory_addr)),
+                Instruction::U32CheckedLT,
+                // [offset < total_width, offset]
+            ],
+            body: [
+                vec![Instruction::Dup(None)],
+                copy_word.to_vec(),
+                vec![Instruction::Push(1), Instruction::U32CheckedAdd],
+                // [offset = offset + 1]
+            ]
+            .concat(),
+        },
+        Instruction::Drop,
+    ];
+
+    compiler.instructions.push(Instruction::If {
+        condition: vec![
+            Instruction::MemLoad(Some(source_ptr.memory_addr)),
+            Instruction::MemLoad(Some(target_ptr.memory_addr)),
+            Instruction::U32CheckedLT,
+            // [source_ptr < target_ptr]
+        ],
+        then: backwards,
+        else_: forwards,
+    });
+
+    Ok(arr.clone())
+}
+
 fn copy_from_element(
     compiler: &mut Compiler,
     source_element: &Symbol,
@@ -1334,3 +1776,106 @@ pub(crate) fn unshift(
 
     Ok(length(&new_arr))
 }
+
+/// Allocates an array of `len` elements of `element_type`, without initializing them.
+fn with_length(compiler: &mut Compiler, element_type: Type, len: &Symbol) -> Result<Symbol> {
+    ensure_eq_type!(len, Type::PrimitiveType(PrimitiveType::UInt32));
+
+    let element_width = element_type.miden_width();
+    let array = compiler
+        .memory
+        .allocate_symbol(Type::Array(Box::new(element_type)));
+
+    compiler.instructions.extend([
+        Instruction::MemLoad(Some(len.memory_addr)),
+        // [len]
+        Instruction::Push(element_width),
+        Instruction::U32CheckedMul,
+        // [len * element_width]
+        Instruction::MemStore(Some(capacity(&array).memory_addr)),
+        // []
+    ]);
+    compiler.memory.write(
+        compiler.instructions,
+        length(&array).memory_addr,
+        &[ValueSource::Memory(len.memory_addr)],
+    );
+
+    let ptr = super::dynamic_alloc(compiler, &[capacity(&array)])?;
+    compiler.memory.write(
+        compiler.instructions,
+        data_ptr(&array).memory_addr,
+        &[ValueSource::Memory(ptr.memory_addr)],
+    );
+
+    Ok(array)
+}
+
+/// Compiles `Array.from(len, value)`, an array of `len` copies of `value`.
+pub(crate) fn fill(compiler: &mut Compiler, len: &Symbol, value: &Symbol) -> Result<Symbol> {
+    let array = with_length(compiler, value.type_.clone(), len)?;
+    let element_width = value.type_.miden_width();
+
+    compiler.instructions.extend([
+        Instruction::MemLoad(Some(data_ptr(&array).memory_addr)),
+        // [ptr = data_ptr]
+        Instruction::MemLoad(Some(capacity(&array).memory_addr)),
+        Instruction::Dup(Some(1)),
+        Instruction::U32CheckedAdd,
+        // [end = data_ptr + len * element_width, ptr]
+        Instruction::Swap,
+        // [ptr, end]
+        Instruction::While {
+            condition: vec![
+                Instruction::Dup(None),
+                // [ptr, ptr, end]
+                Instruction::Dup(Some(2)),
+                // [end, ptr, ptr, end]
+                Instruction::U32CheckedLT,
+                // [ptr < end, ptr, end]
+            ],
+            body: (0..element_width)
+                .flat_map(|i| {
+                    [
+                        Instruction::MemLoad(Some(value.memory_addr + i)),
+                        // [value[i], ptr, end]
+                        Instruction::Dup(Some(1)),
+                        Instruction::Push(i),
+                        Instruction::U32CheckedAdd,
+                        // [ptr + i, value[i], ptr, end]
+                        Instruction::MemStore(None),
+                        // [ptr, end]
+                    ]
+                })
+                .chain([
+                    Instruction::Push(element_width),
+                    Instruction::U32CheckedAdd,
+                    // [ptr = ptr + element_width, end]
+                ])
+                .collect(),
+        },
+        // [ptr, end]
+        Instruction::Drop,
+        Instruction::Drop,
+    ]);
+
+    Ok(array)
+}
+
+/// Compiles `Array.from(arr)`, a shallow copy of `arr`.
+pub(crate) fn copy_array(compiler: &mut Compiler, arr: &Symbol) -> Result<Symbol> {
+    ensure_eq_type!(arr, Type::Array(_));
+    let element_type = element_type(&arr.type_);
+
+    let new_arr = with_length(compiler, element_type.clone(), &length(arr))?;
+    copy(
+        compiler,
+        &data_ptr(arr),
+        &length(arr),
+        &data_ptr(&new_arr),
+        &capacity(&new_arr),
+        element_type.miden_width(),
+    )?;
+
+    Ok(new_arr)
+}
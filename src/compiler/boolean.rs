@@ -18,48 +18,65 @@ pub(crate) fn new(compiler: &mut Compiler, value: bool) -> Symbol {
     symbol
 }
 
-pub(crate) fn compile_and(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
-    assert_eq!(a.type_, b.type_);
-    assert_eq!(a.type_, Type::PrimitiveType(PrimitiveType::Boolean));
+/// Compiles `a && b`. `b` is only evaluated if `a` is true.
+pub(crate) fn compile_and<'ast>(
+    compiler: &mut Compiler<'ast, '_, '_>,
+    a: &Symbol,
+    b: impl FnOnce(&mut Compiler<'ast, '_, '_>) -> Result<Symbol>,
+) -> Result<Symbol> {
+    let (result, b_instructions) = short_circuit(compiler, a, b)?;
+    compiler.instructions.push(encoder::Instruction::If {
+        condition: vec![encoder::Instruction::MemLoad(Some(a.memory_addr))],
+        then: b_instructions,
+        else_: vec![],
+    });
 
-    let result = compiler
-        .memory
-        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Boolean));
-    compiler
-        .memory
-        .read(compiler.instructions, a.memory_addr, a.type_.miden_width());
-    compiler
-        .memory
-        .read(compiler.instructions, b.memory_addr, b.type_.miden_width());
-    compiler.instructions.push(encoder::Instruction::And);
-    compiler.memory.write(
-        compiler.instructions,
-        result.memory_addr,
-        &[ValueSource::Stack],
-    );
+    Ok(result)
+}
 
-    result
+/// Compiles `a || b`. `b` is only evaluated if `a` is false.
+pub(crate) fn compile_or<'ast>(
+    compiler: &mut Compiler<'ast, '_, '_>,
+    a: &Symbol,
+    b: impl FnOnce(&mut Compiler<'ast, '_, '_>) -> Result<Symbol>,
+) -> Result<Symbol> {
+    let (result, b_instructions) = short_circuit(compiler, a, b)?;
+    compiler.instructions.push(encoder::Instruction::If {
+        condition: vec![encoder::Instruction::MemLoad(Some(a.memory_addr))],
+        then: vec![],
+        else_: b_instructions,
+    });
+
+    Ok(result)
 }
 
-pub(crate) fn compile_or(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
-    assert_eq!(a.type_, b.type_);
-    assert_eq!(a.type_, Type::PrimitiveType(PrimitiveType::Boolean));
+/// Initializes the result to `a` and returns it,
+/// along with the instructions that evaluate `b` and overwrite the result with it.
+fn short_circuit<'ast>(
+    compiler: &mut Compiler<'ast, '_, '_>,
+    a: &Symbol,
+    b: impl FnOnce(&mut Compiler<'ast, '_, '_>) -> Result<Symbol>,
+) -> Result<(Symbol, Vec<encoder::Instruction<'ast>>)> {
+    ensure_eq_type!(a, Type::PrimitiveType(PrimitiveType::Boolean));
 
     let result = compiler
         .memory
         .allocate_symbol(Type::PrimitiveType(PrimitiveType::Boolean));
-    compiler
-        .memory
-        .read(compiler.instructions, a.memory_addr, a.type_.miden_width());
-    compiler
-        .memory
-        .read(compiler.instructions, b.memory_addr, b.type_.miden_width());
-    compiler.instructions.push(encoder::Instruction::Or);
     compiler.memory.write(
         compiler.instructions,
         result.memory_addr,
-        &[ValueSource::Stack],
+        &[ValueSource::Memory(a.memory_addr)],
+    );
+
+    let mut b_instructions = vec![];
+    let mut b_compiler = Compiler::new(&mut b_instructions, compiler.memory, compiler.root_scope);
+    let b = b(&mut b_compiler)?;
+    ensure_eq_type!(b, Type::PrimitiveType(PrimitiveType::Boolean));
+    b_compiler.memory.write(
+        b_compiler.instructions,
+        result.memory_addr,
+        &[ValueSource::Memory(b.memory_addr)],
     );
 
-    result
+    Ok((result, b_instructions))
 }
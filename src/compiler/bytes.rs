@@ -1 +1,224 @@
 // Layout: [len, data_ptr]
+
+use super::*;
+
+/// Allocates `value`, one byte per element, like [`string::new`] does for strings.
+pub(crate) fn new(compiler: &mut Compiler, value: &[u8]) -> Symbol {
+    let symbol = compiler.memory.allocate_symbol(Type::Bytes);
+    let data_addr = compiler.memory.allocate(value.len() as u32);
+
+    // The symbol is reused when this runs in a loop, so the length is written even if it's 0.
+    compiler.memory.write(
+        compiler.instructions,
+        symbol.memory_addr,
+        &[
+            ValueSource::Immediate(value.len() as u32),
+            ValueSource::Immediate(data_addr),
+        ],
+    );
+
+    compiler.memory.write(
+        compiler.instructions,
+        data_addr,
+        &value
+            .iter()
+            .map(|b| ValueSource::Immediate(*b as u32))
+            .collect::<Vec<_>>(),
+    );
+
+    symbol
+}
+
+/// The number of bytes of the big-endian length prefix.
+const LENGTH_PREFIX_WIDTH: u32 = 4;
+
+pub(super) fn builtins() -> impl Iterator<Item = (String, Option<TypeConstraint>, Function<'static>)>
+{
+    IntoIterator::into_iter([
+        (
+            "encodeLengthPrefixed",
+            Function::Builtin(|compiler, _scope, args| -> Result<Symbol> {
+                ensure!(
+                    args.len() == 1,
+                    ArgumentsCountSnafu {
+                        found: args.len(),
+                        expected: 1usize
+                    }
+                );
+                encode_length_prefixed(compiler, &args[0])
+            }),
+        ),
+        (
+            "decodeLengthPrefixed",
+            Function::Builtin(|compiler, _scope, args| -> Result<Symbol> {
+                ensure!(
+                    args.len() == 1,
+                    ArgumentsCountSnafu {
+                        found: args.len(),
+                        expected: 1usize
+                    }
+                );
+                decode_length_prefixed(compiler, &args[0])
+            }),
+        ),
+    ])
+    .map(|(name, func)| (name.to_string(), None, func))
+}
+
+/// Returns `value` prefixed with its length as a 4-byte big-endian integer.
+fn encode_length_prefixed(compiler: &mut Compiler, value: &Symbol) -> Result<Symbol> {
+    ensure_eq_type!(value, Type::Bytes);
+
+    let value_len = string::length(value);
+    let value_data_ptr = string::data_ptr(value);
+
+    let result = compiler.memory.allocate_symbol(Type::Bytes);
+    let result_len = string::length(&result);
+    let result_data_ptr = string::data_ptr(&result);
+
+    compiler
+        .instructions
+        .push(encoder::Instruction::MemLoad(Some(value_len.memory_addr)));
+    compiler
+        .instructions
+        .push(encoder::Instruction::Push(LENGTH_PREFIX_WIDTH));
+    compiler
+        .instructions
+        .push(encoder::Instruction::U32CheckedAdd);
+    // [value_len + 4]
+    compiler.memory.write(
+        compiler.instructions,
+        result_len.memory_addr,
+        &[ValueSource::Stack],
+    );
+
+    let allocated_ptr = dynamic_alloc(compiler, &[result_len])?;
+    compiler.memory.write(
+        compiler.instructions,
+        result_data_ptr.memory_addr,
+        &[ValueSource::Memory(allocated_ptr.memory_addr)],
+    );
+
+    for i in 0..LENGTH_PREFIX_WIDTH {
+        let shift = 8 * (LENGTH_PREFIX_WIDTH - 1 - i);
+        compiler
+            .instructions
+            .push(encoder::Instruction::MemLoad(Some(value_len.memory_addr)));
+        if shift > 0 {
+            compiler
+                .instructions
+                .push(encoder::Instruction::U32CheckedSHR(Some(shift)));
+        }
+        compiler.instructions.extend([
+            encoder::Instruction::Push(0xff),
+            encoder::Instruction::U32CheckedAnd,
+            // [byte]
+            encoder::Instruction::MemLoad(Some(result_data_ptr.memory_addr)),
+            encoder::Instruction::Push(i),
+            encoder::Instruction::U32CheckedAdd,
+            // [result_data_ptr + i, byte]
+            encoder::Instruction::MemStore(None),
+        ]);
+    }
+
+    compiler.instructions.extend([
+        encoder::Instruction::MemLoad(Some(result_data_ptr.memory_addr)),
+        encoder::Instruction::Push(LENGTH_PREFIX_WIDTH),
+        encoder::Instruction::U32CheckedAdd,
+        // [result_data_ptr + 4]
+        encoder::Instruction::MemLoad(Some(value_data_ptr.memory_addr)),
+        // [value_data_ptr, result_data_ptr + 4]
+        encoder::Instruction::MemLoad(Some(value_len.memory_addr)),
+        // [value_len, value_data_ptr, result_data_ptr + 4]
+    ]);
+    string::copy_str_stack(compiler);
+
+    Ok(result)
+}
+
+/// Strips the length prefix added by [`encode_length_prefixed`],
+/// failing if `value` is shorter than the prefix or the prefix doesn't match its length.
+fn decode_length_prefixed(compiler: &mut Compiler, value: &Symbol) -> Result<Symbol> {
+    ensure_eq_type!(value, Type::Bytes);
+
+    let value_len = string::length(value);
+    let value_data_ptr = string::data_ptr(value);
+    let assert_fn = compiler.root_scope.find_function("assert").unwrap();
+
+    let has_prefix = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Boolean));
+    compiler.instructions.extend([
+        encoder::Instruction::MemLoad(Some(value_len.memory_addr)),
+        encoder::Instruction::Push(LENGTH_PREFIX_WIDTH),
+        encoder::Instruction::U32CheckedGTE,
+        // [value_len >= 4]
+        encoder::Instruction::MemStore(Some(has_prefix.memory_addr)),
+    ]);
+    let (error_str, _) = string::new(compiler, "bytes are too short to have a length prefix");
+    compile_function_call(compiler, assert_fn, &[has_prefix, error_str], None)?;
+
+    let result = compiler.memory.allocate_symbol(Type::Bytes);
+    let result_len = string::length(&result);
+    let result_data_ptr = string::data_ptr(&result);
+
+    compiler.instructions.push(encoder::Instruction::Push(0));
+    for i in 0..LENGTH_PREFIX_WIDTH {
+        compiler.instructions.extend([
+            // [prefix]
+            encoder::Instruction::Push(256),
+            encoder::Instruction::U32CheckedMul,
+            encoder::Instruction::MemLoad(Some(value_data_ptr.memory_addr)),
+            encoder::Instruction::Push(i),
+            encoder::Instruction::U32CheckedAdd,
+            encoder::Instruction::MemLoad(None),
+            // [value_data_ptr[i], prefix * 256]
+            encoder::Instruction::U32CheckedAdd,
+        ]);
+    }
+    compiler.memory.write(
+        compiler.instructions,
+        result_len.memory_addr,
+        &[ValueSource::Stack],
+    );
+
+    let prefix_matches = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Boolean));
+    compiler.instructions.extend([
+        encoder::Instruction::MemLoad(Some(value_len.memory_addr)),
+        encoder::Instruction::Push(LENGTH_PREFIX_WIDTH),
+        encoder::Instruction::U32CheckedSub,
+        // [value_len - 4]
+        encoder::Instruction::MemLoad(Some(result_len.memory_addr)),
+        encoder::Instruction::U32CheckedEq,
+        // [value_len - 4 == prefix]
+        encoder::Instruction::MemStore(Some(prefix_matches.memory_addr)),
+    ]);
+    let (error_str, _) = string::new(
+        compiler,
+        "length prefix does not match the length of the bytes",
+    );
+    compile_function_call(compiler, assert_fn, &[prefix_matches, error_str], None)?;
+
+    let allocated_ptr = dynamic_alloc(compiler, std::slice::from_ref(&result_len))?;
+    compiler.memory.write(
+        compiler.instructions,
+        result_data_ptr.memory_addr,
+        &[ValueSource::Memory(allocated_ptr.memory_addr)],
+    );
+
+    compiler.instructions.extend([
+        encoder::Instruction::MemLoad(Some(result_data_ptr.memory_addr)),
+        // [result_data_ptr]
+        encoder::Instruction::MemLoad(Some(value_data_ptr.memory_addr)),
+        encoder::Instruction::Push(LENGTH_PREFIX_WIDTH),
+        encoder::Instruction::U32CheckedAdd,
+        // [value_data_ptr + 4, result_data_ptr]
+        encoder::Instruction::MemLoad(Some(result_len.memory_addr)),
+        // [result_len, value_data_ptr + 4, result_data_ptr]
+    ]);
+    string::copy_str_stack(compiler);
+
+    Ok(result)
+}
@@ -1,6 +1,9 @@
+use error::span::Span;
+
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) enum Instruction<'a> {
-    Comment(String),            // # ...
+    /// Carries the span of the code it was emitted for, see [`add_source_locations`].
+    Comment(String, Option<Span>), // # ...
     Drop,                       // drop
     Dropw,                      // dropw
     Push(u32),                  // push.1234
@@ -39,6 +42,7 @@ pub(crate) enum Instruction<'a> {
     U32WrappingSub,             // u32wrapping_sub
     U32WrappingMul,             // u32wrapping_mul
     U32OverflowingMul,          // u32overflowing_mul
+    U32Split,                   // u32split
     Exec(&'a str),              // exec.u64::checked_add
     MemStore(Option<u32>),      // mem_store.1234
     MemLoad(Option<u32>),       // mem_load.1234
@@ -64,6 +68,11 @@ pub(crate) enum Instruction<'a> {
     IfTrue,
     IfElse,
     IfEnd,
+    /// A statement with a breakpoint, compiled into its own procedure, see [`encode_procedures`].
+    Breakpoint {
+        procedure: String,
+        body: Vec<Instruction<'a>>,
+    },
     Abstract(AbstractInstruction<'a>),
 }
 
@@ -88,7 +97,7 @@ impl Instruction<'_> {
         }
 
         match self {
-            Instruction::Comment(s) => write_indent!(f, "# {}", s),
+            Instruction::Comment(s, _) => write_indent!(f, "# {}", s),
             Instruction::Drop => write_indent!(f, "drop"),
             Instruction::Dropw => write_indent!(f, "dropw"),
             Instruction::Push(value) => write_indent!(f, "push.{}", value),
@@ -131,6 +140,7 @@ impl Instruction<'_> {
             Instruction::U32WrappingSub => write_indent!(f, "u32wrapping_sub"),
             Instruction::U32WrappingMul => write_indent!(f, "u32wrapping_mul"),
             Instruction::U32OverflowingMul => write_indent!(f, "u32overflowing_mul"),
+            Instruction::U32Split => write_indent!(f, "u32split"),
             Instruction::Exec(name) => write_indent!(f, "exec.{}", name),
             Instruction::HMerge => write_indent!(f, "hmerge"),
             Instruction::While { condition, body } => {
@@ -211,6 +221,7 @@ impl Instruction<'_> {
             Instruction::IfTrue => write_indent!(f, "if.true"),
             Instruction::IfElse => write_indent!(f, "else"),
             Instruction::IfEnd => write_indent!(f, "end"),
+            Instruction::Breakpoint { procedure, .. } => write_indent!(f, "exec.{}", procedure),
             Instruction::Abstract(_) => {
                 unreachable!("abstract instructions should be unabstracted before encoding")
             }
@@ -332,6 +343,20 @@ pub(crate) fn unabstract<'a>(
                             ),
                         });
                     }
+                    // The body always runs, so it can break or return like the code around it.
+                    Instruction::Breakpoint { procedure, body } => {
+                        result.push(Instruction::Breakpoint {
+                            procedure,
+                            body: unabstract(
+                                body,
+                                allocate,
+                                break_ptr,
+                                return_ptr,
+                                ptr_value_might_have_been_flipped,
+                                false,
+                            ),
+                        });
+                    }
                     other => result.push(other),
                 }
             };
@@ -414,6 +439,9 @@ pub(crate) fn walk(instructions: &[Instruction], f: &mut impl FnMut(&Instruction
                 walk(then, f);
                 walk(else_, f);
             }
+            Instruction::Breakpoint { body, .. } => {
+                walk(body, f);
+            }
             Instruction::Abstract(AbstractInstruction::InlinedFunction(func)) => {
                 walk(func, f);
             }
@@ -422,6 +450,124 @@ pub(crate) fn walk(instructions: &[Instruction], f: &mut impl FnMut(&Instruction
     }
 }
 
+pub(crate) fn walk_mut(instructions: &mut [Instruction], f: &mut impl FnMut(&mut Instruction)) {
+    for instruction in instructions {
+        f(instruction);
+        match instruction {
+            Instruction::While { condition, body } => {
+                walk_mut(condition, f);
+                walk_mut(body, f);
+            }
+            Instruction::WhileTrueRaw { instructions } => {
+                walk_mut(instructions, f);
+            }
+            Instruction::Repeat { instructions, .. } => {
+                walk_mut(instructions, f);
+            }
+            Instruction::If {
+                condition,
+                then,
+                else_,
+            } => {
+                walk_mut(condition, f);
+                walk_mut(then, f);
+                walk_mut(else_, f);
+            }
+            Instruction::Breakpoint { body, .. } => {
+                walk_mut(body, f);
+            }
+            Instruction::Abstract(AbstractInstruction::InlinedFunction(func)) => {
+                walk_mut(func, f);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Writes a `proc` for every [`Instruction::Breakpoint`] in `instructions`,
+/// which must come before the `begin` of the program that `exec`s them.
+/// Procedures are written before the ones that `exec` them.
+pub(crate) fn encode_procedures(
+    instructions: &[Instruction],
+    f: &mut impl std::io::Write,
+) -> std::io::Result<()> {
+    let mut procedures = vec![];
+    walk_post_order(instructions, &mut |instruction| {
+        if let Instruction::Breakpoint { procedure, body } = instruction {
+            procedures.push((procedure, body));
+        }
+    });
+
+    for (procedure, body) in procedures {
+        writeln!(f, "proc.{procedure}")?;
+        for instruction in body {
+            instruction.encode(f, 1)?;
+            f.write_all(b"\n")?;
+        }
+        // A procedure can't be empty.
+        if body
+            .iter()
+            .all(|instruction| matches!(instruction, Instruction::Comment(..)))
+        {
+            f.write_all(b"  push.0\n  drop\n")?;
+        }
+        f.write_all(b"end\n")?;
+    }
+
+    Ok(())
+}
+
+/// Like [`walk`], but calls `f` on an instruction after the instructions nested in it.
+fn walk_post_order<'i, 'a>(
+    instructions: &'i [Instruction<'a>],
+    f: &mut impl FnMut(&'i Instruction<'a>),
+) {
+    for instruction in instructions {
+        match instruction {
+            Instruction::While { condition, body } => {
+                walk_post_order(condition, f);
+                walk_post_order(body, f);
+            }
+            Instruction::WhileTrueRaw { instructions } => {
+                walk_post_order(instructions, f);
+            }
+            Instruction::Repeat { instructions, .. } => {
+                walk_post_order(instructions, f);
+            }
+            Instruction::If {
+                condition,
+                then,
+                else_,
+            } => {
+                walk_post_order(condition, f);
+                walk_post_order(then, f);
+                walk_post_order(else_, f);
+            }
+            Instruction::Breakpoint { body, .. } => {
+                walk_post_order(body, f);
+            }
+            Instruction::Abstract(AbstractInstruction::InlinedFunction(func)) => {
+                walk_post_order(func, f);
+            }
+            _ => {}
+        }
+        f(instruction);
+    }
+}
+
+/// Prefixes every comment that carries a span with the line and column
+/// of `source` that the span starts at.
+pub(crate) fn add_source_locations(instructions: &mut [Instruction], source: &str) {
+    walk_mut(instructions, &mut |inst| {
+        if let Instruction::Comment(comment, span) = inst {
+            if let Some(span) = span.take() {
+                let (line, column) = span.start_line_col(source);
+                *comment = format!("{line}:{column}: {comment}");
+            }
+        }
+    });
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
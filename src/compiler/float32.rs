@@ -915,6 +915,179 @@ pub(crate) fn sub(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
     add_impl(compiler)
 }
 
+/// Clears the sign bit, so that NaN stays NaN.
+pub(crate) fn abs(compiler: &mut Compiler, a: &Symbol) -> Symbol {
+    let result = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Float32));
+
+    compiler
+        .memory
+        .read(compiler.instructions, a.memory_addr, a.type_.miden_width());
+    compiler
+        .instructions
+        .extend([Instruction::Push(!SIGN_MASK), Instruction::U32CheckedAnd]);
+    compiler.memory.write(
+        compiler.instructions,
+        result.memory_addr,
+        &[ValueSource::Stack],
+    );
+
+    result
+}
+
+/// Whether `|a - b| <= epsilon`, which is false if any of them is NaN.
+pub(crate) fn approx_eq(
+    compiler: &mut Compiler,
+    a: &Symbol,
+    b: &Symbol,
+    epsilon: &Symbol,
+) -> Symbol {
+    let diff = sub(compiler, a, b);
+    let diff = abs(compiler, &diff);
+
+    lte(compiler, &diff, epsilon)
+}
+
+// Literals have to be exact f32 values, so constants are written as fractions:
+// 13176795 / 4194304 is pi, 11863283 / 8388608 is sqrt(2) and 11629080 / 16777216 is ln(2).
+lazy_static::lazy_static! {
+    // Reduces x into [-pi, pi] by subtracting 2pi * 4^k for k = 8..0,
+    // then into [-pi/2, pi/2], and evaluates the Taylor polynomial up to x^11.
+    static ref SIN: ast::Function = polylang_parser::parse_function(r#"
+        function sin(x: f32): f32 {
+            if (x > 100000 || x < 0 - 100000) {
+                error('sin argument must be between -100000 and 100000');
+            }
+
+            let step = 13176795 / 32;
+            while (step > 6) {
+                let half = step / 2;
+                while (x > half) {
+                    x = x - step;
+                }
+                while (x < 0 - half) {
+                    x = x + step;
+                }
+                step = step / 4;
+            }
+
+            let pi = step * 2;
+            if (x > step) x = pi - x;
+            if (x < 0 - step) x = 0 - pi - x;
+
+            let x2 = x * x;
+            let p = 1 / 39916800;
+            p = 1 / 362880 - x2 * p;
+            p = 1 / 5040 - x2 * p;
+            p = 1 / 120 - x2 * p;
+            p = 1 / 6 - x2 * p;
+            p = 1 - x2 * p;
+            return x * p;
+        }
+    "#).unwrap();
+    // Halves x until it's in [-0.5, 0.5], evaluates the Taylor polynomial
+    // up to x^8 and squares the result back.
+    static ref EXP: ast::Function = polylang_parser::parse_function(r#"
+        function exp(x: f32): f32 {
+            let tooSmall = x < 0 - 87;
+            if (tooSmall) x = 0;
+            if (x > 89) x = 89;
+
+            let n = 0;
+            while (x > 0.5 || x < 0 - 0.5) {
+                x = x / 2;
+                n = n + 1;
+            }
+
+            let p = 1 + x / 8;
+            p = 1 + x / 7 * p;
+            p = 1 + x / 6 * p;
+            p = 1 + x / 5 * p;
+            p = 1 + x / 4 * p;
+            p = 1 + x / 3 * p;
+            p = 1 + x / 2 * p;
+            p = 1 + x * p;
+
+            while (n > 0) {
+                p = p * p;
+                n = n - 1;
+            }
+
+            if (tooSmall) p = 0;
+            return p;
+        }
+    "#).unwrap();
+    // Splits x into m * 2^e with m in [sqrt(2)/2, sqrt(2)], then
+    // ln(m) = 2 * atanh(s) with s = (m - 1) / (m + 1), up to s^11.
+    static ref LN: ast::Function = polylang_parser::parse_function(r#"
+        function ln(x: f32): f32 {
+            if (x <= 0) {
+                error('ln argument must be positive');
+            }
+
+            let m = x;
+            let e = 0;
+            while (m >= 2 && e < 128) {
+                m = m * 0.5;
+                e = e + 1;
+            }
+            while (m < 1 && e > 0 - 150) {
+                m = m * 2;
+                e = e - 1;
+            }
+            if (m > 11863283 / 8388608) {
+                m = m * 0.5;
+                e = e + 1;
+            }
+
+            let s = (m - 1) / (m + 1);
+            let s2 = s * s;
+            let p = 1 / 11;
+            p = p * s2 + 1 / 9;
+            p = p * s2 + 1 / 7;
+            p = p * s2 + 1 / 5;
+            p = p * s2 + 1 / 3;
+            p = p * s2 + 1;
+
+            let result = e * (11629080 / 16777216) + 2 * s * p;
+            if (e >= 128) result = x;
+            return result;
+        }
+    "#).unwrap();
+}
+
+/// Sine of `x`, which must be in [-1e5, 1e5].
+///
+/// The absolute error is below 1e-6 for |x| <= pi. Reducing larger arguments
+/// loses precision, up to about 3e-8 * |x|.
+/// Expects `compiler.root_scope` to be the builtins scope.
+pub(crate) fn sin(compiler: &mut Compiler, x: &Symbol) -> Result<Symbol> {
+    Ok(compile_builtin_function_call(&SIN, compiler, std::slice::from_ref(x))?.unwrap())
+}
+
+/// Cosine of `x`, computed as `sin(pi/2 - x)`, with the same bounds as [`sin`].
+pub(crate) fn cos(compiler: &mut Compiler, x: &Symbol) -> Result<Symbol> {
+    let half_pi = new(compiler, std::f32::consts::FRAC_PI_2);
+    let x = sub(compiler, &half_pi, x);
+
+    sin(compiler, &x)
+}
+
+/// e^x, with a relative error below 1e-5 for |x| <= 10 and below 1e-4 elsewhere.
+/// Returns infinity above ~88.7 and 0 below -87, where the result isn't a normal f32.
+/// Expects `compiler.root_scope` to be the builtins scope.
+pub(crate) fn exp(compiler: &mut Compiler, x: &Symbol) -> Result<Symbol> {
+    Ok(compile_builtin_function_call(&EXP, compiler, std::slice::from_ref(x))?.unwrap())
+}
+
+/// Natural logarithm of `x`, which must be positive.
+/// The absolute error is below 2e-5, and the relative error below 5e-7.
+/// Expects `compiler.root_scope` to be the builtins scope.
+pub(crate) fn ln(compiler: &mut Compiler, x: &Symbol) -> Result<Symbol> {
+    Ok(compile_builtin_function_call(&LN, compiler, std::slice::from_ref(x))?.unwrap())
+}
+
 pub(crate) fn eq(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
     let result = compiler
         .memory
@@ -1 +1,21 @@
+use super::*;
+
 // Layout: [high, low]
+
+pub(crate) fn new(compiler: &mut Compiler, value: f64) -> Symbol {
+    let symbol = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Float64));
+
+    let bits = value.to_bits();
+    compiler.memory.write(
+        compiler.instructions,
+        symbol.memory_addr,
+        &[
+            ValueSource::Immediate((bits >> 32) as u32),
+            ValueSource::Immediate(bits as u32),
+        ],
+    );
+
+    symbol
+}
@@ -2,7 +2,6 @@ use super::*;
 
 // TODO: optimize the instructions for int32 artihmetic operations
 
-#[allow(dead_code)]
 pub(crate) fn new(compiler: &mut Compiler, value: i32) -> Symbol {
     let symbol = compiler
         .memory
@@ -85,25 +84,33 @@ fn abs_stack(compiler: &mut Compiler) {
         // [value == -2147483648, value]
         encoder::Instruction::AssertZero,
         // [value]
-        encoder::Instruction::If {
-            condition: vec![
-                encoder::Instruction::Dup(None),
-                // [value, value]
-                encoder::Instruction::U32CheckedSHR(Some(31)),
-                // [sign, value]
-            ],
-            then: vec![
-                // [value]
-                encoder::Instruction::U32CheckedNot,
-                // [~value]
-                encoder::Instruction::Push(1),
-                // [1, ~value]
-                encoder::Instruction::U32CheckedAdd,
-                // [~value + 1]
-            ],
-            else_: vec![],
-        },
     ]);
+    unsigned_abs_stack(compiler);
+    // [abs(value)]
+}
+
+/// Like `abs_stack`, but returns the absolute value as a u32,
+/// so `i32::MIN` gives 2^31 instead of failing.
+fn unsigned_abs_stack(compiler: &mut Compiler) {
+    // current stack: [value]
+    compiler.instructions.extend([encoder::Instruction::If {
+        condition: vec![
+            encoder::Instruction::Dup(None),
+            // [value, value]
+            encoder::Instruction::U32CheckedSHR(Some(31)),
+            // [sign, value]
+        ],
+        then: vec![
+            // [value]
+            encoder::Instruction::U32CheckedNot,
+            // [~value]
+            encoder::Instruction::Push(1),
+            // [1, ~value]
+            encoder::Instruction::U32CheckedAdd,
+            // [~value + 1]
+        ],
+        else_: vec![],
+    }]);
     // [abs(value)]
 }
 
@@ -369,28 +376,18 @@ pub(crate) fn mul(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
 }
 
 /// divides two int32s with overflow checking.
-// First overflow check: b == 0
+// First overflow check: b == 0, throws `division by zero`
 // Second overflow check: a == i32::MIN && b == -1
 pub(crate) fn div(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
     let result = compiler
         .memory
         .allocate_symbol(Type::PrimitiveType(PrimitiveType::Int32));
 
+    compile_check_divisor(compiler, b);
+
     prepare_stack_for_arithmetic(compiler, a, b);
     // current stack: [b, a, b_sign, a_sign]
 
-    // fail if b == 0
-    compiler.instructions.push(encoder::Instruction::Dup(None));
-    // [b, b, a, b_sign, a_sign]
-    compiler.instructions.push(encoder::Instruction::Push(0));
-    // [0, b, b, a, b_sign, a_sign]
-    compiler
-        .instructions
-        .push(encoder::Instruction::U32CheckedEq);
-    // [b == 0, b, a, b_sign, a_sign]
-    compiler.instructions.push(encoder::Instruction::AssertZero);
-    // [b, a, b_sign, a_sign]
-
     // fail if a == i32::MIN && b == -1
     compiler.instructions.push(encoder::Instruction::Dup(None));
     // [b, b, a, b_sign, a_sign]
@@ -471,14 +468,20 @@ pub(crate) fn div(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
     result
 }
 
-/// calculates the modulo of two int32s with overflow checking.
-// First overflow check: b == 0
-// Second overflow check: a == i32::MIN && b == -1
+/// calculates the modulo of two int32s, failing on division by zero.
+///
+/// The result takes the sign of the dividend `a`, and the sign of the divisor `b` is ignored,
+/// so `-7 % 3 == -1` and `7 % -3 == 1`. This matches JavaScript's `%` and Rust's `wrapping_rem`,
+/// and `a == (a / b) * b + a % b` holds with our truncating division.
+/// `i32::MIN % -1` is 0, where Rust's `%` panics.
+/// Int64 follows the same policy, see `int64::modulo`.
 pub(crate) fn modulo(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
     let result = compiler
         .memory
         .allocate_symbol(Type::PrimitiveType(PrimitiveType::Int32));
 
+    compile_check_divisor(compiler, b);
+
     compiler
         .memory
         .read(compiler.instructions, a.memory_addr, a.type_.miden_width());
@@ -488,53 +491,6 @@ pub(crate) fn modulo(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol
         .read(compiler.instructions, b.memory_addr, b.type_.miden_width());
     // [b, a]
 
-    compiler.instructions.push(encoder::Instruction::Dup(None));
-    // [b, b, a]
-    compiler.instructions.push(encoder::Instruction::Push(0));
-    // [0, b, b, a]
-    compiler
-        .instructions
-        .push(encoder::Instruction::U32CheckedEq);
-    // [b == 0, b, a]
-    compiler.instructions.push(encoder::Instruction::Not);
-    // [b != 0, b, a]
-    compiler.instructions.push(encoder::Instruction::Assert);
-    // [b, a]
-    // fails on a % 0
-
-    // assert(a != min() || b != negate(1), 'modInt32 overflow, dividing min by -1');
-    compiler.instructions.push(encoder::Instruction::Dup(None));
-    // [b, b, a]
-    compiler
-        .instructions
-        .push(encoder::Instruction::Push(-1i32 as u32));
-    // [-1, b, b, a]
-    compiler
-        .instructions
-        .push(encoder::Instruction::U32CheckedEq);
-    // [b == -1, b, a]
-    compiler.instructions.push(encoder::Instruction::Not);
-    // [b != -1, b, a]
-    compiler
-        .instructions
-        .push(encoder::Instruction::Dup(Some(2)));
-    // [a, b != -1, b, a]
-    compiler
-        .instructions
-        .push(encoder::Instruction::Push(i32::MIN as u32));
-    // [i32::MIN, a, b != -1, b, a]
-    compiler
-        .instructions
-        .push(encoder::Instruction::U32CheckedEq);
-    // [a == i32::MIN, b != -1, b, a]
-    compiler.instructions.push(encoder::Instruction::Not);
-    // [a != i32::MIN, b != -1, b, a]
-    compiler.instructions.push(encoder::Instruction::Or);
-    // [a != i32::MIN || b != -1, b, a]
-    compiler.instructions.push(encoder::Instruction::Assert);
-    // [b, a]
-    // fails on i32::MIN by -1
-
     compiler
         .instructions
         .push(encoder::Instruction::Dup(Some(1)));
@@ -546,11 +502,11 @@ pub(crate) fn modulo(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol
     compiler.instructions.push(encoder::Instruction::MovDown(2));
     // [b, a, a_sign]
 
-    abs_stack(compiler);
+    unsigned_abs_stack(compiler);
     // [abs(b), a, a_sign]
     compiler.instructions.push(encoder::Instruction::Swap);
     // [a, abs(b), a_sign]
-    abs_stack(compiler);
+    unsigned_abs_stack(compiler);
     // [abs(a), abs(b), a_sign]
     compiler.instructions.push(encoder::Instruction::Swap);
     // [abs(b), abs(a), a_sign]
@@ -592,11 +548,9 @@ pub(crate) fn modulo(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol
     result
 }
 
-fn shift(compiler: &mut Compiler, a: &Symbol, b: &Symbol, is_right: bool) -> Symbol {
-    let result = compiler
-        .memory
-        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Int32));
-
+/// Reads `a` and `b` onto the stack, failing if `b` is negative.
+/// Stack: [] -> [b, a]
+fn read_shift_operands(compiler: &mut Compiler, a: &Symbol, b: &Symbol) {
     compiler
         .memory
         .read(compiler.instructions, a.memory_addr, a.type_.miden_width());
@@ -605,16 +559,23 @@ fn shift(compiler: &mut Compiler, a: &Symbol, b: &Symbol, is_right: bool) -> Sym
         .memory
         .read(compiler.instructions, b.memory_addr, b.type_.miden_width());
     // [b, a]
+    compiler.instructions.extend([
+        encoder::Instruction::Dup(None),
+        // [b, b, a]
+        encoder::Instruction::U32CheckedSHR(Some(31)),
+        // [b_sign, b, a]
+        encoder::Instruction::AssertZero,
+        // [b, a]
+    ]);
+}
 
-    compiler.instructions.push(encoder::Instruction::Dup(None));
-    // [b, b, a]
-    compiler
-        .instructions
-        .push(encoder::Instruction::U32CheckedSHR(Some(31)));
-    // [b_sign, b, a]
-    compiler.instructions.push(encoder::Instruction::AssertZero);
+pub(crate) fn shift_left(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    let result = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Int32));
+
+    read_shift_operands(compiler, a, b);
     // [b, a]
-    // fails if shifting by a negative number
 
     compiler
         .instructions
@@ -636,14 +597,12 @@ fn shift(compiler: &mut Compiler, a: &Symbol, b: &Symbol, is_right: bool) -> Sym
     compiler.instructions.push(encoder::Instruction::Swap);
     // [abs(b), abs(a), a_sign]
 
-    compiler.instructions.push(if is_right {
-        encoder::Instruction::U32CheckedSHR(None)
-    } else {
-        encoder::Instruction::U32CheckedSHL(None)
-    });
-    // [abs(a) >> abs(b), a_sign]
+    compiler
+        .instructions
+        .push(encoder::Instruction::U32CheckedSHL(None));
+    // [abs(a) << abs(b), a_sign]
     compiler.instructions.push(encoder::Instruction::Swap);
-    // [a_sign, abs(a) >> abs(b)]
+    // [a_sign, abs(a) << abs(b)]
 
     let negation = {
         let mut instructions = Vec::new();
@@ -659,7 +618,7 @@ fn shift(compiler: &mut Compiler, a: &Symbol, b: &Symbol, is_right: bool) -> Sym
     compiler.instructions.push(encoder::Instruction::If {
         // if a_sign == 1
         condition: vec![],
-        // [abs(a) >> abs(b)]
+        // [abs(a) << abs(b)]
         then: negation,
         else_: vec![
             // do nothing, return the result as is
@@ -675,12 +634,72 @@ fn shift(compiler: &mut Compiler, a: &Symbol, b: &Symbol, is_right: bool) -> Sym
     result
 }
 
+/// Arithmetic shift, the sign bit is copied into the vacated bits, so `-8 >> 1 == -4` and `-1 >> 1 == -1`.
 pub(crate) fn shift_right(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
-    shift(compiler, a, b, true)
+    let result = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Int32));
+
+    read_shift_operands(compiler, a, b);
+    // [b, a]
+    compiler.instructions.push(encoder::Instruction::Swap);
+    // [a, b]
+
+    // For negative a, ~a is non-negative and a >> b == ~(~a >> b).
+    compiler.instructions.push(encoder::Instruction::If {
+        condition: vec![
+            encoder::Instruction::Dup(None),
+            // [a, a, b]
+            encoder::Instruction::U32CheckedSHR(Some(31)),
+            // [a_sign, a, b]
+        ],
+        then: vec![
+            encoder::Instruction::U32CheckedNot,
+            // [~a, b]
+            encoder::Instruction::Swap,
+            // [b, ~a]
+            encoder::Instruction::U32CheckedSHR(None),
+            // [~a >> b]
+            encoder::Instruction::U32CheckedNot,
+            // [~(~a >> b)]
+        ],
+        else_: vec![
+            encoder::Instruction::Swap,
+            // [b, a]
+            encoder::Instruction::U32CheckedSHR(None),
+            // [a >> b]
+        ],
+    });
+
+    compiler.memory.write(
+        compiler.instructions,
+        result.memory_addr,
+        &[ValueSource::Stack],
+    );
+
+    result
 }
 
-pub(crate) fn shift_left(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
-    shift(compiler, a, b, false)
+/// Logical shift (`>>>`), shifts in zeros, so `-1 >>> 28 == 15`.
+pub(crate) fn logical_shift_right(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    let result = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Int32));
+
+    read_shift_operands(compiler, a, b);
+    // [b, a]
+    compiler
+        .instructions
+        .push(encoder::Instruction::U32CheckedSHR(None));
+    // [a >>> b]
+
+    compiler.memory.write(
+        compiler.instructions,
+        result.memory_addr,
+        &[ValueSource::Stack],
+    );
+
+    result
 }
 
 /// Turns stack [b, a, b_sign, a_sign] into [a > b]
@@ -1406,11 +1425,6 @@ mod test {
 
         test!(0, 0, Err(miden::ExecutionError::FailedAssertion(_, _)));
         test!(1, 0, Err(miden::ExecutionError::FailedAssertion(_, _)));
-        test!(
-            i32::MIN,
-            -1,
-            Err(miden::ExecutionError::FailedAssertion(_, _))
-        );
 
         test!(0, 1, Ok(0));
         test!(1, 1, Ok(0));
@@ -1420,11 +1434,27 @@ mod test {
         test!(i32::MAX, 1, Ok(0));
         test!(i32::MAX, -1, Ok(0));
         test!(-1, i32::MAX, Ok(-1));
-        // TODO: fix this case
-        // test!(i32::MIN, 1, Ok(0));
+
+        // the result takes the sign of the dividend
+        test!(-7, 3, Ok(-1));
+        test!(7, -3, Ok(1));
+        test!(-7, -3, Ok(-1));
+        test!(7, 3, Ok(1));
+
+        // i32::MIN has no positive counterpart, but its remainder still fits
+        test!(i32::MIN, 1, Ok(0));
+        test!(i32::MIN, -1, Ok(0));
+        test!(i32::MIN, 3, Ok(-2));
+        test!(i32::MIN, i32::MAX, Ok(-1));
+        test!(i32::MIN, i32::MIN, Ok(0));
+        test!(i32::MAX, i32::MIN, Ok(i32::MAX));
     }
 
-    fn shift_right(a: i32, b: i32) -> Result<i32, miden::ExecutionError> {
+    fn shift_right(
+        op: fn(&mut Compiler, &Symbol, &Symbol) -> Symbol,
+        a: i32,
+        b: i32,
+    ) -> Result<i32, miden::ExecutionError> {
         let mut instructions = Vec::new();
         let mut memory = Memory::new();
         let scope = Scope::new();
@@ -1432,7 +1462,7 @@ mod test {
         let a = new(&mut compiler, a);
         let b = new(&mut compiler, b);
 
-        let result = super::shift_right(&mut compiler, &a, &b);
+        let result = op(&mut compiler, &a, &b);
         compiler.memory.read(
             compiler.instructions,
             result.memory_addr,
@@ -1464,7 +1494,7 @@ mod test {
     fn test_shift_right() {
         macro_rules! test {
             ($a:expr, $b:expr, $expected:pat_param) => {
-                let result = shift_right($a, $b);
+                let result = shift_right(super::shift_right, $a, $b);
                 assert!(
                     matches!(result, $expected),
                     "shift_right({}, {}) = {:?}, expected {}",
@@ -1482,9 +1512,38 @@ mod test {
         test!(-1, 0, Ok(-1));
         test!(-2, 1, Ok(-1));
         test!(-2, -1, Err(miden::ExecutionError::FailedAssertion(_, _)));
+        test!(-8, 1, Ok(-4));
+        test!(-1, 1, Ok(-1));
+        test!(-1, 31, Ok(-1));
+        test!(-7, 1, Ok(-4));
+        test!(i32::MAX, 30, Ok(1));
+        test!(i32::MIN, 0, Ok(i32::MIN));
+        test!(i32::MIN, 31, Ok(-1));
+    }
 
-        // TODO: fix this case
-        // test!(i32::MIN, 0, Ok(i32::MIN));
+    #[test]
+    fn test_logical_shift_right() {
+        macro_rules! test {
+            ($a:expr, $b:expr, $expected:pat_param) => {
+                let result = shift_right(super::logical_shift_right, $a, $b);
+                assert!(
+                    matches!(result, $expected),
+                    "logical_shift_right({}, {}) = {:?}, expected {}",
+                    $a,
+                    $b,
+                    result,
+                    stringify!($expected)
+                );
+            };
+        }
+
+        test!(0, 0, Ok(0));
+        test!(8, 1, Ok(4));
+        test!(-1, 0, Ok(-1));
+        test!(-1, 28, Ok(15));
+        test!(-8, 1, Ok(i32::MAX - 3));
+        test!(i32::MIN, 31, Ok(1));
+        test!(-2, -1, Err(miden::ExecutionError::FailedAssertion(_, _)));
     }
 
     fn shift_left(a: i32, b: i32) -> Result<i32, miden::ExecutionError> {
@@ -1607,4 +1666,76 @@ mod test {
         test!(-2, -1, Ok(false));
         test!(i32::MIN, 0, Ok(false));
     }
+
+    fn compare(
+        op: fn(&mut Compiler, &Symbol, &Symbol) -> Symbol,
+        a: i32,
+        b: i32,
+    ) -> Result<bool, miden::ExecutionError> {
+        let mut instructions = Vec::new();
+        let mut memory = Memory::new();
+        let scope = Scope::new();
+        let mut compiler = Compiler::new(&mut instructions, &mut memory, &scope);
+        let a = new(&mut compiler, a);
+        let b = new(&mut compiler, b);
+
+        let result = op(&mut compiler, &a, &b);
+        compiler.memory.read(
+            compiler.instructions,
+            result.memory_addr,
+            result.type_.miden_width(),
+        );
+
+        let mut program = "begin\n".to_string();
+        for instruction in &instructions {
+            instruction
+                .encode(unsafe { program.as_mut_vec() }, 1)
+                .unwrap();
+        }
+        program.push_str("\nend\n");
+
+        let host = DefaultHost::new(MemAdviceProvider::default());
+        let outputs = miden::execute(
+            &miden::Assembler::default().compile(&program).unwrap(),
+            miden::StackInputs::default(),
+            host,
+            ProvingOptions::default().exec_options,
+        )?;
+
+        let stack = outputs.stack_outputs().stack();
+
+        Ok(stack[0] != 0)
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let values = [0, 1, -1, 2, -2, i32::MAX, i32::MIN, i32::MIN + 1];
+
+        let ops: [(
+            &str,
+            fn(&mut Compiler, &Symbol, &Symbol) -> Symbol,
+            fn(&i32, &i32) -> bool,
+        ); 4] = [
+            ("gt", super::gt, i32::gt),
+            ("gte", super::gte, i32::ge),
+            ("lt", super::lt, i32::lt),
+            ("lte", super::lte, i32::le),
+        ];
+
+        for (name, op, expected) in ops {
+            for a in values {
+                for b in values {
+                    let result = compare(op, a, b);
+                    assert_eq!(
+                        result.ok(),
+                        Some(expected(&a, &b)),
+                        "{}({}, {})",
+                        name,
+                        a,
+                        b
+                    );
+                }
+            }
+        }
+    }
 }
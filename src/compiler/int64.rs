@@ -1 +1,589 @@
-// Layout: [high, low]
+use super::*;
+
+// Layout: [high, low], two's complement across both limbs
+pub(crate) fn new(compiler: &mut Compiler, value: i64) -> Symbol {
+    let symbol = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Int64));
+
+    // memory is zero-initialized, so we don't need to write for 0
+    if value != 0 {
+        compiler.memory.write(
+            compiler.instructions,
+            symbol.memory_addr,
+            &[
+                ValueSource::Immediate((value >> 32) as u32),
+                ValueSource::Immediate(value as u32),
+            ],
+        );
+    }
+
+    symbol
+}
+
+/// Negates the 64-bit value on top of the stack, wrapping on overflow.
+/// Stack: [high, low] -> [negate(n)_high, negate(n)_low]
+fn negate_stack(compiler: &mut Compiler) {
+    // [high, low]
+    compiler.instructions.push(encoder::Instruction::Push(0));
+    // [0, high, low]
+    compiler.instructions.push(encoder::Instruction::MovDown(2));
+    // [high, low, 0]
+    compiler.instructions.push(encoder::Instruction::Push(0));
+    // [0, high, low, 0]
+    compiler.instructions.push(encoder::Instruction::MovDown(3));
+    // [high, low, 0, 0]
+    compiler
+        .instructions
+        .push(encoder::Instruction::Exec("u64::wrapping_sub"));
+    // [0 - n]
+}
+
+/// Returns a boolean symbol that is true if `n` is negative.
+fn sign(compiler: &mut Compiler, n: &Symbol) -> Symbol {
+    let result = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Boolean));
+
+    compiler
+        .instructions
+        .push(encoder::Instruction::MemLoad(Some(n.memory_addr)));
+    // [high]
+    compiler
+        .instructions
+        .push(encoder::Instruction::U32CheckedSHR(Some(31)));
+    // [sign]
+    compiler.memory.write(
+        compiler.instructions,
+        result.memory_addr,
+        &[ValueSource::Stack],
+    );
+
+    result
+}
+
+/// Returns the absolute value of `n` as a UInt64.
+/// The absolute value of i64::MIN is 2^63, which fits in a UInt64.
+fn abs(compiler: &mut Compiler, n: &Symbol, n_sign: &Symbol) -> Symbol {
+    let result = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt64));
+
+    compiler
+        .memory
+        .read(compiler.instructions, n.memory_addr, n.type_.miden_width());
+    // [high, low]
+
+    let negation = {
+        let mut instructions = Vec::new();
+        let mut compiler = Compiler::new(&mut instructions, compiler.memory, compiler.root_scope);
+        negate_stack(&mut compiler);
+        instructions
+    };
+
+    compiler.instructions.push(encoder::Instruction::If {
+        condition: vec![encoder::Instruction::MemLoad(Some(n_sign.memory_addr))],
+        then: negation,
+        else_: vec![],
+    });
+    // [abs(n)_high, abs(n)_low]
+
+    compiler.memory.write(
+        compiler.instructions,
+        result.memory_addr,
+        &[ValueSource::Stack, ValueSource::Stack],
+    );
+
+    result
+}
+
+/// Pushes `n` onto the stack with the sign bit flipped: [high ^ 0x8000_0000, low].
+/// This maps i64::MIN..=i64::MAX onto 0..=u64::MAX in order,
+/// so signed values can be compared with the unsigned u64 procedures.
+fn read_biased(compiler: &mut Compiler, n: &Symbol) {
+    compiler
+        .memory
+        .read(compiler.instructions, n.memory_addr, n.type_.miden_width());
+    // [high, low]
+    compiler
+        .instructions
+        .push(encoder::Instruction::Push(0x8000_0000));
+    // [0x8000_0000, high, low]
+    compiler
+        .instructions
+        .push(encoder::Instruction::U32CheckedXOR);
+    // [high ^ 0x8000_0000, low]
+}
+
+fn compare(compiler: &mut Compiler, a: &Symbol, b: &Symbol, procedure: &'static str) -> Symbol {
+    let result = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Boolean));
+
+    read_biased(compiler, a);
+    // [a_high, a_low]
+    read_biased(compiler, b);
+    // [b_high, b_low, a_high, a_low]
+    compiler
+        .instructions
+        .push(encoder::Instruction::Exec(procedure));
+    // [result]
+    compiler.memory.write(
+        compiler.instructions,
+        result.memory_addr,
+        &[ValueSource::Stack],
+    );
+
+    result
+}
+
+pub(crate) fn gt(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    compare(compiler, a, b, "u64::checked_gt")
+}
+
+pub(crate) fn gte(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    compare(compiler, a, b, "u64::checked_gte")
+}
+
+pub(crate) fn lt(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    compare(compiler, a, b, "u64::checked_lt")
+}
+
+pub(crate) fn lte(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    compare(compiler, a, b, "u64::checked_lte")
+}
+
+/// Divides `a` by `b`, truncating towards zero.
+/// Fails on division by zero and on i64::MIN / -1, which overflows.
+pub(crate) fn div(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    let result = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Int64));
+
+    compile_check_divisor(compiler, b);
+
+    let a_sign = sign(compiler, a);
+    let b_sign = sign(compiler, b);
+    let abs_a = abs(compiler, a, &a_sign);
+    let abs_b = abs(compiler, b, &b_sign);
+
+    let quotient = uint64::div(compiler, &abs_a, &abs_b);
+
+    compiler
+        .memory
+        .read(compiler.instructions, quotient.memory_addr, 2);
+    // [quotient_high, quotient_low]
+
+    let negation = {
+        let mut instructions = Vec::new();
+        let mut compiler = Compiler::new(&mut instructions, compiler.memory, compiler.root_scope);
+        negate_stack(&mut compiler);
+        instructions
+    };
+
+    compiler.instructions.push(encoder::Instruction::If {
+        condition: vec![
+            encoder::Instruction::MemLoad(Some(a_sign.memory_addr)),
+            encoder::Instruction::MemLoad(Some(b_sign.memory_addr)),
+            // [b_sign, a_sign, quotient_high, quotient_low]
+            encoder::Instruction::Eq,
+            encoder::Instruction::Not,
+            // [a_sign != b_sign, quotient_high, quotient_low]
+        ],
+        // quotient <= 2^63, so negating it always fits
+        then: negation,
+        else_: vec![
+            // a positive result must not have the sign bit set,
+            // this only happens for i64::MIN / -1
+            encoder::Instruction::Dup(None),
+            encoder::Instruction::U32CheckedSHR(Some(31)),
+            encoder::Instruction::AssertZero,
+        ],
+    });
+    // [result_high, result_low]
+
+    compiler.memory.write(
+        compiler.instructions,
+        result.memory_addr,
+        &[ValueSource::Stack, ValueSource::Stack],
+    );
+
+    result
+}
+
+/// Calculates the remainder of `a / b`, the result takes the sign of `a`,
+/// following the same policy as `int32::modulo`, so `i64::MIN % -1` is 0.
+/// Fails on division by zero.
+pub(crate) fn modulo(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    let result = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Int64));
+
+    compile_check_divisor(compiler, b);
+
+    let a_sign = sign(compiler, a);
+    let b_sign = sign(compiler, b);
+    let abs_a = abs(compiler, a, &a_sign);
+    let abs_b = abs(compiler, b, &b_sign);
+
+    let remainder = uint64::modulo(compiler, &abs_a, &abs_b);
+
+    compiler
+        .memory
+        .read(compiler.instructions, remainder.memory_addr, 2);
+    // [remainder_high, remainder_low]
+
+    let negation = {
+        let mut instructions = Vec::new();
+        let mut compiler = Compiler::new(&mut instructions, compiler.memory, compiler.root_scope);
+        negate_stack(&mut compiler);
+        instructions
+    };
+
+    compiler.instructions.push(encoder::Instruction::If {
+        condition: vec![encoder::Instruction::MemLoad(Some(a_sign.memory_addr))],
+        // remainder < abs(b) <= 2^63, so negating it always fits
+        then: negation,
+        else_: vec![],
+    });
+    // [result_high, result_low]
+
+    compiler.memory.write(
+        compiler.instructions,
+        result.memory_addr,
+        &[ValueSource::Stack, ValueSource::Stack],
+    );
+
+    result
+}
+
+/// Flips all bits of the 64-bit value on top of the stack.
+/// Stack: [high, low] -> [~high, ~low]
+fn not_stack(compiler: &mut Compiler) {
+    compiler.instructions.extend([
+        encoder::Instruction::U32CheckedNot,
+        encoder::Instruction::Swap,
+        encoder::Instruction::U32CheckedNot,
+        encoder::Instruction::Swap,
+    ]);
+}
+
+/// Fails if the shift amount `b` is negative.
+fn check_shift_amount(compiler: &mut Compiler, b: &Symbol) {
+    compiler.instructions.extend([
+        encoder::Instruction::MemLoad(Some(b.memory_addr)),
+        encoder::Instruction::U32CheckedSHR(Some(31)),
+        encoder::Instruction::AssertZero,
+    ]);
+}
+
+/// Copies the 64-bit value of `n` into a new symbol of type `type_`,
+/// flipping all bits if `flip` is true.
+fn copy_not_if(compiler: &mut Compiler, n: &Symbol, flip: &Symbol, type_: PrimitiveType) -> Symbol {
+    let result = compiler.memory.allocate_symbol(Type::PrimitiveType(type_));
+
+    compiler
+        .memory
+        .read(compiler.instructions, n.memory_addr, 2);
+    // [high, low]
+
+    let flip_bits = {
+        let mut instructions = Vec::new();
+        let mut compiler = Compiler::new(&mut instructions, compiler.memory, compiler.root_scope);
+        not_stack(&mut compiler);
+        instructions
+    };
+
+    compiler.instructions.push(encoder::Instruction::If {
+        condition: vec![encoder::Instruction::MemLoad(Some(flip.memory_addr))],
+        then: flip_bits,
+        else_: vec![],
+    });
+
+    compiler.memory.write(
+        compiler.instructions,
+        result.memory_addr,
+        &[ValueSource::Stack, ValueSource::Stack],
+    );
+
+    result
+}
+
+/// Arithmetic shift, the sign bit is copied into the vacated bits, so `-8 >> 1 == -4`.
+/// Fails if `b` is negative.
+pub(crate) fn shift_right(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    check_shift_amount(compiler, b);
+
+    // For negative a, ~a is non-negative and a >> b == ~(~a >> b).
+    let a_sign = sign(compiler, a);
+    let complement = copy_not_if(compiler, a, &a_sign, PrimitiveType::UInt64);
+    let shifted = uint64::shift_right(compiler, &complement, b);
+
+    copy_not_if(compiler, &shifted, &a_sign, PrimitiveType::Int64)
+}
+
+/// Logical shift (`>>>`), shifts in zeros regardless of the sign.
+/// Fails if `b` is negative.
+pub(crate) fn logical_shift_right(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    check_shift_amount(compiler, b);
+
+    let result = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Int64));
+    let shifted = uint64::shift_right(compiler, a, b);
+
+    compiler
+        .memory
+        .read(compiler.instructions, shifted.memory_addr, 2);
+    compiler.memory.write(
+        compiler.instructions,
+        result.memory_addr,
+        &[ValueSource::Stack, ValueSource::Stack],
+    );
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use miden::{DefaultHost, MemAdviceProvider, ProvingOptions};
+
+    use super::*;
+
+    fn new(compiler: &mut Compiler, value: i64) -> Symbol {
+        let symbol = compiler
+            .memory
+            .allocate_symbol(Type::PrimitiveType(PrimitiveType::Int64));
+
+        compiler.memory.write(
+            compiler.instructions,
+            symbol.memory_addr,
+            &[
+                ValueSource::Immediate((value >> 32) as u32),
+                ValueSource::Immediate(value as u32),
+            ],
+        );
+
+        symbol
+    }
+
+    fn run_binary(
+        op: fn(&mut Compiler, &Symbol, &Symbol) -> Symbol,
+        a: i64,
+        b: i64,
+    ) -> Result<i64, miden::ExecutionError> {
+        let stack = run_binary_stack(op, a, b)?;
+
+        Ok(((stack[0] << 32) | stack[1]) as i64)
+    }
+
+    fn run_binary_stack(
+        op: fn(&mut Compiler, &Symbol, &Symbol) -> Symbol,
+        a: i64,
+        b: i64,
+    ) -> Result<Vec<u64>, miden::ExecutionError> {
+        let mut instructions = Vec::new();
+        let mut memory = Memory::new();
+        let scope = Scope::new();
+        let mut compiler = Compiler::new(&mut instructions, &mut memory, &scope);
+        let a = new(&mut compiler, a);
+        let b = new(&mut compiler, b);
+
+        let result = op(&mut compiler, &a, &b);
+        compiler.memory.read(
+            compiler.instructions,
+            result.memory_addr,
+            result.type_.miden_width(),
+        );
+
+        let mut program = "use.std::math::u64\nbegin\n".to_string();
+        for instruction in &instructions {
+            instruction
+                .encode(unsafe { program.as_mut_vec() }, 1)
+                .unwrap();
+        }
+        program.push_str("\nend\n");
+
+        let assembler = miden::Assembler::default()
+            .with_library(&miden_stdlib::StdLibrary::default())
+            .unwrap();
+        let host = DefaultHost::new(MemAdviceProvider::default());
+        let outputs = miden::execute(
+            &assembler.compile(&program).unwrap(),
+            miden::StackInputs::default(),
+            host,
+            ProvingOptions::default().exec_options,
+        )?;
+
+        Ok(outputs.stack_outputs().stack().to_vec())
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let values = [
+            0,
+            1,
+            -1,
+            2,
+            -2,
+            i32::MAX as i64,
+            i32::MIN as i64,
+            u32::MAX as i64,
+            -(u32::MAX as i64),
+            0x1_0000_0000,
+            -0x1_0000_0000,
+            i64::MAX,
+            i64::MIN,
+            i64::MIN + 1,
+        ];
+
+        let ops: [(
+            &str,
+            fn(&mut Compiler, &Symbol, &Symbol) -> Symbol,
+            fn(&i64, &i64) -> bool,
+        ); 4] = [
+            ("gt", super::gt, i64::gt),
+            ("gte", super::gte, i64::ge),
+            ("lt", super::lt, i64::lt),
+            ("lte", super::lte, i64::le),
+        ];
+
+        for (name, op, expected) in ops {
+            for a in values {
+                for b in values {
+                    let result = run_binary_stack(op, a, b).map(|stack| stack[0] != 0);
+                    assert_eq!(
+                        result.ok(),
+                        Some(expected(&a, &b)),
+                        "{}({}, {})",
+                        name,
+                        a,
+                        b
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_div() {
+        macro_rules! test {
+            ($a:expr, $b:expr, $expected:pat_param) => {
+                let result = run_binary(super::div, $a, $b);
+                assert!(
+                    matches!(result, $expected),
+                    "div({}, {}) = {:?}, expected {}",
+                    $a,
+                    $b,
+                    result,
+                    stringify!($expected)
+                );
+            };
+        }
+
+        test!(0, 1, Ok(0));
+        test!(42, 6, Ok(7));
+        test!(-42, 6, Ok(-7));
+        test!(42, -6, Ok(-7));
+        test!(-42, -6, Ok(7));
+        test!(43, 6, Ok(7));
+        test!(-43, 6, Ok(-7));
+        test!(0x3_0000_0003, 3, Ok(0x1_0000_0001));
+        test!(i64::MAX, 1, Ok(i64::MAX));
+        test!(i64::MAX, -1, Ok(-0x7FFF_FFFF_FFFF_FFFF));
+        test!(i64::MIN, 1, Ok(i64::MIN));
+        test!(i64::MIN, 2, Ok(-0x4000_0000_0000_0000));
+        test!(i64::MIN, i64::MIN, Ok(1));
+
+        test!(0, 0, Err(miden::ExecutionError::FailedAssertion(_, _)));
+        test!(1, 0, Err(miden::ExecutionError::FailedAssertion(_, _)));
+        test!(-1, 0, Err(miden::ExecutionError::FailedAssertion(_, _)));
+        test!(
+            i64::MIN,
+            -1,
+            Err(miden::ExecutionError::FailedAssertion(_, _))
+        );
+    }
+
+    #[test]
+    fn test_modulo() {
+        macro_rules! test {
+            ($a:expr, $b:expr, $expected:pat_param) => {
+                let result = run_binary(super::modulo, $a, $b);
+                assert!(
+                    matches!(result, $expected),
+                    "modulo({}, {}) = {:?}, expected {}",
+                    $a,
+                    $b,
+                    result,
+                    stringify!($expected)
+                );
+            };
+        }
+
+        test!(0, 1, Ok(0));
+        test!(42, 6, Ok(0));
+        test!(43, 6, Ok(1));
+        test!(-43, 6, Ok(-1));
+        test!(43, -6, Ok(1));
+        test!(-43, -6, Ok(-1));
+        test!(0x1_0000_0005, 0x1_0000_0000, Ok(5));
+
+        // the result takes the sign of the dividend
+        test!(-7, 3, Ok(-1));
+        test!(7, -3, Ok(1));
+        test!(-7, -3, Ok(-1));
+        test!(7, 3, Ok(1));
+        test!(i64::MIN, -1, Ok(0));
+        test!(i64::MIN, i64::MAX, Ok(-1));
+
+        test!(0, 0, Err(miden::ExecutionError::FailedAssertion(_, _)));
+        test!(1, 0, Err(miden::ExecutionError::FailedAssertion(_, _)));
+    }
+
+    #[test]
+    fn test_shift_right() {
+        macro_rules! test {
+            ($op:ident, $a:expr, $b:expr, $expected:pat_param) => {
+                let result = run_binary(super::$op, $a, $b);
+                assert!(
+                    matches!(result, $expected),
+                    "{}({}, {}) = {:?}, expected {}",
+                    stringify!($op),
+                    $a,
+                    $b,
+                    result,
+                    stringify!($expected)
+                );
+            };
+        }
+
+        test!(shift_right, -8, 1, Ok(-4));
+        test!(shift_right, -1, 1, Ok(-1));
+        test!(shift_right, -1, 63, Ok(-1));
+        test!(shift_right, -1, 64, Ok(-1));
+        test!(shift_right, 8, 1, Ok(4));
+        test!(shift_right, 8, 64, Ok(0));
+        test!(shift_right, i64::MIN, 0, Ok(i64::MIN));
+        test!(shift_right, i64::MIN, 32, Ok(-0x8000_0000));
+        test!(shift_right, -0x1_0000_0000, 4, Ok(-0x1000_0000));
+        test!(
+            shift_right,
+            1,
+            -1,
+            Err(miden::ExecutionError::FailedAssertion(_, _))
+        );
+
+        test!(logical_shift_right, -1, 60, Ok(15));
+        test!(logical_shift_right, -8, 1, Ok(i64::MAX - 3));
+        test!(logical_shift_right, 8, 1, Ok(4));
+        test!(logical_shift_right, -1, 0, Ok(-1));
+        test!(logical_shift_right, -1, 64, Ok(0));
+        test!(
+            logical_shift_right,
+            1,
+            -1,
+            Err(miden::ExecutionError::FailedAssertion(_, _))
+        );
+    }
+}
@@ -200,6 +200,99 @@ impl<'a> Builder<'a> {
     }
 }
 
+/// Checks the invariants codegen relies on: every reference points to an
+/// earlier expression and to an output that expression actually produces,
+/// every call matches the arity of a known function and both branches of an
+/// if produce the same number of values.
+fn validate(expressions: &[Expression], functions: &[Function]) -> Result<()> {
+    let num_outputs = |expr_index: usize| -> Result<usize> {
+        Ok(match &expressions[expr_index] {
+            Expression::Number(_) => 1,
+            Expression::FunctionCall { name, .. } => {
+                functions
+                    .iter()
+                    .rev()
+                    .find(|f| f.name == *name)
+                    .not_found("function", name)?
+                    .num_outputs
+            }
+            Expression::If { then, .. } => then.len(),
+        })
+    };
+
+    let check_ref = |expr_index: usize, expr_ref: &ExpressionRef| -> Result<()> {
+        if expr_ref.expr_index >= expr_index {
+            return Err(Error::simple(format!(
+                "expression {expr_index} references {} which is not defined before it",
+                expr_ref.to_string()
+            )));
+        }
+        let outputs = num_outputs(expr_ref.expr_index)?;
+        if expr_ref.nth_element >= outputs {
+            return Err(Error::simple(format!(
+                "expression {expr_index} references {} but expression {} only has {outputs} outputs",
+                expr_ref.to_string(),
+                expr_ref.expr_index
+            )));
+        }
+        Ok(())
+    };
+
+    for (expr_index, expr) in expressions.iter().enumerate() {
+        match expr {
+            Expression::Number(_) => {}
+            Expression::FunctionCall { name, args } => {
+                let func = functions
+                    .iter()
+                    .rev()
+                    .find(|f| f.name == *name)
+                    .not_found("function", name)?;
+                ensure!(
+                    func.num_args == args.len(),
+                    TypeMismatchSnafu {
+                        context: format!(
+                            "expression {expr_index} calls {name} with {} arguments but it requires {}",
+                            args.len(),
+                            func.num_args
+                        ),
+                    }
+                );
+                for arg in args {
+                    check_ref(expr_index, arg)?;
+                }
+            }
+            Expression::If {
+                condition,
+                then,
+                then_dependencies,
+                otherwise,
+                otherwise_dependencies,
+            } => {
+                ensure!(
+                    then.len() == otherwise.len(),
+                    TypeMismatchSnafu {
+                        context: format!(
+                            "expression {expr_index}: num exprs of then branch ({}) mismatches the else one ({})",
+                            then.len(),
+                            otherwise.len()
+                        ),
+                    }
+                );
+                for expr_ref in std::iter::once(condition)
+                    .chain(then)
+                    .chain(then_dependencies)
+                    .chain(otherwise)
+                    .chain(otherwise_dependencies)
+                {
+                    check_ref(expr_index, expr_ref)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Default)]
 struct Compiler<'a> {
     expressions: Vec<Expression>,
@@ -482,6 +575,20 @@ impl<'a> Compiler<'a> {
     }
 
     fn compile(mut self, outputs: &[ExpressionRef]) -> Result<Vec<encoder::Instruction<'a>>> {
+        #[cfg(debug_assertions)]
+        {
+            validate(&self.expressions, &self.functions).nest_err(|| "invalid IR".to_string())?;
+            for output in outputs {
+                ensure!(
+                    output.expr_index < self.expressions.len(),
+                    NotFoundSnafu {
+                        type_name: "expr",
+                        item: output.to_string(),
+                    }
+                );
+            }
+        }
+
         for output in outputs {
             self.compile_expr(output)?;
         }
@@ -702,4 +809,57 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_validate_rejects_malformed_ir() {
+        let functions = [Function {
+            name: Cow::Borrowed("u32wrapping_add"),
+            num_args: 2,
+            num_outputs: 1,
+            instruction: encoder::Instruction::U32WrappingAdd,
+            pure: true,
+        }];
+
+        let mut builder = Builder::new(&functions);
+        let a = builder.number(1);
+        let b = builder.number(2);
+        builder.call("u32wrapping_add", &[a, b]).unwrap();
+        let mut expressions = builder.build();
+        assert!(validate(&expressions, &functions).is_ok());
+
+        // The call refers to a second output of a number, which only has one.
+        expressions[2] = Expression::FunctionCall {
+            name: "u32wrapping_add".to_string(),
+            args: vec![ExpressionRef::new(0, 1), ExpressionRef::new(1, 0)],
+        };
+        let err = validate(&expressions, &functions).unwrap_err();
+        assert!(err.to_string().contains("only has 1 outputs"), "{err}");
+
+        // The call refers to itself.
+        expressions[2] = Expression::FunctionCall {
+            name: "u32wrapping_add".to_string(),
+            args: vec![ExpressionRef::new(0, 0), ExpressionRef::new(2, 0)],
+        };
+        let err = validate(&expressions, &functions).unwrap_err();
+        assert!(err.to_string().contains("not defined before it"), "{err}");
+
+        // The call has the wrong number of arguments.
+        expressions[2] = Expression::FunctionCall {
+            name: "u32wrapping_add".to_string(),
+            args: vec![ExpressionRef::new(0, 0)],
+        };
+        let err = validate(&expressions, &functions).unwrap_err();
+        assert!(err.to_string().contains("with 1 arguments"), "{err}");
+
+        // Release builds skip validation when compiling.
+        #[cfg(debug_assertions)]
+        {
+            let compiler = Compiler {
+                expressions,
+                functions: functions.to_vec(),
+                ..Default::default()
+            };
+            assert!(compiler.compile(&[ExpressionRef::new(2, 0)]).is_err());
+        }
+    }
 }
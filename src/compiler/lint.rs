@@ -0,0 +1,251 @@
+//! Checks on the AST that produce warnings rather than errors.
+
+use super::Diagnostic;
+use crate::ast::{self, Expression, ExpressionKind, Statement, StatementKind};
+
+struct Binding {
+    /// "variable" or "parameter".
+    kind: &'static str,
+    name: String,
+    span: Option<error::span::Span>,
+    used: bool,
+}
+
+#[derive(Default)]
+struct Lints {
+    /// Warn when a declaration shadows a binding from an enclosing scope.
+    shadowing: bool,
+    scopes: Vec<Vec<Binding>>,
+    warnings: Vec<Diagnostic>,
+}
+
+impl Lints {
+    fn push_scope(&mut self) {
+        self.scopes.push(vec![]);
+    }
+
+    fn pop_scope(&mut self) {
+        for binding in self.scopes.pop().unwrap_or_default() {
+            // Like in Rust, a leading underscore marks a variable as intentionally unused.
+            if !binding.used && !binding.name.starts_with('_') {
+                self.warnings.push(Diagnostic {
+                    message: format!("unused {}: {}", binding.kind, binding.name),
+                    span: binding.span,
+                    related_span: None,
+                });
+            }
+        }
+    }
+
+    fn declare(&mut self, kind: &'static str, name: &str, span: Option<ast::Span>) {
+        let span = span.map(Into::into);
+
+        if self.shadowing {
+            if let Some(shadowed) = self
+                .scopes
+                .iter()
+                .rev()
+                .skip(1)
+                .flat_map(|scope| scope.iter().rev())
+                .find(|binding| binding.name == name)
+            {
+                self.warnings.push(Diagnostic {
+                    message: format!("{kind} {name} shadows {} {name}", shadowed.kind),
+                    span,
+                    related_span: shadowed.span,
+                });
+            }
+        }
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(Binding {
+                kind,
+                name: name.to_owned(),
+                span,
+                used: false,
+            });
+        }
+    }
+
+    fn use_(&mut self, name: &str) {
+        if let Some(binding) = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .flat_map(|scope| scope.iter_mut().rev())
+            .find(|binding| binding.name == name)
+        {
+            binding.used = true;
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        self.push_scope();
+        for statement in statements {
+            self.statement(statement);
+        }
+        self.pop_scope();
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match &**statement {
+            StatementKind::Break => {}
+            StatementKind::If(ast::If {
+                condition,
+                then_statements,
+                else_statements,
+            }) => {
+                self.expression(condition);
+                self.statements(then_statements);
+                self.statements(else_statements);
+            }
+            StatementKind::While(ast::While {
+                condition,
+                statements,
+            }) => {
+                self.expression(condition);
+                self.statements(statements);
+            }
+            StatementKind::For(ast::For {
+                for_kind,
+                statements,
+            }) => {
+                self.push_scope();
+                match for_kind {
+                    ast::ForKind::Basic {
+                        initial_statement,
+                        condition,
+                        post_statement,
+                    } => {
+                        match initial_statement {
+                            ast::ForInitialStatement::Let(let_) => {
+                                self.let_(let_, statement.span())
+                            }
+                            ast::ForInitialStatement::Expression(expr) => self.expression(expr),
+                        }
+                        self.expression(condition);
+                        self.expression(post_statement);
+                    }
+                    ast::ForKind::ForEach {
+                        identifier,
+                        iterable,
+                        ..
+                    } => {
+                        self.expression(iterable);
+                        self.declare("variable", identifier, statement.span());
+                    }
+                }
+                self.statements(statements);
+                self.pop_scope();
+            }
+            StatementKind::Return(expr)
+            | StatementKind::Expression(expr)
+            | StatementKind::Throw(expr) => self.expression(expr),
+            StatementKind::Let(let_) => self.let_(let_, statement.span()),
+        }
+    }
+
+    fn let_(&mut self, let_: &ast::Let, span: Option<ast::Span>) {
+        self.expression(&let_.expression);
+        self.declare("variable", &let_.identifier, span);
+    }
+
+    fn expression(&mut self, expr: &Expression) {
+        match &**expr {
+            ExpressionKind::Primitive(_) | ExpressionKind::Boolean(_) | ExpressionKind::Null => {}
+            ExpressionKind::ArrowFunction(f) => {
+                self.push_scope();
+                for param in &f.parameters {
+                    self.declare("parameter", param, expr.span());
+                }
+                match &f.body {
+                    ast::ArrowFunctionBody::Expression(body) => self.expression(body),
+                    ast::ArrowFunctionBody::Statements(statements) => self.statements(statements),
+                }
+                self.pop_scope();
+            }
+            ExpressionKind::Ident(name) => self.use_(name),
+            ExpressionKind::Object(obj) => {
+                for (_, expr) in &obj.fields {
+                    self.expression(expr);
+                }
+            }
+            ExpressionKind::Array(exprs) => {
+                for expr in exprs {
+                    self.expression(expr);
+                }
+            }
+            ExpressionKind::Map(entries) => {
+                for (key, value) in entries {
+                    self.expression(key);
+                    self.expression(value);
+                }
+            }
+            ExpressionKind::Call(func, args) => {
+                self.expression(func);
+                for arg in args {
+                    self.expression(arg);
+                }
+            }
+            ExpressionKind::Increment(a)
+            | ExpressionKind::Not(a)
+            | ExpressionKind::BitNot(a)
+            | ExpressionKind::Negate(a)
+            | ExpressionKind::Dot(a, _) => self.expression(a),
+            ExpressionKind::Assign(a, b) => {
+                // Assigning to a variable doesn't read it, unlike assigning to an index or field of it.
+                if !matches!(&**a, ExpressionKind::Ident(_)) {
+                    self.expression(a);
+                }
+                self.expression(b);
+            }
+            ExpressionKind::AssignSub(a, b)
+            | ExpressionKind::AssignAdd(a, b)
+            | ExpressionKind::Or(a, b)
+            | ExpressionKind::And(a, b)
+            | ExpressionKind::NullishCoalesce(a, b)
+            | ExpressionKind::Equal(a, b)
+            | ExpressionKind::NotEqual(a, b)
+            | ExpressionKind::LessThan(a, b)
+            | ExpressionKind::LessThanOrEqual(a, b)
+            | ExpressionKind::GreaterThan(a, b)
+            | ExpressionKind::GreaterThanOrEqual(a, b)
+            | ExpressionKind::In(a, b)
+            | ExpressionKind::BitOr(a, b)
+            | ExpressionKind::BitXor(a, b)
+            | ExpressionKind::BitAnd(a, b)
+            | ExpressionKind::ShiftLeft(a, b)
+            | ExpressionKind::ShiftRight(a, b)
+            | ExpressionKind::UnsignedShiftRight(a, b)
+            | ExpressionKind::Add(a, b)
+            | ExpressionKind::Subtract(a, b)
+            | ExpressionKind::Multiply(a, b)
+            | ExpressionKind::Divide(a, b)
+            | ExpressionKind::Modulo(a, b)
+            | ExpressionKind::Exponent(a, b)
+            | ExpressionKind::Index(a, b) => {
+                self.expression(a);
+                self.expression(b);
+            }
+        }
+    }
+}
+
+/// Warns about parameters and `let` bindings in `function` that are never read,
+/// and, if `shadowing` is set, about declarations that shadow an outer one.
+pub(crate) fn check_function(function: &ast::Function, shadowing: bool) -> Vec<Diagnostic> {
+    let mut lint = Lints {
+        shadowing,
+        ..Default::default()
+    };
+    lint.push_scope();
+    for param in &function.parameters {
+        if let Some(default) = &param.default {
+            lint.expression(default);
+        }
+        lint.declare("parameter", &param.name, param.span);
+    }
+    lint.statements(&function.statements);
+    lint.pop_scope();
+    lint.warnings
+}
@@ -145,6 +145,10 @@ pub(crate) fn get(
         .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt32));
 
     compiler.instructions.extend(vec![
+        // The symbols are reused when `get` runs in a loop, so reset the result of the
+        // previous lookup. An empty map never runs the key comparison.
+        Instruction::Push(0),
+        Instruction::MemStore(Some(key_equality_bool.memory_addr)),
         // []
         Instruction::MemLoad(Some(array::length(&keys_ptr).memory_addr)),
         // [mapLength]
@@ -282,7 +286,15 @@ pub(crate) fn get(
 
                 inst
             },
-            else_: vec![],
+            else_: (0..found_value_symbol.type_.miden_width())
+                .flat_map(|i| {
+                    // missing keys read as the zero value
+                    [
+                        Instruction::Push(0),
+                        Instruction::MemStore(Some(found_value_symbol.memory_addr + i)),
+                    ]
+                })
+                .collect(),
         },
         // [index]
         Instruction::Drop,
@@ -7,6 +7,7 @@ mod float64;
 mod int32;
 mod int64;
 mod ir;
+mod lint;
 mod map;
 mod nullable;
 mod publickey;
@@ -16,10 +17,10 @@ mod uint64;
 
 use std::collections::HashMap;
 
-use abi::{Abi, PrimitiveType, StdVersion, Struct, Type};
+use abi::{Abi, Breakpoint, PrimitiveType, StdVersion, Struct, Type};
 use error::prelude::*;
 
-use crate::ast::{self, Expression, Statement};
+use crate::ast::{self, Expression, ExpressionKind, Statement};
 
 #[derive(Debug, Clone)]
 enum TypeConstraint {
@@ -38,9 +39,259 @@ impl TypeConstraint {
 
 macro_rules! comment {
     ($compiler:expr, $($arg:tt)*) => {
-        #[cfg(debug_assertions)]
-        $compiler.comment(format!($($arg)*));
+        if cfg!(debug_assertions) || debug_symbols_enabled() {
+            $compiler.comment(format!($($arg)*));
+        }
+    };
+}
+
+thread_local! {
+    /// Symbols added to any scope, collected only while compiling with [`CompileOptions::debug`].
+    static DEBUG_SYMBOLS: std::cell::RefCell<Option<Vec<DebugSymbol>>> = const { std::cell::RefCell::new(None) };
+    /// Non-fatal diagnostics emitted while compiling, returned in [`CompileOutput::warnings`].
+    static WARNINGS: std::cell::RefCell<Vec<Diagnostic>> = const { std::cell::RefCell::new(Vec::new()) };
+    /// [`CompileOptions::max_loop_iterations`] of the current compilation.
+    static DEFAULT_MAX_LOOP_ITERATIONS: std::cell::Cell<Option<u32>> = const { std::cell::Cell::new(None) };
+    /// The iteration limit for loops in the function being compiled.
+    static MAX_LOOP_ITERATIONS: std::cell::Cell<Option<u32>> = const { std::cell::Cell::new(None) };
+    /// Whether the function being compiled is a constructor, which can assign to `@immutable` fields.
+    static IN_CONSTRUCTOR: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+    /// The `// @breakpoint` comments in [`CompileOptions::source`] of the current compilation.
+    static BREAKPOINTS: std::cell::RefCell<Vec<BreakpointSite>> = const { std::cell::RefCell::new(Vec::new()) };
+    /// Whether a builtin written in Polylang is being compiled, whose spans point into its own source.
+    static IN_BUILTIN: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+struct BreakpointSite {
+    /// Where the statement the breakpoint is on starts in the source.
+    statement_start: usize,
+    /// The 1-based line the statement starts on.
+    line: usize,
+    /// The procedure of each copy of the statement that was compiled,
+    /// a function can be inlined more than once.
+    procedures: Vec<String>,
+}
+
+/// Finds `// @breakpoint` comments in `source`,
+/// each one applies to the statement at the start of the next line with code on it.
+fn find_breakpoints(source: &str) -> Vec<BreakpointSite> {
+    let mut sites: Vec<BreakpointSite> = vec![];
+    let mut pending = false;
+    let mut line_start = 0;
+    for (i, line) in source.split('\n').enumerate() {
+        let code = line.trim_start();
+        if code.starts_with("// @breakpoint") {
+            pending = true;
+        } else if pending && !code.is_empty() && !code.starts_with("//") {
+            pending = false;
+            sites.push(BreakpointSite {
+                statement_start: line_start + (line.len() - code.len()),
+                line: i + 1,
+                procedures: vec![],
+            });
+        }
+        line_start += line.len() + 1;
+    }
+    sites
+}
+
+/// If the statement starting at `span` has a breakpoint, returns a new procedure
+/// to compile it into.
+/// Statements of builtins never have one, their spans are offsets into the builtin's source.
+fn breakpoint_at(span: Option<ast::Span>) -> Option<String> {
+    if IN_BUILTIN.with(|c| c.get()) {
+        return None;
+    }
+    let span = span?;
+    BREAKPOINTS.with(|sites| {
+        let mut sites = sites.borrow_mut();
+        let count = sites
+            .iter()
+            .map(|site| site.procedures.len())
+            .sum::<usize>();
+        let site = sites
+            .iter_mut()
+            .find(|site| site.statement_start == span.start)?;
+        let procedure = format!("breakpoint_{count}");
+        site.procedures.push(procedure.clone());
+        Some(procedure)
+    })
+}
+
+/// Sets the loop iteration limit while compiling a function, restoring the caller's when dropped.
+struct MaxLoopIterationsGuard(Option<u32>);
+
+impl MaxLoopIterationsGuard {
+    fn set(max_iterations: Option<u32>) -> Self {
+        MaxLoopIterationsGuard(MAX_LOOP_ITERATIONS.with(|max| max.replace(max_iterations)))
+    }
+}
+
+impl Drop for MaxLoopIterationsGuard {
+    fn drop(&mut self) {
+        MAX_LOOP_ITERATIONS.with(|max| max.set(self.0));
+    }
+}
+
+/// Marks whether a constructor is being compiled, restoring the caller's state when dropped.
+struct InConstructorGuard(bool);
+
+impl InConstructorGuard {
+    fn set(in_constructor: bool) -> Self {
+        InConstructorGuard(IN_CONSTRUCTOR.with(|c| c.replace(in_constructor)))
+    }
+}
+
+impl Drop for InConstructorGuard {
+    fn drop(&mut self) {
+        IN_CONSTRUCTOR.with(|c| c.set(self.0));
+    }
+}
+
+/// Marks whether a builtin written in Polylang is being compiled, restoring the caller's state when dropped.
+struct InBuiltinGuard(bool);
+
+impl InBuiltinGuard {
+    fn set(in_builtin: bool) -> Self {
+        InBuiltinGuard(IN_BUILTIN.with(|c| c.replace(in_builtin)))
+    }
+}
+
+impl Drop for InBuiltinGuard {
+    fn drop(&mut self) {
+        IN_BUILTIN.with(|c| c.set(self.0));
+    }
+}
+
+/// The `@immutable` field of `this` that `expression` is, or is a part of.
+/// A variable copied from an immutable field counts as a part of it,
+/// because copying an array or a map doesn't copy its elements.
+fn immutable_field(expression: &Expression, scope: &Scope) -> Option<String> {
+    let mut expression = expression;
+    let field = loop {
+        match &**expression {
+            ExpressionKind::Dot(object, field) => match &***object {
+                ExpressionKind::Ident(id) if id == "this" => break field,
+                _ => expression = object,
+            },
+            ExpressionKind::Index(object, _) => expression = object,
+            ExpressionKind::Ident(id) => {
+                let symbol = scope.find_symbol(id)?;
+                return scope.find_immutable_alias(symbol.memory_addr);
+            }
+            _ => return None,
+        }
+    };
+
+    let Some(Type::Struct(this_struct)) = scope.find_symbol("this").map(|this| this.type_) else {
+        return None;
+    };
+    scope
+        .find_contract(&this_struct.name)
+        .and_then(|c| c.fields.iter().find(|f| &f.name == field))
+        .filter(|f| f.immutable)
+        .map(|f| f.name.clone())
+}
+
+/// Errors if `target` is an `@immutable` field of `this`, or a part of one,
+/// and the function being compiled is not the constructor.
+fn check_immutable_assignment(target: &Expression, scope: &Scope) -> Result<()> {
+    // Assigning to a variable replaces it, and leaves the field it was copied from alone.
+    if matches!(&**target, ExpressionKind::Ident(_)) || IN_CONSTRUCTOR.with(|c| c.get()) {
+        return Ok(());
+    }
+
+    match immutable_field(target, scope) {
+        Some(field) => Err(Error::simple(format!(
+            "field {field} is @immutable, it can only be assigned in the constructor"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Errors if `method` changes the array `object` in place, `object` is an `@immutable` field of `this`,
+/// or a part of one, and the function being compiled is not the constructor.
+fn check_immutable_method_call(object: &Expression, method: &str, scope: &Scope) -> Result<()> {
+    if !matches!(method, "push" | "splice" | "unshift" | "copyWithin")
+        || IN_CONSTRUCTOR.with(|c| c.get())
+    {
+        return Ok(());
+    }
+
+    match immutable_field(object, scope) {
+        Some(field) => Err(Error::simple(format!(
+            "field {field} is @immutable, it can only be changed by {method} in the constructor"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Returns the `n` of a `@maxIterations(n)` decorator on `function`.
+fn max_iterations_decorator(function: &ast::Function) -> Result<Option<u32>> {
+    let Some(decorator) = function
+        .decorators
+        .iter()
+        .find(|d| d.name == "maxIterations")
+    else {
+        return Ok(None);
     };
+
+    match decorator.arguments.as_slice() {
+        [ast::DecoratorArgument::Number(n)]
+            if *n >= 1.0 && n.fract() == 0.0 && *n <= u32::MAX as f64 =>
+        {
+            Ok(Some(*n as u32))
+        }
+        _ => Err(Error::simple("@maxIterations takes a positive integer")),
+    }
+}
+
+fn warn(message: String) {
+    WARNINGS.with(|warnings| {
+        warnings.borrow_mut().push(Diagnostic {
+            message,
+            span: error::span::current(),
+            related_span: None,
+        })
+    });
+}
+
+/// Returns the warning for using a `@deprecated` item, if it has that decorator.
+fn deprecation_warning(kind: &str, name: &str, decorators: &[ast::Decorator]) -> Option<String> {
+    let decorator = decorators.iter().find(|d| d.name == "deprecated")?;
+
+    Some(match decorator.arguments.first() {
+        Some(ast::DecoratorArgument::String(note)) => {
+            format!("{kind} {name} is deprecated: {note}")
+        }
+        _ => format!("{kind} {name} is deprecated"),
+    })
+}
+
+fn debug_symbols_enabled() -> bool {
+    DEBUG_SYMBOLS.with(|symbols| symbols.borrow().is_some())
+}
+
+/// Stops collecting debug symbols when dropped, even if compilation fails.
+struct DebugSymbolsGuard;
+
+impl DebugSymbolsGuard {
+    fn start() -> Self {
+        DEBUG_SYMBOLS.with(|symbols| *symbols.borrow_mut() = Some(vec![]));
+        DebugSymbolsGuard
+    }
+
+    fn finish(self) -> Vec<DebugSymbol> {
+        DEBUG_SYMBOLS
+            .with(|symbols| symbols.borrow_mut().take())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for DebugSymbolsGuard {
+    fn drop(&mut self) {
+        DEBUG_SYMBOLS.with(|symbols| *symbols.borrow_mut() = None);
+    }
 }
 
 lazy_static::lazy_static! {
@@ -684,7 +935,7 @@ lazy_static::lazy_static! {
         builtins.push(("uint32ToString".to_string(), None, Function::Builtin(|compiler, _, args| {
             let old_root_scope = compiler.root_scope;
             compiler.root_scope = &BUILTINS_SCOPE;
-            let result = compile_ast_function_call(&UINT32_TO_STRING, compiler, args, None)?.unwrap();
+            let result = compile_builtin_function_call(&UINT32_TO_STRING, compiler, args)?.unwrap();
             compiler.root_scope = old_root_scope;
             Ok(result)
         })));
@@ -761,6 +1012,16 @@ lazy_static::lazy_static! {
             Ok(symbol)
         })));
 
+        builtins.push(("uint64WrappingMul".to_string(), None, Function::Builtin(|compiler, _, args| {
+            ensure!(args.len() == 2, ArgumentsCountSnafu { found: args.len(), expected: 2usize });
+            let a = &args[0];
+            let b = &args[1];
+            ensure_eq_type!(a, Type::PrimitiveType(PrimitiveType::UInt64));
+            ensure_eq_type!(b, Type::PrimitiveType(PrimitiveType::UInt64));
+
+            Ok(uint64::wrapping_mul(compiler, a, b))
+        })));
+
         builtins.push(("uint32CheckedXor".to_string(), None, Function::Builtin(|compiler, _, args| {
             ensure!(args.len() == 2, ArgumentsCountSnafu { found: args.len(), expected: 2usize });
             let a = &args[0];
@@ -802,6 +1063,39 @@ lazy_static::lazy_static! {
             Ok(symbol)
         })));
 
+        builtins.push((
+            "approxEqual".to_string(),
+            None,
+            Function::Builtin(|compiler, _, args| {
+                ensure!(args.len() == 3, ArgumentsCountSnafu { found: args.len(), expected: 3usize });
+                for arg in args {
+                    ensure_eq_type!(arg, Type::PrimitiveType(PrimitiveType::Float32));
+                }
+
+                Ok(float32::approx_eq(compiler, &args[0], &args[1], &args[2]))
+            }),
+        ));
+
+        builtins.push((
+            "clamp".to_string(),
+            None,
+            Function::Builtin(|compiler, _, args| {
+                ensure!(args.len() == 3, ArgumentsCountSnafu { found: args.len(), expected: 3usize });
+
+                compile_clamp(compiler, &args[0], &args[1], &args[2])
+            }),
+        ));
+
+        let float32_functions: [(&str, BuiltinFn); 4] = [
+            ("sin", |compiler, _, args| compile_float32_function(compiler, args, float32::sin)),
+            ("cos", |compiler, _, args| compile_float32_function(compiler, args, float32::cos)),
+            ("exp", |compiler, _, args| compile_float32_function(compiler, args, float32::exp)),
+            ("ln", |compiler, _, args| compile_float32_function(compiler, args, float32::ln)),
+        ];
+        for (name, function) in float32_functions {
+            builtins.push((name.to_string(), None, Function::Builtin(function)));
+        }
+
         builtins.push((
             "toHex".to_string(),
             Some(TypeConstraint::Exact(Type::PublicKey)),
@@ -844,6 +1138,46 @@ lazy_static::lazy_static! {
             }),
         ));
 
+        builtins.push((
+            "sum".to_string(),
+            Some(TypeConstraint::Array),
+            Function::Builtin(|compiler, _, args| {
+                ensure!(args.len() == 1, ArgumentsCountSnafu { found: args.len(), expected: 1usize });
+
+                array::sum(compiler, &args[0])
+            }),
+        ));
+
+        builtins.push((
+            "average".to_string(),
+            Some(TypeConstraint::Array),
+            Function::Builtin(|compiler, _, args| {
+                ensure!(args.len() == 1, ArgumentsCountSnafu { found: args.len(), expected: 1usize });
+
+                array::average(compiler, &args[0])
+            }),
+        ));
+
+        builtins.push((
+            "min".to_string(),
+            Some(TypeConstraint::Array),
+            Function::Builtin(|compiler, _, args| {
+                ensure!(args.len() == 1, ArgumentsCountSnafu { found: args.len(), expected: 1usize });
+
+                array::min(compiler, &args[0])
+            }),
+        ));
+
+        builtins.push((
+            "max".to_string(),
+            Some(TypeConstraint::Array),
+            Function::Builtin(|compiler, _, args| {
+                ensure!(args.len() == 1, ArgumentsCountSnafu { found: args.len(), expected: 1usize });
+
+                array::max(compiler, &args[0])
+            }),
+        ));
+
         builtins.push((
             "push".to_string(),
             Some(TypeConstraint::Array),
@@ -888,6 +1222,21 @@ lazy_static::lazy_static! {
             }),
         ));
 
+        builtins.push((
+            "copyWithin".to_string(),
+            Some(TypeConstraint::Array),
+            Function::Builtin(|compiler, _scope, args| {
+                // `args` starts with the array, which isn't counted in the error.
+                ensure!((3..=4).contains(&args.len()), ArgumentsCountSnafu { found: args.len() - 1, expected: 2..=3usize });
+                let arr = &args[0];
+                let target = &args[1];
+                let start = &args[2];
+                let end = args.get(3);
+
+                array::copy_within(compiler, arr, target, start, end)
+            }),
+        ));
+
         builtins.push((
             "mapLength".to_string(),
             None,
@@ -947,7 +1296,29 @@ lazy_static::lazy_static! {
             }),
         ));
 
+        builtins.push((
+            "prng".to_string(),
+            None,
+            Function::Builtin(|compiler, _scope, args| {
+                ensure!(args.len() == 1, ArgumentsCountSnafu { found: args.len(), expected: 1usize });
+                ensure_eq_type!(args[0], Type::PrimitiveType(PrimitiveType::UInt64));
+
+                Ok(prng(compiler, &args[0]))
+            }),
+        ));
+
+        builtins.push((
+            "hash".to_string(),
+            None,
+            Function::Builtin(|compiler, _scope, args| {
+                ensure!(args.len() == 1, ArgumentsCountSnafu { found: args.len(), expected: 1usize });
+
+                hash(compiler, args[0].clone())
+            }),
+        ));
+
         builtins.extend(string::builtins());
+        builtins.extend(bytes::builtins());
 
         Box::leak(Box::new(builtins))
     };
@@ -1012,6 +1383,10 @@ pub(crate) struct ContractField {
     type_: Type,
     delegate: bool,
     read: bool,
+    /// Whether the field is `@immutable`, so that only the constructor can assign to it.
+    immutable: bool,
+    /// The warning to emit when the field is used.
+    deprecated: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -1023,6 +1398,8 @@ struct Contract<'ast> {
     read_directive: bool,
 }
 
+/// The fields keep the order they were declared in, with inherited fields first
+/// (see `resolve_inheritance`), which [`Struct::fields`] relies on.
 impl From<Contract<'_>> for Struct {
     fn from(contract: Contract<'_>) -> Self {
         let mut fields = Vec::new();
@@ -1045,6 +1422,26 @@ enum Function<'ast> {
     Builtin(BuiltinFn),
 }
 
+impl Function<'_> {
+    /// Whether the function can be called with `count` arguments.
+    /// Builtins check their own arguments, so they accept any count.
+    fn accepts_args_count(&self, count: usize) -> bool {
+        match self {
+            Function::Ast(f) => {
+                let variadic = f.parameters.last().is_some_and(|p| p.variadic);
+                let required = f
+                    .parameters
+                    .iter()
+                    .filter(|p| p.required && p.default.is_none() && !p.variadic)
+                    .count();
+
+                required <= count && (variadic || count <= f.parameters.len())
+            }
+            Function::Builtin(_) => true,
+        }
+    }
+}
+
 impl std::fmt::Debug for Function<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -1059,6 +1456,8 @@ pub(crate) struct Scope<'ast, 'b> {
     parent: Option<&'b Scope<'ast, 'b>>,
     symbols: Vec<(String, Symbol)>,
     non_null_symbol_addrs: Vec<u32>,
+    /// The addresses of variables copied from an `@immutable` field, with the name of the field.
+    immutable_aliases: Vec<(u32, String)>,
     functions: Vec<(String, Function<'ast>)>,
     methods: Vec<(TypeConstraint, String, Function<'ast>)>,
     contracts: Vec<(String, Contract<'ast>)>,
@@ -1070,6 +1469,7 @@ impl<'ast> Scope<'ast, '_> {
             parent: None,
             symbols: vec![],
             non_null_symbol_addrs: vec![],
+            immutable_aliases: vec![],
             functions: vec![],
             methods: vec![],
             contracts: vec![],
@@ -1081,6 +1481,7 @@ impl<'ast> Scope<'ast, '_> {
             parent: Some(self),
             symbols: vec![],
             non_null_symbol_addrs: vec![],
+            immutable_aliases: vec![],
             functions: vec![],
             methods: vec![],
             contracts: vec![],
@@ -1088,9 +1489,34 @@ impl<'ast> Scope<'ast, '_> {
     }
 
     fn add_symbol(&mut self, name: String, symbol: Symbol) {
+        DEBUG_SYMBOLS.with(|symbols| {
+            if let Some(symbols) = symbols.borrow_mut().as_mut() {
+                symbols.push(DebugSymbol {
+                    name: name.clone(),
+                    memory_addr: symbol.memory_addr,
+                    type_: symbol.type_.clone(),
+                });
+            }
+        });
         self.symbols.push((name, symbol));
     }
 
+    fn add_immutable_alias(&mut self, memory_addr: u32, field: String) {
+        self.immutable_aliases.push((memory_addr, field));
+    }
+
+    fn find_immutable_alias(&self, memory_addr: u32) -> Option<String> {
+        if let Some((_, field)) = self
+            .immutable_aliases
+            .iter()
+            .find(|(addr, _)| *addr == memory_addr)
+        {
+            return Some(field.clone());
+        }
+
+        self.parent?.find_immutable_alias(memory_addr)
+    }
+
     fn find_symbol(&self, name: &str) -> Option<Symbol> {
         if let Some(symbol) = self
             .symbols
@@ -1131,6 +1557,45 @@ impl<'ast> Scope<'ast, '_> {
         None
     }
 
+    /// Finds the function `name` that can be called with `args_count` arguments,
+    /// which lets functions be overloaded by their number of parameters.
+    /// Fails if more than one declared function in the same scope can be called
+    /// with `args_count` arguments, e.g. `f(a, b)` and `f(a, ...rest)` with two.
+    fn find_function_overload(
+        &self,
+        name: &str,
+        args_count: usize,
+    ) -> Result<Option<&Function<'ast>>> {
+        let mut matching = self
+            .functions
+            .iter()
+            .rev()
+            .filter(|(n, f)| n == name && f.accepts_args_count(args_count))
+            .map(|(_, f)| f);
+        if let Some(func) = matching.next() {
+            let is_ast = |f: &Function| matches!(f, Function::Ast(_));
+            if is_ast(func) && matching.any(is_ast) {
+                return Err(Error::simple(format!(
+                    "call to {name} with {args_count} arguments matches more than one overload"
+                )));
+            }
+
+            return Ok(Some(func));
+        }
+
+        if let Some(parent) = self.parent.as_ref() {
+            return parent.find_function_overload(name, args_count);
+        }
+
+        Ok(None)
+    }
+
+    /// The number of functions named `name` in this scope and its parents.
+    fn count_functions(&self, name: &str) -> usize {
+        self.functions.iter().filter(|(n, _)| n == name).count()
+            + self.parent.map_or(0, |parent| parent.count_functions(name))
+    }
+
     fn add_method(&mut self, type_: TypeConstraint, name: String, function: Function<'ast>) {
         self.methods.push((type_, name, function));
     }
@@ -1207,7 +1672,8 @@ impl Memory {
             // 3 is reserved for the dynamic allocation pointer
             // 4, 5 are reserved for logging
             // 6 is reserved for the selfdestruct flag
-            static_alloc_ptr: 7,
+            // 7 is reserved for the prng call counter
+            static_alloc_ptr: 8,
         }
     }
 
@@ -1275,8 +1741,10 @@ impl<'ast, 'c, 's> Compiler<'ast, 'c, 's> {
     }
 
     fn comment(&mut self, comment: String) {
-        self.instructions
-            .push(encoder::Instruction::Comment(comment));
+        self.instructions.push(encoder::Instruction::Comment(
+            comment,
+            error::span::current(),
+        ));
     }
 
     fn get_record_dependency(&mut self, col: &Contract) -> Option<Symbol> {
@@ -1297,11 +1765,10 @@ fn convert_f64_to_f32(n: f64) -> Option<f32> {
 }
 
 fn compile_expression(expr: &Expression, compiler: &mut Compiler, scope: &Scope) -> Result<Symbol> {
-    comment!(compiler, "Compiling expression {expr:?}");
-
     maybe_start!(expr.span());
 
-    use ast::ExpressionKind;
+    comment!(compiler, "Compiling expression {expr:?}");
+
     let symbol: Symbol = match &**expr {
         ExpressionKind::Ident(id) => scope.find_symbol(id).not_found("symbol", id)?,
         ExpressionKind::Primitive(ast::Primitive::Number(n, _has_decimal_point)) => {
@@ -1310,6 +1777,7 @@ fn compile_expression(expr: &Expression, compiler: &mut Compiler, scope: &Scope)
             float32::new(compiler, n)
         }
         ExpressionKind::Primitive(ast::Primitive::String(s)) => string::new(compiler, s).0,
+        ExpressionKind::Primitive(ast::Primitive::Bytes(b)) => bytes::new(compiler, b),
         ExpressionKind::Boolean(b) => boolean::new(compiler, *b),
         ExpressionKind::Add(a, b) => {
             let a = compile_expression(a, compiler, scope)?;
@@ -1342,14 +1810,12 @@ fn compile_expression(expr: &Expression, compiler: &mut Compiler, scope: &Scope)
             compile_mul(compiler, &a, &b)
         }
         ExpressionKind::Equal(a, b) => {
-            let a = compile_expression(a, compiler, scope)?;
-            let b = compile_expression(b, compiler, scope)?;
+            let (a, b) = compile_comparison_operands(compiler, scope, a, b)?;
 
             compile_eq(compiler, &a, &b)?
         }
         ExpressionKind::NotEqual(a, b) => {
-            let a = compile_expression(a, compiler, scope)?;
-            let b = compile_expression(b, compiler, scope)?;
+            let (a, b) = compile_comparison_operands(compiler, scope, a, b)?;
 
             compile_neq(compiler, &a, &b)
         }
@@ -1421,16 +1887,67 @@ fn compile_expression(expr: &Expression, compiler: &mut Compiler, scope: &Scope)
                         }
                     }
                 }
-                ExpressionKind::Ident(func_name) => (
-                    scope
-                        .find_function(func_name)
-                        .not_found("function", func_name)?,
-                    args.iter()
-                        .map(|arg| compile_expression(arg, compiler, scope))
-                        .collect::<Result<Vec<_>>>()?,
-                ),
+                ExpressionKind::Ident(func_name) => {
+                    let func = match scope.find_function_overload(func_name, args.len())? {
+                        Some(func) => func,
+                        // With a single function, the call reports which arguments are missing.
+                        None if scope.count_functions(func_name) > 1 => {
+                            return Err(Error::simple(format!(
+                                "no overload of {func_name} takes {} arguments",
+                                args.len()
+                            )))
+                        }
+                        None => scope
+                            .find_function(func_name)
+                            .not_found("function", func_name)?,
+                    };
+
+                    if let Function::Ast(f) = func {
+                        if let Some(warning) =
+                            deprecation_warning("function", func_name, &f.decorators)
+                        {
+                            warn(warning);
+                        }
+                    }
+
+                    (func, {
+                        let mut args_symbols = vec![];
+                        for (i, arg) in args.iter().enumerate() {
+                            args_symbols.push(match func {
+                                Function::Ast(f) => {
+                                    // Arguments past the last parameter belong to a rest parameter.
+                                    let param = f
+                                        .parameters
+                                        .get(i)
+                                        .or_else(|| f.parameters.last().filter(|p| p.variadic));
+                                    compile_argument(arg, param, compiler, scope)?
+                                }
+                                Function::Builtin(_) => compile_expression(arg, compiler, scope)?,
+                            });
+                        }
+                        args_symbols
+                    })
+                }
                 ExpressionKind::Dot(obj_expr, func_name) => {
+                    if let (ExpressionKind::Ident(id), "from") = (&***obj_expr, func_name.as_str())
+                    {
+                        if id == "Array" && scope.find_symbol(id).is_none() {
+                            return compile_array_from(compiler, scope, args);
+                        }
+                    }
+
                     let obj = compile_expression(obj_expr, compiler, scope)?;
+                    if let Type::Array(_) = obj.type_ {
+                        check_immutable_method_call(obj_expr, func_name, scope)?;
+                    }
+
+                    if let (Type::Array(_), "forEach" | "map" | "filter" | "reduce") =
+                        (&obj.type_, func_name.as_str())
+                    {
+                        return compile_array_callback_method(
+                            compiler, scope, &obj, func_name, args,
+                        );
+                    }
 
                     let func = scope
                         .find_method(&obj.type_, func_name)
@@ -1459,6 +1976,8 @@ fn compile_expression(expr: &Expression, compiler: &mut Compiler, scope: &Scope)
             })
         }
         ExpressionKind::Assign(a, b) => {
+            check_immutable_assignment(a, scope)?;
+
             if let (ExpressionKind::Index(a, index), b) = (&***a, b) {
                 let a = compile_expression(a, compiler, scope)?;
                 let b = compile_expression(b, compiler, scope)?;
@@ -1515,6 +2034,19 @@ fn compile_expression(expr: &Expression, compiler: &mut Compiler, scope: &Scope)
                 return Ok(b);
             }
 
+            if let ExpressionKind::Null = &***b {
+                let a = compile_expression(a, compiler, scope)?;
+                ensure_eq_type!(a, Type::Nullable(_));
+
+                compiler.memory.write(
+                    compiler.instructions,
+                    nullable::is_not_null(&a).memory_addr,
+                    &[ValueSource::Immediate(0)],
+                );
+
+                return Ok(a);
+            }
+
             let a = compile_expression(a, compiler, scope)?;
             let b = compile_expression(b, compiler, scope)?;
 
@@ -1623,6 +2155,16 @@ fn compile_expression(expr: &Expression, compiler: &mut Compiler, scope: &Scope)
         ExpressionKind::Dot(a, b) => {
             let a = compile_expression(a, compiler, scope)?;
 
+            if let Type::Struct(struct_) = &a.type_ {
+                if let Some(warning) = scope
+                    .find_contract(&struct_.name)
+                    .and_then(|c| c.fields.iter().find(|f| &f.name == b))
+                    .and_then(|f| f.deprecated.clone())
+                {
+                    warn(warning);
+                }
+            }
+
             struct_field(compiler, &a, b)?
         }
         ExpressionKind::GreaterThanOrEqual(a, b) => {
@@ -1631,6 +2173,12 @@ fn compile_expression(expr: &Expression, compiler: &mut Compiler, scope: &Scope)
 
             compile_gte(compiler, &a, &b)
         }
+        ExpressionKind::In(a, b) => {
+            let a = compile_expression(a, compiler, scope)?;
+            let b = compile_expression(b, compiler, scope)?;
+
+            compile_in(compiler, &a, &b)?
+        }
         ExpressionKind::GreaterThan(a, b) => {
             let a = compile_expression(a, compiler, scope)?;
             let b = compile_expression(b, compiler, scope)?;
@@ -1661,17 +2209,32 @@ fn compile_expression(expr: &Expression, compiler: &mut Compiler, scope: &Scope)
 
             compile_shift_right(compiler, &a, &b)
         }
-        ExpressionKind::And(a, b) => {
+        ExpressionKind::UnsignedShiftRight(a, b) => {
             let a = compile_expression(a, compiler, scope)?;
             let b = compile_expression(b, compiler, scope)?;
 
-            boolean::compile_and(compiler, &a, &b)
+            compile_unsigned_shift_right(compiler, &a, &b)
+        }
+        ExpressionKind::And(a, b) => {
+            let a = compile_expression(a, compiler, scope)?;
+
+            boolean::compile_and(compiler, &a, |compiler| {
+                compile_expression(b, compiler, scope)
+            })?
         }
         ExpressionKind::Or(a, b) => {
             let a = compile_expression(a, compiler, scope)?;
-            let b = compile_expression(b, compiler, scope)?;
 
-            boolean::compile_or(compiler, &a, &b)
+            boolean::compile_or(compiler, &a, |compiler| {
+                compile_expression(b, compiler, scope)
+            })?
+        }
+        ExpressionKind::NullishCoalesce(a, b) => {
+            let a = compile_expression(a, compiler, scope)?;
+
+            nullable::coalesce(compiler, &a, |compiler| {
+                compile_expression(b, compiler, scope)
+            })?
         }
         ExpressionKind::Array(exprs) => {
             let all_zeros = exprs.iter().all(|expr| match &**expr {
@@ -1756,12 +2319,23 @@ fn compile_expression(expr: &Expression, compiler: &mut Compiler, scope: &Scope)
 
             symbol
         }
+        ExpressionKind::Map(entries) => compile_map_literal(compiler, scope, entries, None)?,
         ExpressionKind::Index(a, b) => {
             let a = compile_expression(a, compiler, scope)?;
             let b = compile_expression(b, compiler, scope)?;
 
             compile_index(compiler, &a, &b)?
         }
+        ExpressionKind::Null => {
+            return Err(Error::simple(
+                "null can only be assigned to or compared with a nullable value",
+            ))
+        }
+        ExpressionKind::ArrowFunction(_) => {
+            return Err(Error::simple(
+                "arrow functions can only be passed as callbacks to array methods",
+            ))
+        }
         e => return Err(Error::unimplemented(format!("compile {e:?}"))),
     };
 
@@ -1792,6 +2366,27 @@ fn compile_statement(
     return_result: &Option<&mut Symbol>,
 ) -> Result<()> {
     maybe_start!(statement.span());
+    let Some(procedure) = breakpoint_at(statement.span()) else {
+        return compile_statement_kind(statement, compiler, scope, return_result);
+    };
+
+    let mut body = vec![];
+    let mut body_compiler = Compiler::new(&mut body, compiler.memory, compiler.root_scope);
+    body_compiler.record_depenencies = compiler.record_depenencies.clone();
+    compile_statement_kind(statement, &mut body_compiler, scope, return_result)?;
+    compiler
+        .instructions
+        .push(encoder::Instruction::Breakpoint { procedure, body });
+
+    Ok(())
+}
+
+fn compile_statement_kind(
+    statement: &Statement,
+    compiler: &mut Compiler,
+    scope: &mut Scope,
+    return_result: &Option<&mut Symbol>,
+) -> Result<()> {
     match &**statement {
         ast::StatementKind::Return(expr) => {
             let symbol = compile_expression(expr, compiler, scope)?;
@@ -1908,13 +2503,15 @@ fn compile_statement(
                 condition_symbol.type_.miden_width(),
             );
 
-            let mut body_instructions = vec![];
+            let (limit_instructions, mut body_instructions) =
+                compile_loop_iteration_limit(compiler);
             let mut body_compiler =
                 Compiler::new(&mut body_instructions, compiler.memory, compiler.root_scope);
             for statement in statements {
                 compile_statement(statement, &mut body_compiler, &mut scope, return_result)?;
             }
 
+            compiler.instructions.extend(limit_instructions);
             compiler.instructions.push(encoder::Instruction::While {
                 condition: condition_instructions,
                 body: body_instructions,
@@ -2076,8 +2673,10 @@ fn compile_statement(
                 }
             }
 
+            let (limit_instructions, mut body_instructions) =
+                compile_loop_iteration_limit(compiler);
             let body = {
-                let mut body_instructions = pre_instructions;
+                body_instructions.extend(pre_instructions);
                 let mut body_compiler =
                     Compiler::new(&mut body_instructions, compiler.memory, compiler.root_scope);
                 let mut body_scope = scope.deeper();
@@ -2094,6 +2693,7 @@ fn compile_statement(
             };
 
             compiler.instructions.extend(initial_instructions);
+            compiler.instructions.extend(limit_instructions);
             compiler.instructions.push(encoder::Instruction::While {
                 condition: condition_instructions,
                 body,
@@ -2138,6 +2738,7 @@ fn compile_let_statement(
     scope: &mut Scope,
 ) -> Result<()> {
     let new_symbol = match &*let_statement.expression {
+        _ if let_statement.optional => compile_optional_let(let_statement, compiler, scope)?,
         ast::ExpressionKind::Primitive(ast::Primitive::Number(n, has_decimal)) => {
             match &let_statement.type_ {
                 Some(ast::Type::U32) => {
@@ -2159,13 +2760,593 @@ fn compile_let_statement(
                 None => add_new_symbol(&let_statement.expression, compiler, scope)?,
             }
         }
+        ast::ExpressionKind::Map(entries) => match &let_statement.type_ {
+            // The literal is a fresh symbol, so it doesn't need to be copied.
+            Some(ast::Type::Map(k, v)) => {
+                compile_map_literal(compiler, scope, entries, Some((&**k, &**v)))?
+            }
+            _ => add_new_symbol(&let_statement.expression, compiler, scope)?,
+        },
         _ => add_new_symbol(&let_statement.expression, compiler, scope)?,
     };
 
+    if let Some(field) = immutable_field(&let_statement.expression, scope) {
+        scope.add_immutable_alias(new_symbol.memory_addr, field);
+    }
     scope.add_symbol(let_statement.identifier.to_string(), new_symbol);
     Ok(())
 }
 
+/// Compiles the value of `let x?: T = value` into a new nullable `T`.
+/// The value can be `null`, a `T` or a nullable `T`.
+fn compile_optional_let(
+    let_statement: &ast::Let,
+    compiler: &mut Compiler,
+    scope: &Scope,
+) -> Result<Symbol> {
+    let Some(type_) = &let_statement.type_ else {
+        return Err(Error::simple("optional let statement without a type"));
+    };
+    let symbol = nullable::null(compiler, &ast_type_to_type(false, type_));
+    let inner_type = match &symbol.type_ {
+        Type::Nullable(t) => (**t).clone(),
+        _ => unreachable!(),
+    };
+
+    let value = match (&*let_statement.expression, &inner_type) {
+        (ExpressionKind::Null, _) => return Ok(symbol),
+        (
+            ExpressionKind::Primitive(ast::Primitive::Number(n, has_decimal)),
+            Type::PrimitiveType(
+                p @ (PrimitiveType::UInt32
+                | PrimitiveType::UInt64
+                | PrimitiveType::Int32
+                | PrimitiveType::Int64),
+            ),
+        ) => compile_integer_literal(compiler, *n, *has_decimal, *p)?,
+        (expression, _) => compile_expression(expression, compiler, scope)?,
+    };
+
+    if value.type_ == symbol.type_ {
+        compiler.memory.read(
+            compiler.instructions,
+            value.memory_addr,
+            value.type_.miden_width(),
+        );
+        compiler.memory.write(
+            compiler.instructions,
+            symbol.memory_addr,
+            &vec![ValueSource::Stack; symbol.type_.miden_width() as usize],
+        );
+        return Ok(symbol);
+    }
+
+    ensure_eq_type!(value, @&inner_type);
+    compiler.memory.write(
+        compiler.instructions,
+        nullable::is_not_null(&symbol).memory_addr,
+        &[ValueSource::Immediate(1)],
+    );
+    compiler.memory.read(
+        compiler.instructions,
+        value.memory_addr,
+        inner_type.miden_width(),
+    );
+    compiler.memory.write(
+        compiler.instructions,
+        nullable::value(symbol.clone()).memory_addr,
+        &vec![ValueSource::Stack; inner_type.miden_width() as usize],
+    );
+
+    Ok(symbol)
+}
+
+/// Compiles `{ "a": 1, "b": 2 }` by pushing each entry onto the map's keys and values,
+/// the same way `m["a"] = 1` inserts a missing key.
+/// `types` is the declared `map<K, V>`, which lets number literals compile as any numeric type.
+fn compile_map_literal(
+    compiler: &mut Compiler,
+    scope: &Scope,
+    entries: &[(Expression, Expression)],
+    types: Option<(&ast::Type, &ast::Type)>,
+) -> Result<Symbol> {
+    for (i, (key, _)) in entries.iter().enumerate() {
+        if entries[..i].iter().any(|(k, _)| **k == **key) {
+            return Err(Error::simple("duplicate key in map literal"));
+        }
+    }
+
+    let mut compiled = vec![];
+    for (key, value) in entries {
+        compiled.push((
+            compile_map_literal_element(compiler, scope, key, types.map(|(k, _)| k))?,
+            compile_map_literal_element(compiler, scope, value, types.map(|(_, v)| v))?,
+        ));
+    }
+
+    let (key_type, value_type) = match (types, compiled.first()) {
+        (Some((k, v)), _) => (ast_type_to_type(true, k), ast_type_to_type(true, v)),
+        (None, Some((k, v))) => (k.type_.clone(), v.type_.clone()),
+        (None, None) => {
+            return TypeMismatchSnafu {
+                context: "cannot infer the type of an empty map literal",
+            }
+            .fail()
+            .map_err(Into::into)
+        }
+    };
+
+    let map = compiler
+        .memory
+        .allocate_symbol(Type::Map(Box::new(key_type), Box::new(value_type)));
+    // The symbol is reused when the literal is in a loop, so start from empty arrays.
+    compiler.memory.write(
+        compiler.instructions,
+        map.memory_addr,
+        &[ValueSource::Immediate(0); map::WIDTH as usize],
+    );
+
+    let (keys, values) = map::key_values_arr(&map)?;
+    for (key, value) in compiled {
+        array::push(compiler, scope, &[keys.clone(), key])?;
+        array::push(compiler, scope, &[values.clone(), value])?;
+    }
+
+    Ok(map)
+}
+
+fn compile_map_literal_element(
+    compiler: &mut Compiler,
+    scope: &Scope,
+    expr: &Expression,
+    type_: Option<&ast::Type>,
+) -> Result<Symbol> {
+    let type_ = type_.map(|t| ast_type_to_type(true, t));
+    let symbol = match (&**expr, &type_) {
+        (
+            ast::ExpressionKind::Primitive(ast::Primitive::Number(n, has_decimal)),
+            Some(Type::PrimitiveType(
+                p @ (PrimitiveType::UInt32
+                | PrimitiveType::UInt64
+                | PrimitiveType::Int32
+                | PrimitiveType::Int64
+                | PrimitiveType::Float32
+                | PrimitiveType::Float64),
+            )),
+        ) => compile_number_literal(compiler, *n, *has_decimal, *p)?,
+        _ => compile_expression(expr, compiler, scope)?,
+    };
+
+    if let Some(type_) = &type_ {
+        ensure_eq_type!(symbol, @type_);
+    }
+
+    Ok(symbol)
+}
+
+/// Compiles `Array.from(arr)`, which copies `arr`,
+/// and `Array.from(len, value)`, which makes an array of `len` copies of `value`.
+fn compile_array_from(
+    compiler: &mut Compiler,
+    scope: &Scope,
+    args: &[Expression],
+) -> Result<Symbol> {
+    match args {
+        [arr] => {
+            let arr = compile_expression(arr, compiler, scope)?;
+            ensure_eq_type!(arr, Type::Array(_));
+
+            array::copy_array(compiler, &arr)
+        }
+        [len, value] => {
+            // A number literal length is a u32, like `u32` arguments of functions.
+            let len = match &**len {
+                ExpressionKind::Primitive(ast::Primitive::Number(n, has_decimal)) => {
+                    compile_integer_literal(compiler, *n, *has_decimal, PrimitiveType::UInt32)?
+                }
+                _ => compile_expression(len, compiler, scope)?,
+            };
+            let value = compile_expression(value, compiler, scope)?;
+
+            array::fill(compiler, &len, &value)
+        }
+        _ => ArgumentsCountSnafu {
+            found: args.len(),
+            expected: 2usize,
+        }
+        .fail()
+        .map_err(Into::into),
+    }
+}
+
+/// Compiles the array methods that take a callback: `forEach`, `map`, `filter` and `reduce`.
+/// The callback is called with the element, its index and the array,
+/// and binds only as many of them as it declares, e.g. `x => ...` or `(x, i) => ...`.
+/// `reduce` takes the initial value as its second argument, and passes the accumulator
+/// to the callback before the element.
+fn compile_array_callback_method(
+    compiler: &mut Compiler,
+    scope: &Scope,
+    array: &Symbol,
+    method: &str,
+    args: &[Expression],
+) -> Result<Symbol> {
+    let expected_args = if method == "reduce" { 2 } else { 1 };
+    ensure!(
+        args.len() == expected_args,
+        ArgumentsCountSnafu {
+            found: args.len(),
+            expected: expected_args
+        }
+    );
+    let ast::ExpressionKind::ArrowFunction(callback) = &*args[0] else {
+        return TypeMismatchSnafu {
+            context: format!("expected a callback at {method}"),
+        }
+        .fail()
+        .map_err(Into::into);
+    };
+
+    match method {
+        "forEach" => {
+            compile_array_callback_loop(compiler, scope, array, callback, None, |_, _, _| Ok(()))?;
+            Ok(array.clone())
+        }
+        "map" => {
+            // The element type of the result is only known once the callback is compiled,
+            // the layout of an empty array doesn't depend on it.
+            let (mut result, _) =
+                array::new(compiler, 0, array::element_type(&array.type_).clone());
+            compile_array_callback_loop(
+                compiler,
+                scope,
+                array,
+                callback,
+                None,
+                |compiler, _, value| {
+                    let value =
+                        value.ok_or_else(|| Error::simple("map callback must return a value"))?;
+                    result.type_ = Type::Array(Box::new(value.type_.clone()));
+                    array::push(compiler, &Scope::new(), &[result.clone(), value])?;
+                    Ok(())
+                },
+            )?;
+            Ok(result)
+        }
+        "filter" => {
+            let (result, _) = array::new(compiler, 0, array::element_type(&array.type_).clone());
+            compile_array_callback_loop(
+                compiler,
+                scope,
+                array,
+                callback,
+                None,
+                |compiler, element, value| {
+                    let value = value
+                        .ok_or_else(|| Error::simple("filter callback must return a value"))?;
+                    ensure_eq_type!(value, Type::PrimitiveType(PrimitiveType::Boolean));
+
+                    let mut push_instructions = vec![];
+                    let mut push_compiler =
+                        Compiler::new(&mut push_instructions, compiler.memory, compiler.root_scope);
+                    array::push(
+                        &mut push_compiler,
+                        &Scope::new(),
+                        &[result.clone(), element.clone()],
+                    )?;
+
+                    compiler.instructions.push(encoder::Instruction::If {
+                        condition: vec![encoder::Instruction::MemLoad(Some(value.memory_addr))],
+                        then: push_instructions,
+                        else_: vec![],
+                    });
+                    Ok(())
+                },
+            )?;
+            Ok(result)
+        }
+        "reduce" => {
+            let initial = compile_expression(&args[1], compiler, scope)?;
+            let accumulator = compiler.memory.allocate_symbol(initial.type_.clone());
+            compiler.memory.read(
+                compiler.instructions,
+                initial.memory_addr,
+                initial.type_.miden_width(),
+            );
+            compiler.memory.write(
+                compiler.instructions,
+                accumulator.memory_addr,
+                &vec![ValueSource::Stack; accumulator.type_.miden_width() as usize],
+            );
+
+            compile_array_callback_loop(
+                compiler,
+                scope,
+                array,
+                callback,
+                Some(&accumulator),
+                |compiler, _, value| {
+                    let value = value
+                        .ok_or_else(|| Error::simple("reduce callback must return a value"))?;
+                    ensure_eq_type!(@&value.type_, @&accumulator.type_);
+
+                    compiler.memory.read(
+                        compiler.instructions,
+                        value.memory_addr,
+                        value.type_.miden_width(),
+                    );
+                    compiler.memory.write(
+                        compiler.instructions,
+                        accumulator.memory_addr,
+                        &vec![ValueSource::Stack; accumulator.type_.miden_width() as usize],
+                    );
+                    Ok(())
+                },
+            )?;
+            Ok(accumulator)
+        }
+        _ => Err(Error::unimplemented(format!("array method {method}"))),
+    }
+}
+
+/// Compiles a loop that calls `callback` with `accumulator`, if any, then each element
+/// of `array`, its index and the array, and passes the element and what the callback
+/// returned to `each`.
+fn compile_array_callback_loop(
+    compiler: &mut Compiler,
+    scope: &Scope,
+    array: &Symbol,
+    callback: &ast::ArrowFunction,
+    accumulator: Option<&Symbol>,
+    mut each: impl FnMut(&mut Compiler, &Symbol, Option<Symbol>) -> Result<()>,
+) -> Result<()> {
+    let index = uint32::new(compiler, 0);
+    let length = array::length(array);
+
+    let mut condition_instructions = vec![];
+    {
+        let mut condition_compiler = Compiler::new(
+            &mut condition_instructions,
+            compiler.memory,
+            compiler.root_scope,
+        );
+        let condition = uint32::lt(&mut condition_compiler, &index, &length);
+        condition_compiler.memory.read(
+            condition_compiler.instructions,
+            condition.memory_addr,
+            condition.type_.miden_width(),
+        );
+    }
+
+    let (limit_instructions, mut body_instructions) = compile_loop_iteration_limit(compiler);
+    {
+        let mut body_compiler =
+            Compiler::new(&mut body_instructions, compiler.memory, compiler.root_scope);
+        let element = array::get(&mut body_compiler, array, &index);
+        let callback_args = accumulator
+            .into_iter()
+            .cloned()
+            .chain([element.clone(), index.clone(), array.clone()])
+            .collect::<Vec<_>>();
+        let value = compile_callback_call(&mut body_compiler, scope, callback, &callback_args)?;
+        each(&mut body_compiler, &element, value)?;
+
+        body_compiler.instructions.extend([
+            encoder::Instruction::MemLoad(Some(index.memory_addr)),
+            encoder::Instruction::Push(1),
+            encoder::Instruction::U32CheckedAdd,
+            encoder::Instruction::MemStore(Some(index.memory_addr)),
+        ]);
+    }
+
+    compiler.instructions.extend(limit_instructions);
+    compiler.instructions.push(encoder::Instruction::While {
+        condition: condition_instructions,
+        body: body_instructions,
+    });
+
+    Ok(())
+}
+
+/// Inlines a call to an arrow function. Its body is compiled in a scope nested in `scope`,
+/// so variables around it are captured by reference: the body reads and writes
+/// their memory directly, and memory is never freed, so they stay live for as long
+/// as the callback can run. Callers can pass more arguments than the callback has
+/// parameters, like the index of an element, which are then ignored.
+fn compile_callback_call(
+    compiler: &mut Compiler,
+    scope: &Scope,
+    callback: &ast::ArrowFunction,
+    args: &[Symbol],
+) -> Result<Option<Symbol>> {
+    ensure!(
+        callback.parameters.len() <= args.len(),
+        ArgumentsCountSnafu {
+            found: callback.parameters.len(),
+            expected: args.len()
+        }
+    );
+
+    let mut callback_scope = scope.deeper();
+    for (name, arg) in callback.parameters.iter().zip(args) {
+        // Copy the argument, so that assigning to the parameter doesn't modify
+        // the caller's variable, e.g. the loop index.
+        let new_arg = compiler.memory.allocate_symbol(arg.type_.clone());
+        compiler.memory.read(
+            compiler.instructions,
+            arg.memory_addr,
+            arg.type_.miden_width(),
+        );
+        compiler.memory.write(
+            compiler.instructions,
+            new_arg.memory_addr,
+            &vec![ValueSource::Stack; new_arg.type_.miden_width() as usize],
+        );
+
+        callback_scope.add_symbol(name.clone(), new_arg);
+    }
+
+    match &callback.body {
+        ast::ArrowFunctionBody::Expression(expr) => {
+            compile_expression(expr, compiler, &callback_scope).map(Some)
+        }
+        ast::ArrowFunctionBody::Statements(statements) => {
+            let mut result = callback_return_type(compiler, &callback_scope, statements)?
+                .map(|type_| compiler.memory.allocate_symbol(type_));
+
+            let mut callback_instructions = vec![];
+            let mut callback_compiler = Compiler::new(
+                &mut callback_instructions,
+                compiler.memory,
+                compiler.root_scope,
+            );
+            for statement in statements {
+                compile_statement(
+                    statement,
+                    &mut callback_compiler,
+                    &mut callback_scope,
+                    &result.as_mut(),
+                )?;
+            }
+
+            compiler.instructions.push(encoder::Instruction::Abstract(
+                encoder::AbstractInstruction::InlinedFunction(callback_instructions),
+            ));
+            Ok(result)
+        }
+    }
+}
+
+/// Returns the type of the value returned by a block-bodied callback, which is the type
+/// of its last top-level `return`, or `None` if it has none.
+/// The statements are compiled into instructions that are thrown away, so that the types
+/// of variables declared before the `return` are known.
+fn callback_return_type(
+    compiler: &mut Compiler,
+    scope: &Scope,
+    statements: &[Statement],
+) -> Result<Option<Type>> {
+    let mut instructions = vec![];
+    let mut scratch_compiler =
+        Compiler::new(&mut instructions, compiler.memory, compiler.root_scope);
+    let mut scope = scope.deeper();
+    // Returns nested in other statements write to this symbol, its type is never read.
+    let mut placeholder = Symbol {
+        type_: Type::PrimitiveType(PrimitiveType::Boolean),
+        memory_addr: 0,
+    };
+
+    let mut return_type = None;
+    for statement in statements {
+        match &**statement {
+            ast::StatementKind::Return(expr) => {
+                return_type = Some(compile_expression(expr, &mut scratch_compiler, &scope)?.type_);
+            }
+            _ => compile_statement(
+                statement,
+                &mut scratch_compiler,
+                &mut scope,
+                &Some(&mut placeholder),
+            )?,
+        }
+    }
+
+    Ok(return_type)
+}
+
+/// Compiles an argument passed to `param`.
+/// Number literals are compiled as f32 by default, so integer literals passed
+/// to an integer parameter, or an integer rest parameter, are compiled as that type instead.
+/// `null` passed to an optional parameter takes the parameter's type.
+fn compile_argument(
+    expr: &Expression,
+    param: Option<&ast::Parameter>,
+    compiler: &mut Compiler,
+    scope: &Scope,
+) -> Result<Symbol> {
+    let expected = param.and_then(|p| match &p.type_ {
+        ast::ParameterType::U32 => Some(PrimitiveType::UInt32),
+        ast::ParameterType::U64 => Some(PrimitiveType::UInt64),
+        ast::ParameterType::I32 => Some(PrimitiveType::Int32),
+        ast::ParameterType::I64 => Some(PrimitiveType::Int64),
+        ast::ParameterType::Array(t) if p.variadic => match t {
+            ast::Type::U32 => Some(PrimitiveType::UInt32),
+            ast::Type::U64 => Some(PrimitiveType::UInt64),
+            ast::Type::I32 => Some(PrimitiveType::Int32),
+            ast::Type::I64 => Some(PrimitiveType::Int64),
+            _ => None,
+        },
+        _ => None,
+    });
+
+    // `null` passed to an optional parameter is a null of the parameter's type.
+    if let (Some(param), ExpressionKind::Null) = (param, &**expr) {
+        if !param.required && !matches!(param.type_, ast::ParameterType::Record) {
+            let type_ = ast_param_type_to_type(false, &param.type_, None)?;
+            return Ok(nullable::null(compiler, &type_));
+        }
+    }
+
+    let (Some(expected), ExpressionKind::Primitive(ast::Primitive::Number(n, has_decimal))) =
+        (expected, &**expr)
+    else {
+        return compile_expression(expr, compiler, scope);
+    };
+
+    compile_integer_literal(compiler, *n, *has_decimal, expected)
+}
+
+/// Compiles a number literal as the numeric type `expected`.
+/// Integer types are compiled with [`compile_integer_literal`].
+fn compile_number_literal(
+    compiler: &mut Compiler,
+    n: f64,
+    has_decimal: bool,
+    expected: PrimitiveType,
+) -> Result<Symbol> {
+    match expected {
+        PrimitiveType::Float32 => Ok(float32::new(compiler, n as f32)),
+        PrimitiveType::Float64 => Ok(float64::new(compiler, n)),
+        _ => compile_integer_literal(compiler, n, has_decimal, expected),
+    }
+}
+
+/// Compiles a number literal as the integer type `expected`,
+/// rejecting literals with a decimal point and literals out of the type's range.
+fn compile_integer_literal(
+    compiler: &mut Compiler,
+    n: f64,
+    has_decimal: bool,
+    expected: PrimitiveType,
+) -> Result<Symbol> {
+    ensure!(
+        !has_decimal,
+        TypeMismatchSnafu {
+            context: format!("expected {expected:?}, not float {n}"),
+        }
+    );
+
+    let range = match expected {
+        PrimitiveType::UInt32 => 0.0..=u32::MAX as f64,
+        PrimitiveType::UInt64 => 0.0..=u64::MAX as f64,
+        PrimitiveType::Int32 => i32::MIN as f64..=i32::MAX as f64,
+        _ => i64::MIN as f64..=i64::MAX as f64,
+    };
+    ensure!(
+        range.contains(&n),
+        TypeMismatchSnafu {
+            context: format!("{n} is out of range for {expected:?}"),
+        }
+    );
+
+    Ok(match expected {
+        PrimitiveType::UInt32 => uint32::new(compiler, n as u32),
+        PrimitiveType::UInt64 => uint64::new(compiler, n as u64),
+        PrimitiveType::Int32 => int32::new(compiler, n as i32),
+        _ => int64::new(compiler, n as i64),
+    })
+}
+
 fn compile_ast_function_call(
     function: &ast::Function,
     compiler: &mut Compiler,
@@ -2182,6 +3363,15 @@ fn compile_ast_function_call(
     let scope = &mut Scope::new();
     scope.parent = Some(compiler.root_scope);
 
+    let max_iterations = match max_iterations_decorator(function)? {
+        Some(max_iterations) => Some(max_iterations),
+        // Builtins written in Polylang bound their own loops, the caller's limit doesn't apply to them.
+        None if IN_BUILTIN.with(|c| c.get()) => None,
+        None => DEFAULT_MAX_LOOP_ITERATIONS.with(|max| max.get()),
+    };
+    let _max_loop_iterations = MaxLoopIterationsGuard::set(max_iterations);
+    let _in_constructor = InConstructorGuard::set(function.name == "constructor");
+
     if let Some(this) = this {
         scope.add_symbol("this".to_string(), this);
     }
@@ -2191,7 +3381,28 @@ fn compile_ast_function_call(
             .memory
             .allocate_symbol(ast_type_to_type(true, ty))
     });
-    for (arg, param) in args.iter().zip(function.parameters.iter()) {
+    for (i, param) in function.parameters.iter().enumerate() {
+        // Omitted trailing arguments take the parameter's default,
+        // evaluated in the function scope so it can refer to earlier parameters.
+        let arg = match (args.get(i), &param.default) {
+            // A rest parameter collects the remaining arguments into an array.
+            _ if param.variadic => {
+                let ast::ParameterType::Array(element_type) = &param.type_ else {
+                    return Err(Error::simple("rest parameter must be an array"));
+                };
+                array::from_symbols(
+                    &mut function_compiler,
+                    ast_type_to_type(true, element_type),
+                    args.get(i..).unwrap_or_default(),
+                )?
+            }
+            (Some(arg), _) => arg.clone(),
+            (None, Some(default)) => {
+                compile_argument(default, Some(param), &mut function_compiler, scope)?
+            }
+            (None, None) => break,
+        };
+
         // We need to make a copy of the arg, because Ident expressions return symbols of variables.
         // Modifying them in a function would modify the original variable.
         // TODO: fix this
@@ -2238,6 +3449,16 @@ fn compile_function_call(
     }
 }
 
+/// Compiles a call to a builtin written in Polylang, such as `uint32ToString`.
+fn compile_builtin_function_call(
+    function: &ast::Function,
+    compiler: &mut Compiler,
+    args: &[Symbol],
+) -> Result<Option<Symbol>> {
+    let _in_builtin = InBuiltinGuard::set(true);
+    compile_ast_function_call(function, compiler, args, None)
+}
+
 fn cast(compiler: &mut Compiler, from: &Symbol, to: &Symbol) {
     match (&from.type_, &to.type_) {
         (
@@ -2326,6 +3547,9 @@ fn compile_mod(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
         (Type::PrimitiveType(PrimitiveType::Int32), Type::PrimitiveType(PrimitiveType::Int32)) => {
             int32::modulo(compiler, a, b)
         }
+        (Type::PrimitiveType(PrimitiveType::Int64), Type::PrimitiveType(PrimitiveType::Int64)) => {
+            int64::modulo(compiler, a, b)
+        }
         (
             Type::PrimitiveType(PrimitiveType::UInt64),
             Type::PrimitiveType(PrimitiveType::UInt32),
@@ -2341,6 +3565,100 @@ fn compile_mod(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
     }
 }
 
+/// Returns the instructions that throw `message`, like calling the `error` builtin does.
+fn compile_throw<'ast>(
+    compiler: &mut Compiler<'ast, '_, '_>,
+    message: &str,
+) -> Vec<encoder::Instruction<'ast>> {
+    let mut instructions = vec![];
+    {
+        let mut error_compiler =
+            Compiler::new(&mut instructions, compiler.memory, compiler.root_scope);
+        let (message, _) = string::new(&mut error_compiler, message);
+        let error_fn = &USABLE_BUILTINS
+            .iter()
+            .find(|(name, _, _)| name == "error")
+            .unwrap()
+            .2;
+        compile_function_call(&mut error_compiler, error_fn, &[message], None)
+            .expect("error builtin accepts a string");
+    }
+
+    instructions
+}
+
+/// If loops in the current function have an iteration limit, returns the instructions
+/// that reset an iteration counter before the loop, and the instructions that increment it
+/// at the start of each iteration and throw once it exceeds the limit.
+fn compile_loop_iteration_limit<'ast>(
+    compiler: &mut Compiler<'ast, '_, '_>,
+) -> (
+    Vec<encoder::Instruction<'ast>>,
+    Vec<encoder::Instruction<'ast>>,
+) {
+    let Some(max_iterations) = MAX_LOOP_ITERATIONS.with(|max| max.get()) else {
+        return (vec![], vec![]);
+    };
+
+    let counter = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt32));
+
+    let error_branch = compile_throw(
+        compiler,
+        &format!("loop exceeded the maximum of {max_iterations} iterations"),
+    );
+
+    let reset = vec![
+        encoder::Instruction::Push(0),
+        encoder::Instruction::MemStore(Some(counter.memory_addr)),
+    ];
+    let check = vec![
+        encoder::Instruction::MemLoad(Some(counter.memory_addr)),
+        encoder::Instruction::Push(1),
+        encoder::Instruction::U32CheckedAdd,
+        encoder::Instruction::MemStore(Some(counter.memory_addr)),
+        encoder::Instruction::If {
+            condition: vec![
+                encoder::Instruction::MemLoad(Some(counter.memory_addr)),
+                encoder::Instruction::Push(max_iterations),
+                encoder::Instruction::U32CheckedGT,
+            ],
+            then: error_branch,
+            else_: vec![],
+        },
+    ];
+
+    (reset, check)
+}
+
+/// Throws `division by zero` if the integer `divisor` is zero.
+///
+/// Without this, a zero divisor fails deep inside `u32checked_div` or the
+/// `u64` procedures with an assertion that carries no error message.
+fn compile_check_divisor(compiler: &mut Compiler, divisor: &Symbol) {
+    let error_branch = compile_throw(compiler, "division by zero");
+
+    let mut condition = vec![encoder::Instruction::Push(1)];
+    for i in 0..divisor.type_.miden_width() {
+        condition.extend([
+            encoder::Instruction::MemLoad(Some(divisor.memory_addr + i)),
+            // [word, is_zero]
+            encoder::Instruction::Push(0),
+            encoder::Instruction::Eq,
+            // [word == 0, is_zero]
+            encoder::Instruction::And,
+            // [is_zero && word == 0]
+        ]);
+    }
+
+    compiler.instructions.push(encoder::Instruction::If {
+        condition,
+        then: error_branch,
+        else_: vec![],
+    });
+}
+
 fn compile_div(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
     match (&a.type_, &b.type_) {
         (
@@ -2354,6 +3672,9 @@ fn compile_div(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
         (Type::PrimitiveType(PrimitiveType::Int32), Type::PrimitiveType(PrimitiveType::Int32)) => {
             int32::div(compiler, a, b)
         }
+        (Type::PrimitiveType(PrimitiveType::Int64), Type::PrimitiveType(PrimitiveType::Int64)) => {
+            int64::div(compiler, a, b)
+        }
         (
             Type::PrimitiveType(PrimitiveType::UInt64),
             Type::PrimitiveType(PrimitiveType::UInt32),
@@ -2429,6 +3750,9 @@ fn compile_eq(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Result<Symbol>
         (Type::PrimitiveType(PrimitiveType::Int32), Type::PrimitiveType(PrimitiveType::Int32)) => {
             uint32::eq(compiler, a, b)
         }
+        (Type::PrimitiveType(PrimitiveType::Int64), Type::PrimitiveType(PrimitiveType::Int64)) => {
+            uint64::eq(compiler, a, b)
+        }
         (
             Type::PrimitiveType(PrimitiveType::Float32),
             Type::PrimitiveType(PrimitiveType::Float32),
@@ -2460,7 +3784,25 @@ fn compile_eq(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Result<Symbol>
         }
         (Type::PublicKey, Type::PublicKey) => publickey::eq(compiler, a, b),
         (Type::String, Type::String) => string::eq(compiler, a, b),
-        (Type::Nullable(lt), Type::Nullable(rt)) if lt == rt => nullable::eq(compiler, a, b),
+        (Type::Struct(a_struct), Type::Struct(b_struct)) if a_struct.fields == b_struct.fields => {
+            // Structs are equal if all of their fields are equal
+            let result = boolean::new(compiler, true);
+            for (field, _) in &a_struct.fields {
+                let a_field = struct_field(compiler, a, field)?;
+                let b_field = struct_field(compiler, b, field)?;
+                let field_eq = compile_eq(compiler, &a_field, &b_field)?;
+
+                compiler.instructions.extend([
+                    encoder::Instruction::MemLoad(Some(result.memory_addr)),
+                    encoder::Instruction::MemLoad(Some(field_eq.memory_addr)),
+                    encoder::Instruction::And,
+                    encoder::Instruction::MemStore(Some(result.memory_addr)),
+                ]);
+            }
+
+            result
+        }
+        (Type::Nullable(lt), Type::Nullable(rt)) if lt == rt => nullable::eq(compiler, a, b)?,
         (Type::Nullable(type_from_nullable), not_null_type)
         | (not_null_type, Type::Nullable(type_from_nullable))
             if &**type_from_nullable == not_null_type =>
@@ -2476,18 +3818,75 @@ fn compile_eq(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Result<Symbol>
             std::mem::swap(compiler.instructions, &mut eq_instructions);
             let eq_result = compile_eq(compiler, &nullable::value(a.clone()), b);
             std::mem::swap(compiler.instructions, &mut eq_instructions);
+            let eq_result = eq_result?;
 
             compiler.instructions.push(encoder::Instruction::If {
                 condition: vec![encoder::Instruction::MemLoad(Some(
                     nullable::is_not_null(a).memory_addr,
                 ))],
                 then: eq_instructions,
-                else_: vec![],
+                // null is never equal to a value. The result is written explicitly,
+                // because the symbol keeps its previous value when this runs in a loop.
+                else_: vec![
+                    encoder::Instruction::Push(0),
+                    encoder::Instruction::MemStore(Some(eq_result.memory_addr)),
+                ],
             });
 
-            eq_result?
+            eq_result
+        }
+        e => return Err(Error::unimplemented(format!("eq {:?} {:?}", e.0, e.1))),
+    })
+}
+
+/// `element in array` is `array.includes(element)`, `key in map` checks the map's keys.
+fn compile_in(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Result<Symbol> {
+    match &b.type_ {
+        Type::Array(_) => {
+            let old_root_scope = compiler.root_scope;
+            compiler.root_scope = &BUILTINS_SCOPE;
+            let result = array::includes(compiler, b, a);
+            compiler.root_scope = old_root_scope;
+
+            result
+        }
+        Type::Map(key_type, _) => {
+            ensure_eq_type!(a, @&**key_type);
+
+            let (_key, _value, _value_ptr, found) = map::get(compiler, b, a)?;
+            Ok(found)
         }
-        e => return Err(Error::unimplemented(format!("eq {:?} {:?}", e.0, e.1))),
+        t => Err(ErrorKind::TypeMismatch {
+            context: format!("cannot use `in` on {:?}", t),
+        }
+        .into()),
+    }
+}
+
+/// Compiles both sides of `==` or `!=`. A `null` side takes its type from the other side.
+fn compile_comparison_operands(
+    compiler: &mut Compiler,
+    scope: &Scope,
+    a: &Expression,
+    b: &Expression,
+) -> Result<(Symbol, Symbol)> {
+    Ok(match (&**a, &**b) {
+        (ExpressionKind::Null, ExpressionKind::Null) => {
+            return Err(Error::simple("cannot compare null with null"))
+        }
+        (ExpressionKind::Null, _) => {
+            let b = compile_expression(b, compiler, scope)?;
+            (nullable::null(compiler, &b.type_), b)
+        }
+        (_, ExpressionKind::Null) => {
+            let a = compile_expression(a, compiler, scope)?;
+            let b = nullable::null(compiler, &a.type_);
+            (a, b)
+        }
+        _ => (
+            compile_expression(a, compiler, scope)?,
+            compile_expression(b, compiler, scope)?,
+        ),
     })
 }
 
@@ -2529,6 +3928,9 @@ fn compile_gte(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
         (Type::PrimitiveType(PrimitiveType::Int32), Type::PrimitiveType(PrimitiveType::Int32)) => {
             int32::gte(compiler, a, b)
         }
+        (Type::PrimitiveType(PrimitiveType::Int64), Type::PrimitiveType(PrimitiveType::Int64)) => {
+            int64::gte(compiler, a, b)
+        }
         (
             Type::PrimitiveType(PrimitiveType::UInt64),
             Type::PrimitiveType(PrimitiveType::UInt32),
@@ -2544,6 +3946,7 @@ fn compile_gte(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
             Type::PrimitiveType(PrimitiveType::Float32),
             Type::PrimitiveType(PrimitiveType::Float32),
         ) => float32::gte(compiler, a, b),
+        (Type::String, Type::String) => string::gte(compiler, a, b),
         e => unimplemented!("{:?}", e),
     }
 }
@@ -2561,6 +3964,9 @@ fn compile_gt(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
         (Type::PrimitiveType(PrimitiveType::Int32), Type::PrimitiveType(PrimitiveType::Int32)) => {
             int32::gt(compiler, a, b)
         }
+        (Type::PrimitiveType(PrimitiveType::Int64), Type::PrimitiveType(PrimitiveType::Int64)) => {
+            int64::gt(compiler, a, b)
+        }
         (
             Type::PrimitiveType(PrimitiveType::UInt64),
             Type::PrimitiveType(PrimitiveType::UInt32),
@@ -2576,6 +3982,7 @@ fn compile_gt(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
             Type::PrimitiveType(PrimitiveType::Float32),
             Type::PrimitiveType(PrimitiveType::Float32),
         ) => float32::gt(compiler, a, b),
+        (Type::String, Type::String) => string::gt(compiler, a, b),
         e => unimplemented!("{:?}", e),
     }
 }
@@ -2593,6 +4000,9 @@ fn compile_lte(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
         (Type::PrimitiveType(PrimitiveType::Int32), Type::PrimitiveType(PrimitiveType::Int32)) => {
             int32::lte(compiler, a, b)
         }
+        (Type::PrimitiveType(PrimitiveType::Int64), Type::PrimitiveType(PrimitiveType::Int64)) => {
+            int64::lte(compiler, a, b)
+        }
         (
             Type::PrimitiveType(PrimitiveType::UInt64),
             Type::PrimitiveType(PrimitiveType::UInt32),
@@ -2608,6 +4018,7 @@ fn compile_lte(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
             Type::PrimitiveType(PrimitiveType::Float32),
             Type::PrimitiveType(PrimitiveType::Float32),
         ) => float32::lte(compiler, a, b),
+        (Type::String, Type::String) => string::lte(compiler, a, b),
         e => unimplemented!("{:?}", e),
     }
 }
@@ -2625,6 +4036,9 @@ fn compile_lt(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
         (Type::PrimitiveType(PrimitiveType::Int32), Type::PrimitiveType(PrimitiveType::Int32)) => {
             int32::lt(compiler, a, b)
         }
+        (Type::PrimitiveType(PrimitiveType::Int64), Type::PrimitiveType(PrimitiveType::Int64)) => {
+            int64::lt(compiler, a, b)
+        }
         (
             Type::PrimitiveType(PrimitiveType::UInt64),
             Type::PrimitiveType(PrimitiveType::UInt32),
@@ -2640,10 +4054,107 @@ fn compile_lt(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
             Type::PrimitiveType(PrimitiveType::Float32),
             Type::PrimitiveType(PrimitiveType::Float32),
         ) => float32::lt(compiler, a, b),
+        (Type::String, Type::String) => string::lt(compiler, a, b),
         e => unimplemented!("{:?}", e),
     }
 }
 
+/// Compiles a call to `function`, which takes a single `f32`, such as `sin(x)`.
+fn compile_float32_function(
+    compiler: &mut Compiler,
+    args: &[Symbol],
+    function: fn(&mut Compiler, &Symbol) -> Result<Symbol>,
+) -> Result<Symbol> {
+    ensure!(
+        args.len() == 1,
+        ArgumentsCountSnafu {
+            found: args.len(),
+            expected: 1usize
+        }
+    );
+    ensure_eq_type!(args[0], Type::PrimitiveType(PrimitiveType::Float32));
+
+    let old_root_scope = compiler.root_scope;
+    compiler.root_scope = &BUILTINS_SCOPE;
+    let result = function(compiler, &args[0]);
+    compiler.root_scope = old_root_scope;
+
+    result
+}
+
+/// Compiles `clamp(value, min, max)`, which throws if `min > max`.
+fn compile_clamp(
+    compiler: &mut Compiler,
+    value: &Symbol,
+    min: &Symbol,
+    max: &Symbol,
+) -> Result<Symbol> {
+    ensure!(
+        matches!(
+            value.type_,
+            Type::PrimitiveType(
+                PrimitiveType::UInt32
+                    | PrimitiveType::UInt64
+                    | PrimitiveType::Int32
+                    | PrimitiveType::Int64
+                    | PrimitiveType::Float32
+            )
+        ),
+        TypeMismatchSnafu {
+            context: format!("clamp expects a number, found {:?}", value.type_),
+        }
+    );
+    ensure_eq_type!(min, @&value.type_);
+    ensure_eq_type!(max, @&value.type_);
+
+    let error_branch = compile_throw(compiler, "clamp min is greater than max");
+    let min_gt_max = compile_gt(compiler, min, max);
+    compiler.instructions.push(encoder::Instruction::If {
+        condition: vec![encoder::Instruction::MemLoad(Some(min_gt_max.memory_addr))],
+        then: error_branch,
+        else_: vec![],
+    });
+
+    let result = compiler.memory.allocate_symbol(value.type_.clone());
+    let width = result.type_.miden_width();
+    compiler
+        .memory
+        .read(compiler.instructions, value.memory_addr, width);
+    compiler.memory.write(
+        compiler.instructions,
+        result.memory_addr,
+        &vec![ValueSource::Stack; width as usize],
+    );
+
+    // min <= max, so at most one of the bounds is copied into the result.
+    for (is_outside, bound) in [
+        (compile_lt(compiler, value, min), min),
+        (compile_gt(compiler, value, max), max),
+    ] {
+        let mut copy_bound = vec![];
+        {
+            let copy_compiler =
+                Compiler::new(&mut copy_bound, compiler.memory, compiler.root_scope);
+            copy_compiler
+                .memory
+                .read(copy_compiler.instructions, bound.memory_addr, width);
+            copy_compiler.memory.write(
+                copy_compiler.instructions,
+                result.memory_addr,
+                &vec![ValueSource::Stack; width as usize],
+            );
+        }
+
+        compiler.instructions.push(encoder::Instruction::If {
+            condition: vec![encoder::Instruction::MemLoad(Some(is_outside.memory_addr))],
+            then: copy_bound,
+            else_: vec![],
+        });
+    }
+
+    Ok(result)
+}
+
 fn compile_shift_left(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
     match (&a.type_, &b.type_) {
         (
@@ -2685,6 +4196,9 @@ fn compile_shift_right(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbo
         (Type::PrimitiveType(PrimitiveType::Int32), Type::PrimitiveType(PrimitiveType::Int32)) => {
             int32::shift_right(compiler, a, b)
         }
+        (Type::PrimitiveType(PrimitiveType::Int64), Type::PrimitiveType(PrimitiveType::Int64)) => {
+            int64::shift_right(compiler, a, b)
+        }
         (
             Type::PrimitiveType(PrimitiveType::UInt64),
             Type::PrimitiveType(PrimitiveType::UInt32),
@@ -2700,6 +4214,19 @@ fn compile_shift_right(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbo
     }
 }
 
+/// `a >>> b`, the same as `>>` for unsigned types, but shifts in zeros for signed ones.
+fn compile_unsigned_shift_right(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    match (&a.type_, &b.type_) {
+        (Type::PrimitiveType(PrimitiveType::Int32), Type::PrimitiveType(PrimitiveType::Int32)) => {
+            int32::logical_shift_right(compiler, a, b)
+        }
+        (Type::PrimitiveType(PrimitiveType::Int64), Type::PrimitiveType(PrimitiveType::Int64)) => {
+            int64::logical_shift_right(compiler, a, b)
+        }
+        _ => compile_shift_right(compiler, a, b),
+    }
+}
+
 fn compile_index(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Result<Symbol> {
     match &a.type_ {
         Type::Map(k, _v) => {
@@ -2802,7 +4329,7 @@ fn log(compiler: &mut Compiler, scope: &mut Scope, args: &[Symbol]) -> Result<Sy
     }
 
     for arg in str_args {
-        compile_function_call(compiler, &Function::Ast(&LOG_STRING), &[arg], None)?;
+        compile_builtin_function_call(&LOG_STRING, compiler, &[arg])?;
     }
 
     Ok(Symbol {
@@ -3263,6 +4790,57 @@ fn generic_hash(compiler: &mut Compiler, value: &Symbol) -> Symbol {
     result
 }
 
+/// Returns the next `u64` of a sequence determined by `seed`: the n-th call
+/// in a program hashes `seed` and n with RPO.
+///
+/// The values are reproducible, so proofs stay deterministic, but they are not
+/// cryptographically secure: anyone who knows or chooses the seed knows the sequence.
+fn prng(compiler: &mut Compiler, seed: &Symbol) -> Symbol {
+    let result = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt64));
+
+    compiler.instructions.extend([
+        encoder::Instruction::MemLoad(Some(7)),
+        encoder::Instruction::Push(1),
+        encoder::Instruction::U32CheckedAdd,
+        encoder::Instruction::Dup(None),
+        encoder::Instruction::MemStore(Some(7)),
+        // [n]
+    ]);
+    compiler.memory.read(
+        compiler.instructions,
+        seed.memory_addr,
+        seed.type_.miden_width(),
+    );
+    // [seed_high, seed_low, n]
+    compiler.instructions.extend([
+        encoder::Instruction::Push(0),
+        // [0, seed_high, seed_low, n]
+        encoder::Instruction::Push(0),
+        encoder::Instruction::Push(0),
+        encoder::Instruction::Push(0),
+        encoder::Instruction::Push(0),
+        // [0, 0, 0, 0, 0, seed_high, seed_low, n]
+        encoder::Instruction::HMerge,
+        // [h[3], h[2], h[1], h[0]]
+        encoder::Instruction::U32Split,
+        // [h[3]_high, h[3]_low, h[2], h[1], h[0]]
+    ]);
+    compiler.memory.write(
+        compiler.instructions,
+        result.memory_addr,
+        &[ValueSource::Stack, ValueSource::Stack],
+    );
+    compiler.instructions.extend([
+        encoder::Instruction::Drop,
+        encoder::Instruction::Drop,
+        encoder::Instruction::Drop,
+    ]);
+
+    result
+}
+
 fn hash(compiler: &mut Compiler, value: Symbol) -> Result<Symbol> {
     let result = match &value.type_ {
         Type::Nullable(_) => {
@@ -3722,6 +5300,8 @@ fn prepare_scope(program: &ast::Program) -> Scope {
                                 type_: ast_type_to_type(f.required, &f.type_),
                                 delegate: f.decorators.iter().any(|d| d.name == "delegate"),
                                 read: f.decorators.iter().any(|d| d.name == "read"),
+                                immutable: f.decorators.iter().any(|d| d.name == "immutable"),
+                                deprecated: deprecation_warning("field", &f.name, &f.decorators),
                             });
                         }
                         ast::ContractItem::Function(f) => {
@@ -3736,22 +5316,341 @@ fn prepare_scope(program: &ast::Program) -> Scope {
             ast::RootNode::Function(function) => scope
                 .functions
                 .push((function.name.clone(), Function::Ast(function))),
+            ast::RootNode::Interface(_) | ast::RootNode::Import(_) => {}
         }
     }
 
     scope
 }
 
+/// Merges the fields and functions of the contracts each contract `extends` into it.
+///
+/// Inherited fields come first. A function defined in the contract itself overrides an inherited
+/// one with the same name, but a field can only be redeclared with the same type.
+fn resolve_inheritance<'ast>(
+    program: &'ast ast::Program,
+    scope: &mut Scope<'ast, '_>,
+) -> Result<()> {
+    let extends = program
+        .nodes
+        .iter()
+        .filter_map(|node| match node {
+            ast::RootNode::Contract(c) => Some((c.name.as_str(), c.extends.as_deref())),
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+    let own_contracts = scope.contracts.clone();
+    let find_own = |name: &str| {
+        own_contracts
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, c)| c)
+            .not_found("contract", name)
+    };
+
+    for (name, contract) in scope.contracts.iter_mut() {
+        let name = name.as_str();
+        // Ancestors, from the direct parent to the root of the chain.
+        let mut chain = vec![];
+        let mut base = extends.get(name).copied().flatten();
+        while let Some(base_name) = base {
+            ensure!(
+                base_name != name && !chain.contains(&base_name),
+                CyclicInheritanceSnafu { contract: name }
+            );
+            find_own(base_name)?;
+            chain.push(base_name);
+            base = extends.get(base_name).copied().flatten();
+        }
+
+        if chain.is_empty() {
+            continue;
+        }
+
+        // Each field with the contract it was first declared in.
+        let mut fields = Vec::<(ContractField, &str)>::new();
+        let mut functions = Vec::<(String, &'ast ast::Function)>::new();
+        for from in chain.iter().rev().copied().chain([name]) {
+            let from_contract = find_own(from)?;
+
+            for field in &from_contract.fields {
+                match fields.iter().find(|(f, _)| f.name == field.name) {
+                    Some((existing, declared_in)) => ensure!(
+                        existing.type_ == field.type_,
+                        TypeMismatchSnafu {
+                            context: format!(
+                                "field {} is {:?} in {declared_in} but {:?} in {from}",
+                                field.name, existing.type_, field.type_
+                            ),
+                        }
+                    ),
+                    None => fields.push((field.clone(), from)),
+                }
+            }
+
+            for (function_name, function) in &from_contract.functions {
+                match functions.iter_mut().find(|(n, _)| n == function_name) {
+                    Some(existing) => existing.1 = *function,
+                    None => functions.push((function_name.clone(), *function)),
+                }
+            }
+        }
+
+        contract.fields = fields.into_iter().map(|(f, _)| f).collect();
+        contract.functions = functions;
+    }
+
+    Ok(())
+}
+
+/// Checks that `contract` has every method of the interfaces it implements, with the same signature.
+fn check_interfaces(
+    program: &ast::Program,
+    implements: &[String],
+    contract: &Contract,
+) -> Result<()> {
+    fn signature(
+        name: &str,
+        parameters: &[ast::Parameter],
+        return_type: &Option<ast::Type>,
+    ) -> String {
+        let parameters = parameters
+            .iter()
+            .map(|p| {
+                let optional = if p.required { "" } else { "?" };
+                format!("{}{optional}: {:?}", p.name, p.type_)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        match return_type {
+            Some(return_type) => format!("{name}({parameters}): {return_type:?}"),
+            None => format!("{name}({parameters})"),
+        }
+    }
+
+    for interface_name in implements {
+        let interface = program
+            .nodes
+            .iter()
+            .find_map(|node| match node {
+                ast::RootNode::Interface(i) if &i.name == interface_name => Some(i),
+                _ => None,
+            })
+            .not_found("interface", interface_name)?;
+
+        for method in &interface.methods {
+            let (_, function) = contract
+                .functions
+                .iter()
+                .find(|(name, _)| *name == method.name)
+                .not_found(
+                    "method",
+                    &format!("{} of interface {}", method.name, interface.name),
+                )?;
+
+            let matches = function.parameters.len() == method.parameters.len()
+                && function
+                    .parameters
+                    .iter()
+                    .zip(&method.parameters)
+                    .all(|(a, b)| a.type_ == b.type_ && a.required == b.required)
+                && function.return_type == method.return_type;
+            ensure!(
+                matches,
+                TypeMismatchSnafu {
+                    context: format!(
+                        "{} in contract {} does not match {} in interface {}",
+                        signature(&function.name, &function.parameters, &function.return_type),
+                        contract.name,
+                        signature(&method.name, &method.parameters, &method.return_type),
+                        interface.name,
+                    ),
+                }
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Records are looked up by their id, so every contract must have an `id: string` field.
+fn validate_record_ids(scope: &Scope) -> Result<()> {
+    for (_, contract) in &scope.contracts {
+        let Some(id) = contract.fields.iter().find(|f| f.name == "id") else {
+            return Err(Error::simple(format!(
+                "contract {} must have an `id: string` field",
+                contract.name
+            )));
+        };
+
+        ensure!(
+            id.type_ == Type::String,
+            TypeMismatchSnafu {
+                context: format!(
+                    "field id of contract {} must be a string, found {:?}",
+                    contract.name, id.type_
+                )
+            }
+        );
+    }
+
+    Ok(())
+}
+
+/// Rejects `@index` on fields that can't be indexed, see [`Type::is_indexable`].
+fn validate_indexes(contract: &ast::Contract, this_struct: &Struct) -> Result<()> {
+    fn field_type(type_: Type, name: &str) -> Option<Type> {
+        match type_ {
+            Type::Nullable(t) => field_type(*t, name),
+            Type::Struct(s) => s
+                .fields
+                .into_iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, t)| t),
+            _ => None,
+        }
+    }
+
+    let this_type = Type::Struct(this_struct.clone());
+
+    for item in &contract.items {
+        let ast::ContractItem::Index(index) = item else {
+            continue;
+        };
+
+        for field in &index.fields {
+            let path = field.path.join(".");
+            let type_ = field
+                .path
+                .iter()
+                .try_fold(this_type.clone(), |t, part| field_type(t, part))
+                .not_found("field", &path)?;
+
+            ensure!(
+                type_.is_indexable(),
+                TypeMismatchSnafu {
+                    context: format!("cannot index field {path} of type {type_:?}")
+                }
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CompileOptions<'a> {
+    /// The source code the program was parsed from.
+    /// When set, comments in the generated masm are prefixed with the line and column they were compiled from,
+    /// and statements marked with `// @breakpoint` are recorded in [`Abi::breakpoints`].
+    pub source: Option<&'a str>,
+    /// Emit comments even in release builds and collect a symbol table of `this`, parameters and locals.
+    pub debug: bool,
+    /// Warn when a `let` or loop variable shadows a variable or parameter from an enclosing scope.
+    pub warn_shadowing: bool,
+    /// Throw an error when a loop runs more than this many iterations,
+    /// so that a runaway loop can't make the proof arbitrarily large.
+    /// A `@maxIterations(n)` decorator on a function overrides this for the loops in it.
+    pub max_loop_iterations: Option<u32>,
+}
+
+/// A non-fatal diagnostic, such as an unused variable or the use of a `@deprecated` function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    /// Where in the source the warning was emitted, if known.
+    pub span: Option<error::span::Span>,
+    /// Another location the warning refers to, such as the declaration a variable shadows.
+    pub related_span: Option<error::span::Span>,
+}
+
+/// The result of [`compile_with_options`].
+#[derive(Debug)]
+pub struct CompileOutput {
+    pub miden_code: String,
+    pub abi: Abi,
+    /// Empty unless [`CompileOptions::debug`] is set.
+    pub debug_symbols: Vec<DebugSymbol>,
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// A named symbol and the memory it was allocated at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugSymbol {
+    pub name: String,
+    pub memory_addr: u32,
+    pub type_: Type,
+}
+
 pub fn compile(
     program: ast::Program,
     contract_name: Option<&str>,
     function_name: &str,
 ) -> Result<(String, Abi)> {
+    compile_with_options(
+        program,
+        contract_name,
+        function_name,
+        CompileOptions::default(),
+    )
+    .map(|output| (output.miden_code, output.abi))
+}
+
+/// Like [`compile`], but also returns the debug symbol table and warnings.
+pub fn compile_with_options(
+    program: ast::Program,
+    contract_name: Option<&str>,
+    function_name: &str,
+    options: CompileOptions,
+) -> Result<CompileOutput> {
+    let debug_symbols = options.debug.then(DebugSymbolsGuard::start);
+    WARNINGS.with(|warnings| warnings.borrow_mut().clear());
+    DEFAULT_MAX_LOOP_ITERATIONS.with(|max| max.set(options.max_loop_iterations));
+    BREAKPOINTS.with(|sites| {
+        *sites.borrow_mut() = options.source.map(find_breakpoints).unwrap_or_default()
+    });
+
+    if let Some(import) = program.nodes.iter().find_map(|node| match node {
+        ast::RootNode::Import(import) => Some(import),
+        _ => None,
+    }) {
+        return Err(Error::simple(format!(
+            "import from {} was not resolved, parse the program with a SourceResolver",
+            import.path
+        )));
+    }
+
     let mut scope = prepare_scope(&program);
+    resolve_inheritance(&program, &mut scope)?;
+    validate_record_ids(&scope)?;
     let contract = contract_name.map(|name| scope.find_contract(name).cloned().unwrap());
     let contract = contract.as_ref();
     let contract_struct = contract.map(|c| Struct::from(c.clone()));
 
+    let ast_contract = program.nodes.iter().find_map(|node| match node {
+        ast::RootNode::Contract(c) if Some(c.name.as_str()) == contract_name => Some(c),
+        _ => None,
+    });
+    if let Some((ast_contract, this_struct)) = ast_contract.zip(contract_struct.as_ref()) {
+        // Indexes of the contracts it extends apply to the merged fields too.
+        // `resolve_inheritance` has already rejected cyclic chains.
+        let mut declaring = Some(ast_contract);
+        while let Some(c) = declaring {
+            validate_indexes(c, this_struct)?;
+            declaring = c.extends.as_deref().and_then(|base| {
+                program.nodes.iter().find_map(|node| match node {
+                    ast::RootNode::Contract(base_contract) if base_contract.name == base => {
+                        Some(base_contract)
+                    }
+                    _ => None,
+                })
+            });
+        }
+    }
+    if let Some((ast_contract, contract)) = ast_contract.zip(contract) {
+        check_interfaces(&program, &ast_contract.implements, contract)?;
+    }
+
     let (function, param_types) = match function_name {
         ".readAuth" => (None, vec![]),
         _ => {
@@ -3786,6 +5685,8 @@ pub fn compile(
     // A vector of hashmaps for each field, mapping the address of one of the field elements to the count of times it was used
     let mut used_fields_count: Vec<HashMap<u32, usize>>;
     let mut dependent_fields = Vec::<(String, Type)>::new();
+    let mut written_fields = Vec::<String>::new();
+    let mut read_fields = Vec::new();
     // hashing will generate read instructions
     const USED_FIELD_COUNT_THRESHOLD: usize = 2;
 
@@ -4000,18 +5901,29 @@ pub fn compile(
             }
         }
 
+        let function_start = compiler.instructions.len();
         result = match function {
             // read auth
             None => {
                 let ctx_pk = struct_field(&mut compiler, &ctx, "publicKey")?;
 
-                let read_auth = compile_read_authorization_proof(
+                let (read_auth, field_read_auths) = compile_read_authorization_proof(
                     &mut compiler,
                     &salts_this_symbol.as_ref().unwrap().1,
                     contract.as_ref().unwrap(),
                     &ctx_pk,
                 )?;
 
+                // The field results go below read_auth, with the first field right under it.
+                for (_, field_read_auth) in field_read_auths.iter().rev() {
+                    compiler.memory.read(
+                        compiler.instructions,
+                        field_read_auth.memory_addr,
+                        field_read_auth.type_.miden_width(),
+                    );
+                }
+                read_fields = field_read_auths.into_iter().map(|(name, _)| name).collect();
+
                 compiler.memory.read(
                     compiler.instructions,
                     read_auth.memory_addr,
@@ -4027,6 +5939,7 @@ pub fn compile(
                 salts_this_symbol.as_ref().map(|(_, ts)| ts).cloned(),
             )?,
         };
+        let function_end = compiler.instructions.len();
 
         if let Some(result) = &result {
             let result_hash = hash(&mut compiler, result.clone())?;
@@ -4103,6 +6016,30 @@ pub fn compile(
             start..end
         });
         if let Some(struct_addr_range) = struct_addr_range.as_ref() {
+            // Fields that aren't loaded read back as default values after the run,
+            // so the host needs to know which of them the function replaced.
+            let mut written = vec![false; field_addr_ranges.len()];
+            encoder::walk(
+                &compiler.instructions[function_start..function_end],
+                &mut |inst| {
+                    if let encoder::Instruction::MemStore(Some(addr)) = inst {
+                        if let Some(i) = field_addr_ranges
+                            .iter()
+                            .position(|range| range.contains(addr))
+                        {
+                            written[i] = true;
+                        }
+                    }
+                },
+            );
+            written_fields = contract_struct
+                .iter()
+                .flat_map(|s| &s.fields)
+                .zip(written)
+                .filter(|(_, written)| *written)
+                .map(|((name, _), _)| name.clone())
+                .collect();
+
             encoder::walk(compiler.instructions, &mut |inst| {
                 if let encoder::Instruction::MemLoad(Some(addr)) = inst {
                     // First, check if the address is in the struct
@@ -4160,7 +6097,7 @@ pub fn compile(
         );
     }
 
-    let instructions = encoder::unabstract(
+    let mut instructions = encoder::unabstract(
         instructions,
         &mut |size| memory.allocate(size),
         &mut None,
@@ -4169,13 +6106,37 @@ pub fn compile(
         false,
     );
 
+    if let Some(source) = options.source {
+        encoder::add_source_locations(&mut instructions, source);
+    }
+
+    // A function outside of a contract gets an empty `this` that reads and writes nothing,
+    // so that callers can handle `this` the same way for every program.
+    let (this_addr, this_struct) = match contract_struct {
+        Some(contract_struct) => (this_addr, contract_struct),
+        None => (
+            Some(0),
+            Struct {
+                name: "Empty".to_owned(),
+                fields: vec![],
+            },
+        ),
+    };
+
     let abi = Abi {
         dependent_fields,
+        written_fields: Some(written_fields),
         this_addr,
-        this_type: contract_struct.map(Type::Struct),
+        this_type: Some(Type::Struct(this_struct)),
         result_addr: result.as_ref().map(|r| r.memory_addr),
         result_type: result.map(|r| r.type_),
         param_types,
+        param_names: function
+            .map(|f| f.parameters.iter().map(|p| p.name.clone()).collect())
+            .unwrap_or_default(),
+        variadic: function
+            .and_then(|f| f.parameters.last())
+            .is_some_and(|p| p.variadic),
         other_contract_types: scope
             .contracts
             .iter()
@@ -4186,6 +6147,21 @@ pub fn compile(
             .map(|x| x.0)
             .collect(),
         std_version: Some(StdVersion::V0_7_0),
+        breakpoints: BREAKPOINTS.with(|sites| {
+            sites
+                .take()
+                .into_iter()
+                .flat_map(|site| {
+                    site.procedures
+                        .into_iter()
+                        .map(move |procedure| Breakpoint {
+                            line: site.line,
+                            procedure,
+                        })
+                })
+                .collect()
+        }),
+        read_fields,
     };
 
     let mut uses_sha256 = false;
@@ -4209,6 +6185,8 @@ pub fn compile(
     if uses_blake3 {
         miden_code.push_str("use.std::crypto::hashes::blake3\n");
     }
+    encoder::encode_procedures(&instructions, unsafe { miden_code.as_mut_vec() })
+        .context(IoSnafu)?;
     miden_code.push_str("begin\n");
     miden_code.push_str("  push.");
     miden_code.push_str(&memory.static_alloc_ptr.to_string());
@@ -4221,15 +6199,33 @@ pub fn compile(
     }
     miden_code.push_str("end\n");
 
-    Ok((miden_code, abi))
+    let debug_symbols = debug_symbols
+        .map(DebugSymbolsGuard::finish)
+        .unwrap_or_default();
+
+    // Imported declarations aren't linted, their spans point into the file they were imported from.
+    let mut warnings = function
+        .filter(|f| !program.is_imported(f))
+        .map(|f| lint::check_function(f, options.warn_shadowing))
+        .unwrap_or_default();
+    warnings.extend(WARNINGS.with(|warnings| warnings.take()));
+
+    Ok(CompileOutput {
+        miden_code,
+        abi,
+        debug_symbols,
+        warnings,
+    })
 }
 
+/// Returns whether `auth_pk` can read the record,
+/// along with whether each `@read` field on its own authorizes `auth_pk`.
 fn compile_read_authorization_proof(
     compiler: &mut Compiler,
     struct_symbol: &Symbol,
     contract: &Contract,
     auth_pk: &Symbol,
-) -> Result<Symbol> {
+) -> Result<(Symbol, Vec<(String, Symbol)>)> {
     let result = compiler
         .memory
         .allocate_symbol(Type::PrimitiveType(PrimitiveType::Boolean));
@@ -4239,9 +6235,18 @@ fn compile_read_authorization_proof(
         compiler
             .instructions
             .push(encoder::Instruction::MemStore(Some(result.memory_addr)));
-        return Ok(result);
+
+        // Every field of a public contract can be read.
+        let field_results = contract
+            .fields
+            .iter()
+            .filter(|f| f.read)
+            .map(|field| (field.name.clone(), result.clone()))
+            .collect();
+        return Ok((result, field_results));
     }
 
+    let mut field_results = vec![];
     for field in contract.fields.iter().filter(|f| f.read) {
         let field_symbol = struct_field(compiler, struct_symbol, &field.name)?;
         compiler.memory.read(
@@ -4259,9 +6264,10 @@ fn compile_read_authorization_proof(
             ],
             else_: vec![],
         });
+        field_results.push((field.name.clone(), passed));
     }
 
-    Ok(result)
+    Ok((result, field_results))
 }
 
 fn compile_call_authorization_proof(
@@ -4341,6 +6347,43 @@ fn compile_call_authorization_proof(
         maybe_start!(decorator_span);
 
         let arg_value = match call_arg {
+            // `@call(canCall)`, where `canCall` is a function of the contract returning a boolean.
+            ast::DecoratorArgument::Identifier(id)
+                if !contract.fields.iter().any(|f| &f.name == id) =>
+            {
+                let Some((_, auth_function)) =
+                    contract.functions.iter().find(|(name, _)| name == id)
+                else {
+                    return Err(Error::simple(format!(
+                        "@call argument {id} is not a field or function of {contract_name}"
+                    )));
+                };
+                ensure!(
+                    auth_function.parameters.is_empty(),
+                    TypeMismatchSnafu {
+                        context: format!("@call function {id} must not take any parameters"),
+                    }
+                );
+
+                let passed = compile_ast_function_call(
+                    auth_function,
+                    compiler,
+                    &[],
+                    Some(contract_symbol.clone()),
+                )?
+                .ok_or_else(|| Error::simple(format!("@call function {id} must return a value")))?;
+                ensure_eq_type!(passed, Type::PrimitiveType(PrimitiveType::Boolean));
+
+                compiler.instructions.push(encoder::Instruction::If {
+                    condition: vec![encoder::Instruction::MemLoad(Some(passed.memory_addr))],
+                    then: vec![
+                        encoder::Instruction::Push(1),
+                        encoder::Instruction::MemStore(Some(result.memory_addr)),
+                    ],
+                    else_: vec![],
+                });
+                continue;
+            }
             ast::DecoratorArgument::Identifier(id) => {
                 let mut current_field = contract_symbol.clone();
                 {
@@ -4356,6 +6399,11 @@ fn compile_call_authorization_proof(
                     publickey::new(compiler, key)
                 }
             },
+            ast::DecoratorArgument::String(_) | ast::DecoratorArgument::Number(_) => {
+                return Err(Error::simple(
+                    "@call arguments must be fields, functions or public keys",
+                ))
+            }
         };
 
         let passed = compile_check_eq_or_ownership(compiler, arg_value, auth_pk)?;
@@ -4678,7 +6726,7 @@ fn ast_type_to_type(required: bool, type_: &ast::Type) -> Type {
 pub fn compile_hasher(t: Type, salts: Option<&[u32]>) -> Result<String> {
     let mut instructions = vec![];
     let mut memory = Memory::new();
-    let empty_program = ast::Program { nodes: vec![] };
+    let empty_program = ast::Program::default();
     let scope = prepare_scope(&empty_program);
 
     {
@@ -4770,4 +6818,40 @@ mod tests {
         assert_eq!(convert_f64_to_f32(std::f64::MAX), None);
         assert_eq!(convert_f64_to_f32(std::f64::MIN), None);
     }
+
+    #[test]
+    fn breakpoints_are_not_placed_in_builtins() {
+        // Pad the breakpoint comment so that `log` starts at the same offset
+        // as the first statement of the `logString` builtin.
+        let builtin_start = LOG_STRING.statements[0].span().unwrap().start;
+        let prefix = "function main() {\n    // @breakpoint";
+        let padding = " ".repeat(builtin_start - prefix.len() - "\n    ".len());
+        let code = format!("{prefix}{padding}\n    log('a');\n}}\n");
+
+        let program = polylang_parser::parse(&code).unwrap();
+        let output = compile_with_options(
+            program,
+            None,
+            "main",
+            CompileOptions {
+                source: Some(&code),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let [breakpoint] = &output.abi.breakpoints[..] else {
+            panic!("expected one breakpoint: {:?}", output.abi.breakpoints);
+        };
+        assert_eq!(breakpoint.line, 3);
+        for instruction in ["proc", "exec"] {
+            let line = format!("{instruction}.{}\n", breakpoint.procedure);
+            assert_eq!(
+                output.miden_code.matches(&line).count(),
+                1,
+                "{}",
+                output.miden_code
+            );
+        }
+    }
 }
@@ -13,6 +13,24 @@ pub(crate) fn is_not_null(value: &Symbol) -> Symbol {
     }
 }
 
+/// Allocates a null of `t`, or of its inner type if `t` is already nullable.
+pub(crate) fn null(compiler: &mut Compiler, t: &Type) -> Symbol {
+    let t = match t {
+        Type::Nullable(t) => *t.clone(),
+        t => t.clone(),
+    };
+    let symbol = compiler.memory.allocate_symbol(Type::Nullable(Box::new(t)));
+
+    // The symbol is reused when this runs in a loop, so the flag is written explicitly.
+    compiler.memory.write(
+        compiler.instructions,
+        is_not_null(&symbol).memory_addr,
+        &[ValueSource::Immediate(0)],
+    );
+
+    symbol
+}
+
 pub(crate) fn value(value: Symbol) -> Symbol {
     Symbol {
         memory_addr: value.memory_addr + 1,
@@ -23,7 +41,9 @@ pub(crate) fn value(value: Symbol) -> Symbol {
     }
 }
 
-pub(crate) fn eq(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+/// Two nulls are equal, a null is never equal to a value,
+/// and two values are compared with `compile_eq`.
+pub(crate) fn eq(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Result<Symbol> {
     let result = compiler
         .memory
         .allocate_symbol(Type::PrimitiveType(PrimitiveType::Boolean));
@@ -32,10 +52,10 @@ pub(crate) fn eq(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
         let mut insts = vec![];
         std::mem::swap(compiler.instructions, &mut insts);
 
-        let res = compile_eq(compiler, &value(a.clone()), &value(b.clone())).unwrap();
+        let res = compile_eq(compiler, &value(a.clone()), &value(b.clone()));
 
         std::mem::swap(compiler.instructions, &mut insts);
-        (res, insts)
+        (res?, insts)
     };
 
     compiler.instructions.extend([
@@ -64,5 +84,50 @@ pub(crate) fn eq(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
         },
     ]);
 
-    result
+    Ok(result)
+}
+
+/// Compiles `a ?? b`. `b` is only evaluated if `a` is null.
+/// The result is nullable only if `b` is, a non-nullable `a` is returned as is.
+pub(crate) fn coalesce<'ast>(
+    compiler: &mut Compiler<'ast, '_, '_>,
+    a: &Symbol,
+    b: impl FnOnce(&mut Compiler<'ast, '_, '_>) -> Result<Symbol>,
+) -> Result<Symbol> {
+    let Type::Nullable(inner_type) = &a.type_ else {
+        return Ok(a.clone());
+    };
+
+    let mut b_instructions = vec![];
+    let mut b_compiler = Compiler::new(&mut b_instructions, compiler.memory, compiler.root_scope);
+    let b = b(&mut b_compiler)?;
+
+    let a_value = if b.type_ == a.type_ {
+        a.clone()
+    } else {
+        ensure_eq_type!(b, @inner_type.as_ref());
+        value(a.clone())
+    };
+
+    let result = compiler.memory.allocate_symbol(b.type_.clone());
+    let copy = |from: &Symbol| {
+        (0..from.type_.miden_width())
+            .map(|i| ValueSource::Memory(from.memory_addr + i))
+            .collect::<Vec<_>>()
+    };
+    let mut then = vec![];
+    compiler
+        .memory
+        .write(&mut then, result.memory_addr, &copy(&a_value));
+    compiler
+        .memory
+        .write(&mut b_instructions, result.memory_addr, &copy(&b));
+
+    compiler.instructions.push(Instruction::If {
+        condition: vec![Instruction::MemLoad(Some(is_not_null(a).memory_addr))],
+        then,
+        else_: b_instructions,
+    });
+
+    Ok(result)
 }
@@ -46,7 +46,7 @@ pub(crate) fn data_ptr(string: &Symbol) -> Symbol {
 }
 
 /// Expects the stack to be: [len, src_ptr, dest_ptr]
-fn copy_str_stack(compiler: &mut Compiler) {
+pub(crate) fn copy_str_stack(compiler: &mut Compiler) {
     // [len, src_ptr, dest_ptr]
     compiler.instructions.push(Instruction::While {
         // len > 0
@@ -778,6 +778,132 @@ pub(crate) fn eq(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
     result
 }
 
+/// Compares the bytes of `a` and `b` until they differ.
+/// If one string is a prefix of the other, the shorter one is smaller.
+pub(crate) fn lt(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    let result = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Boolean));
+    let decided = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Boolean));
+    let i = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt32));
+
+    let condition = [
+        Instruction::MemLoad(Some(decided.memory_addr)),
+        Instruction::Not,
+        // [!decided]
+        Instruction::MemLoad(Some(i.memory_addr)),
+        Instruction::MemLoad(Some(length(a).memory_addr)),
+        Instruction::U32CheckedLT,
+        Instruction::And,
+        // [!decided && i < a_len]
+        Instruction::MemLoad(Some(i.memory_addr)),
+        Instruction::MemLoad(Some(length(b).memory_addr)),
+        Instruction::U32CheckedLT,
+        Instruction::And,
+        // [!decided && i < a_len && i < b_len]
+    ];
+
+    compiler.instructions.extend([
+        Instruction::Push(0),
+        Instruction::MemStore(Some(result.memory_addr)),
+        Instruction::Push(0),
+        Instruction::MemStore(Some(decided.memory_addr)),
+        Instruction::Push(0),
+        Instruction::MemStore(Some(i.memory_addr)),
+    ]);
+    compiler.instructions.extend(condition.clone());
+    compiler.instructions.push(Instruction::WhileTrueRaw {
+        instructions: [
+            Instruction::MemLoad(Some(data_ptr(a).memory_addr)),
+            Instruction::MemLoad(Some(i.memory_addr)),
+            Instruction::U32CheckedAdd,
+            Instruction::MemLoad(None),
+            // [a[i]]
+            Instruction::MemLoad(Some(data_ptr(b).memory_addr)),
+            Instruction::MemLoad(Some(i.memory_addr)),
+            Instruction::U32CheckedAdd,
+            Instruction::MemLoad(None),
+            // [b[i], a[i]]
+            Instruction::If {
+                condition: vec![
+                    Instruction::Dup(Some(1)),
+                    Instruction::Dup(Some(1)),
+                    Instruction::U32CheckedEq,
+                    // [a[i] == b[i], b[i], a[i]]
+                ],
+                then: vec![
+                    Instruction::Drop,
+                    Instruction::Drop,
+                    // []
+                    Instruction::MemLoad(Some(i.memory_addr)),
+                    Instruction::Push(1),
+                    Instruction::U32CheckedAdd,
+                    Instruction::MemStore(Some(i.memory_addr)),
+                ],
+                else_: vec![
+                    Instruction::U32CheckedLT,
+                    // [a[i] < b[i]]
+                    Instruction::MemStore(Some(result.memory_addr)),
+                    Instruction::Push(1),
+                    Instruction::MemStore(Some(decided.memory_addr)),
+                ],
+            },
+        ]
+        .into_iter()
+        .chain(condition)
+        .collect(),
+    });
+    compiler.instructions.push(Instruction::If {
+        condition: vec![
+            Instruction::MemLoad(Some(decided.memory_addr)),
+            Instruction::Not,
+        ],
+        // all compared bytes are equal, so the shorter string is smaller
+        then: vec![
+            Instruction::MemLoad(Some(length(a).memory_addr)),
+            Instruction::MemLoad(Some(length(b).memory_addr)),
+            Instruction::U32CheckedLT,
+            // [a_len < b_len]
+            Instruction::MemStore(Some(result.memory_addr)),
+        ],
+        else_: vec![],
+    });
+
+    result
+}
+
+pub(crate) fn gt(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    lt(compiler, b, a)
+}
+
+pub(crate) fn lte(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    let gt = gt(compiler, a, b);
+    not(compiler, &gt)
+}
+
+pub(crate) fn gte(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    let lt = lt(compiler, a, b);
+    not(compiler, &lt)
+}
+
+fn not(compiler: &mut Compiler, x: &Symbol) -> Symbol {
+    let result = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::Boolean));
+
+    compiler.instructions.extend([
+        Instruction::MemLoad(Some(x.memory_addr)),
+        Instruction::Not,
+        Instruction::MemStore(Some(result.memory_addr)),
+    ]);
+
+    result
+}
+
 pub(crate) fn hash(compiler: &mut Compiler, _scope: &Scope, args: &[Symbol]) -> Result<Symbol> {
     ensure!(
         args.len() == 1,
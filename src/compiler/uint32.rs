@@ -175,6 +175,9 @@ pub(crate) fn modulo(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol
     let result = compiler
         .memory
         .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt32));
+
+    compile_check_divisor(compiler, b);
+
     compiler
         .memory
         .read(compiler.instructions, a.memory_addr, a.type_.miden_width());
@@ -197,6 +200,9 @@ pub(crate) fn div(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
     let result = compiler
         .memory
         .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt32));
+
+    compile_check_divisor(compiler, b);
+
     compiler
         .memory
         .read(compiler.instructions, a.memory_addr, a.type_.miden_width());
@@ -1,6 +1,26 @@
 use super::*;
 
 // Layout: [high, low]
+pub(crate) fn new(compiler: &mut Compiler, value: u64) -> Symbol {
+    let symbol = compiler
+        .memory
+        .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt64));
+
+    // memory is zero-initialized, so we don't need to write for 0
+    if value != 0 {
+        compiler.memory.write(
+            compiler.instructions,
+            symbol.memory_addr,
+            &[
+                ValueSource::Immediate((value >> 32) as u32),
+                ValueSource::Immediate(value as u32),
+            ],
+        );
+    }
+
+    symbol
+}
+
 pub(crate) fn cast_from_uint32(compiler: &mut Compiler, from: &Symbol, dest: &Symbol) {
     assert_eq!(from.type_, Type::PrimitiveType(PrimitiveType::UInt32));
     assert_eq!(dest.type_, Type::PrimitiveType(PrimitiveType::UInt64));
@@ -183,6 +203,8 @@ pub(crate) fn modulo(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol
         .memory
         .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt64));
 
+    compile_check_divisor(compiler, b);
+
     compiler
         .memory
         .read(compiler.instructions, a.memory_addr, a.type_.miden_width());
@@ -206,6 +228,8 @@ pub(crate) fn div(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
         .memory
         .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt64));
 
+    compile_check_divisor(compiler, b);
+
     compiler
         .memory
         .read(compiler.instructions, a.memory_addr, a.type_.miden_width());
@@ -224,30 +248,21 @@ pub(crate) fn div(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
     result
 }
 
+/// Multiplies `a` by `b`, failing if the product does not fit in 64 bits.
+///
+/// `u64::checked_mul` does schoolbook multiplication of the 32-bit limbs,
+/// propagating carries between them, and asserts that all the limbs above
+/// the low 64 bits of the 128-bit product are zero.
 pub(crate) fn mul(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
-    let result = compiler
-        .memory
-        .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt64));
-
-    compiler
-        .memory
-        .read(compiler.instructions, a.memory_addr, a.type_.miden_width());
-    compiler
-        .memory
-        .read(compiler.instructions, b.memory_addr, b.type_.miden_width());
-    compiler
-        .instructions
-        .push(encoder::Instruction::Exec("u64::checked_mul"));
-    compiler.memory.write(
-        compiler.instructions,
-        result.memory_addr,
-        &[ValueSource::Stack, ValueSource::Stack],
-    );
+    mul_with(compiler, a, b, "u64::checked_mul")
+}
 
-    result
+/// Multiplies `a` by `b`, discarding the bits of the product above 64 bits.
+pub(crate) fn wrapping_mul(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    mul_with(compiler, a, b, "u64::wrapping_mul")
 }
 
-pub(crate) fn shift_left(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+fn mul_with(compiler: &mut Compiler, a: &Symbol, b: &Symbol, procedure: &'static str) -> Symbol {
     let result = compiler
         .memory
         .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt64));
@@ -255,12 +270,15 @@ pub(crate) fn shift_left(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Sym
     compiler
         .memory
         .read(compiler.instructions, a.memory_addr, a.type_.miden_width());
+    // [a_high, a_low]
     compiler
         .memory
         .read(compiler.instructions, b.memory_addr, b.type_.miden_width());
+    // [b_high, b_low, a_high, a_low]
     compiler
         .instructions
-        .push(encoder::Instruction::Exec("u64::checked_shl"));
+        .push(encoder::Instruction::Exec(procedure));
+    // [c_high, c_low]
     compiler.memory.write(
         compiler.instructions,
         result.memory_addr,
@@ -270,25 +288,340 @@ pub(crate) fn shift_left(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Sym
     result
 }
 
+/// Shifts `a` left by `b` bits, discarding the bits shifted out. Shifting by 64 or more gives 0.
+pub(crate) fn shift_left(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    shift(compiler, a, b, true)
+}
+
+/// Shifts `a` right by `b` bits. Shifting by 64 or more gives 0.
 pub(crate) fn shift_right(compiler: &mut Compiler, a: &Symbol, b: &Symbol) -> Symbol {
+    shift(compiler, a, b, false)
+}
+
+fn shift(compiler: &mut Compiler, a: &Symbol, b: &Symbol, left: bool) -> Symbol {
+    use encoder::Instruction;
+
     let result = compiler
         .memory
         .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt64));
-
-    compiler
-        .memory
-        .read(compiler.instructions, a.memory_addr, a.type_.miden_width());
-    compiler
-        .memory
-        .read(compiler.instructions, b.memory_addr, b.type_.miden_width());
-    compiler
-        .instructions
-        .push(encoder::Instruction::Exec("u64::checked_shr"));
     compiler.memory.write(
         compiler.instructions,
         result.memory_addr,
-        &[ValueSource::Stack, ValueSource::Stack],
+        &[ValueSource::Immediate(0), ValueSource::Immediate(0)],
     );
 
+    let (a_high, a_low) = (a.memory_addr, a.memory_addr + 1);
+    let (result_high, result_low) = (result.memory_addr, result.memory_addr + 1);
+    let (b_high, b_low) = (b.memory_addr, b.memory_addr + 1);
+
+    // Bits move from the `from` limb into the `to` limb.
+    let (from, to, result_from, result_to) = if left {
+        (a_low, a_high, result_low, result_high)
+    } else {
+        (a_high, a_low, result_high, result_low)
+    };
+    let (toward, away) = if left {
+        (
+            Instruction::U32CheckedSHL(None),
+            Instruction::U32CheckedSHR(None),
+        )
+    } else {
+        (
+            Instruction::U32CheckedSHR(None),
+            Instruction::U32CheckedSHL(None),
+        )
+    };
+
+    // 32 <= b < 64: only the `from` limb is left, shifted by b - 32, in the `to` limb.
+    let across_limbs = vec![
+        Instruction::MemLoad(Some(from)),
+        Instruction::MemLoad(Some(b_low)),
+        Instruction::Push(32),
+        Instruction::U32CheckedSub,
+        // [b - 32, from]
+        toward.clone(),
+        Instruction::MemStore(Some(result_to)),
+    ];
+    // b == 0: u32 shifts take amounts below 32, so `from` can't be shifted by 32 - b.
+    let identity = vec![
+        Instruction::MemLoad(Some(a_high)),
+        Instruction::MemStore(Some(result_high)),
+        Instruction::MemLoad(Some(a_low)),
+        Instruction::MemStore(Some(result_low)),
+    ];
+    // 0 < b < 32: the `to` limb gets the top b bits of the `from` limb.
+    let within_limbs = vec![
+        Instruction::MemLoad(Some(to)),
+        Instruction::MemLoad(Some(b_low)),
+        toward.clone(),
+        // [to shifted by b]
+        Instruction::MemLoad(Some(from)),
+        Instruction::Push(32),
+        Instruction::MemLoad(Some(b_low)),
+        Instruction::U32CheckedSub,
+        // [32 - b, from, to shifted by b]
+        away,
+        Instruction::U32CheckedOr,
+        Instruction::MemStore(Some(result_to)),
+        Instruction::MemLoad(Some(from)),
+        Instruction::MemLoad(Some(b_low)),
+        toward,
+        Instruction::MemStore(Some(result_from)),
+    ];
+
+    compiler.instructions.push(Instruction::If {
+        condition: vec![
+            Instruction::MemLoad(Some(b_high)),
+            Instruction::Push(0),
+            Instruction::U32CheckedEq,
+            Instruction::MemLoad(Some(b_low)),
+            Instruction::Push(64),
+            Instruction::U32CheckedLT,
+            Instruction::And,
+            // [b < 64]
+        ],
+        then: vec![Instruction::If {
+            condition: vec![
+                Instruction::MemLoad(Some(b_low)),
+                Instruction::Push(32),
+                Instruction::U32CheckedGTE,
+            ],
+            then: across_limbs,
+            else_: vec![Instruction::If {
+                condition: vec![
+                    Instruction::MemLoad(Some(b_low)),
+                    Instruction::Push(0),
+                    Instruction::U32CheckedEq,
+                ],
+                then: identity,
+                else_: within_limbs,
+            }],
+        }],
+        else_: vec![],
+    });
+
     result
 }
+
+#[cfg(test)]
+mod test {
+    use miden::{DefaultHost, MemAdviceProvider, ProvingOptions};
+
+    use super::*;
+
+    fn new(compiler: &mut Compiler, value: u64) -> Symbol {
+        let symbol = compiler
+            .memory
+            .allocate_symbol(Type::PrimitiveType(PrimitiveType::UInt64));
+
+        compiler.memory.write(
+            compiler.instructions,
+            symbol.memory_addr,
+            &[
+                ValueSource::Immediate((value >> 32) as u32),
+                ValueSource::Immediate(value as u32),
+            ],
+        );
+
+        symbol
+    }
+
+    fn run_binary(
+        op: fn(&mut Compiler, &Symbol, &Symbol) -> Symbol,
+        a: u64,
+        b: u64,
+    ) -> Result<u64, miden::ExecutionError> {
+        let mut instructions = Vec::new();
+        let mut memory = Memory::new();
+        let scope = Scope::new();
+        let mut compiler = Compiler::new(&mut instructions, &mut memory, &scope);
+        let a = new(&mut compiler, a);
+        let b = new(&mut compiler, b);
+
+        let result = op(&mut compiler, &a, &b);
+        compiler.memory.read(
+            compiler.instructions,
+            result.memory_addr,
+            result.type_.miden_width(),
+        );
+
+        let mut program = "use.std::math::u64\nbegin\n".to_string();
+        for instruction in &instructions {
+            instruction
+                .encode(unsafe { program.as_mut_vec() }, 1)
+                .unwrap();
+        }
+        program.push_str("\nend\n");
+
+        let assembler = miden::Assembler::default()
+            .with_library(&miden_stdlib::StdLibrary::default())
+            .unwrap();
+        let host = DefaultHost::new(MemAdviceProvider::default());
+        let outputs = miden::execute(
+            &assembler.compile(&program).unwrap(),
+            miden::StackInputs::default(),
+            host,
+            ProvingOptions::default().exec_options,
+        )?;
+
+        let stack = outputs.stack_outputs().stack();
+
+        Ok((stack[0] << 32) | stack[1])
+    }
+
+    #[test]
+    fn test_mul() {
+        macro_rules! test {
+            ($a:expr, $b:expr, $expected:pat_param) => {
+                let result = run_binary(super::mul, $a, $b);
+                assert!(
+                    matches!(result, $expected),
+                    "mul({}, {}) = {:?}, expected {}",
+                    $a,
+                    $b,
+                    result,
+                    stringify!($expected)
+                );
+            };
+        }
+
+        test!(0, 0, Ok(0));
+        test!(1, 0, Ok(0));
+        test!(0, 1, Ok(0));
+        test!(1, 1, Ok(1));
+        test!(6, 7, Ok(42));
+        test!(u64::MAX, 1, Ok(u64::MAX));
+
+        // the product no longer fits in the low limb
+        test!(0x1_0000, 0x1_0000, Ok(0x1_0000_0000));
+        test!(u32::MAX as u64, u32::MAX as u64, Ok(0xFFFF_FFFE_0000_0001));
+        test!(0x1_0000_0001, 3, Ok(0x3_0000_0003));
+        test!(0x1234_5678, 0x9ABC, Ok(0xB00_DA73_B020));
+
+        test!(u64::MAX, 2, Err(_));
+        test!(0x1_0000_0000, 0x1_0000_0000, Err(_));
+        test!(u64::MAX, u64::MAX, Err(_));
+    }
+
+    #[test]
+    fn test_div() {
+        macro_rules! test {
+            ($a:expr, $b:expr, $expected:pat_param) => {
+                let result = run_binary(super::div, $a, $b);
+                assert!(
+                    matches!(result, $expected),
+                    "div({}, {}) = {:?}, expected {}",
+                    $a,
+                    $b,
+                    result,
+                    stringify!($expected)
+                );
+            };
+        }
+
+        test!(0, 1, Ok(0));
+        test!(42, 6, Ok(7));
+        test!(43, 6, Ok(7));
+        test!(u64::MAX, 1, Ok(u64::MAX));
+        test!(u64::MAX, u64::MAX, Ok(1));
+        test!(0x3_0000_0003, 3, Ok(0x1_0000_0001));
+        test!(u64::MAX, 0x1_0000_0000, Ok(0xFFFF_FFFF));
+        test!(1, u64::MAX, Ok(0));
+
+        test!(0, 0, Err(miden::ExecutionError::FailedAssertion(_, _)));
+        test!(u64::MAX, 0, Err(miden::ExecutionError::FailedAssertion(_, _)));
+    }
+
+    #[test]
+    fn test_modulo() {
+        macro_rules! test {
+            ($a:expr, $b:expr, $expected:pat_param) => {
+                let result = run_binary(super::modulo, $a, $b);
+                assert!(
+                    matches!(result, $expected),
+                    "modulo({}, {}) = {:?}, expected {}",
+                    $a,
+                    $b,
+                    result,
+                    stringify!($expected)
+                );
+            };
+        }
+
+        test!(0, 1, Ok(0));
+        test!(42, 6, Ok(0));
+        test!(43, 6, Ok(1));
+        test!(u64::MAX, 0x1_0000_0000, Ok(0xFFFF_FFFF));
+        test!(0x1_0000_0005, 0x1_0000_0000, Ok(5));
+        test!(1, u64::MAX, Ok(1));
+
+        test!(0, 0, Err(miden::ExecutionError::FailedAssertion(_, _)));
+        test!(u64::MAX, 0, Err(miden::ExecutionError::FailedAssertion(_, _)));
+    }
+
+    #[test]
+    fn test_wrapping_mul() {
+        macro_rules! test {
+            ($a:expr, $b:expr, $expected:expr) => {
+                let expected: u64 = $expected;
+                let result = run_binary(super::wrapping_mul, $a, $b);
+                assert!(
+                    matches!(result, Ok(r) if r == expected),
+                    "wrapping_mul({}, {}) = {:?}, expected {}",
+                    $a,
+                    $b,
+                    result,
+                    expected
+                );
+            };
+        }
+
+        test!(0, 0, 0);
+        test!(6, 7, 42);
+        test!(u32::MAX as u64, u32::MAX as u64, 0xFFFF_FFFE_0000_0001);
+        test!(u64::MAX, 2, u64::MAX.wrapping_mul(2));
+        test!(u64::MAX, u64::MAX, 1);
+        test!(0x1_0000_0000, 0x1_0000_0000, 0);
+        test!(
+            0xDEAD_BEEF_CAFE_BABE,
+            0x0123_4567_89AB_CDEF,
+            0xDEAD_BEEF_CAFE_BABEu64.wrapping_mul(0x0123_4567_89AB_CDEF)
+        );
+    }
+
+    #[test]
+    fn test_shift() {
+        macro_rules! test {
+            ($op:ident, $a:expr, $b:expr, $expected:expr) => {
+                let expected: u64 = $expected;
+                let result = run_binary(super::$op, $a, $b);
+                assert!(
+                    matches!(result, Ok(r) if r == expected),
+                    "{}({}, {}) = {:?}, expected {}",
+                    stringify!($op),
+                    $a,
+                    $b,
+                    result,
+                    expected
+                );
+            };
+        }
+
+        test!(shift_left, 1, 40, 1 << 40);
+        test!(shift_left, 0xDEAD_BEEF, 0, 0xDEAD_BEEF);
+        test!(shift_left, u64::MAX, 0, u64::MAX);
+        test!(shift_left, 0xFFFF_FFFF, 4, 0xF_FFFF_FFF0);
+        test!(shift_left, 0x1234_5678_9ABC_DEF0, 32, 0x9ABC_DEF0_0000_0000);
+        test!(shift_left, u64::MAX, 63, 1 << 63);
+        test!(shift_left, 1, 64, 0);
+        test!(shift_left, 1, 1 << 32, 0);
+
+        test!(shift_right, 1 << 40, 40, 1);
+        test!(shift_right, 0xDEAD_BEEF, 0, 0xDEAD_BEEF);
+        test!(shift_right, 0x1_0000_000F, 4, 0x1000_0000);
+        test!(shift_right, 0x1234_5678_9ABC_DEF0, 32, 0x1234_5678);
+        test!(shift_right, u64::MAX, 63, 1);
+        test!(shift_right, u64::MAX, 64, 0);
+        test!(shift_right, u64::MAX, 1 << 32, 0);
+    }
+}
@@ -7,13 +7,73 @@ mod validation;
 
 use polylang_parser::{LexicalError, ParseError};
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 pub use polylang_parser::ast;
 
+/// What kind of error an [`Error`] is, so API consumers can branch on it
+/// without matching on the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ErrorKind {
+    /// The source code is not valid Polylang syntax.
+    Parse,
+    /// An imported file couldn't be loaded or doesn't declare an imported name.
+    Import,
+    /// A value, argument or field has a type that can't be used where it is.
+    TypeMismatch,
+    /// A contract, function, field or variable that doesn't exist was referenced.
+    NotFound,
+    /// A function was called with the wrong number of arguments.
+    ArgumentsCount,
+    /// The code uses a feature the compiler doesn't support yet.
+    NotImplemented,
+    /// Any other error reported by the compiler,
+    /// or a parsed program that can't be converted to the stable AST.
+    Compile,
+    /// Data doesn't match the contract it's validated against.
+    Validation,
+    /// A contract AST or data couldn't be deserialized.
+    Serialization,
+}
+
 #[derive(Debug, Serialize)]
 pub struct Error {
+    pub kind: ErrorKind,
     pub message: String,
+    /// The byte range of the source code the error points to, if known.
+    pub span: Option<error::span::Span>,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Error {
+            kind,
+            message: message.into(),
+            span: None,
+        }
+    }
+}
+
+impl From<error::Error> for Error {
+    fn from(e: error::Error) -> Self {
+        // Context added by the compiler wraps the error that actually happened.
+        fn root_kind(kind: &error::kind::ErrorKind) -> ErrorKind {
+            match kind {
+                error::kind::ErrorKind::Nested { source, .. } => root_kind(source),
+                error::kind::ErrorKind::TypeMismatch { .. } => ErrorKind::TypeMismatch,
+                error::kind::ErrorKind::NotFound { .. } => ErrorKind::NotFound,
+                error::kind::ErrorKind::ArgumentsCount { .. } => ErrorKind::ArgumentsCount,
+                error::kind::ErrorKind::NotImplemented { .. } => ErrorKind::NotImplemented,
+                _ => ErrorKind::Compile,
+            }
+        }
+
+        Error {
+            kind: root_kind(&e),
+            message: e.to_string(),
+            span: e.span(),
+        }
+    }
 }
 
 impl core::fmt::Display for Error {
@@ -60,7 +120,11 @@ where
         } else {
             end_byte - start_byte
         }));
-        Error { message }
+        Error {
+            kind: ErrorKind::Parse,
+            message,
+            span: Some(error::span::Span::new(start_byte, end_byte)),
+        }
     };
 
     match error {
@@ -104,10 +168,179 @@ where
     }
 }
 
+/// Parses and compiles `input`, see [`compiler::compile_with_options`].
+///
+/// The error kind tells parse errors apart from the kinds of compile errors,
+/// and the span, when known, points into `input`.
+pub fn compile<'a>(
+    input: &'a str,
+    contract_name: Option<&str>,
+    function_name: &str,
+    options: compiler::CompileOptions<'a>,
+) -> Result<compiler::CompileOutput, Error> {
+    let program = parse_program(input)?;
+
+    compiler::compile_with_options(
+        program,
+        contract_name,
+        function_name,
+        compiler::CompileOptions {
+            source: options.source.or(Some(input)),
+            ..options
+        },
+    )
+    .map_err(Error::from)
+}
+
 pub fn parse_program(input: &str) -> Result<ast::Program, Error> {
     polylang_parser::parse(input).map_err(|e| parse_error_to_error(input, e))
 }
 
+/// Loads the source code of files named in `import` statements,
+/// so that the host, not the library, controls file access.
+pub trait SourceResolver {
+    /// Returns the source code at `path`.
+    ///
+    /// Paths starting with `./` or `../` are resolved relative to the importing file
+    /// and passed relative to the main file, e.g. `import { Id } from "./id"` in `./lib/user`
+    /// resolves `./lib/id`. Other paths are passed exactly as written.
+    fn resolve(&self, path: &str) -> Result<String, Error>;
+}
+
+/// Resolves an import of `path` in the file at `importer`, `None` for the main file.
+fn resolve_import_path(importer: Option<&str>, path: &str) -> String {
+    if !path.starts_with("./") && !path.starts_with("../") {
+        return path.to_owned();
+    }
+
+    let dir = importer
+        .and_then(|importer| importer.rsplit_once('/'))
+        .map_or(".", |(dir, _)| dir);
+    let mut segments = Vec::<&str>::new();
+    for segment in dir.split('/').chain(path.split('/')) {
+        match segment {
+            "" | "." => {}
+            ".." if segments.last().is_some_and(|s| *s != "..") => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    let resolved = segments.join("/");
+    if resolved.starts_with("../") || resolved == ".." {
+        resolved
+    } else {
+        format!("./{resolved}")
+    }
+}
+
+/// Parses `input` and merges in the declarations it imports, directly or indirectly.
+///
+/// Only the imported names are merged, so a declaration that uses another one
+/// from its file needs that one imported too. Each file is loaded once, so shared
+/// and circular imports are fine, but a name can only be declared once across all of them.
+pub fn parse_program_with_imports(
+    input: &str,
+    resolver: &dyn SourceResolver,
+) -> Result<ast::Program, Error> {
+    fn take_imports(program: &mut ast::Program, importer: Option<&str>) -> Vec<ast::Import> {
+        let (imports, nodes) = std::mem::take(&mut program.nodes)
+            .into_iter()
+            .partition::<Vec<_>, _>(|node| matches!(node, ast::RootNode::Import(_)));
+        program.nodes = nodes;
+        imports
+            .into_iter()
+            .filter_map(|node| match node {
+                ast::RootNode::Import(import) => Some(ast::Import {
+                    path: resolve_import_path(importer, &import.path),
+                    ..import
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn declared_name(node: &ast::RootNode) -> Option<&str> {
+        match node {
+            ast::RootNode::Contract(c) => Some(c.name.as_str()),
+            ast::RootNode::Function(f) => Some(f.name.as_str()),
+            ast::RootNode::Interface(i) => Some(i.name.as_str()),
+            ast::RootNode::Import(_) => None,
+        }
+    }
+
+    let mut program = parse_program(input)?;
+    let mut pending = VecDeque::from(take_imports(&mut program, None));
+    // Name -> the file it was declared in, `None` for `input`.
+    let mut declared_in = program
+        .nodes
+        .iter()
+        .filter_map(declared_name)
+        .map(|name| (name.to_owned(), None))
+        .collect::<HashMap<String, Option<String>>>();
+    // Path -> the declarations in that file that haven't been merged yet.
+    let mut loaded = HashMap::<String, HashMap<String, ast::RootNode>>::new();
+
+    while let Some(import) = pending.pop_front() {
+        if !loaded.contains_key(&import.path) {
+            let source = resolver.resolve(&import.path)?;
+            let mut imported = parse_program(&source).map_err(|e| Error {
+                message: format!("in {}: {}", import.path, e.message),
+                // The span points into the imported file, not `input`.
+                span: None,
+                ..e
+            })?;
+            pending.extend(take_imports(&mut imported, Some(&import.path)));
+
+            loaded.insert(
+                import.path.clone(),
+                imported
+                    .nodes
+                    .into_iter()
+                    .filter_map(|node| Some((declared_name(&node)?.to_owned(), node)))
+                    .collect(),
+            );
+        }
+
+        for name in &import.names {
+            if matches!(declared_in.get(name), Some(Some(path)) if *path == import.path) {
+                continue;
+            }
+
+            let Some(node) = loaded.get_mut(&import.path).unwrap().remove(name) else {
+                return Err(Error::new(
+                    ErrorKind::Import,
+                    format!("{} does not declare {}", import.path, name),
+                ));
+            };
+            match declared_in.get(name) {
+                Some(Some(path)) => {
+                    return Err(Error::new(
+                        ErrorKind::Import,
+                        format!("{name} is declared in both {path} and {}", import.path),
+                    ))
+                }
+                Some(None) => {
+                    return Err(Error::new(
+                        ErrorKind::Import,
+                        format!(
+                            "{name} is imported from {} but already declared",
+                            import.path
+                        ),
+                    ))
+                }
+                None => {}
+            }
+            declared_in.insert(name.clone(), Some(import.path.clone()));
+            program.nodes.push(node);
+            program.imported.push(name.clone());
+        }
+    }
+
+    Ok(program)
+}
+
 pub fn parse<'a>(
     input: &'a str,
     namespace: &'a str,
@@ -118,7 +351,7 @@ pub fn parse<'a>(
     Ok((
         program_holder.as_ref().unwrap(),
         stableast::Root::from_ast(namespace, program_holder.as_ref().unwrap())
-            .map_err(|e| Error { message: e })?,
+            .map_err(|e| Error::new(ErrorKind::Compile, e))?,
     ))
 }
 
@@ -129,25 +362,16 @@ fn parse_out_json(input: &str, namespace: &str) -> String {
 fn validate_set(contract_ast_json: &str, data_json: &str) -> Result<(), Error> {
     let contract_ast: stableast::Contract = match serde_json::from_str(contract_ast_json) {
         Ok(ast) => ast,
-        Err(err) => {
-            return Err(Error {
-                message: err.to_string(),
-            })
-        }
+        Err(err) => return Err(Error::new(ErrorKind::Serialization, err.to_string())),
     };
 
     let data: HashMap<String, validation::Value> = match serde_json::from_str(data_json) {
         Ok(data) => data,
-        Err(err) => {
-            return Err(Error {
-                message: err.to_string(),
-            })
-        }
+        Err(err) => return Err(Error::new(ErrorKind::Serialization, err.to_string())),
     };
 
-    validation::validate_set(&contract_ast, &data).map_err(|e| Error {
-        message: e.to_string(),
-    })
+    validation::validate_set(&contract_ast, &data)
+        .map_err(|e| Error::new(ErrorKind::Validation, e.to_string()))
 }
 
 fn validate_set_out_json(contract_ast_json: &str, data_json: &str) -> String {
@@ -155,10 +379,8 @@ fn validate_set_out_json(contract_ast_json: &str, data_json: &str) -> String {
 }
 
 fn generate_contract_function(contract_ast: &str) -> Result<js::JSContract, Error> {
-    let contract_ast: stableast::Contract =
-        serde_json::from_str(contract_ast).map_err(|e| Error {
-            message: e.to_string(),
-        })?;
+    let contract_ast: stableast::Contract = serde_json::from_str(contract_ast)
+        .map_err(|e| Error::new(ErrorKind::Serialization, e.to_string()))?;
 
     Ok(js::generate_js_contract(&contract_ast))
 }
@@ -173,6 +395,16 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_resolve_import_path() {
+        assert_eq!(resolve_import_path(None, "./id"), "./id");
+        assert_eq!(resolve_import_path(None, "../id"), "../id");
+        assert_eq!(resolve_import_path(Some("./lib/user"), "./id"), "./lib/id");
+        assert_eq!(resolve_import_path(Some("./lib/user"), "../id"), "./id");
+        assert_eq!(resolve_import_path(Some("./user"), "../id"), "../id");
+        assert_eq!(resolve_import_path(Some("./lib/user"), "std/id"), "std/id");
+    }
+
     #[test]
     fn test_parse() {
         let input = "contract Test {}";
@@ -216,7 +448,7 @@ mod tests {
 
         assert_eq!(program.nodes.len(), 1);
         assert!(
-            matches!(&program.nodes[0], ast::RootNode::Contract(ast::Contract { name, decorators, items }) if name == "Test" && decorators.is_empty() && items.is_empty())
+            matches!(&program.nodes[0], ast::RootNode::Contract(ast::Contract { name, decorators, items, .. }) if name == "Test" && decorators.is_empty() && items.is_empty())
         );
     }
 
@@ -237,7 +469,7 @@ mod tests {
 
         assert_eq!(program.nodes.len(), 1);
         assert!(
-            matches!(&program.nodes[0], ast::RootNode::Contract(ast::Contract { name, decorators, items }) if name == "Test" && decorators.is_empty() && items.len() == 2)
+            matches!(&program.nodes[0], ast::RootNode::Contract(ast::Contract { name, decorators, items, .. }) if name == "Test" && decorators.is_empty() && items.len() == 2)
         );
 
         let contract = match &program.nodes[0] {
@@ -270,7 +502,7 @@ mod tests {
 
         assert_eq!(program.nodes.len(), 1);
         assert!(
-            matches!(&program.nodes[0], ast::RootNode::Contract(ast::Contract { name, decorators, items }) if name == "Test" && decorators.is_empty() && items.len() == 2)
+            matches!(&program.nodes[0], ast::RootNode::Contract(ast::Contract { name, decorators, items, .. }) if name == "Test" && decorators.is_empty() && items.len() == 2)
         );
 
         let contract = match &program.nodes[0] {
@@ -304,7 +536,7 @@ mod tests {
 
         assert_eq!(program.nodes.len(), 1);
         assert!(
-            matches!(&program.nodes[0], ast::RootNode::Contract(ast::Contract { name, decorators, items }) if name == "Test" && decorators.is_empty() && items.len() == 1)
+            matches!(&program.nodes[0], ast::RootNode::Contract(ast::Contract { name, decorators, items, .. }) if name == "Test" && decorators.is_empty() && items.len() == 1)
         );
 
         let contract = match &program.nodes[0] {
@@ -332,10 +564,10 @@ mod tests {
             matches!(&*function.statements[0], ast::StatementKind::Return(ref expr) if matches!(**expr, ast::ExpressionKind::Primitive(ast::Primitive::Number(number, has_decimal_point)) if number == 42.0 && !has_decimal_point))
         );
         assert!(
-            matches!(&function.parameters[0], ast::Parameter{ name, type_, required } if *required && name == "a" && *type_ == ast::ParameterType::Number)
+            matches!(&function.parameters[0], ast::Parameter{ name, type_, required, .. } if *required && name == "a" && *type_ == ast::ParameterType::Number)
         );
         assert!(
-            matches!(&function.parameters[1], ast::Parameter{ name, type_, required } if !(*required) && name == "b" && *type_ == ast::ParameterType::String)
+            matches!(&function.parameters[1], ast::Parameter{ name, type_, required, .. } if !(*required) && name == "b" && *type_ == ast::ParameterType::String)
         );
     }
 
@@ -394,6 +626,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bytes_literal() {
+        let bytes = polylang_parser::parse_expression("bytes(\"0xdeadbeef\")");
+
+        assert!(bytes.is_ok());
+        assert_eq!(
+            *bytes.unwrap(),
+            ast::ExpressionKind::Primitive(ast::Primitive::Bytes(vec![0xde, 0xad, 0xbe, 0xef]))
+        );
+
+        assert!(polylang_parser::parse_expression("bytes(\"xyz\")").is_err());
+    }
+
     #[test]
     fn test_comparison() {
         let comparison = polylang_parser::parse_expression("1 > 2");
@@ -456,6 +701,118 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_arrow_function_expression_body() {
+        let call = polylang_parser::parse_expression("arr.map(x => x + 1)").unwrap();
+
+        assert_eq!(
+            &*call,
+            &ast::ExpressionKind::Call(
+                Box::new(
+                    ast::ExpressionKind::Dot(
+                        Box::new(ast::ExpressionKind::Ident("arr".to_owned()).into()),
+                        "map".to_owned()
+                    )
+                    .into()
+                ),
+                vec![ast::ExpressionKind::ArrowFunction(ast::ArrowFunction {
+                    parameters: vec!["x".to_owned()],
+                    body: ast::ArrowFunctionBody::Expression(Box::new(
+                        ast::ExpressionKind::Add(
+                            Box::new(ast::ExpressionKind::Ident("x".to_owned()).into()),
+                            Box::new(
+                                ast::ExpressionKind::Primitive(ast::Primitive::Number(1.0, false))
+                                    .into()
+                            ),
+                        )
+                        .into()
+                    )),
+                })
+                .into()],
+            )
+        );
+    }
+
+    #[test]
+    fn test_arrow_function_statements_body() {
+        let call =
+            polylang_parser::parse_expression("arr.forEach((x, i) => { total += x; })").unwrap();
+
+        let ast::ExpressionKind::Call(_, args) = &*call else {
+            panic!("expected call, got {call:?}");
+        };
+        assert_eq!(
+            &*args[0],
+            &ast::ExpressionKind::ArrowFunction(ast::ArrowFunction {
+                parameters: vec!["x".to_owned(), "i".to_owned()],
+                body: ast::ArrowFunctionBody::Statements(vec![ast::StatementKind::Expression(
+                    ast::ExpressionKind::AssignAdd(
+                        Box::new(ast::ExpressionKind::Ident("total".to_owned()).into()),
+                        Box::new(ast::ExpressionKind::Ident("x".to_owned()).into()),
+                    )
+                    .into()
+                )
+                .into()]),
+            })
+        );
+    }
+
+    #[test]
+    fn test_arrow_function_parameters_must_be_identifiers() {
+        assert!(polylang_parser::parse_expression("f((1) => 1)").is_err());
+    }
+
+    #[test]
+    fn test_map_literal() {
+        let expr = polylang_parser::parse_expression(r#"{ "a": 1, "b": 2 }"#).unwrap();
+
+        assert_eq!(
+            &*expr,
+            &ast::ExpressionKind::Map(vec![
+                (
+                    ast::ExpressionKind::Primitive(ast::Primitive::String("a".to_owned())).into(),
+                    ast::ExpressionKind::Primitive(ast::Primitive::Number(1.0, false)).into(),
+                ),
+                (
+                    ast::ExpressionKind::Primitive(ast::Primitive::String("b".to_owned())).into(),
+                    ast::ExpressionKind::Primitive(ast::Primitive::Number(2.0, false)).into(),
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_null() {
+        let expr = polylang_parser::parse_expression("a = null").unwrap();
+
+        assert_eq!(
+            &*expr,
+            &ast::ExpressionKind::Assign(
+                Box::new(ast::ExpressionKind::Ident("a".to_owned()).into()),
+                Box::new(ast::ExpressionKind::Null.into()),
+            )
+        );
+    }
+
+    #[test]
+    fn test_in() {
+        let expr = polylang_parser::parse_expression("a in b || c").unwrap();
+
+        assert_eq!(
+            &*expr,
+            &ast::ExpressionKind::Or(
+                Box::new(
+                    ast::ExpressionKind::In(
+                        Box::new(ast::ExpressionKind::Ident("a".to_owned()).into()),
+                        Box::new(ast::ExpressionKind::Ident("b".to_owned()).into()),
+                    )
+                    .into()
+                ),
+                Box::new(ast::ExpressionKind::Ident("c".to_owned()).into()),
+            )
+        );
+    }
+
     #[test]
     fn test_dot() {
         let dot = polylang_parser::parse_expression("a.b").unwrap();
@@ -686,6 +1043,56 @@ function x() {
         );
     }
 
+    #[test]
+    fn test_error_kind_parse() {
+        let code = "
+            contract test-cities {}
+        ";
+
+        let err = parse_program(code).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Parse);
+        assert_eq!(err.span, Some(error::span::Span::new(26, 27)));
+    }
+
+    #[test]
+    fn test_error_kind_type_mismatch() {
+        let code = "
+            contract A {
+                id: string;
+
+                f(a: u32) {
+                    this.id = a;
+                }
+            }
+        ";
+
+        let err = compile(code, Some("A"), "f", Default::default()).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert!(err.span.is_some());
+    }
+
+    #[test]
+    fn test_error_kind_compile() {
+        let code = "
+            contract A {
+                id: string;
+
+                f() {
+                    g(1, 2);
+                }
+            }
+        ";
+
+        let err = compile(code, Some("A"), "f", Default::default()).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::NotFound);
+
+        let err = compile("contract A { id: string; ", None, "f", Default::default()).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Parse);
+
+        let err = parse("function f() {}", "ns", &mut None).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Compile);
+    }
+
     #[test]
     fn test_foreign_record_field() {
         let code = "
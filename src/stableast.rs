@@ -350,6 +350,9 @@ impl<'a> Root<'a> {
                         .collect(),
                 }),
                 ast::RootNode::Function(_) => Err("Functions are not supported at the root level")?,
+                // Interfaces are only checked by the compiler.
+                ast::RootNode::Interface(_) => continue,
+                ast::RootNode::Import(_) => Err("Imports must be resolved before conversion")?,
             });
         }
 
@@ -494,6 +497,9 @@ impl<'a> DirectiveArgument<'a> {
                 })
             }
             ast::DecoratorArgument::Literal(_) => todo!(),
+            ast::DecoratorArgument::String(_) | ast::DecoratorArgument::Number(_) => {
+                DirectiveArgument::Unknown
+            }
         }
     }
 }
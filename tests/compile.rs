@@ -0,0 +1,54 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn compile(code: &str, args: &[&str]) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_compile"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(code.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{stderr}");
+    (stdout, stderr)
+}
+
+#[test]
+fn debug_symbols() {
+    let code = r#"
+        contract Account {
+            id: string;
+            balance: number;
+
+            deposit(amount: number) {
+                let newBalance = this.balance + amount;
+                this.balance = newBalance;
+            }
+        }
+    "#;
+
+    let (masm, symbols) = compile(
+        code,
+        &["contract:Account", "function:deposit", "debug:true"],
+    );
+
+    assert!(
+        masm.lines()
+            .any(|line| line.trim_start().starts_with("# 7:")
+                && line.contains("Compiling expression")),
+        "{masm}"
+    );
+    for name in ["this", "amount", "newBalance"] {
+        assert!(symbols.contains(&format!(": {name} (")), "{symbols}");
+    }
+}
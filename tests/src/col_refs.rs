@@ -22,29 +22,25 @@ fn assign_param_record_to_field_record() {
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "constructor",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("constructor")
+        .this(serde_json::json!({
             "id": "",
             "name": "",
             "user": {
                 "id": "",
                 "pk": fixtures::pk1(),
             },
-        }),
-        vec![
-            serde_json::json!("john1"),
-            serde_json::json!("John"),
-            serde_json::json!({
-                "id": "user1",
-            }),
-        ],
-        None,
-        HashMap::new(),
-    )
-    .unwrap();
+        }))
+        .arg(serde_json::json!("john1"))
+        .arg(serde_json::json!("John"))
+        .arg(serde_json::json!({
+            "id": "user1",
+        }))
+        .run()
+        .unwrap();
 
     assert_eq!(
         output.this(&abi).unwrap(),
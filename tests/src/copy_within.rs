@@ -0,0 +1,186 @@
+use super::*;
+use test_case::test_case;
+
+fn run_copy_within(
+    arr: serde_json::Value,
+    target: u32,
+    start: u32,
+    end: Option<u32>,
+) -> Result<serde_json::Value, error::Error> {
+    let code = r#"
+        contract Account {
+            id: string;
+            arr: u32[];
+
+            copyWithin3(target: u32, start: u32, end: u32) {
+                this.arr.copyWithin(target, start, end);
+            }
+
+            copyWithin2(target: u32, start: u32) {
+                this.arr.copyWithin(target, start);
+            }
+        }
+    "#;
+
+    let (function_name, args) = match end {
+        Some(end) => (
+            "copyWithin3",
+            vec![
+                serde_json::json!(target),
+                serde_json::json!(start),
+                serde_json::json!(end),
+            ],
+        ),
+        None => (
+            "copyWithin2",
+            vec![serde_json::json!(target), serde_json::json!(start)],
+        ),
+    };
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(function_name)
+        .this(serde_json::json!({
+            "id": "test",
+            "arr": arr,
+        }))
+        .args(args)
+        .run()?;
+
+    let this: serde_json::Value = output.this(&abi)?.try_into().unwrap();
+    Ok(this["arr"].clone())
+}
+
+#[test_case(
+    serde_json::json!([1, 2, 3, 4, 5]),
+    0,
+    3,
+    None,
+    serde_json::json!([4, 5, 3, 4, 5])
+    ; "non-overlapping to the front"
+)]
+#[test_case(
+    serde_json::json!([1, 2, 3, 4, 5]),
+    3,
+    0,
+    Some(2),
+    serde_json::json!([1, 2, 3, 1, 2])
+    ; "non-overlapping to the back"
+)]
+#[test_case(
+    serde_json::json!([1, 2, 3, 4, 5]),
+    1,
+    0,
+    None,
+    serde_json::json!([1, 1, 2, 3, 4])
+    ; "overlapping to the back"
+)]
+#[test_case(
+    serde_json::json!([1, 2, 3, 4, 5]),
+    0,
+    1,
+    Some(4),
+    serde_json::json!([2, 3, 4, 4, 5])
+    ; "overlapping to the front"
+)]
+#[test_case(
+    serde_json::json!([1, 2, 3, 4, 5]),
+    2,
+    4,
+    Some(1),
+    serde_json::json!([1, 2, 3, 4, 5])
+    ; "end before start"
+)]
+#[test_case(
+    serde_json::json!([1, 2, 3]),
+    10,
+    0,
+    None,
+    serde_json::json!([1, 2, 3])
+    ; "target out of bounds"
+)]
+#[test_case(
+    serde_json::json!([]),
+    0,
+    0,
+    None,
+    serde_json::json!([])
+    ; "empty array"
+)]
+fn test_copy_within(
+    arr: serde_json::Value,
+    target: u32,
+    start: u32,
+    end: Option<u32>,
+    expected: serde_json::Value,
+) {
+    let result = run_copy_within(arr, target, start, end).unwrap();
+
+    assert_eq!(result, expected);
+}
+
+#[test_case(-2, -3, -1, serde_json::json!([1, 2, 3, 3, 4]); "all negative")]
+#[test_case(0, -2, 5, serde_json::json!([4, 5, 3, 4, 5]); "negative start")]
+#[test_case(1, 0, -1, serde_json::json!([1, 1, 2, 3, 4]); "negative end")]
+#[test_case(-10, 3, 5, serde_json::json!([4, 5, 3, 4, 5]); "negative target before the start")]
+fn copy_within_negative_indices(target: i32, start: i32, end: i32, expected: serde_json::Value) {
+    let code = r#"
+        contract Account {
+            id: string;
+            arr: u32[];
+
+            copyWithinSigned(target: i32, start: i32, end: i32) {
+                this.arr.copyWithin(target, start, end);
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("copyWithinSigned")
+        .this(serde_json::json!({
+            "id": "test",
+            "arr": [1, 2, 3, 4, 5],
+        }))
+        .args(vec![
+            serde_json::json!(target),
+            serde_json::json!(start),
+            serde_json::json!(end),
+        ])
+        .run()
+        .unwrap();
+
+    let this: serde_json::Value = output.this(&abi).unwrap().try_into().unwrap();
+    assert_eq!(this["arr"], expected);
+}
+
+#[test_case("this.arr.copyWithin(0)", 1; "too few")]
+#[test_case("this.arr.copyWithin(0, 1, 2, 3)", 4; "too many")]
+fn copy_within_arguments_count(call: &str, found: usize) {
+    let code = format!(
+        r#"
+        contract Account {{
+            id: string;
+            arr: u32[];
+
+            f() {{
+                {call};
+            }}
+        }}
+    "#
+    );
+
+    let err = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("f")
+        .compile()
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        format!("incorrect number of arguments {found} but expected 2 to 3")
+    );
+}
@@ -0,0 +1,142 @@
+use super::*;
+
+/// Compiles and runs a contract function.
+/// `this` and the other records are hashed with zero salts.
+#[derive(Default)]
+pub(crate) struct TestHarness<'a> {
+    code: &'a str,
+    contract: &'a str,
+    function: &'a str,
+    this: serde_json::Value,
+    args: Vec<serde_json::Value>,
+    ctx_pk: Option<abi::publickey::Key>,
+    other_records: HashMap<String, Vec<serde_json::Value>>,
+    options: polylang::compiler::CompileOptions<'a>,
+}
+
+impl<'a> TestHarness<'a> {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn code(mut self, code: &'a str) -> Self {
+        self.code = code;
+        self
+    }
+
+    pub(crate) fn contract(mut self, contract: &'a str) -> Self {
+        self.contract = contract;
+        self
+    }
+
+    pub(crate) fn function(mut self, function: &'a str) -> Self {
+        self.function = function;
+        self
+    }
+
+    pub(crate) fn this(mut self, this: serde_json::Value) -> Self {
+        self.this = this;
+        self
+    }
+
+    /// Runs with a `this` that only has an empty `id`, for contracts without other fields.
+    pub(crate) fn default_this(self) -> Self {
+        self.this(serde_json::json!({ "id": "" }))
+    }
+
+    pub(crate) fn arg(mut self, arg: serde_json::Value) -> Self {
+        self.args.push(arg);
+        self
+    }
+
+    pub(crate) fn args(mut self, args: impl IntoIterator<Item = serde_json::Value>) -> Self {
+        self.args.extend(args);
+        self
+    }
+
+    pub(crate) fn ctx_pk(mut self, ctx_pk: abi::publickey::Key) -> Self {
+        self.ctx_pk = Some(ctx_pk);
+        self
+    }
+
+    pub(crate) fn other_record(mut self, contract: &str, record: serde_json::Value) -> Self {
+        self.other_records
+            .entry(contract.to_owned())
+            .or_default()
+            .push(record);
+        self
+    }
+
+    pub(crate) fn options(mut self, options: polylang::compiler::CompileOptions<'a>) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Compiles the function without running it.
+    pub(crate) fn compile(&self) -> Result<polylang::compiler::CompileOutput, error::Error> {
+        let program = polylang::parse_program(self.code).unwrap();
+
+        polylang::compiler::compile_with_options(
+            program,
+            Some(self.contract),
+            self.function,
+            self.options,
+        )
+    }
+
+    /// Compiles the function and returns the messages of its warnings.
+    pub(crate) fn compile_warnings(&self) -> Result<Vec<String>, error::Error> {
+        Ok(self
+            .compile()?
+            .warnings
+            .into_iter()
+            .map(|w| w.message)
+            .collect())
+    }
+
+    pub(crate) fn run(self) -> Result<(abi::Abi, polylang_prover::RunOutput), error::Error> {
+        let polylang::compiler::CompileOutput {
+            miden_code, abi, ..
+        } = self.compile()?;
+
+        let program = polylang_prover::compile_program(&abi, &miden_code).unwrap();
+
+        let this_salts = match &abi.this_type {
+            Some(abi::Type::Struct(s)) => s.fields.iter().map(|_| 0).collect(),
+            _ => unreachable!(),
+        };
+
+        let mut other_records = HashMap::new();
+        for (contract, records) in self.other_records {
+            let col = abi
+                .other_contract_types
+                .iter()
+                .find_map(|t| match t {
+                    abi::Type::Struct(s) if s.name == contract => Some(s),
+                    _ => None,
+                })
+                .unwrap();
+
+            other_records.insert(
+                contract,
+                records
+                    .into_iter()
+                    .map(|record| (record, col.fields.iter().map(|_| 0).collect::<Vec<_>>()))
+                    .collect(),
+            );
+        }
+
+        let inputs = polylang_prover::Inputs::new(
+            abi.clone(),
+            self.ctx_pk,
+            this_salts,
+            self.this,
+            self.args,
+            other_records,
+        )?;
+
+        let (output, _) = polylang_prover::run(&program, &inputs)?;
+
+        Ok((abi, output))
+    }
+}
@@ -1,6 +1,8 @@
 #![cfg(test)]
 
 mod col_refs;
+mod copy_within;
+mod harness;
 mod push;
 mod slice;
 mod splice;
@@ -8,8 +10,10 @@ mod string;
 mod unshift;
 
 use expect_test::expect;
+use harness::TestHarness;
 use serde::de::Deserialize;
 use std::collections::HashMap;
+use test_case::test_case;
 
 mod fixtures {
     use super::*;
@@ -49,70 +53,13 @@ mod fixtures {
 macro_rules! consistency_checks {
     ($output:expr, $abi:expr, hashes: $hashes_expect:expr, dependencies: $dependencies_expect:expr) => {{
         let expected_hashes = $hashes_expect;
-        expected_hashes.assert_debug_eq(&$output.hashes());
+        expected_hashes.assert_debug_eq(&$output.hashes().unwrap());
 
         let expected_dependencies = $dependencies_expect;
         expected_dependencies.assert_debug_eq(&$abi.dependent_fields);
     }};
 }
 
-fn run(
-    polylang_code: &str,
-    contract: &str,
-    function: &str,
-    this: serde_json::Value,
-    args: Vec<serde_json::Value>,
-    ctx_public_key: Option<abi::publickey::Key>,
-    other_records: HashMap<String, Vec<serde_json::Value>>,
-) -> Result<(abi::Abi, polylang_prover::RunOutput), error::Error> {
-    let program = polylang::parse_program(polylang_code).unwrap();
-
-    let (miden_code, abi) = polylang::compiler::compile(program, Some(contract), function)?;
-
-    let program = polylang_prover::compile_program(&abi, &miden_code).unwrap();
-    let inputs = polylang_prover::Inputs::new(
-        abi.clone(),
-        ctx_public_key,
-        match &abi.this_type {
-            Some(abi::Type::Struct(s)) => s.fields.iter().map(|_| 0).collect(),
-            _ => unreachable!(),
-        },
-        this,
-        args,
-        {
-            let mut hm = HashMap::new();
-            for (contract, records) in other_records {
-                let col = abi
-                    .other_contract_types
-                    .iter()
-                    .find_map(|t| match t {
-                        abi::Type::Struct(s) if s.name == contract => Some(s),
-                        _ => None,
-                    })
-                    .unwrap();
-
-                hm.insert(
-                    contract,
-                    records
-                        .into_iter()
-                        .map(|record| {
-                            (
-                                record.clone(),
-                                col.fields.iter().map(|_| 0).collect::<Vec<_>>(),
-                            )
-                        })
-                        .collect(),
-                );
-            }
-            hm
-        },
-    )?;
-
-    let (output, _) = polylang_prover::run(&program, &inputs)?;
-
-    Ok((abi, output))
-}
-
 #[test]
 fn call_public_collection() {
     let code = r#"
@@ -126,19 +73,17 @@ fn call_public_collection() {
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "setName",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("setName")
+        .this(serde_json::json!({
             "id": "",
             "name": "",
-        }),
-        vec![serde_json::json!("test")],
-        None,
-        HashMap::new(),
-    )
-    .unwrap();
+        }))
+        .arg(serde_json::json!("test"))
+        .run()
+        .unwrap();
 
     assert_eq!(
         output.this(&abi).unwrap(),
@@ -175,19 +120,17 @@ fn call_public_contract() {
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "setName",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("setName")
+        .this(serde_json::json!({
             "id": "",
             "name": "",
-        }),
-        vec![serde_json::json!("test")],
-        None,
-        HashMap::new(),
-    )
-    .unwrap();
+        }))
+        .arg(serde_json::json!("test"))
+        .run()
+        .unwrap();
 
     assert_eq!(
         output.this(&abi).unwrap(),
@@ -225,19 +168,17 @@ fn call_any_call_collection() {
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "setName",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("setName")
+        .this(serde_json::json!({
             "id": "",
             "name": "",
-        }),
-        vec![serde_json::json!("test")],
-        None,
-        HashMap::new(),
-    )
-    .unwrap();
+        }))
+        .arg(serde_json::json!("test"))
+        .run()
+        .unwrap();
 
     assert_eq!(
         output.this(&abi).unwrap(),
@@ -275,19 +216,17 @@ fn call_any_call_contract() {
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "setName",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("setName")
+        .this(serde_json::json!({
             "id": "",
             "name": "",
-        }),
-        vec![serde_json::json!("test")],
-        None,
-        HashMap::new(),
-    )
-    .unwrap();
+        }))
+        .arg(serde_json::json!("test"))
+        .run()
+        .unwrap();
 
     assert_eq!(
         output.this(&abi).unwrap(),
@@ -323,18 +262,14 @@ fn call_constructor_no_auth() {
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "constructor",
-        serde_json::json!({
-            "id": "",
-        }),
-        vec![serde_json::json!("id1")],
-        None,
-        HashMap::new(),
-    )
-    .unwrap();
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("constructor")
+        .default_this()
+        .arg(serde_json::json!("id1"))
+        .run()
+        .unwrap();
 
     assert_eq!(
         output.this(&abi).unwrap(),
@@ -374,19 +309,18 @@ fn call_constructor_with_auth() {
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "constructor",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("constructor")
+        .this(serde_json::json!({
             "id": "",
             "pk": fixtures::pk2(),
-        }),
-        vec![serde_json::json!("id1")],
-        Some(fixtures::pk1_key()),
-        HashMap::new(),
-    )
-    .unwrap();
+        }))
+        .arg(serde_json::json!("id1"))
+        .ctx_pk(fixtures::pk1_key())
+        .run()
+        .unwrap();
 
     assert_eq!(
         output.this(&abi).unwrap(),
@@ -422,6 +356,62 @@ fn call_constructor_with_auth() {
     );
 }
 
+#[test_case(Some(fixtures::pk1_key()); "public key provided")]
+#[test_case(None; "public key absent")]
+fn constructor_branches_on_ctx_public_key(ctx_pk: Option<abi::publickey::Key>) {
+    let code = r#"
+        contract Account {
+            id: string;
+            owner?: PublicKey;
+            creator: PublicKey;
+            signed: boolean;
+
+            constructor (id: string, fallback: PublicKey) {
+                this.id = id;
+                this.owner = ctx.publicKey;
+                this.creator = ctx.publicKey ?? fallback;
+                if (ctx.publicKey) {
+                    this.signed = true;
+                } else {
+                    this.signed = false;
+                }
+            }
+        }
+    "#;
+
+    let mut harness = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("constructor")
+        .this(serde_json::json!({
+            "id": "",
+            "owner": null,
+            "creator": fixtures::pk2(),
+            "signed": false,
+        }))
+        .args(vec![serde_json::json!("id1"), fixtures::pk2()]);
+    if let Some(pk) = ctx_pk.clone() {
+        harness = harness.ctx_pk(pk);
+    }
+    let (abi, output) = harness.run().unwrap();
+
+    assert_eq!(
+        output.this(&abi).unwrap(),
+        abi::Value::StructValue(vec![
+            ("id".to_owned(), abi::Value::String("id1".to_owned())),
+            (
+                "owner".to_owned(),
+                abi::Value::Nullable(ctx_pk.clone().map(|pk| Box::new(abi::Value::PublicKey(pk)))),
+            ),
+            (
+                "creator".to_owned(),
+                abi::Value::PublicKey(ctx_pk.clone().unwrap_or_else(fixtures::pk2_key)),
+            ),
+            ("signed".to_owned(), abi::Value::Boolean(ctx_pk.is_some())),
+        ])
+    );
+}
+
 fn call_auth_public_key(use_correct_pk: bool) -> Result<(), Box<dyn std::error::Error>> {
     let code = r#"
         contract Account {
@@ -445,27 +435,26 @@ fn call_auth_public_key(use_correct_pk: bool) -> Result<(), Box<dyn std::error::
     let new_pk = fixtures::pk2();
     let new_pk_key = fixtures::pk2_key();
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "changePk",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("changePk")
+        .this(serde_json::json!({
             "id": "test",
             "pk": old_pk,
-        }),
-        vec![new_pk],
-        Some(if use_correct_pk {
+        }))
+        .arg(new_pk)
+        .ctx_pk(if use_correct_pk {
             old_pk_key
         } else {
             new_pk_key.clone()
-        }),
-        HashMap::new(),
-    )?;
+        })
+        .run()?;
 
     assert_eq!(
         output.this(&abi).unwrap(),
         abi::Value::StructValue(vec![
-            ("id".to_owned(), abi::Value::String("".to_owned())),
+            ("id".to_owned(), abi::Value::String("test".to_owned())),
             ("pk".to_owned(), abi::Value::PublicKey(new_pk_key)),
         ]),
     );
@@ -530,19 +519,17 @@ fn call_auth_public_key_no_pk() {
         }
     "#;
 
-    let err = run(
-        code,
-        "Account",
-        "changePk",
-        serde_json::json!({
+    let err = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("changePk")
+        .this(serde_json::json!({
             "id": "test",
             "pk": fixtures::pk1(),
-        }),
-        vec![fixtures::pk2()],
-        None,
-        HashMap::new(),
-    )
-    .unwrap_err();
+        }))
+        .arg(fixtures::pk2())
+        .run()
+        .unwrap_err();
 
     assert!(err
         .to_string()
@@ -568,24 +555,22 @@ fn call_auth_public_key_allow_all() {
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "changePk",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("changePk")
+        .this(serde_json::json!({
             "id": "test",
             "pk": fixtures::pk1(),
-        }),
-        vec![fixtures::pk2()],
-        None,
-        HashMap::new(),
-    )
-    .unwrap();
+        }))
+        .arg(fixtures::pk2())
+        .run()
+        .unwrap();
 
     assert_eq!(
         output.this(&abi).unwrap(),
         abi::Value::StructValue(vec![
-            ("id".to_owned(), abi::Value::String("".to_owned())),
+            ("id".to_owned(), abi::Value::String("test".to_owned())),
             ("pk".to_owned(), abi::Value::PublicKey(fixtures::pk2_key())),
         ]),
     );
@@ -635,19 +620,17 @@ fn call_auth_no_directive() {
         }
     "#;
 
-    let err = run(
-        code,
-        "Account",
-        "changePk",
-        serde_json::json!({
+    let err = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("changePk")
+        .this(serde_json::json!({
             "id": "test",
             "pk": fixtures::pk1(),
-        }),
-        vec![fixtures::pk2()],
-        None,
-        HashMap::new(),
-    )
-    .unwrap_err();
+        }))
+        .arg(fixtures::pk2())
+        .run()
+        .unwrap_err();
 
     assert!(err
         .to_string()
@@ -668,24 +651,22 @@ fn call_contract_auth_any() {
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "changePk",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("changePk")
+        .this(serde_json::json!({
             "id": "test",
             "pk": fixtures::pk1(),
-        }),
-        vec![fixtures::pk2()],
-        None,
-        HashMap::new(),
-    )
-    .unwrap();
+        }))
+        .arg(fixtures::pk2())
+        .run()
+        .unwrap();
 
     assert_eq!(
         output.this(&abi).unwrap(),
         abi::Value::StructValue(vec![
-            ("id".to_owned(), abi::Value::String("".to_owned())),
+            ("id".to_owned(), abi::Value::String("test".to_owned())),
             ("pk".to_owned(), abi::Value::PublicKey(fixtures::pk2_key())),
         ]),
     );
@@ -736,41 +717,37 @@ fn call_auth_delegate(use_correct_pk: bool) -> Result<(), Box<dyn std::error::Er
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "changeName",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("changeName")
+        .this(serde_json::json!({
             "id": "test",
             "name": "test",
             "user": {
                 "id": "user1",
                 "pk": fixtures::pk1(),
             },
-        }),
-        vec![serde_json::json!("test2")],
-        Some(if use_correct_pk {
+        }))
+        .arg(serde_json::json!("test2"))
+        .ctx_pk(if use_correct_pk {
             fixtures::pk1_key()
         } else {
             fixtures::pk2_key()
-        }),
-        {
-            let mut hm = HashMap::new();
-            hm.insert(
-                "User".to_owned(),
-                vec![serde_json::json!({
-                    "id": "user1",
-                    "pk": fixtures::pk1(),
-                })],
-            );
-            hm
-        },
-    )?;
+        })
+        .other_record(
+            "User",
+            serde_json::json!({
+                "id": "user1",
+                "pk": fixtures::pk1(),
+            }),
+        )
+        .run()?;
 
     assert_eq!(
         output.this(&abi).unwrap(),
         abi::Value::StructValue(vec![
-            ("id".to_owned(), abi::Value::String("".to_owned())),
+            ("id".to_owned(), abi::Value::String("test".to_owned())),
             ("name".to_owned(), abi::Value::String("test2".to_owned())),
             (
                 "user".to_owned(),
@@ -838,27 +815,26 @@ fn call_auth_literal_pk(use_correct_pk: bool) -> Result<(), Box<dyn std::error::
     "#
     );
 
-    let (abi, output) = run(
-        &code,
-        "Account",
-        "changeName",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("changeName")
+        .this(serde_json::json!({
             "id": "test",
             "name": "test",
-        }),
-        vec![serde_json::json!("test2")],
-        Some(if use_correct_pk {
+        }))
+        .arg(serde_json::json!("test2"))
+        .ctx_pk(if use_correct_pk {
             fixtures::pk1_key()
         } else {
             fixtures::pk2_key()
-        }),
-        HashMap::new(),
-    )?;
+        })
+        .run()?;
 
     assert_eq!(
         output.this(&abi).unwrap(),
         abi::Value::StructValue(vec![
-            ("id".to_owned(), abi::Value::String("".to_owned())),
+            ("id".to_owned(), abi::Value::String("test".to_owned())),
             ("name".to_owned(), abi::Value::String("test2".to_owned())),
         ]),
     );
@@ -909,24 +885,23 @@ fn call_auth_literal_compressed() {
     "#
     );
 
-    let (abi, output) = run(
-        &code,
-        "Account",
-        "changeName",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("changeName")
+        .this(serde_json::json!({
             "id": "test",
             "name": "test",
-        }),
-        vec![serde_json::json!("test2")],
-        Some(fixtures::pk1_key()),
-        HashMap::new(),
-    )
-    .unwrap();
+        }))
+        .arg(serde_json::json!("test2"))
+        .ctx_pk(fixtures::pk1_key())
+        .run()
+        .unwrap();
 
     assert_eq!(
         output.this(&abi).unwrap(),
         abi::Value::StructValue(vec![
-            ("id".to_owned(), abi::Value::String("".to_owned())),
+            ("id".to_owned(), abi::Value::String("test".to_owned())),
             ("name".to_owned(), abi::Value::String("test2".to_owned())),
         ]),
     );
@@ -945,6 +920,66 @@ fn call_auth_literal_compressed() {
     );
 }
 
+#[test_case(false, fixtures::pk1_key(), true; "unlocked admin")]
+#[test_case(true, fixtures::pk1_key(), false; "locked admin")]
+#[test_case(false, fixtures::pk2_key(), false; "not admin")]
+fn call_auth_function(locked: bool, ctx_pk: abi::publickey::Key, authorized: bool) {
+    let code = r#"
+        contract Account {
+            id: string;
+            admin: PublicKey;
+            locked: boolean;
+            balance: u32;
+
+            canCall(): boolean {
+                if (this.locked) return false;
+                return ctx.publicKey == this.admin;
+            }
+
+            @call(canCall)
+            deposit(amount: u32) {
+                this.balance = this.balance + amount;
+            }
+        }
+    "#;
+
+    let result = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("deposit")
+        .this(serde_json::json!({
+            "id": "test",
+            "admin": fixtures::pk1(),
+            "locked": locked,
+            "balance": 10,
+        }))
+        .arg(serde_json::json!(5))
+        .ctx_pk(ctx_pk)
+        .run();
+
+    if !authorized {
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("You are not authorized to call this function"));
+        return;
+    }
+
+    let (abi, output) = result.unwrap();
+    assert_eq!(
+        output.this(&abi).unwrap(),
+        abi::Value::StructValue(vec![
+            ("id".to_owned(), abi::Value::String("test".to_owned())),
+            (
+                "admin".to_owned(),
+                abi::Value::PublicKey(fixtures::pk1_key())
+            ),
+            ("locked".to_owned(), abi::Value::Boolean(false)),
+            ("balance".to_owned(), abi::Value::UInt32(15)),
+        ]),
+    );
+}
+
 #[test]
 fn read_auth_field_correct_ctx() {
     let code = r#"
@@ -956,21 +991,19 @@ fn read_auth_field_correct_ctx() {
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        ".readAuth",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(".readAuth")
+        .this(serde_json::json!({
             "id": "",
             "pk": fixtures::pk1(),
-        }),
-        vec![],
-        Some(fixtures::pk1_key()),
-        HashMap::new(),
-    )
-    .unwrap();
+        }))
+        .ctx_pk(fixtures::pk1_key())
+        .run()
+        .unwrap();
 
-    assert!(output.read_auth());
+    assert!(output.read_auth().unwrap());
 
     consistency_checks!(
         output,
@@ -998,6 +1031,65 @@ fn read_auth_field_correct_ctx() {
     );
 }
 
+#[test_case(fixtures::pk1_key(), true; "owner")]
+#[test_case(fixtures::pk2_key(), false; "other key")]
+fn read_auth_with_result(ctx_pk: abi::publickey::Key, authorized: bool) {
+    let code = r#"
+        @private
+        contract Account {
+            id: string;
+            @read
+            pk: PublicKey;
+            balance: u32;
+
+            @call
+            getBalance(): u32 {
+                return this.balance;
+            }
+        }
+    "#;
+
+    let this = serde_json::json!({
+        "id": "",
+        "pk": fixtures::pk1(),
+        "balance": 42,
+    });
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("getBalance")
+        .this(this.clone())
+        .ctx_pk(ctx_pk.clone())
+        .run()
+        .unwrap();
+
+    // The result hash takes the place of the read-auth flag.
+    assert!(!output.read_auth().unwrap());
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::UInt32(42));
+    assert_eq!(
+        output.result_hash(&abi).unwrap().unwrap(),
+        polylang_prover::hash_this(
+            abi::Type::PrimitiveType(abi::PrimitiveType::UInt32),
+            &abi::Value::UInt32(42),
+            None
+        )
+        .unwrap()
+    );
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(".readAuth")
+        .this(this)
+        .ctx_pk(ctx_pk)
+        .run()
+        .unwrap();
+
+    assert_eq!(output.read_auth().unwrap(), authorized);
+    assert_eq!(output.result_hash(&abi).unwrap(), None);
+}
+
 #[test]
 fn read_auth_field_wrong_ctx() {
     let code = r#"
@@ -1009,21 +1101,19 @@ fn read_auth_field_wrong_ctx() {
         }
     "#;
 
-    let (_, output) = run(
-        code,
-        "Account",
-        ".readAuth",
-        serde_json::json!({
+    let (_, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(".readAuth")
+        .this(serde_json::json!({
             "id": "",
             "pk": fixtures::pk1(),
-        }),
-        vec![],
-        Some(fixtures::pk2_key()),
-        HashMap::new(),
-    )
-    .unwrap();
+        }))
+        .ctx_pk(fixtures::pk2_key())
+        .run()
+        .unwrap();
 
-    assert!(!output.read_auth());
+    assert!(!output.read_auth().unwrap());
 }
 
 #[test]
@@ -1037,21 +1127,18 @@ fn read_auth_field_no_ctx() {
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        ".readAuth",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(".readAuth")
+        .this(serde_json::json!({
             "id": "",
             "pk": fixtures::pk1(),
-        }),
-        vec![],
-        None,
-        HashMap::new(),
-    )
-    .unwrap();
+        }))
+        .run()
+        .unwrap();
 
-    assert!(!output.read_auth());
+    assert!(!output.read_auth().unwrap());
 
     consistency_checks!(
         output,
@@ -1079,6 +1166,137 @@ fn read_auth_field_no_ctx() {
     );
 }
 
+#[test_case(fixtures::pk1_key(), true, false; "owner")]
+#[test_case(fixtures::pk2_key(), false, true; "auditor")]
+fn read_auth_per_field(ctx_pk: abi::publickey::Key, owner: bool, auditor: bool) {
+    let code = r#"
+        @private
+        contract Account {
+            id: string;
+            @read
+            owner: PublicKey;
+            @read
+            auditor: PublicKey;
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(".readAuth")
+        .this(serde_json::json!({
+            "id": "",
+            "owner": fixtures::pk1(),
+            "auditor": fixtures::pk2(),
+        }))
+        .ctx_pk(ctx_pk)
+        .run()
+        .unwrap();
+
+    assert_eq!(abi.read_fields, vec!["owner", "auditor"]);
+    assert!(output.read_auth().unwrap());
+    assert_eq!(
+        output.read_fields().unwrap(),
+        HashMap::from([("owner".to_owned(), owner), ("auditor".to_owned(), auditor),])
+    );
+}
+
+#[test]
+fn read_auth_redacts_unauthorized_fields() {
+    let code = r#"
+        @private
+        contract Account {
+            id: string;
+            @read
+            owner: PublicKey;
+            @read
+            auditor: PublicKey;
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(".readAuth")
+        .this(serde_json::json!({
+            "id": "",
+            "owner": fixtures::pk1(),
+            "auditor": fixtures::pk2(),
+        }))
+        .ctx_pk(fixtures::pk2_key())
+        .run()
+        .unwrap();
+
+    assert!(output.read_auth().unwrap());
+    assert_eq!(
+        output.this_redacted(&abi).unwrap(),
+        abi::Value::StructValue(vec![
+            (
+                "id".to_owned(),
+                abi::Value::Nullable(Some(Box::new(abi::Value::String("".to_owned()))))
+            ),
+            ("owner".to_owned(), abi::Value::Nullable(None)),
+            (
+                "auditor".to_owned(),
+                abi::Value::Nullable(Some(Box::new(abi::Value::PublicKey(fixtures::pk2_key()))))
+            ),
+        ])
+    );
+}
+
+#[test]
+fn read_auth_redacts_every_field_of_unreadable_record() {
+    let code = r#"
+        @private
+        contract Account {
+            id: string;
+            @read
+            owner: PublicKey;
+            name: string;
+            nickname?: string;
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(".readAuth")
+        .this(serde_json::json!({
+            "id": "id1",
+            "owner": fixtures::pk1(),
+            "name": "John",
+            "nickname": "J",
+        }))
+        .ctx_pk(fixtures::pk2_key())
+        .run()
+        .unwrap();
+
+    assert!(!output.read_auth().unwrap());
+    assert_eq!(
+        output.this_redacted(&abi).unwrap(),
+        abi::Value::StructValue(vec![
+            ("id".to_owned(), abi::Value::Nullable(None)),
+            ("owner".to_owned(), abi::Value::Nullable(None)),
+            ("name".to_owned(), abi::Value::Nullable(None)),
+            ("nickname".to_owned(), abi::Value::Nullable(None)),
+        ])
+    );
+
+    let nullable = |type_| abi::Type::Nullable(Box::new(type_));
+    assert_eq!(
+        polylang_prover::RunOutput::redacted_this_type(&abi).unwrap(),
+        abi::Type::Struct(abi::Struct {
+            name: "Account".to_owned(),
+            fields: vec![
+                ("id".to_owned(), nullable(abi::Type::String)),
+                ("owner".to_owned(), nullable(abi::Type::PublicKey)),
+                ("name".to_owned(), nullable(abi::Type::String)),
+                ("nickname".to_owned(), nullable(abi::Type::String)),
+            ],
+        })
+    );
+}
+
 #[test]
 fn read_auth_contract_with_pk() {
     let code = r#"
@@ -1089,21 +1307,19 @@ fn read_auth_contract_with_pk() {
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        ".readAuth",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(".readAuth")
+        .this(serde_json::json!({
             "id": "",
             "pk": fixtures::pk1(),
-        }),
-        vec![],
-        Some(fixtures::pk1_key()),
-        HashMap::new(),
-    )
-    .unwrap();
+        }))
+        .ctx_pk(fixtures::pk1_key())
+        .run()
+        .unwrap();
 
-    assert!(output.read_auth());
+    assert!(output.read_auth().unwrap());
 
     consistency_checks!(
         output,
@@ -1141,21 +1357,18 @@ fn read_auth_contract_without_pk() {
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        ".readAuth",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(".readAuth")
+        .this(serde_json::json!({
             "id": "",
             "pk": fixtures::pk1(),
-        }),
-        vec![],
-        None,
-        HashMap::new(),
-    )
-    .unwrap();
+        }))
+        .run()
+        .unwrap();
 
-    assert!(output.read_auth());
+    assert!(output.read_auth().unwrap());
 
     consistency_checks!(
         output,
@@ -1196,24 +1409,22 @@ fn field_hashes() {
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "addBalance",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("addBalance")
+        .this(serde_json::json!({
             "id": "john",
             "balance": 0,
-        }),
-        vec![serde_json::json!(10)],
-        None,
-        HashMap::new(),
-    )
-    .unwrap();
+        }))
+        .arg(serde_json::json!(10))
+        .run()
+        .unwrap();
 
     assert_eq!(
         output.this(&abi).unwrap(),
         abi::Value::StructValue(vec![
-            ("id".to_owned(), abi::Value::String("".to_owned())),
+            ("id".to_owned(), abi::Value::String("john".to_owned())),
             ("balance".to_owned(), abi::Value::UInt32(10)),
         ])
     );
@@ -1260,26 +1471,24 @@ fn field_dependencies() {
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "addBalance",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("addBalance")
+        .this(serde_json::json!({
             "id": "john",
             "name": "John Doe",
             "balance": 0,
-        }),
-        vec![serde_json::json!(10)],
-        None,
-        HashMap::new(),
-    )
-    .unwrap();
+        }))
+        .arg(serde_json::json!(10))
+        .run()
+        .unwrap();
 
     assert_eq!(
         output.this(&abi).unwrap(),
         abi::Value::StructValue(vec![
-            ("id".to_owned(), abi::Value::String("".to_owned())), // id was not passed to the VM
-            ("name".to_owned(), abi::Value::String("".to_owned())), // name was not passed to the VM
+            ("id".to_owned(), abi::Value::String("john".to_owned())), // id was not passed to the VM
+            ("name".to_owned(), abi::Value::String("John Doe".to_owned())), // name was not passed to the VM
             ("balance".to_owned(), abi::Value::UInt32(10)),
         ])
     );
@@ -1312,6 +1521,114 @@ fn field_dependencies() {
     );
 }
 
+/// `this` is never put on the advice tape, each field the function uses is
+/// committed to by its hash and looked up in the advice map by its address.
+/// Reading a variable-length field that isn't the first one checks that the
+/// lookup, the read and the hash check all agree on the same field.
+#[test]
+fn this_field_read_from_advice_map() {
+    let code = r#"
+        contract Account {
+            id: string;
+            name: string;
+            tags: string[];
+            balance: u32;
+
+            getTag(i: u32): string {
+                return this.tags[i];
+            }
+        }
+    "#;
+
+    let tags = serde_json::json!(["a", "bc", "def"]);
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("getTag")
+        .this(serde_json::json!({
+            "id": "john",
+            "name": "John Doe",
+            "tags": tags,
+            "balance": 10,
+        }))
+        .arg(serde_json::json!(2))
+        .run()
+        .unwrap();
+
+    assert_eq!(
+        output.result(&abi).unwrap(),
+        abi::Value::String("def".to_owned())
+    );
+    assert_eq!(
+        output.this(&abi).unwrap(),
+        abi::Value::StructValue(vec![
+            ("id".to_owned(), abi::Value::String("john".to_owned())),
+            ("name".to_owned(), abi::Value::String("John Doe".to_owned())),
+            (
+                "tags".to_owned(),
+                abi::Value::Array(
+                    ["a", "bc", "def"]
+                        .into_iter()
+                        .map(|t| abi::Value::String(t.to_owned()))
+                        .collect()
+                ),
+            ),
+            ("balance".to_owned(), abi::Value::UInt32(10)),
+        ])
+    );
+
+    let tags_type = abi::Type::Array(Box::new(abi::Type::String));
+    assert_eq!(
+        output.hashes().unwrap(),
+        vec![polylang_prover::hash_this(
+            tags_type.clone(),
+            &abi::Parser::parse(&tags_type, &tags).unwrap(),
+            Some(&[0])
+        )
+        .unwrap()]
+    );
+    assert_eq!(abi.dependent_fields, vec![("tags".to_owned(), tags_type)]);
+}
+
+#[test]
+fn this_field_written_but_not_read() {
+    let code = r#"
+        contract Account {
+            id: string;
+            name: string;
+            balance: u32;
+
+            setName(name: string) {
+                this.name = name;
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("setName")
+        .this(serde_json::json!({
+            "id": "john",
+            "name": "John Doe",
+            "balance": 10,
+        }))
+        .arg(serde_json::json!("Johnny"))
+        .run()
+        .unwrap();
+
+    assert_eq!(abi.dependent_fields, vec![]);
+    assert_eq!(abi.written_fields, Some(vec!["name".to_owned()]));
+    assert_eq!(
+        output.this(&abi).unwrap(),
+        abi::Value::StructValue(vec![
+            ("id".to_owned(), abi::Value::String("john".to_owned())),
+            ("name".to_owned(), abi::Value::String("Johnny".to_owned())),
+            ("balance".to_owned(), abi::Value::UInt32(10)),
+        ])
+    );
+}
+
 #[test]
 fn index_of() {
     fn run_index_of(
@@ -1331,18 +1648,17 @@ fn index_of() {
         "#
         .replace("$ELEMENT_TYPE", element_type);
 
-        let (abi, output) = run(
-            &code,
-            "Account",
-            "indexOf",
-            serde_json::json!({
+        let (abi, output) = TestHarness::new()
+            .code(&code)
+            .contract("Account")
+            .function("indexOf")
+            .this(serde_json::json!({
                 "id": "test",
                 "result": 123456,
-            }),
-            vec![serde_json::json!(arr), serde_json::json!(item)],
-            None,
-            HashMap::new(),
-        )?;
+            }))
+            .arg(serde_json::json!(arr))
+            .arg(serde_json::json!(item))
+            .run()?;
 
         let this = output.this(&abi).unwrap();
         Ok(match this {
@@ -1393,75 +1709,3037 @@ fn index_of() {
         .unwrap(),
         abi::Value::Int32(1),
     );
+
+    assert_eq!(
+        run_index_of(
+            "PublicKey",
+            vec![fixtures::pk1(), fixtures::pk2()],
+            fixtures::pk2()
+        )
+        .unwrap(),
+        abi::Value::Int32(1),
+    );
 }
 
-#[test]
-fn returning() {
+#[test_case("alice", 30, 1; "found")]
+#[test_case("alice", 31, -1; "one field differs")]
+fn index_of_struct(name: &str, age: u32, expected: i32) {
     let code = r#"
-        @public
         contract Account {
             id: string;
-            name: string;
+            people: {
+                name: string;
+                age: u32;
+            }[];
 
+            find(name: string, age: u32): i32 {
+                return this.people.indexOf({ name: name, age: age });
+            }
+        }
+    "#;
 
-            @call
-            getName(): string {
-                return this.name;
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("find")
+        .this(serde_json::json!({
+            "id": "",
+            "people": [
+                { "name": "bob", "age": 30 },
+                { "name": "alice", "age": 30 },
+            ],
+        }))
+        .args(vec![serde_json::json!(name), serde_json::json!(age)])
+        .run()
+        .unwrap();
+
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::Int32(expected));
+}
+
+#[test]
+fn map_missing_key_in_loop() {
+    let code = r#"
+        contract WordCount {
+            id: string;
+            counts: map<string, u32>;
+
+            count(words: string[]) {
+                let one: u32 = 1;
+                for (let word of words) {
+                    this.counts[word] = this.counts[word] + one;
+                }
             }
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "getName",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("WordCount")
+        .function("count")
+        .this(serde_json::json!({
             "id": "",
-            "name": "John",
-        }),
-        vec![],
+            "counts": {},
+        }))
+        .arg(serde_json::json!(["a", "b", "a", "c"]))
+        .run()
+        .unwrap();
+
+    // `c` is looked up right after `a` was found, it must not reuse its count
+    let this: serde_json::Value = output.this(&abi).unwrap().try_into().unwrap();
+    assert_eq!(
+        this["counts"],
+        serde_json::json!({ "a": 2, "b": 1, "c": 1 })
+    );
+}
+
+#[test]
+fn map_literal() {
+    let code = r#"
+        contract Counts {
+            id: string;
+            a: u32;
+            b: u32;
+
+            fromLiteral() {
+                let m: map<string, u32> = { "a": 1, "b": 2 };
+                this.a = m["a"];
+                this.b = m["b"];
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Counts")
+        .function("fromLiteral")
+        .this(serde_json::json!({ "id": "", "a": 0, "b": 0 }))
+        .run()
+        .unwrap();
+
+    let this: serde_json::Value = output.this(&abi).unwrap().try_into().unwrap();
+    assert_eq!(this, serde_json::json!({ "id": "", "a": 1, "b": 2 }));
+}
+
+#[test_case("u32", "5", serde_json::json!(5); "u32")]
+#[test_case("u64", "5", serde_json::json!(5); "u64")]
+#[test_case("i32", "5", serde_json::json!(5); "i32")]
+#[test_case("i64", "5", serde_json::json!(5); "i64")]
+#[test_case("f32", "2.5", serde_json::json!(2.5); "f32")]
+#[test_case("f64", "2.5", serde_json::json!(2.5); "f64")]
+#[test_case("number", "2.5", serde_json::json!(2.5); "number")]
+fn map_literal_numeric_values(type_: &str, literal: &str, expected: serde_json::Value) {
+    let code = format!(
+        r#"
+        contract Account {{
+            id: string;
+
+            get(): {type_} {{
+                let m: map<string, {type_}> = {{ "a": {literal} }};
+                return m["a"];
+            }}
+        }}
+    "#
+    );
+
+    let (abi, output) = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("get")
+        .default_this()
+        .run()
+        .unwrap();
+
+    let result: serde_json::Value = output.result(&abi).unwrap().try_into().unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test_case("i32", "2.5"; "float for an integer")]
+#[test_case("u32", "4294967296"; "out of range")]
+fn map_literal_rejects_invalid_numbers(type_: &str, literal: &str) {
+    let code = format!(
+        r#"
+        contract Account {{
+            id: string;
+
+            get(): {type_} {{
+                let m: map<string, {type_}> = {{ "a": {literal} }};
+                return m["a"];
+            }}
+        }}
+    "#
+    );
+
+    let err = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("get")
+        .compile()
+        .unwrap_err();
+
+    assert_eq!(err.name(), "TypeMismatch", "{err}");
+}
+
+#[test]
+fn index_on_array_field() {
+    let code = r#"
+        contract Account {
+            id: string;
+            tags: string[];
+
+            @index(tags);
+
+            setTags(tags: string[]) {
+                this.tags = tags;
+            }
+        }
+    "#;
+
+    let err = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("setTags")
+        .this(serde_json::json!({ "id": "", "tags": [] }))
+        .arg(serde_json::json!(["a"]))
+        .run()
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "type mismatch: cannot index field tags of type Array(String)"
+    );
+}
+
+#[test]
+fn index_on_nested_field() {
+    let code = r#"
+        contract Account {
+            id: string;
+            person?: {
+                name: string;
+            };
+
+            @index(person.name);
+
+            setId(id: string) {
+                this.id = id;
+            }
+        }
+    "#;
+
+    TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("setId")
+        .this(serde_json::json!({ "id": "", "person": { "name": "" } }))
+        .arg(serde_json::json!("id1"))
+        .run()
+        .unwrap();
+}
+
+#[test]
+fn noop_function_outside_contract() {
+    let program = polylang::parse_program("function noop() {}").unwrap();
+    let (miden_code, abi) = polylang::compiler::compile(program, None, "noop").unwrap();
+
+    // The ABI is complete without any patching by the caller.
+    assert_eq!(
+        abi.this_type,
+        Some(abi::Type::Struct(abi::Struct {
+            name: "Empty".to_owned(),
+            fields: vec![],
+        }))
+    );
+
+    let program = polylang_prover::compile_program(&abi, &miden_code).unwrap();
+    let inputs = polylang_prover::Inputs::new(
+        abi.clone(),
         None,
+        vec![],
+        serde_json::json!({}),
+        vec![],
         HashMap::new(),
     )
     .unwrap();
+    let (output, _) = polylang_prover::run(&program, &inputs).unwrap();
+
+    assert_eq!(output.this(&abi).unwrap(), abi::Value::StructValue(vec![]));
+    assert!(!output.self_destructed().unwrap());
+}
+
+#[test]
+fn extends_inherits_fields_and_functions() {
+    let code = r#"
+        contract Owned {
+            id: string;
+            owner: string;
+
+            setOwner(owner: string) {
+                this.owner = owner;
+            }
+        }
+
+        contract Account extends Owned {
+            balance: number;
+
+            transfer(owner: string, balance: number) {
+                this.owner = owner;
+                this.balance = balance;
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("transfer")
+        .this(serde_json::json!({ "id": "a", "owner": "alice", "balance": 1 }))
+        .args(vec![serde_json::json!("bob"), serde_json::json!(2)])
+        .run()
+        .unwrap();
 
     assert_eq!(
-        output.result(&abi).unwrap(),
-        abi::Value::String("John".to_owned())
+        output.this(&abi).unwrap(),
+        abi::Value::StructValue(vec![
+            ("id".to_owned(), abi::Value::String("a".to_owned())),
+            ("owner".to_owned(), abi::Value::String("bob".to_owned())),
+            ("balance".to_owned(), abi::Value::Float32(2.0)),
+        ])
     );
 
-    consistency_checks!(
-        output,
-        abi,
-        hashes:
-            expect![[r#"
-                [
-                    [
-                        13624894021325080686,
-                        17508316994659426056,
-                        2620902411312551361,
-                        8347809021004383550,
-                    ],
-                ]
-            "#]],
-        dependencies:
-            expect![[r#"
-                [
-                    (
-                        "name",
-                        String,
-                    ),
-                ]
-            "#]]
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("setOwner")
+        .this(serde_json::json!({ "id": "a", "owner": "alice", "balance": 1 }))
+        .arg(serde_json::json!("carol"))
+        .run()
+        .unwrap();
+
+    assert_eq!(
+        output.this(&abi).unwrap(),
+        abi::Value::StructValue(vec![
+            ("id".to_owned(), abi::Value::String("a".to_owned())),
+            ("owner".to_owned(), abi::Value::String("carol".to_owned())),
+            ("balance".to_owned(), abi::Value::Float32(1.0)),
+        ])
     );
+}
 
-    let expected_return_hash = expect![[r#"
-        [
-            3850899504691128854,
-            5609950875147406075,
-            16200726738043745980,
-            18156263845020900466,
-        ]
-    "#]];
-    expected_return_hash.assert_debug_eq(&output.result_hash(&abi).unwrap());
+#[test]
+fn this_type_fields_are_in_declaration_order() {
+    let code = r#"
+        contract Named {
+            id: string;
+            zeta: string;
+        }
+
+        contract Account extends Named {
+            beta: u32;
+            alpha: { z: u32; a: string; };
+            zeta: string;
+            gamma: u32[];
+
+            f(beta: u32) {
+                this.beta = beta;
+            }
+        }
+    "#;
+
+    let abi = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("f")
+        .compile()
+        .unwrap()
+        .abi;
+
+    let Some(abi::Type::Struct(this_type)) = abi.this_type else {
+        panic!("expected a struct, got {:?}", abi.this_type);
+    };
+    let field_names = |s: &abi::Struct| {
+        s.fields
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        field_names(&this_type),
+        ["id", "zeta", "beta", "alpha", "gamma"]
+    );
+
+    let abi::Type::Struct(alpha) = &this_type.fields[3].1 else {
+        panic!("expected a struct, got {:?}", this_type.fields[3].1);
+    };
+    assert_eq!(field_names(alpha), ["z", "a"]);
+}
+
+#[test]
+fn extends_conflicting_field() {
+    let code = r#"
+        contract Owned {
+            id: string;
+            owner: string;
+        }
+
+        contract Account extends Owned {
+            owner: number;
+
+            setOwner(owner: number) {
+                this.owner = owner;
+            }
+        }
+    "#;
+
+    let err = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("setOwner")
+        .this(serde_json::json!({ "id": "", "owner": 0 }))
+        .arg(serde_json::json!(1))
+        .run()
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "type mismatch: field owner is String in Owned but PrimitiveType(Float32) in Account"
+    );
+}
+
+#[test_case("contract Account extends Account { id: string; }"; "self")]
+#[test_case(
+    "contract Owned extends Account { id: string; } contract Account extends Owned {}";
+    "two contracts"
+)]
+fn extends_cycle(code: &str) {
+    let err = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(".readAuth")
+        .compile()
+        .unwrap_err();
+
+    assert_eq!(err.name(), "CyclicInheritance");
+    assert!(
+        err.to_string().ends_with("has a cyclic extends chain"),
+        "{err}"
+    );
+}
+
+#[test]
+fn extends_validates_inherited_indexes() {
+    let code = r#"
+        contract Tagged {
+            id: string;
+            tags: string[];
+
+            @index(tags);
+        }
+
+        contract Account extends Tagged {
+            name: string;
+
+            setName(name: string) {
+                this.name = name;
+            }
+        }
+    "#;
+
+    let err = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("setName")
+        .this(serde_json::json!({ "id": "", "tags": [], "name": "" }))
+        .arg(serde_json::json!("a"))
+        .run()
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "type mismatch: cannot index field tags of type Array(String)"
+    );
+}
+
+const IMMUTABLE_FIELDS: &str = r#"
+    contract Owned {
+        id: string;
+        @immutable
+        owner?: PublicKey;
+    }
+
+    contract Account extends Owned {
+        @immutable
+        meta: map<string, string>;
+        name: string;
+
+        constructor (id: string) {
+            this.id = id;
+            this.owner = ctx.publicKey;
+            this.meta["kind"] = "account";
+        }
+
+        setName(name: string) {
+            this.name = name;
+        }
+
+        setOwner() {
+            this.owner = ctx.publicKey;
+        }
+
+        setMeta(key: string, value: string) {
+            this.meta[key] = value;
+        }
+    }
+"#;
+
+#[test]
+fn immutable_fields_assigned_in_constructor() {
+    let (abi, output) = TestHarness::new()
+        .code(IMMUTABLE_FIELDS)
+        .contract("Account")
+        .function("constructor")
+        .this(serde_json::json!({ "id": "", "meta": {}, "name": "" }))
+        .arg(serde_json::json!("id1"))
+        .ctx_pk(fixtures::pk1_key())
+        .run()
+        .unwrap();
+
+    assert_eq!(
+        output.this(&abi).unwrap(),
+        abi::Value::StructValue(vec![
+            ("id".to_owned(), abi::Value::String("id1".to_owned())),
+            (
+                "owner".to_owned(),
+                abi::Value::Nullable(Some(Box::new(abi::Value::PublicKey(fixtures::pk1_key())))),
+            ),
+            (
+                "meta".to_owned(),
+                abi::Value::Map(vec![(
+                    abi::Value::String("kind".to_owned()),
+                    abi::Value::String("account".to_owned()),
+                )]),
+            ),
+            ("name".to_owned(), abi::Value::String("".to_owned())),
+        ])
+    );
+
+    let (abi, output) = TestHarness::new()
+        .code(IMMUTABLE_FIELDS)
+        .contract("Account")
+        .function("setName")
+        .this(serde_json::json!({ "id": "id1", "meta": {}, "name": "" }))
+        .arg(serde_json::json!("John"))
+        .run()
+        .unwrap();
+
+    let this: serde_json::Value = output.this(&abi).unwrap().try_into().unwrap();
+    assert_eq!(this["name"], "John");
+}
+
+#[test_case("setOwner", vec![], "owner"; "inherited field")]
+#[test_case("setMeta", vec![serde_json::json!("kind"), serde_json::json!("user")], "meta"; "map entry")]
+fn immutable_field_assigned_outside_constructor(
+    function: &str,
+    args: Vec<serde_json::Value>,
+    field: &str,
+) {
+    let err = TestHarness::new()
+        .code(IMMUTABLE_FIELDS)
+        .contract("Account")
+        .function(function)
+        .this(serde_json::json!({ "id": "id1", "meta": {}, "name": "" }))
+        .args(args)
+        .ctx_pk(fixtures::pk1_key())
+        .run()
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        format!("field {field} is @immutable, it can only be assigned in the constructor")
+    );
+}
+
+/// A contract whose `f` has `body`, to check what it may do with `@immutable` fields.
+fn immutable_collections(body: &str) -> String {
+    format!(
+        r#"
+        contract Account {{
+            id: string;
+            @immutable
+            tags: string[];
+            @immutable
+            meta: map<string, string>;
+            names: string[];
+
+            constructor (id: string) {{
+                this.id = id;
+                this.tags.push('a');
+                let meta = this.meta;
+                meta['kind'] = 'account';
+            }}
+
+            f() {{
+                {body}
+            }}
+        }}
+    "#
+    )
+}
+
+#[test]
+fn immutable_collections_changed_in_constructor() {
+    let code = immutable_collections("this.names.push('a');");
+    for function in ["constructor", "f"] {
+        TestHarness::new()
+            .code(&code)
+            .contract("Account")
+            .function(function)
+            .compile()
+            .unwrap();
+    }
+}
+
+#[test_case("this.tags.push('b');", "tags", "changed by push"; "push")]
+#[test_case("this.tags.splice(0, 1);", "tags", "changed by splice"; "splice")]
+#[test_case("this.tags.unshift('b');", "tags", "changed by unshift"; "unshift")]
+#[test_case("this.tags.copyWithin(0, 1);", "tags", "changed by copyWithin"; "copy within")]
+#[test_case("let tags = this.tags; tags.push('b');", "tags", "changed by push"; "push to alias")]
+#[test_case("let tags = this.tags; tags[0] = 'b';", "tags", "assigned"; "index of alias")]
+#[test_case("let meta = this.meta; meta['kind'] = 'user';", "meta", "assigned"; "map entry of alias")]
+#[test_case(
+    "let meta = this.meta; let other = meta; other['kind'] = 'user';",
+    "meta",
+    "assigned";
+    "map entry of alias of alias"
+)]
+fn immutable_collections_changed_outside_constructor(body: &str, field: &str, change: &str) {
+    let err = TestHarness::new()
+        .code(&immutable_collections(body))
+        .contract("Account")
+        .function("f")
+        .compile()
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        format!("field {field} is @immutable, it can only be {change} in the constructor")
+    );
+}
+
+const TOKEN_INTERFACE: &str = r#"
+    interface Token {
+        balanceOf(owner: PublicKey): u64;
+        transfer(to: PublicKey, amount: u64);
+    }
+"#;
+
+#[test]
+fn implements_interface() {
+    let code = format!(
+        "{TOKEN_INTERFACE}{}",
+        r#"
+        contract Wallet implements Token {
+            id: string;
+            owner: PublicKey;
+            balance: u64;
+
+            balanceOf(owner: PublicKey): u64 {
+                return this.balance;
+            }
+
+            transfer(to: PublicKey, amount: u64) {
+                this.owner = to;
+                this.balance = this.balance - amount;
+            }
+        }
+    "#
+    );
+
+    TestHarness::new()
+        .code(&code)
+        .contract("Wallet")
+        .function("transfer")
+        .compile()
+        .unwrap();
+}
+
+#[test_case(
+    r#"
+    contract Wallet implements Token {
+        id: string;
+
+        balanceOf(owner: PublicKey): u64 {
+            return 0;
+        }
+    }
+    "#,
+    "method transfer of interface Token not found";
+    "missing method"
+)]
+#[test_case(
+    r#"
+    contract Wallet implements Token {
+        id: string;
+
+        balanceOf(owner: PublicKey): u32 {
+            return 0;
+        }
+
+        transfer(to: PublicKey, amount: u64) {}
+    }
+    "#,
+    "type mismatch: balanceOf(owner: PublicKey): U32 in contract Wallet does not match balanceOf(owner: PublicKey): U64 in interface Token";
+    "mismatched return type"
+)]
+#[test_case(
+    r#"
+    contract Wallet implements Missing {
+        id: string;
+    }
+    "#,
+    "interface Missing not found";
+    "unknown interface"
+)]
+fn implements_interface_error(contract: &str, expected: &str) {
+    let code = format!("{TOKEN_INTERFACE}{contract}");
+    let err = TestHarness::new()
+        .code(&code)
+        .contract("Wallet")
+        .function(".readAuth")
+        .compile()
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), expected);
+}
+
+struct InMemoryResolver(HashMap<&'static str, &'static str>);
+
+impl polylang::SourceResolver for InMemoryResolver {
+    fn resolve(&self, path: &str) -> Result<String, polylang::Error> {
+        self.0
+            .get(path)
+            .map(|source| source.to_string())
+            .ok_or_else(|| {
+                polylang::Error::new(
+                    polylang::ErrorKind::Import,
+                    format!("file {path} not found"),
+                )
+            })
+    }
+}
+
+#[test]
+fn import_from_resolver() {
+    let resolver = InMemoryResolver(HashMap::from([
+        (
+            "./user",
+            r#"
+                import { Id } from "./id";
+
+                contract User {
+                    id: string;
+                }
+            "#,
+        ),
+        ("./id", "function Id(id: string): string { return id; }"),
+    ]));
+    let code = r#"
+        import { User } from "./user";
+        import { Id } from "./id";
+
+        contract Account {
+            id: string;
+            owner: User;
+
+            setOwner(owner: User) {
+                this.owner = owner;
+            }
+        }
+    "#;
+
+    let program = polylang::parse_program_with_imports(code, &resolver).unwrap();
+    let (_, abi) = polylang::compiler::compile(program, Some("Account"), "setOwner").unwrap();
+
+    assert_eq!(
+        abi.this_type,
+        Some(abi::Type::Struct(abi::Struct {
+            name: "Account".to_owned(),
+            fields: vec![
+                ("id".to_owned(), abi::Type::String),
+                (
+                    "owner".to_owned(),
+                    abi::Type::ContractReference {
+                        contract: "User".to_owned()
+                    }
+                ),
+            ],
+        }))
+    );
+
+    let err =
+        polylang::parse_program_with_imports(r#"import { Missing } from "./user";"#, &resolver)
+            .unwrap_err();
+    assert_eq!(err.message, "./user does not declare Missing");
+}
+
+fn declared_names(program: &polylang::ast::Program) -> Vec<&str> {
+    program
+        .nodes
+        .iter()
+        .filter_map(|node| match node {
+            polylang::ast::RootNode::Contract(c) => Some(c.name.as_str()),
+            polylang::ast::RootNode::Function(f) => Some(f.name.as_str()),
+            polylang::ast::RootNode::Interface(i) => Some(i.name.as_str()),
+            polylang::ast::RootNode::Import(_) => None,
+        })
+        .collect()
+}
+
+#[test]
+fn import_merges_only_imported_names() {
+    let resolver = InMemoryResolver(HashMap::from([(
+        "./math",
+        r#"
+            function double(x: u32): u32 { return x * 2; }
+            function triple(x: u32): u32 { return x * 3; }
+        "#,
+    )]));
+    let code = r#"
+        import { double } from "./math";
+
+        contract Account {
+            id: string;
+        }
+    "#;
+
+    let program = polylang::parse_program_with_imports(code, &resolver).unwrap();
+
+    assert_eq!(declared_names(&program), ["Account", "double"]);
+}
+
+#[test]
+fn import_resolves_relative_to_importing_file() {
+    let resolver = InMemoryResolver(HashMap::from([
+        (
+            "./models/user",
+            r#"
+                import { Id } from "./id";
+                import { Name } from "../shared/name";
+
+                contract User {
+                    id: string;
+                }
+            "#,
+        ),
+        (
+            "./models/id",
+            "function Id(id: string): string { return id; }",
+        ),
+        (
+            "./shared/name",
+            "function Name(n: string): string { return n; }",
+        ),
+    ]));
+    let code = r#"
+        import { User } from "./models/user";
+
+        contract Account {
+            id: string;
+        }
+    "#;
+
+    let program = polylang::parse_program_with_imports(code, &resolver).unwrap();
+
+    let mut names = declared_names(&program);
+    names.sort();
+    assert_eq!(names, ["Account", "Id", "Name", "User"]);
+}
+
+#[test_case(
+    r#"
+        import { User } from "./user";
+
+        contract User {
+            id: string;
+        }
+    "#,
+    "User is imported from ./user but already declared";
+    "declared in the importing file"
+)]
+#[test_case(
+    r#"
+        import { Id } from "./id";
+        import { Id } from "./other_id";
+    "#,
+    "Id is declared in both ./id and ./other_id";
+    "imported from two files"
+)]
+fn import_rejects_duplicate_names(code: &str, expected: &str) {
+    let resolver = InMemoryResolver(HashMap::from([
+        ("./user", "contract User { id: string; }"),
+        ("./id", "function Id(id: string): string { return id; }"),
+        (
+            "./other_id",
+            "function Id(id: string): string { return id; }",
+        ),
+    ]));
+
+    let err = polylang::parse_program_with_imports(code, &resolver).unwrap_err();
+
+    assert_eq!(err.message, expected);
+}
+
+#[test]
+fn returning() {
+    let code = r#"
+        @public
+        contract Account {
+            id: string;
+            name: string;
+
+
+            @call
+            getName(): string {
+                return this.name;
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("getName")
+        .this(serde_json::json!({
+            "id": "",
+            "name": "John",
+        }))
+        .run()
+        .unwrap();
+
+    assert_eq!(
+        output.result(&abi).unwrap(),
+        abi::Value::String("John".to_owned())
+    );
+
+    consistency_checks!(
+        output,
+        abi,
+        hashes:
+            expect![[r#"
+                [
+                    [
+                        13624894021325080686,
+                        17508316994659426056,
+                        2620902411312551361,
+                        8347809021004383550,
+                    ],
+                ]
+            "#]],
+        dependencies:
+            expect![[r#"
+                [
+                    (
+                        "name",
+                        String,
+                    ),
+                ]
+            "#]]
+    );
+
+    let expected_return_hash = expect![[r#"
+        [
+            3850899504691128854,
+            5609950875147406075,
+            16200726738043745980,
+            18156263845020900466,
+        ]
+    "#]];
+    expected_return_hash.assert_debug_eq(&output.result_hash(&abi).unwrap().unwrap());
+}
+
+#[test]
+fn returning_tuple() {
+    let code = r#"
+        contract Account {
+            id: string;
+
+            @call
+            pair(a: u32, b: string): (u32, string) {
+                return (a + 1, b);
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("pair")
+        .default_this()
+        .args(vec![serde_json::json!(41), serde_json::json!("John")])
+        .run()
+        .unwrap();
+
+    assert_eq!(
+        output.result(&abi).unwrap(),
+        abi::Value::StructValue(vec![
+            ("0".to_owned(), abi::Value::UInt32(42)),
+            ("1".to_owned(), abi::Value::String("John".to_owned())),
+        ])
+    );
+}
+
+#[test_case("f(5)", 10; "omitted argument uses default")]
+#[test_case("f(5, 3)", 3; "explicit argument overrides default")]
+fn default_parameter_values(call: &str, expected: u32) {
+    let code = format!(
+        r#"
+        function f(a: u32, b: u32 = 10): u32 {{
+            return b;
+        }}
+
+        contract Account {{
+            id: string;
+
+            @call
+            get(): u32 {{
+                return {call};
+            }}
+        }}
+    "#
+    );
+
+    let (abi, output) = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("get")
+        .default_this()
+        .run()
+        .unwrap();
+
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::UInt32(expected));
+}
+
+#[test]
+fn rest_parameters() {
+    let code = r#"
+        function sum(...nums: u32[]): u32 {
+            let total: u32 = 0;
+            for (let n of nums) {
+                total += n;
+            }
+            return total;
+        }
+
+        contract Account {
+            id: string;
+
+            @call
+            get(): u32 {
+                return sum(1, 2, 3);
+            }
+
+            @call
+            getAll(...nums: u32[]): u32 {
+                let total: u32 = 0;
+                for (let n of nums) {
+                    total += n;
+                }
+                return total;
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("get")
+        .default_this()
+        .run()
+        .unwrap();
+
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::UInt32(6));
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("getAll")
+        .default_this()
+        .args(vec![serde_json::json!(4), serde_json::json!(5)])
+        .run()
+        .unwrap();
+
+    assert!(abi.variadic);
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::UInt32(9));
+}
+
+#[test_case("transfer(10, 3)", 7; "two arguments")]
+#[test_case("transfer(10, 3, 2)", 5; "three arguments")]
+fn overloading_by_parameter_count(call: &str, expected: u32) {
+    let code = format!(
+        r#"
+        function transfer(balance: u32, amount: u32): u32 {{
+            return balance - amount;
+        }}
+
+        function transfer(balance: u32, amount: u32, fee: u32): u32 {{
+            return balance - amount - fee;
+        }}
+
+        contract Account {{
+            id: string;
+
+            @call
+            get(): u32 {{
+                return {call};
+            }}
+        }}
+    "#
+    );
+
+    let (abi, output) = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("get")
+        .default_this()
+        .run()
+        .unwrap();
+
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::UInt32(expected));
+}
+
+#[test]
+fn overloading_without_matching_parameter_count() {
+    let code = r#"
+        function transfer(balance: u32, amount: u32): u32 {
+            return balance - amount;
+        }
+
+        function transfer(balance: u32, amount: u32, fee: u32): u32 {
+            return balance - amount - fee;
+        }
+
+        contract Account {
+            id: string;
+
+            @call
+            get(): u32 {
+                return transfer(10);
+            }
+        }
+    "#;
+
+    let err = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("get")
+        .default_this()
+        .run()
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), "no overload of transfer takes 1 arguments");
+}
+
+#[test_case("u64", "f()", abi::Value::UInt64(10); "u64 default")]
+#[test_case("u64", "f(5000000000)", abi::Value::UInt64(5_000_000_000); "u64 argument")]
+#[test_case("i32", "f()", abi::Value::Int32(10); "i32 default")]
+#[test_case("i32", "f(7)", abi::Value::Int32(7); "i32 argument")]
+#[test_case("i64", "f()", abi::Value::Int64(10); "i64 default")]
+#[test_case("i64", "f(5000000000)", abi::Value::Int64(5_000_000_000); "i64 argument")]
+fn integer_literal_arguments(type_: &str, call: &str, expected: abi::Value) {
+    let code = format!(
+        r#"
+        function f(a: {type_} = 10): {type_} {{
+            return a;
+        }}
+
+        contract Account {{
+            id: string;
+
+            @call
+            get(): {type_} {{
+                return {call};
+            }}
+        }}
+    "#
+    );
+
+    let (abi, output) = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("get")
+        .default_this()
+        .run()
+        .unwrap();
+
+    assert_eq!(output.result(&abi).unwrap(), expected);
+}
+
+#[test_case("u32", "f(1.5)", "type mismatch: expected UInt32, not float 1.5"; "float to u32")]
+#[test_case("i64", "f(2.5)", "type mismatch: expected Int64, not float 2.5"; "float to i64")]
+#[test_case("u32", "f(5000000000)", "type mismatch: 5000000000 is out of range for UInt32"; "u32 out of range")]
+#[test_case("i32", "f(3000000000)", "type mismatch: 3000000000 is out of range for Int32"; "i32 out of range")]
+fn invalid_integer_literal_arguments(type_: &str, call: &str, expected: &str) {
+    let code = format!(
+        r#"
+        function f(a: {type_}): {type_} {{
+            return a;
+        }}
+
+        contract Account {{
+            id: string;
+
+            @call
+            get(): {type_} {{
+                return {call};
+            }}
+        }}
+    "#
+    );
+
+    let err = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("get")
+        .default_this()
+        .run()
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), expected);
+}
+
+#[test]
+fn overloading_rejects_ambiguous_calls() {
+    let code = r#"
+        function total(a: u32, b: u32): u32 {
+            return a + b;
+        }
+
+        function total(a: u32, ...rest: u32[]): u32 {
+            return a;
+        }
+
+        contract Account {
+            id: string;
+
+            @call
+            get(): u32 {
+                let a: u32 = 1;
+                return total(a, a);
+            }
+        }
+    "#;
+
+    let err = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("get")
+        .compile()
+        .unwrap_err();
+
+    assert!(
+        err.to_string()
+            .contains("call to total with 2 arguments matches more than one overload"),
+        "{err}"
+    );
+}
+
+#[test_case("u32", serde_json::json!(7); "u32")]
+#[test_case("i32", serde_json::json!(7); "i32")]
+#[test_case("i32", serde_json::json!(-7); "i32 negative")]
+#[test_case("i32", serde_json::json!(i32::MIN); "i32 min")]
+#[test_case("u64", serde_json::json!(7); "u64")]
+#[test_case("i64", serde_json::json!(7); "i64")]
+#[test_case("i64", serde_json::json!(-7); "i64 negative")]
+#[test_case("i64", serde_json::json!(i64::MIN); "i64 min")]
+fn division_by_zero(type_: &str, a: serde_json::Value) {
+    for op in ["/", "%"] {
+        let code = format!(
+            r#"
+            @public
+            contract Account {{
+                id: string;
+
+                @call
+                compute(a: {type_}, b: {type_}): {type_} {{
+                    return a {op} b;
+                }}
+            }}
+        "#
+        );
+
+        let err = TestHarness::new()
+            .code(&code)
+            .contract("Account")
+            .function("compute")
+            .default_this()
+            .arg(a.clone())
+            .arg(serde_json::json!(0))
+            .run()
+            .unwrap_err();
+
+        assert!(
+            err.to_string().contains("division by zero"),
+            "{type_} {op}: {err}"
+        );
+    }
+}
+
+#[test]
+fn execute_without_proof() {
+    let code = r#"
+        contract Account {
+            id: string;
+            balance: u32;
+
+            deposit(amount: u32) {
+                this.balance = this.balance + amount;
+            }
+        }
+    "#;
+
+    let program = polylang::parse_program(code).unwrap();
+    let (miden_code, abi) =
+        polylang::compiler::compile(program, Some("Account"), "deposit").unwrap();
+    let program = polylang_prover::compile_program(&abi, &miden_code).unwrap();
+
+    let inputs = polylang_prover::Inputs::new(
+        abi.clone(),
+        None,
+        vec![0, 0],
+        serde_json::json!({
+            "id": "",
+            "balance": 10,
+        }),
+        vec![serde_json::json!(5)],
+        HashMap::new(),
+    )
+    .unwrap();
+
+    let output = polylang_prover::execute(&program, &inputs).unwrap();
+
+    assert_eq!(
+        output.this(&abi).unwrap(),
+        abi::Value::StructValue(vec![
+            ("id".to_owned(), abi::Value::String("".to_owned())),
+            ("balance".to_owned(), abi::Value::UInt32(15)),
+        ])
+    );
+}
+
+#[test]
+fn stack_typed_matches_accessors() {
+    let code = r#"
+        @public
+        contract Account {
+            id: string;
+            name: string;
+
+            @call
+            getName(): string {
+                return this.name;
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("getName")
+        .this(serde_json::json!({
+            "id": "",
+            "name": "John",
+        }))
+        .run()
+        .unwrap();
+
+    let typed = output.stack_typed().unwrap();
+    assert_eq!(typed.self_destructed, output.self_destructed().unwrap());
+    assert_eq!(typed.field_hashes, output.hashes().unwrap());
+    assert_eq!(typed.result_hash, output.result_hash(&abi).unwrap());
+    assert!(typed.result_hash.is_some());
+    assert_eq!(typed.read_auth, output.read_auth().unwrap());
+    assert_eq!(typed.read_fields, output.read_fields().unwrap());
+}
+
+#[test]
+fn stack_typed_with_result_and_read_fields() {
+    let code = r#"
+        @private
+        contract Account {
+            id: string;
+            @read
+            owner: PublicKey;
+            balance: u32;
+
+            @call
+            getBalance(): u32 {
+                return this.balance;
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("getBalance")
+        .this(serde_json::json!({
+            "id": "",
+            "owner": fixtures::pk1(),
+            "balance": 10,
+        }))
+        .ctx_pk(fixtures::pk1_key())
+        .run()
+        .unwrap();
+
+    let typed = output.stack_typed().unwrap();
+    assert!(!typed.self_destructed);
+    assert!(typed.result_hash.is_some());
+    // A function that returns a value has no read auth results, even if the contract has `@read` fields.
+    assert!(!typed.read_auth);
+    assert!(typed.read_fields.is_empty());
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::UInt32(10));
+}
+
+#[test_case(
+    r#"
+        contract Counter {
+            id: string;
+            value: u32;
+
+            increment() {
+                this.value = this.value + 1;
+            }
+        }
+    "#,
+    "Counter",
+    "increment",
+    serde_json::json!({ "id": "", "value": 1 }),
+    vec![];
+    "counter"
+)]
+#[test_case(
+    r#"
+        contract Account {
+            id: string;
+            balance: u64;
+
+            split(parts: u64) {
+                this.balance = this.balance / parts;
+            }
+        }
+    "#,
+    "Account",
+    "split",
+    serde_json::json!({ "id": "", "balance": 100 }),
+    vec![serde_json::json!(3)];
+    "u64 division"
+)]
+fn cycle_estimate(
+    code: &str,
+    contract: &str,
+    function: &str,
+    this: serde_json::Value,
+    args: Vec<serde_json::Value>,
+) {
+    let harness = TestHarness::new()
+        .code(code)
+        .contract(contract)
+        .function(function)
+        .this(this)
+        .args(args);
+    let estimate = polylang_prover::cycle_estimate(&harness.compile().unwrap().miden_code);
+
+    let (_, output) = harness.run().unwrap();
+    let actual = output.cycle_count as u64;
+
+    // the estimate is rough, it only has to be in the right ballpark
+    assert!(
+        estimate >= actual / 3 && estimate <= actual * 3,
+        "estimate {estimate} is too far from actual {actual}"
+    );
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn comments_have_source_locations() {
+    let code = "contract Account {\n    id: string;\n    balance: number;\n\n    deposit(amount: number) {\n        this.balance = this.balance + amount;\n    }\n}\n";
+
+    let miden_code = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("deposit")
+        .options(polylang::compiler::CompileOptions {
+            source: Some(code),
+            ..Default::default()
+        })
+        .compile()
+        .unwrap()
+        .miden_code;
+
+    let comment = miden_code
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with("# ") && line.contains("Compiling expression"))
+        .unwrap_or_else(|| panic!("no expression comment in:\n{miden_code}"));
+    assert!(comment.starts_with("# 6:"), "{comment}");
+}
+
+#[test]
+fn deprecated_function_warning() {
+    let code = r#"
+        @deprecated("use add instead")
+        function plus(a: u32, b: u32): u32 {
+            return a + b;
+        }
+
+        contract Account {
+            id: string;
+
+            @call
+            get(): u32 {
+                return plus(1, 2);
+            }
+        }
+    "#;
+
+    let warnings = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("get")
+        .compile_warnings()
+        .unwrap();
+
+    assert_eq!(
+        warnings,
+        vec!["function plus is deprecated: use add instead"]
+    );
+}
+
+#[test]
+fn unused_let_warning() {
+    let code = r#"
+        contract Account {
+            id: string;
+            balance: u32;
+
+            @call
+            deposit(amount: u32) {
+                let previous = this.balance;
+                this.balance = this.balance + amount;
+            }
+        }
+    "#;
+
+    let warnings = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("deposit")
+        .compile_warnings()
+        .unwrap();
+
+    assert_eq!(warnings, vec!["unused variable: previous"]);
+}
+
+#[test_case("let unused = amount; this.balance = this.balance + amount;", &["unused variable: unused"]; "unused local")]
+#[test_case("let one: u32 = 1; this.balance = this.balance + one;", &["unused parameter: amount"]; "unused parameter")]
+#[test_case("let total = this.balance + amount; this.balance = total;", &[]; "used variable")]
+#[test_case("let total = amount; total = amount; this.balance = this.balance + amount;", &["unused variable: total"]; "only assigned")]
+#[test_case("let total = amount; total += amount; this.balance = total;", &[]; "compound assignment")]
+fn unused_variable_warnings(body: &str, expected: &[&str]) {
+    let code = format!(
+        r#"
+        contract Account {{
+            id: string;
+            balance: u32;
+
+            @call
+            deposit(amount: u32) {{
+                {body}
+            }}
+        }}
+    "#
+    );
+
+    let harness = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("deposit");
+
+    assert_eq!(harness.compile_warnings().unwrap(), expected);
+    for warning in &harness.compile().unwrap().warnings {
+        let (line, _) = warning.span.unwrap().start_line_col(&code);
+        assert!(code.lines().nth(line - 1).unwrap().contains("amount"));
+    }
+}
+
+#[test]
+fn unused_variable_warnings_only_for_compiled_function() {
+    let code = r#"
+        function double(x: u32): u32 {
+            return 2;
+        }
+
+        contract Account {
+            id: string;
+            balance: u32;
+
+            deposit(amount: u32) {
+                this.balance = this.balance + amount;
+            }
+
+            withdraw(amount: u32) {
+                let previous = this.balance;
+                this.balance = this.balance - amount;
+            }
+        }
+    "#;
+
+    let warnings = |function| {
+        TestHarness::new()
+            .code(code)
+            .contract("Account")
+            .function(function)
+            .compile_warnings()
+            .unwrap()
+    };
+
+    assert_eq!(warnings("deposit"), Vec::<String>::new());
+    assert_eq!(warnings("withdraw"), vec!["unused variable: previous"]);
+}
+
+#[test]
+fn no_lint_warnings_for_imported_declarations() {
+    let resolver = InMemoryResolver(HashMap::from([(
+        "./account",
+        r#"
+            contract Account {
+                id: string;
+                balance: u32;
+
+                withdraw(amount: u32) {
+                    let previous = this.balance;
+                    this.balance = this.balance - amount;
+                }
+            }
+        "#,
+    )]));
+    let code = r#"import { Account } from "./account";"#;
+
+    let program = polylang::parse_program_with_imports(code, &resolver).unwrap();
+    let output = polylang::compiler::compile_with_options(
+        program,
+        Some("Account"),
+        "withdraw",
+        Default::default(),
+    )
+    .unwrap();
+
+    assert!(output.warnings.is_empty(), "{:?}", output.warnings);
+}
+
+#[test_case("let total = amount; if (total > amount) { let total = amount; this.balance = total; }", &["variable total shadows variable total"]; "shadowed")]
+#[test_case("let total = amount; if (total > amount) { let other = total; this.balance = other; }", &[]; "distinct names")]
+fn shadowing_warnings(body: &str, expected: &[&str]) {
+    let code = format!(
+        r#"
+        contract Account {{
+            id: string;
+            balance: u32;
+
+            @call
+            deposit(amount: u32) {{
+                {body}
+            }}
+        }}
+    "#
+    );
+
+    let harness = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("deposit")
+        .options(polylang::compiler::CompileOptions {
+            warn_shadowing: true,
+            ..Default::default()
+        });
+
+    assert_eq!(harness.compile_warnings().unwrap(), expected);
+    for warning in &harness.compile().unwrap().warnings {
+        assert!(warning.span.is_some());
+        assert!(warning.related_span.is_some());
+    }
+}
+
+#[test_case("i < limit && check(i, limit)"; "and")]
+#[test_case("!(i >= limit || !check(i, limit))"; "or")]
+fn while_condition_short_circuits(condition: &str) {
+    let code = format!(
+        r#"
+        function check(i: u32, limit: u32): boolean {{
+            if (i >= limit) {{
+                error("right-hand side evaluated after the loop should have stopped");
+            }}
+            return true;
+        }}
+
+        contract Account {{
+            id: string;
+
+            @call
+            count(limit: u32): u32 {{
+                let i: u32 = 0;
+                let one: u32 = 1;
+                while ({condition}) {{
+                    i = i + one;
+                }}
+                return i;
+            }}
+        }}
+    "#
+    );
+
+    let (abi, output) = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("count")
+        .default_this()
+        .arg(serde_json::json!(3))
+        .run()
+        .unwrap();
+
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::UInt32(3));
+}
+
+#[test]
+fn max_loop_iterations() {
+    let code = r#"
+        contract Account {
+            id: string;
+
+            @call
+            @maxIterations(3)
+            count(limit: u32): u32 {
+                let i: u32 = 0;
+                let one: u32 = 1;
+                while (i < limit) {
+                    i = i + one;
+                }
+                return i;
+            }
+        }
+    "#;
+
+    let run = |limit: u32| {
+        TestHarness::new()
+            .code(code)
+            .contract("Account")
+            .function("count")
+            .default_this()
+            .arg(serde_json::json!(limit))
+            .run()
+    };
+
+    let (abi, output) = run(3).unwrap();
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::UInt32(3));
+
+    let err = run(4).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("loop exceeded the maximum of 3 iterations"),
+        "{err}"
+    );
+}
+
+#[test]
+fn max_loop_iterations_option() {
+    let code = r#"
+        contract Account {
+            id: string;
+
+            @call
+            count(limit: u32): u32 {
+                let i: u32 = 0;
+                let one: u32 = 1;
+                while (i < limit) {
+                    i = i + one;
+                }
+                return i;
+            }
+
+            @call
+            @maxIterations(5)
+            countMore(limit: u32): u32 {
+                let i: u32 = 0;
+                let one: u32 = 1;
+                while (i < limit) {
+                    i = i + one;
+                }
+                return i;
+            }
+        }
+    "#;
+
+    let run = |function: &str, limit: u32| {
+        TestHarness::new()
+            .code(code)
+            .contract("Account")
+            .function(function)
+            .default_this()
+            .arg(serde_json::json!(limit))
+            .options(polylang::compiler::CompileOptions {
+                max_loop_iterations: Some(3),
+                ..Default::default()
+            })
+            .run()
+    };
+
+    let (abi, output) = run("count", 3).unwrap();
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::UInt32(3));
+
+    let err = run("count", 4).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("loop exceeded the maximum of 3 iterations"),
+        "{err}"
+    );
+
+    // @maxIterations takes precedence over the option.
+    let (abi, output) = run("countMore", 5).unwrap();
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::UInt32(5));
+}
+
+#[test]
+fn max_loop_iterations_option_skips_builtins() {
+    let code = r#"
+        contract Account {
+            id: string;
+
+            @call
+            digits(n: u32): string {
+                return uint32ToString(n);
+            }
+
+            @call
+            sine(x: f32): f32 {
+                return sin(x);
+            }
+        }
+    "#;
+
+    let run = |function: &str, arg: serde_json::Value| {
+        TestHarness::new()
+            .code(code)
+            .contract("Account")
+            .function(function)
+            .default_this()
+            .arg(arg)
+            .options(polylang::compiler::CompileOptions {
+                max_loop_iterations: Some(1),
+                ..Default::default()
+            })
+            .run()
+            .unwrap()
+    };
+
+    let (abi, output) = run("digits", serde_json::json!(12345));
+    assert_eq!(
+        output.result(&abi).unwrap(),
+        abi::Value::String("12345".to_owned())
+    );
+
+    let (abi, output) = run("sine", serde_json::json!(100));
+    let abi::Value::Float32(result) = output.result(&abi).unwrap() else {
+        panic!("sin didn't return a float");
+    };
+    assert!(
+        (result - -0.506_365_64).abs() <= 1e-5,
+        "sin(100) = {result}"
+    );
+}
+
+#[test]
+fn array_for_each() {
+    let code = r#"
+        contract Account {
+            id: string;
+            total: u32;
+            indexTotal: u32;
+
+            @call
+            sum(nums: u32[]) {
+                nums.forEach((n, i) => {
+                    this.total += n;
+                    this.indexTotal += i;
+                });
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("sum")
+        .this(serde_json::json!({
+            "id": "",
+            "total": 0,
+            "indexTotal": 0,
+        }))
+        .arg(serde_json::json!([10, 20, 30]))
+        .run()
+        .unwrap();
+
+    assert_eq!(
+        output.this(&abi).unwrap(),
+        abi::Value::StructValue(vec![
+            ("id".to_owned(), abi::Value::String("".to_owned())),
+            ("total".to_owned(), abi::Value::UInt32(60)),
+            ("indexTotal".to_owned(), abi::Value::UInt32(3)),
+        ])
+    );
+}
+
+#[test]
+fn array_map_with_arrow_function() {
+    let code = r#"
+        contract Account {
+            id: string;
+
+            @call
+            increment(nums: u32[]): u32[] {
+                let one: u32 = 1;
+                return nums.map(n => n + one);
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("increment")
+        .default_this()
+        .arg(serde_json::json!([1, 2, 3]))
+        .run()
+        .unwrap();
+
+    assert_eq!(
+        output.result(&abi).unwrap(),
+        abi::Value::Array(vec![
+            abi::Value::UInt32(2),
+            abi::Value::UInt32(3),
+            abi::Value::UInt32(4),
+        ])
+    );
+}
+
+#[test]
+fn arrow_function_captures_variables() {
+    let code = r#"
+        contract Account {
+            id: string;
+
+            @call
+            scale(nums: u32[]): u32[] {
+                let factor: u32 = 2;
+                return nums.map(x => x * factor);
+            }
+
+            @call
+            sum(nums: u32[]): u32 {
+                let total: u32 = 0;
+                nums.forEach(x => {
+                    total += x;
+                });
+                return total;
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("scale")
+        .default_this()
+        .arg(serde_json::json!([1, 2, 3]))
+        .run()
+        .unwrap();
+
+    assert_eq!(
+        output.result(&abi).unwrap(),
+        abi::Value::Array(vec![
+            abi::Value::UInt32(2),
+            abi::Value::UInt32(4),
+            abi::Value::UInt32(6),
+        ])
+    );
+
+    // Assigning to a captured variable modifies the variable itself.
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("sum")
+        .default_this()
+        .arg(serde_json::json!([1, 2, 3]))
+        .run()
+        .unwrap();
+
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::UInt32(6));
+}
+
+#[test_case("forEach1", serde_json::json!(31); "forEach element")]
+#[test_case("forEach2", serde_json::json!(41); "forEach element and index")]
+#[test_case("map1", serde_json::json!([20, 2, 40]); "map element")]
+#[test_case("map2", serde_json::json!([0, 1, 40]); "map element and index")]
+#[test_case("filter1", serde_json::json!([20]); "filter element")]
+#[test_case("filter2", serde_json::json!([10, 20]); "filter element and index")]
+#[test_case("reduce1", serde_json::json!(3); "reduce accumulator")]
+#[test_case("reduce2", serde_json::json!(31); "reduce accumulator and element")]
+#[test_case("reduce4", serde_json::json!(9); "reduce accumulator, element, index and array")]
+fn array_callback_arity(function: &str, expected: serde_json::Value) {
+    let code = r#"
+        contract Account {
+            id: string;
+
+            forEach1(nums: u32[]): u32 {
+                let total: u32 = 0;
+                nums.forEach(x => {
+                    total += x;
+                });
+                return total;
+            }
+
+            forEach2(nums: u32[]): u32 {
+                let total: u32 = 0;
+                nums.forEach((x, i) => {
+                    total += x * i;
+                });
+                return total;
+            }
+
+            map1(nums: u32[]): u32[] {
+                return nums.map(x => x + x);
+            }
+
+            map2(nums: u32[]): u32[] {
+                return nums.map((x, i) => x * i);
+            }
+
+            filter1(nums: u32[]): u32[] {
+                let limit: u32 = 15;
+                return nums.filter(x => x > limit);
+            }
+
+            filter2(nums: u32[]): u32[] {
+                return nums.filter((x, i) => x > i);
+            }
+
+            reduce1(nums: u32[]): u32 {
+                let zero: u32 = 0;
+                let one: u32 = 1;
+                return nums.reduce(acc => acc + one, zero);
+            }
+
+            reduce2(nums: u32[]): u32 {
+                let zero: u32 = 0;
+                return nums.reduce((acc, x) => acc + x, zero);
+            }
+
+            reduce4(nums: u32[]): u32 {
+                let zero: u32 = 0;
+                return nums.reduce((acc, _x, _i, arr) => acc + arr.length, zero);
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(function)
+        .default_this()
+        .arg(serde_json::json!([10, 1, 20]))
+        .run()
+        .unwrap();
+
+    let result: serde_json::Value = output.result(&abi).unwrap().try_into().unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test_case("map", serde_json::json!([20, 2, 40]); "map")]
+#[test_case("mapEarlyReturn", serde_json::json!([10, 2, 20]); "map with an early return")]
+#[test_case("filter", serde_json::json!([10, 20]); "filter")]
+#[test_case("reduce", serde_json::json!(20); "reduce")]
+fn array_block_callback_return(function: &str, expected: serde_json::Value) {
+    let code = r#"
+        contract Account {
+            id: string;
+
+            map(nums: u32[]): u32[] {
+                return nums.map(x => {
+                    let double: u32 = x + x;
+                    return double;
+                });
+            }
+
+            mapEarlyReturn(nums: u32[]): u32[] {
+                let limit: u32 = 5;
+                return nums.map(x => {
+                    if (x > limit) {
+                        return x;
+                    }
+                    return x + x;
+                });
+            }
+
+            filter(nums: u32[]): u32[] {
+                let limit: u32 = 5;
+                return nums.filter(x => {
+                    return x > limit;
+                });
+            }
+
+            reduce(nums: u32[]): u32 {
+                let zero: u32 = 0;
+                return nums.reduce((acc, x) => {
+                    if (x > acc) {
+                        return x;
+                    }
+                    return acc;
+                }, zero);
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(function)
+        .default_this()
+        .arg(serde_json::json!([10, 1, 20]))
+        .run()
+        .unwrap();
+
+    let result: serde_json::Value = output.result(&abi).unwrap().try_into().unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test_case("sameName", serde_json::json!("alice"), true; "equal strings")]
+#[test_case("sameName", serde_json::json!("bob"), false; "different strings")]
+#[test_case("sameNums", serde_json::json!([1, 2]), true; "equal arrays")]
+#[test_case("sameNums", serde_json::json!([2, 1]), false; "different arrays")]
+#[test_case("sameProfile", serde_json::json!(30), true; "equal structs")]
+#[test_case("sameProfile", serde_json::json!(31), false; "different structs")]
+fn generic_hash(function: &str, arg: serde_json::Value, expected: bool) {
+    let code = r#"
+        contract Account {
+            id: string;
+            name: string;
+            nums: u32[];
+            profile: { age: u32; };
+            other: { age: u32; };
+
+            @call
+            sameName(name: string): boolean {
+                return hash(this.name) == hash(name);
+            }
+
+            @call
+            sameNums(nums: u32[]): boolean {
+                return hash(this.nums) == hash(nums);
+            }
+
+            @call
+            sameProfile(age: u32): boolean {
+                this.other.age = age;
+                return hash(this.profile) == hash(this.other);
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(function)
+        .this(serde_json::json!({
+            "id": "",
+            "name": "alice",
+            "nums": [1, 2],
+            "profile": { "age": 30 },
+            "other": { "age": 0 },
+        }))
+        .arg(arg)
+        .run()
+        .unwrap();
+
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::Boolean(expected));
+}
+
+#[test_case(serde_json::json!(null), serde_json::json!(null), true; "null equals null")]
+#[test_case(serde_json::json!(null), serde_json::json!(1), false; "null does not equal a value")]
+#[test_case(serde_json::json!(1), serde_json::json!(null), false; "a value does not equal null")]
+#[test_case(serde_json::json!(1), serde_json::json!(1), true; "equal values")]
+#[test_case(serde_json::json!(1), serde_json::json!(2), false; "different values")]
+fn nullable_eq(a: serde_json::Value, b: serde_json::Value, expected: bool) {
+    let code = r#"
+        contract Account {
+            id: string;
+            a?: u32;
+            b?: u32;
+
+            isEqual(): boolean {
+                return this.a == this.b;
+            }
+
+            isNotEqual(): boolean {
+                return this.a != this.b;
+            }
+        }
+    "#;
+
+    for (function, expected) in [("isEqual", expected), ("isNotEqual", !expected)] {
+        let (abi, output) = TestHarness::new()
+            .code(code)
+            .contract("Account")
+            .function(function)
+            .this(serde_json::json!({ "id": "", "a": a, "b": b }))
+            .run()
+            .unwrap();
+
+        assert_eq!(output.result(&abi).unwrap(), abi::Value::Boolean(expected));
+    }
+}
+
+#[test_case(serde_json::json!(null), false; "null")]
+#[test_case(serde_json::json!(1), true; "equal value")]
+#[test_case(serde_json::json!(2), false; "different value")]
+fn nullable_eq_value(a: serde_json::Value, expected: bool) {
+    let code = r#"
+        contract Account {
+            id: string;
+            a?: u32;
+
+            isOne(): boolean {
+                let one: u32 = 1;
+                return this.a == one;
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("isOne")
+        .this(serde_json::json!({ "id": "", "a": a }))
+        .run()
+        .unwrap();
+
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::Boolean(expected));
+}
+
+#[test]
+fn assign_null() {
+    let code = r#"
+        contract Account {
+            id: string;
+            a?: u32;
+
+            clear() {
+                this.a = null;
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("clear")
+        .this(serde_json::json!({ "id": "", "a": 1 }))
+        .run()
+        .unwrap();
+
+    let this: serde_json::Value = output.this(&abi).unwrap().try_into().unwrap();
+    assert_eq!(this, serde_json::json!({ "id": "", "a": null }));
+}
+
+#[test_case(serde_json::json!(null), true; "null")]
+#[test_case(serde_json::json!(1), false; "value")]
+fn eq_null(a: serde_json::Value, is_null: bool) {
+    let code = r#"
+        contract Account {
+            id: string;
+            a?: u32;
+
+            isNull(): boolean {
+                return this.a == null;
+            }
+
+            isNotNull(): boolean {
+                return null != this.a;
+            }
+        }
+    "#;
+
+    for (function, expected) in [("isNull", is_null), ("isNotNull", !is_null)] {
+        let (abi, output) = TestHarness::new()
+            .code(code)
+            .contract("Account")
+            .function(function)
+            .this(serde_json::json!({ "id": "", "a": a }))
+            .run()
+            .unwrap();
+
+        assert_eq!(output.result(&abi).unwrap(), abi::Value::Boolean(expected));
+    }
+}
+
+#[test_case("null", serde_json::json!(null); "null")]
+#[test_case("5", serde_json::json!(5); "value")]
+#[test_case("this.b", serde_json::json!(2); "nullable value")]
+fn optional_let(value: &str, expected: serde_json::Value) {
+    let code = format!(
+        r#"
+        contract Account {{
+            id: string;
+            a?: u32;
+            b?: u32;
+
+            set() {{
+                let x?: u32 = {value};
+                this.a = x;
+            }}
+        }}
+    "#
+    );
+
+    let (abi, output) = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("set")
+        .this(serde_json::json!({ "id": "", "a": 1, "b": 2 }))
+        .run()
+        .unwrap();
+
+    let this: serde_json::Value = output.this(&abi).unwrap().try_into().unwrap();
+    assert_eq!(this["a"], expected);
+}
+
+#[test]
+fn null_argument() {
+    let code = r#"
+        function isMissing(x?: u32): boolean {
+            return x == null;
+        }
+
+        contract Account {
+            id: string;
+            missing: boolean;
+
+            check() {
+                this.missing = isMissing(null);
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("check")
+        .this(serde_json::json!({ "id": "", "missing": false }))
+        .run()
+        .unwrap();
+
+    let this: serde_json::Value = output.this(&abi).unwrap().try_into().unwrap();
+    assert_eq!(this, serde_json::json!({ "id": "", "missing": true }));
+}
+
+#[test]
+fn absent_optional_field_reads_as_null() {
+    let code = r#"
+        contract Account {
+            id: string;
+            name?: string;
+
+            hasName(): boolean {
+                return this.name != null;
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("hasName")
+        .default_this()
+        .run()
+        .unwrap();
+
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::Boolean(false));
+
+    let this: serde_json::Value = output.this(&abi).unwrap().try_into().unwrap();
+    assert_eq!(this, serde_json::json!({ "id": "", "name": null }));
+}
+
+#[test_case("inArray", serde_json::json!("b"), true; "element in array")]
+#[test_case("inArray", serde_json::json!("d"), false; "element not in array")]
+#[test_case("inMap", serde_json::json!("x"), true; "key in map")]
+#[test_case("inMap", serde_json::json!("y"), false; "key not in map")]
+fn in_operator(function: &str, arg: serde_json::Value, expected: bool) {
+    let code = r#"
+        contract Account {
+            id: string;
+            tags: string[];
+            counts: map<string, u32>;
+
+            inArray(tag: string): boolean {
+                return tag in this.tags;
+            }
+
+            inMap(key: string): boolean {
+                return key in this.counts;
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(function)
+        .this(serde_json::json!({
+            "id": "",
+            "tags": ["a", "b", "c"],
+            "counts": { "x": 1 },
+        }))
+        .arg(arg)
+        .run()
+        .unwrap();
+
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::Boolean(expected));
+}
+
+#[test_case("apple", "banana", [true, true, false, false]; "first byte differs")]
+#[test_case("banana", "apple", [false, false, true, true]; "first byte differs reversed")]
+#[test_case("ab", "abc", [true, true, false, false]; "prefix")]
+#[test_case("abc", "ab", [false, false, true, true]; "prefix reversed")]
+#[test_case("abc", "abc", [false, true, false, true]; "equal")]
+#[test_case("", "", [false, true, false, true]; "both empty")]
+#[test_case("", "a", [true, true, false, false]; "empty")]
+fn string_ordering(a: &str, b: &str, expected: [bool; 4]) {
+    let code = r#"
+        contract Account {
+            id: string;
+
+            less(a: string, b: string): boolean {
+                return a < b;
+            }
+
+            lessOrEqual(a: string, b: string): boolean {
+                return a <= b;
+            }
+
+            greater(a: string, b: string): boolean {
+                return a > b;
+            }
+
+            greaterOrEqual(a: string, b: string): boolean {
+                return a >= b;
+            }
+        }
+    "#;
+
+    let functions = ["less", "lessOrEqual", "greater", "greaterOrEqual"];
+    for (function, expected) in functions.into_iter().zip(expected) {
+        let (abi, output) = TestHarness::new()
+            .code(code)
+            .contract("Account")
+            .function(function)
+            .default_this()
+            .args(vec![serde_json::json!(a), serde_json::json!(b)])
+            .run()
+            .unwrap();
+
+        assert_eq!(
+            output.result(&abi).unwrap(),
+            abi::Value::Boolean(expected),
+            "{a:?} {function} {b:?}"
+        );
+    }
+}
+
+#[test_case(
+    "contract Account { noop() {} }",
+    "contract Account must have an `id: string` field";
+    "contract without fields"
+)]
+#[test_case(
+    "contract User { name: string; } contract Account { id: string; }",
+    "contract User must have an `id: string` field";
+    "other contract without id"
+)]
+#[test_case(
+    "contract User { name: string; } contract Account { id: string; owner: User; }",
+    "contract User must have an `id: string` field";
+    "referenced contract without id"
+)]
+#[test_case(
+    "contract Account { id: number; }",
+    "type mismatch: field id of contract Account must be a string, found PrimitiveType(Float32)";
+    "id is not a string"
+)]
+fn record_id_is_a_required_string(code: &str, expected: &str) {
+    let err = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(".readAuth")
+        .compile()
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), expected);
+}
+
+#[test]
+fn this_and_result_json() {
+    let code = r#"
+        contract Account {
+            id: string;
+            balance: u32;
+
+            deposit(amount: u32): u32 {
+                this.balance = this.balance + amount;
+                return this.balance;
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("deposit")
+        .this(serde_json::json!({ "id": "a", "balance": 1 }))
+        .arg(serde_json::json!(2))
+        .run()
+        .unwrap();
+
+    let this: serde_json::Value = output.this(&abi).unwrap().try_into().unwrap();
+    assert_eq!(output.this_json(&abi).unwrap(), this);
+    assert_eq!(this, serde_json::json!({ "id": "a", "balance": 3 }));
+
+    let result: serde_json::Value = output.result(&abi).unwrap().try_into().unwrap();
+    assert_eq!(output.result_json(&abi).unwrap(), result);
+    assert_eq!(result, serde_json::json!(3));
+}
+
+#[test]
+fn abi_param_names() {
+    let code = r#"
+        contract Account {
+            id: string;
+            balance: u32;
+
+            transfer(to: Account, amount: u32) {}
+        }
+    "#;
+
+    let abi = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("transfer")
+        .compile()
+        .unwrap()
+        .abi;
+
+    assert_eq!(abi.param_names, ["to", "amount"]);
+    assert_eq!(abi.param_names.len(), abi.param_types.len());
+}
+
+// Literals have to be exact f32 values, so inexact ones are computed.
+// 1 + 1 / 1048576 is 8 ULPs above 1.
+#[test_case("1 + 1 / 1048576 == 1", false; "not exactly equal")]
+#[test_case("approxEqual(1 + 1 / 1048576, 1, 1 / 10000)", true; "a few ulps within epsilon")]
+#[test_case("approxEqual(1, 1 + 1 / 1048576, 1 / 10000)", true; "swapped")]
+#[test_case("approxEqual(0.25, 0.75, 1 / 10000)", false; "outside epsilon")]
+#[test_case("approxEqual(1.0, 1.5, 0.5)", true; "difference equals epsilon")]
+fn approx_equal(expression: &str, expected: bool) {
+    let code = format!(
+        r#"
+        contract Account {{
+            id: string;
+
+            check(): boolean {{
+                return {expression};
+            }}
+        }}
+    "#
+    );
+
+    let (abi, output) = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("check")
+        .default_this()
+        .run()
+        .unwrap();
+
+    assert_eq!(
+        output.result(&abi).unwrap(),
+        abi::Value::Boolean(expected),
+        "{expression}"
+    );
+}
+
+/// Evaluates the f32 `expression` in a function of an otherwise empty contract.
+fn float_expression(expression: &str) -> Result<f32, error::Error> {
+    let code = format!(
+        r#"
+        contract Account {{
+            id: string;
+
+            f(): f32 {{
+                return {expression};
+            }}
+        }}
+    "#
+    );
+
+    let (abi, output) = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("f")
+        .default_this()
+        .run()?;
+
+    match output.result(&abi)? {
+        abi::Value::Float32(result) => Ok(result),
+        value => panic!("{expression} returned {value:?} instead of a float"),
+    }
+}
+
+// The tolerances are the error bounds documented on the functions in `float32.rs`.
+#[test_case("sin(0)", 0.0, 1e-6; "sin of zero")]
+#[test_case("sin(1)", 0.841_470_96, 1e-6; "sin of one")]
+#[test_case("sin(2)", 0.909_297_4, 1e-6; "sin past pi over two")]
+#[test_case("sin(0 - 4)", 0.756_802_5, 1e-6 + 3e-8 * 4.0; "sin of negative")]
+#[test_case("sin(100)", -0.506_365_64, 1e-6 + 3e-8 * 100.0; "sin after range reduction")]
+#[test_case("cos(0)", 1.0, 1e-6; "cos of zero")]
+#[test_case("cos(2)", -0.416_146_84, 1e-6; "cos of two")]
+#[test_case("exp(0)", 1.0, 1e-5; "exp of zero")]
+#[test_case("exp(1)", std::f32::consts::E, 1e-5 * std::f32::consts::E; "exp of one")]
+#[test_case("exp(0 - 3)", 0.049_787_07, 1e-5 * 0.049_787_07; "exp of negative")]
+#[test_case("exp(10)", 22_026.465, 1e-5 * 22_026.465; "exp of ten")]
+#[test_case("exp(0 - 100)", 0.0, 0.0; "exp underflows to zero")]
+#[test_case("ln(1)", 0.0, 2e-5; "ln of one")]
+#[test_case("ln(2)", std::f32::consts::LN_2, 2e-5; "ln of two")]
+#[test_case("ln(0.125)", -2.079_441_6, 2e-5; "ln below one")]
+#[test_case("ln(1000)", 6.907_755_4, 2e-5; "ln of thousand")]
+fn float_math(expression: &str, expected: f32, tolerance: f32) {
+    let result = float_expression(expression).unwrap();
+    assert!(
+        (result - expected).abs() <= tolerance,
+        "{expression} = {result}, expected {expected} within {tolerance}"
+    );
+}
+
+#[test_case("sin(200000)", "sin argument must be between -100000 and 100000"; "sin out of range")]
+#[test_case("ln(0)", "ln argument must be positive"; "ln of zero")]
+fn float_math_errors(expression: &str, expected: &str) {
+    let err = float_expression(expression).unwrap_err();
+
+    assert!(err.to_string().contains(expected), "{err}");
+}
+
+fn clamp(
+    type_: &str,
+    value: serde_json::Value,
+    min: serde_json::Value,
+    max: serde_json::Value,
+) -> Result<abi::Value, error::Error> {
+    let code = format!(
+        r#"
+        contract Account {{
+            id: string;
+
+            f(value: {type_}, min: {type_}, max: {type_}): {type_} {{
+                return clamp(value, min, max);
+            }}
+        }}
+    "#
+    );
+
+    let (abi, output) = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("f")
+        .default_this()
+        .args([value, min, max])
+        .run()?;
+
+    Ok(output.result(&abi).unwrap())
+}
+
+#[test_case(serde_json::json!(-5), abi::Value::Int32(-2); "i32 below")]
+#[test_case(serde_json::json!(1), abi::Value::Int32(1); "i32 within")]
+#[test_case(serde_json::json!(7), abi::Value::Int32(3); "i32 above")]
+fn clamp_i32(value: serde_json::Value, expected: abi::Value) {
+    let result = clamp("i32", value, serde_json::json!(-2), serde_json::json!(3)).unwrap();
+
+    assert_eq!(result, expected);
+}
+
+#[test_case(serde_json::json!(0.25), abi::Value::Float32(0.5); "f32 below")]
+#[test_case(serde_json::json!(1.25), abi::Value::Float32(1.25); "f32 within")]
+#[test_case(serde_json::json!(2.5), abi::Value::Float32(1.5); "f32 above")]
+fn clamp_f32(value: serde_json::Value, expected: abi::Value) {
+    let result = clamp("f32", value, serde_json::json!(0.5), serde_json::json!(1.5)).unwrap();
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn clamp_min_greater_than_max() {
+    let err = clamp(
+        "i32",
+        serde_json::json!(1),
+        serde_json::json!(3),
+        serde_json::json!(-2),
+    )
+    .unwrap_err();
+
+    assert!(
+        err.to_string().contains("clamp min is greater than max"),
+        "{err}"
+    );
+}
+
+#[test_case("sum", "u32", serde_json::json!([1, 2, 3]), abi::Value::UInt32(6); "sum u32")]
+#[test_case("sum", "i32", serde_json::json!([1, -2, 3]), abi::Value::Int32(2); "sum i32")]
+#[test_case("sum", "u32", serde_json::json!([]), abi::Value::UInt32(0); "sum empty")]
+#[test_case("average", "u32", serde_json::json!([1, 2, 3]), abi::Value::Float32(2.0); "average u32")]
+#[test_case("average", "f32", serde_json::json!([0.5, 1.5]), abi::Value::Float32(1.0); "average f32")]
+#[test_case("average", "u32", serde_json::json!([3000000000u32, 3000000000u32]), abi::Value::Float32(3000000000.0); "average u32 past u32 max")]
+#[test_case("average", "i32", serde_json::json!([2147483647, 2147483647]), abi::Value::Float32(2147483647.0); "average i32 past i32 max")]
+fn array_aggregation(method: &str, type_: &str, arr: serde_json::Value, expected: abi::Value) {
+    let return_type = if method == "average" { "f32" } else { type_ };
+    let code = format!(
+        r#"
+        contract Account {{
+            id: string;
+
+            f(arr: {type_}[]): {return_type} {{
+                return arr.{method}();
+            }}
+        }}
+    "#
+    );
+
+    let (abi, output) = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("f")
+        .default_this()
+        .arg(arr)
+        .run()
+        .unwrap();
+
+    assert_eq!(output.result(&abi).unwrap(), expected);
+}
+
+#[test_case("min", "u32", serde_json::json!([3, 1, 2]), serde_json::json!(1); "min u32")]
+#[test_case("max", "u32", serde_json::json!([3, 1, 2]), serde_json::json!(3); "max u32")]
+#[test_case("min", "i32", serde_json::json!([3, -1, 2]), serde_json::json!(-1); "min i32")]
+#[test_case("max", "f32", serde_json::json!([0.5, 2.5, 1.5]), serde_json::json!(2.5); "max f32")]
+#[test_case("min", "string", serde_json::json!(["b", "a", "c"]), serde_json::json!("a"); "min string")]
+#[test_case("max", "string", serde_json::json!(["b", "a", "c"]), serde_json::json!("c"); "max string")]
+#[test_case("min", "u32", serde_json::json!([]), serde_json::json!(null); "min empty")]
+#[test_case("max", "string", serde_json::json!([]), serde_json::json!(null); "max empty")]
+fn array_min_max(method: &str, type_: &str, arr: serde_json::Value, expected: serde_json::Value) {
+    let code = format!(
+        r#"
+        contract Account {{
+            id: string;
+            result?: {type_};
+
+            f(arr: {type_}[]) {{
+                this.result = arr.{method}();
+            }}
+        }}
+    "#
+    );
+
+    let (abi, output) = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("f")
+        .default_this()
+        .arg(arr)
+        .run()
+        .unwrap();
+
+    let this: serde_json::Value = output.this(&abi).unwrap().try_into().unwrap();
+    assert_eq!(this, serde_json::json!({ "id": "", "result": expected }));
+}
+
+#[test]
+fn array_from_length_and_value() {
+    let code = r#"
+        contract Account {
+            id: string;
+            arr: f32[];
+
+            f() {
+                this.arr = Array.from(3, 0);
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("f")
+        .this(serde_json::json!({ "id": "", "arr": [] }))
+        .run()
+        .unwrap();
+
+    assert_eq!(
+        output.this(&abi).unwrap(),
+        abi::Value::StructValue(vec![
+            ("id".to_owned(), abi::Value::String("".to_owned())),
+            (
+                "arr".to_owned(),
+                abi::Value::Array(vec![abi::Value::Float32(0.0); 3])
+            ),
+        ])
+    );
+}
+
+#[test_case("2.5"; "float")]
+#[test_case("4294967296"; "out of range")]
+#[test_case("0 - 3"; "negative")]
+fn array_from_rejects_invalid_length(len: &str) {
+    let code = format!(
+        r#"
+        contract Account {{
+            id: string;
+            arr: f32[];
+
+            f() {{
+                this.arr = Array.from({len}, 0);
+            }}
+        }}
+    "#
+    );
+
+    let err = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("f")
+        .compile()
+        .unwrap_err();
+
+    assert_eq!(err.name(), "TypeMismatch", "{err}");
+}
+
+#[test_case("u32", serde_json::json!([1, 2, 3]); "u32")]
+#[test_case("string", serde_json::json!(["a", "bc"]); "string")]
+#[test_case("u32", serde_json::json!([]); "empty")]
+fn array_from_copies_array(type_: &str, arr: serde_json::Value) {
+    let code = format!(
+        r#"
+        contract Account {{
+            id: string;
+            copy: {type_}[];
+
+            f(arr: {type_}[]) {{
+                this.copy = Array.from(arr);
+            }}
+        }}
+    "#
+    );
+
+    let (abi, output) = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("f")
+        .this(serde_json::json!({ "id": "", "copy": [] }))
+        .arg(arr.clone())
+        .run()
+        .unwrap();
+
+    let this: serde_json::Value = output.this(&abi).unwrap().try_into().unwrap();
+    assert_eq!(this, serde_json::json!({ "id": "", "copy": arr }));
+}
+
+fn prng_sequence(seed: u64) -> serde_json::Value {
+    let code = r#"
+        contract Account {
+            id: string;
+            a: u64;
+            b: u64;
+            c: u64;
+
+            f(seed: u64) {
+                this.a = prng(seed);
+                this.b = prng(seed);
+                this.c = prng(seed);
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("f")
+        .this(serde_json::json!({ "id": "", "a": 0, "b": 0, "c": 0 }))
+        .arg(serde_json::json!(seed))
+        .run()
+        .unwrap();
+
+    output.this(&abi).unwrap().try_into().unwrap()
+}
+
+#[test]
+fn prng_is_deterministic() {
+    let sequence = prng_sequence(42);
+
+    assert_eq!(sequence, prng_sequence(42));
+    assert_ne!(sequence["a"], sequence["b"]);
+    assert_ne!(sequence["b"], sequence["c"]);
+    assert_ne!(sequence, prng_sequence(43));
+}
+
+#[test]
+fn prng_seed_uses_both_limbs() {
+    // The high and low 32 bits differ, so swapping or dropping a limb changes the seed.
+    let seed = (1 << 32) | 2;
+    let sequence = prng_sequence(seed);
+
+    assert_eq!(sequence, prng_sequence(seed));
+    assert_ne!(sequence, prng_sequence((2 << 32) | 1));
+    assert_ne!(sequence, prng_sequence(2));
+    assert_ne!(sequence, prng_sequence(1 << 32));
+}
+
+#[test_case(">>", -8, 1, -4; "arithmetic")]
+#[test_case(">>", -1, 28, -1; "arithmetic keeps sign")]
+#[test_case(">>>", -1, 28, 15; "logical")]
+#[test_case(">>>", 8, 1, 4; "logical positive")]
+fn shift_right_i32(op: &str, a: i32, b: i32, expected: i32) {
+    let code = format!(
+        r#"
+        contract Account {{
+            id: string;
+
+            f(a: i32, b: i32): i32 {{
+                return a {op} b;
+            }}
+        }}
+    "#
+    );
+
+    let (abi, output) = TestHarness::new()
+        .code(&code)
+        .contract("Account")
+        .function("f")
+        .default_this()
+        .args([serde_json::json!(a), serde_json::json!(b)])
+        .run()
+        .unwrap();
+
+    assert_eq!(output.result(&abi).unwrap(), abi::Value::Int32(expected));
+}
+
+#[test_case("literal", vec![], abi::Value::Bytes(vec![0xde, 0xad, 0xbe, 0xef]); "literal")]
+#[test_case("check", vec![serde_json::json!("222,173,190,239")], abi::Value::Boolean(true); "same hash")]
+#[test_case("check", vec![serde_json::json!("222,173,190,238")], abi::Value::Boolean(false); "different hash")]
+fn bytes_literal(function: &str, args: Vec<serde_json::Value>, expected: abi::Value) {
+    let code = r#"
+        contract Account {
+            id: string;
+
+            literal(): bytes {
+                return bytes("0xdeadbeef");
+            }
+
+            check(b: bytes): boolean {
+                return hash(bytes("deadbeef")) == hash(b);
+            }
+        }
+    "#;
+
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(function)
+        .default_this()
+        .args(args)
+        .run()
+        .unwrap();
+
+    assert_eq!(output.result(&abi).unwrap(), expected);
+}
+
+#[test_case(vec![]; "empty")]
+#[test_case(vec![0x2a]; "one byte")]
+#[test_case(vec![0xde, 0xad, 0xbe, 0xef, 0x00, 0xff]; "several bytes")]
+#[test_case((0..=255).collect(); "256 bytes")]
+fn length_prefixed_round_trip(bytes: Vec<u8>) {
+    let code = r#"
+        contract Account {
+            id: string;
+
+            encode(b: bytes): bytes {
+                return encodeLengthPrefixed(b);
+            }
+
+            roundTrip(b: bytes): bytes {
+                return decodeLengthPrefixed(encodeLengthPrefixed(b));
+            }
+        }
+    "#;
+
+    // Bytes arguments are comma-separated decimal values, and null when empty.
+    let arg = match bytes.is_empty() {
+        true => serde_json::Value::Null,
+        false => serde_json::json!(bytes
+            .iter()
+            .map(|b| b.to_string())
+            .collect::<Vec<_>>()
+            .join(",")),
+    };
+    let run = |function| {
+        let (abi, output) = TestHarness::new()
+            .code(code)
+            .contract("Account")
+            .function(function)
+            .default_this()
+            .arg(arg.clone())
+            .run()
+            .unwrap();
+        output.result(&abi).unwrap()
+    };
+
+    let mut encoded = (bytes.len() as u32).to_be_bytes().to_vec();
+    encoded.extend_from_slice(&bytes);
+    assert_eq!(run("encode"), abi::Value::Bytes(encoded));
+    assert_eq!(run("roundTrip"), abi::Value::Bytes(bytes));
+}
+
+#[test_case("0,0,0", "bytes are too short to have a length prefix"; "too short")]
+#[test_case("0,0,0,2,255", "length prefix does not match the length of the bytes"; "wrong length")]
+fn length_prefixed_decode_invalid(input: &str, expected_error: &str) {
+    let code = r#"
+        contract Account {
+            id: string;
+
+            decode(b: bytes): bytes {
+                return decodeLengthPrefixed(b);
+            }
+        }
+    "#;
+
+    let err = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("decode")
+        .default_this()
+        .arg(serde_json::json!(input))
+        .run()
+        .unwrap_err();
+
+    assert!(err.to_string().contains(expected_error), "{err}");
 }
@@ -16,19 +16,17 @@ fn run_push(
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "push",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("push")
+        .this(serde_json::json!({
             "id": "test",
             "arr": arr.clone(),
             "result": 0,
-        }),
-        vec![element],
-        None,
-        HashMap::new(),
-    )?;
+        }))
+        .arg(element)
+        .run()?;
 
     let this = output.this(&abi)?;
     match this {
@@ -78,19 +76,17 @@ fn run_push17(
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "push17",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("push17")
+        .this(serde_json::json!({
             "id": "test",
             "arr": arr.clone(),
             "result": 0,
-        }),
-        elements.to_vec(),
-        None,
-        HashMap::new(),
-    )?;
+        }))
+        .args(elements.to_vec())
+        .run()?;
 
     let this = output.this(&abi)?;
     match this {
@@ -33,19 +33,17 @@ fn run_slice(
         _ => panic!("Unsupported argument combination"),
     };
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        function_name,
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(function_name)
+        .this(serde_json::json!({
             "id": "test",
             "arr": arr.clone(),
             "sliced": [],
-        }),
-        args,
-        None,
-        HashMap::new(),
-    )?;
+        }))
+        .args(args)
+        .run()?;
 
     let this = output.this(&abi)?;
     match this {
@@ -18,19 +18,18 @@ fn run_splice(
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        "splice",
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function("splice")
+        .this(serde_json::json!({
             "id": "test",
             "arr": arr,
             "ret": [],
-        }),
-        vec![serde_json::json!(start), serde_json::json!(delete_count)],
-        None,
-        HashMap::new(),
-    )?;
+        }))
+        .arg(serde_json::json!(start))
+        .arg(serde_json::json!(delete_count))
+        .run()?;
 
     let this = output.this(&abi)?;
     let (arr, ret) = match this {
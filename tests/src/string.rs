@@ -3,6 +3,7 @@ use super::*;
 fn run_fn(f: &str, result: &str, s1: &str, s2: &str) -> Result<abi::Value, error::Error> {
     let code = r#"
         contract Account {
+            id: string;
             result_bool: boolean;
             result_i32: i32;
 
@@ -20,21 +21,18 @@ fn run_fn(f: &str, result: &str, s1: &str, s2: &str) -> Result<abi::Value, error
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        f,
-        serde_json::json!({
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(f)
+        .this(serde_json::json!({
+            "id": "",
             "result_bool": false,
             "result_i32": 123,
-        }),
-        vec![
-            serde_json::Value::String(s1.into()),
-            serde_json::Value::String(s2.into()),
-        ],
-        None,
-        HashMap::new(),
-    )?;
+        }))
+        .arg(serde_json::Value::String(s1.into()))
+        .arg(serde_json::Value::String(s2.into()))
+        .run()?;
 
     let this = output.this(&abi)?;
     match this {
@@ -21,23 +21,21 @@ fn run_unshift(
         }
     "#;
 
-    let (abi, output) = run(
-        code,
-        "Account",
-        match elems.len() {
+    let (abi, output) = TestHarness::new()
+        .code(code)
+        .contract("Account")
+        .function(match elems.len() {
             1 => "unshift1",
             2 => "unshift2",
             _ => panic!("unexpected number of elements"),
-        },
-        serde_json::json!({
+        })
+        .this(serde_json::json!({
             "id": "test",
             "arr": arr,
             "len": 0,
-        }),
-        elems,
-        None,
-        HashMap::new(),
-    )?;
+        }))
+        .args(elems)
+        .run()?;
 
     let this = output.this(&abi)?;
     let (arr, len) = match this {
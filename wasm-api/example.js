@@ -38,6 +38,7 @@ function withContracts() {
 function withArrays() {
   let program = pkg.compile(
     `contract ReverseArray {
+        id: string;
         elements: number[];
 
         constructor (elements: number[]) {
@@ -63,7 +64,7 @@ function withArrays() {
     "reverse"
   );
   let output = program.run(
-    JSON.stringify({ elements: [1, 2, 3, 4, 5] }),
+    JSON.stringify({ id: "", elements: [1, 2, 3, 4, 5] }),
     JSON.stringify([]),
     true
   );
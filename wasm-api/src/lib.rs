@@ -4,9 +4,7 @@ use abi::Abi;
 use base64::Engine;
 use error::prelude::*;
 use miden::utils::Serializable;
-use miden::{
-    utils::Deserializable, verify as miden_verify, ProgramInfo, StackInputs, StackOutputs,
-};
+use miden::{utils::Deserializable, ProgramInfo, StackInputs, StackOutputs};
 use polylang_prover::{Inputs, RunOutput};
 use wasm_bindgen::prelude::*;
 
@@ -16,22 +14,61 @@ pub struct Program {
     abi: Abi,
 }
 
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Ctx {
+    public_key: Option<abi::publickey::Key>,
+}
+
+/// Error thrown to JS as a plain object, so front-ends can tell errors apart
+/// by `kind` and highlight the `span` of code that caused a compile error.
+#[derive(Debug, serde::Serialize)]
+pub struct WasmError {
+    kind: &'static str,
+    message: String,
+    span: Option<error::span::Span>,
+}
+
+impl From<Error> for WasmError {
+    fn from(e: Error) -> Self {
+        Self {
+            kind: e.name(),
+            message: e.to_string(),
+            span: e.span(),
+        }
+    }
+}
+
+impl From<serde_wasm_bindgen::Error> for WasmError {
+    fn from(e: serde_wasm_bindgen::Error) -> Self {
+        Self {
+            kind: "Serialization",
+            message: e.to_string(),
+            span: None,
+        }
+    }
+}
+
+impl From<WasmError> for JsValue {
+    fn from(e: WasmError) -> Self {
+        serde_wasm_bindgen::to_value(&e).unwrap_or_else(Into::into)
+    }
+}
+
 #[wasm_bindgen]
 pub fn compile(
     code: String,
     contract_name: Option<String>,
     fn_name: &str,
-) -> Result<Program, JsError> {
-    let program = polylang::parse_program(&code)?;
+) -> Result<Program, WasmError> {
+    Ok(compile_inner(code, contract_name, fn_name)?)
+}
+
+fn compile_inner(code: String, contract_name: Option<String>, fn_name: &str) -> Result<Program> {
+    let program = polylang::parse_program(&code).wrap_err()?;
     let (miden_code, mut abi) =
         polylang::compiler::compile(program, contract_name.as_deref(), fn_name)?;
-
-    if contract_name.is_none() {
-        abi.this_type = Some(abi::Type::Struct(abi::Struct {
-            name: "Empty".to_string(),
-            fields: Vec::new(),
-        }));
-    }
+    abi.normalize();
 
     Ok(Program { miden_code, abi })
 }
@@ -42,34 +79,122 @@ impl Program {
         self.miden_code.clone()
     }
 
+    /// The ABI of the compiled function, as plain JSON objects.
+    pub fn abi(&self) -> Result<JsValue, WasmError> {
+        Ok(self.serialize_abi(&serde_wasm_bindgen::Serializer::json_compatible())?)
+    }
+
+    /// `ctx_json` is an object like `{ "publicKey": ... }`,
+    /// `other_records_json` maps contract names to the records the function can read.
+    /// `this_salts` has one salt per field of `this`, they hide the field values in the
+    /// output hashes. Without them, all salts are zero.
     pub fn run(
         &self,
         this_json: String,
         args_json: String,
         generate_proof: bool,
-    ) -> Result<Output, JsError> {
-        let this = match serde_json::from_str(&this_json)? {
+        ctx_json: Option<String>,
+        other_records_json: Option<String>,
+        this_salts: Option<Vec<u32>>,
+    ) -> Result<Output, WasmError> {
+        Ok(self.run_inner(
+            &this_json,
+            &args_json,
+            generate_proof,
+            ctx_json.as_deref(),
+            other_records_json.as_deref(),
+            this_salts,
+        )?)
+    }
+}
+
+impl Program {
+    /// Serializes the ABI for [`Self::abi`], which can only create JS values on wasm,
+    /// so tests pass a JSON serializer instead.
+    fn serialize_abi<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::Serialize;
+
+        self.abi.serialize(serializer)
+    }
+
+    fn run_inner(
+        &self,
+        this_json: &str,
+        args_json: &str,
+        generate_proof: bool,
+        ctx_json: Option<&str>,
+        other_records_json: Option<&str>,
+        this_salts: Option<Vec<u32>>,
+    ) -> Result<Output> {
+        let this = match serde_json::from_str(this_json).wrap_err()? {
             serde_json::Value::Null => serde_json::Value::Object(serde_json::Map::new()),
             other => other,
         };
-        let args = serde_json::from_str(&args_json)?;
+        let args = serde_json::from_str(args_json).wrap_err()?;
+        let ctx = ctx_json
+            .map(|ctx| serde_json::from_str::<Ctx>(ctx).wrap_err())
+            .transpose()?
+            .unwrap_or_default();
+        let other_records = other_records_json
+            .map(|records| {
+                serde_json::from_str::<HashMap<String, Vec<serde_json::Value>>>(records).wrap_err()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        let mut other_records_with_salts = HashMap::new();
+        for (contract, records) in other_records {
+            let struct_ = self
+                .abi
+                .other_contract_types
+                .iter()
+                .find_map(|t| match t {
+                    abi::Type::Struct(s) if s.name == contract => Some(s),
+                    _ => None,
+                })
+                .not_found("contract", &contract)?;
+
+            let records = records
+                .into_iter()
+                .map(|record| (record, struct_.fields.iter().map(|_| 0).collect()))
+                .collect::<Vec<_>>();
+
+            other_records_with_salts.insert(contract, records);
+        }
+
+        let field_count = self
+            .abi
+            .this_type
+            .as_ref()
+            .map(|ty| match ty {
+                abi::Type::Struct(st) => Ok(st.fields.len()),
+                _ => Err(Error::simple("this type must be a struct")),
+            })
+            .transpose()?
+            .unwrap_or(0);
+        let this_salts = match this_salts {
+            Some(salts) if salts.len() != field_count => {
+                return Err(Error::simple(format!(
+                    "expected {} salts, one per field, got {}",
+                    field_count,
+                    salts.len()
+                )))
+            }
+            Some(salts) => salts,
+            None => vec![0; field_count],
+        };
 
         let program = polylang_prover::compile_program(&self.abi, &self.miden_code)?;
         let inputs = Inputs::new(
             self.abi.clone(),
-            None,
-            self.abi
-                .this_type
-                .as_ref()
-                .map(|ty| match ty {
-                    abi::Type::Struct(st) => Ok(st.fields.iter().map(|_| 0).collect()),
-                    _ => Err(Error::simple("this type must be a struct")),
-                })
-                .transpose()?
-                .unwrap_or(vec![]),
+            ctx.public_key,
+            this_salts,
             this,
             args,
-            HashMap::new(),
+            other_records_with_salts,
         )?;
 
         let (output, prove) = polylang_prover::run(&program, &inputs)?;
@@ -146,28 +271,28 @@ impl Output {
             .collect::<Vec<_>>()
     }
 
-    pub fn this(&self) -> Result<JsValue, JsError> {
-        let json_value: serde_json::Value = self.output.this(&self.abi)?.try_into()?;
+    pub fn this(&self) -> Result<JsValue, WasmError> {
+        let json_value = self.output.this_json(&self.abi)?;
         Ok(serde_wasm_bindgen::to_value(&json_value)?)
     }
 
-    pub fn result(&self) -> Result<JsValue, JsError> {
-        let json_value: serde_json::Value = self.output.result(&self.abi)?.try_into()?;
+    pub fn result(&self) -> Result<JsValue, WasmError> {
+        let json_value = self.output.result_json(&self.abi)?;
         Ok(serde_wasm_bindgen::to_value(&json_value)?)
     }
 
-    pub fn result_hash(&self) -> Result<JsValue, JsError> {
+    pub fn result_hash(&self) -> Result<JsValue, WasmError> {
         let hash = self
             .output
-            .result_hash(&self.abi)
+            .result_hash(&self.abi)?
             .map(|h| h.into_iter().map(|x| x.to_string()).collect::<Vec<_>>());
         Ok(serde_wasm_bindgen::to_value(&hash)?)
     }
 
-    pub fn hashes(&self) -> Result<JsValue, JsError> {
+    pub fn hashes(&self) -> Result<JsValue, WasmError> {
         let hashes = self
             .output
-            .hashes()
+            .hashes()?
             .into_iter()
             .map(|h| {
                 [
@@ -183,17 +308,21 @@ impl Output {
         Ok(serde_wasm_bindgen::to_value(&hashes)?)
     }
 
-    pub fn logs(&self) -> Result<JsValue, JsError> {
+    pub fn logs(&self) -> Result<JsValue, WasmError> {
         let logs = self.output.logs();
         Ok(serde_wasm_bindgen::to_value(&logs)?)
     }
 
-    pub fn self_destructed(&self) -> Result<bool, JsError> {
+    pub fn self_destructed(&self) -> Result<bool, WasmError> {
         Ok(self.output.self_destructed()?)
     }
 
-    pub fn read_auth(&self) -> bool {
-        self.output.read_auth()
+    pub fn read_auth(&self) -> Result<bool, WasmError> {
+        Ok(self.output.read_auth()?)
+    }
+
+    pub fn read_fields(&self) -> Result<JsValue, WasmError> {
+        Ok(serde_wasm_bindgen::to_value(&self.output.read_fields()?)?)
     }
 }
 
@@ -204,39 +333,260 @@ pub fn verify(
     stack_inputs: Vec<JsValue>,
     output_stack: Vec<JsValue>,
     overflow_addrs: Vec<JsValue>,
-) -> Result<bool, JsError> {
+) -> Result<bool, WasmError> {
+    let string = |value: &JsValue, name: &str| {
+        value
+            .as_string()
+            .ok_or_else(|| Error::simple(format!("{name} must be a string, got {value:?}")))
+    };
+    let strings = |values: Vec<JsValue>, name: &str| {
+        values
+            .iter()
+            .map(|value| string(value, name))
+            .collect::<Result<Vec<_>>>()
+    };
+
+    Ok(verify_inner(&ProofBundle {
+        proof,
+        program_info: string(&program_info, "programInfo")?,
+        stack_inputs: strings(stack_inputs, "stackInputs")?,
+        output_stack: strings(output_stack, "outputStack")?,
+        overflow_addrs: strings(overflow_addrs, "overflowAddrs")?,
+    })?)
+}
+
+/// Same as `verify`, but takes a single JSON object with the `proof` bytes and the
+/// `programInfo`, `stackInputs`, `outputStack` and `overflowAddrs` returned by `Output`.
+#[wasm_bindgen]
+pub fn verify_json(proof_bundle: &str) -> Result<bool, WasmError> {
+    let bundle = serde_json::from_str::<ProofBundle>(proof_bundle).wrap_err()?;
+    Ok(verify_inner(&bundle)?)
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProofBundle {
+    proof: Option<Vec<u8>>,
+    program_info: String,
+    stack_inputs: Vec<String>,
+    output_stack: Vec<String>,
+    overflow_addrs: Vec<String>,
+}
+
+fn verify_inner(bundle: &ProofBundle) -> Result<bool> {
+    let parse_u64s = |values: &[String]| {
+        values
+            .iter()
+            .map(|s| s.parse::<u64>().parse_err("u64", s))
+            .collect::<Result<Vec<_>>>()
+    };
+
     let program_info = ProgramInfo::read_from_bytes(
-        &base64::engine::general_purpose::STANDARD.decode(&program_info.as_string().unwrap())?,
+        &base64::engine::general_purpose::STANDARD
+            .decode(&bundle.program_info)
+            .wrap_err()?,
     )
-    .map_err(|e| JsError::new(&e.to_string()))?;
-
-    let mut stack_inputs = stack_inputs
-        .into_iter()
-        .map(|s| s.as_string().unwrap().parse::<u64>().unwrap())
-        .collect::<Vec<_>>();
+    .map_err(|e| Error::simple(e.to_string()))?;
 
+    let mut stack_inputs = parse_u64s(&bundle.stack_inputs)?;
     stack_inputs.reverse();
     let stack_inputs =
-        StackInputs::try_from_values(stack_inputs).map_err(|e| JsError::new(&e.to_string()))?;
+        StackInputs::try_from_values(stack_inputs).map_err(|e| Error::simple(e.to_string()))?;
 
-    let overflow_addrs = overflow_addrs
-        .into_iter()
-        .map(|s| s.as_string().unwrap().parse::<u64>().unwrap())
-        .collect::<Vec<_>>();
+    let overflow_addrs = parse_u64s(&bundle.overflow_addrs)?;
+    let output_stack = StackOutputs::new(parse_u64s(&bundle.output_stack)?, overflow_addrs)
+        .map_err(|e| Error::simple(e.to_string()))?;
 
-    let output_stack = output_stack
-        .into_iter()
-        .map(|s| s.as_string().unwrap().parse::<u64>().unwrap())
-        .collect::<Vec<_>>();
-    let output_stack = StackOutputs::new(output_stack, overflow_addrs);
+    let proof = bundle
+        .proof
+        .as_ref()
+        .ok_or_else(|| Error::simple("missing proof"))?;
 
-    miden_verify(
+    polylang_prover::verify(
         program_info,
         stack_inputs,
-        output_stack.map_err(|e| JsError::new(&e.to_string()))?,
-        miden::ExecutionProof::from_bytes(&proof.unwrap())
-            .map_err(|err| JsError::new(&format!("failed to parse proof: {}", err)))?,
+        output_stack,
+        miden::ExecutionProof::from_bytes(proof)
+            .map_err(|err| Error::simple(format!("failed to parse proof: {}", err)))?,
     )
-    .map_err(|e| JsError::new(&e.to_string()))
     .map(|_| true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pk() -> serde_json::Value {
+        serde_json::json!({
+            "kty": "EC",
+            "crv": "secp256k1",
+            "alg": "ES256K",
+            "use": "sig",
+            "x": "nnzHFO4bZ239bIuAo8t0wQwXH3fPwbKQnpWPzOptv0Q=",
+            "y": "Z1-oY62A6q5kCRGfBuk6E3IrSUjPCK2F6_EwVhW22lY="
+        })
+    }
+
+    const AUTH_CODE: &str = r#"
+        contract Account {
+            id: string;
+            pk: PublicKey;
+            name: string;
+
+            @call(pk)
+            setName(name: string) {
+                this.name = name;
+            }
+        }
+    "#;
+
+    // Spans are tracked in a global, so compiling in parallel tests
+    // could attach the span of one program to an error from another.
+    static SPAN_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    fn compile_locked(code: &str, contract_name: &str, fn_name: &str) -> Result<Program> {
+        let _span = SPAN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        compile_inner(code.to_owned(), Some(contract_name.to_owned()), fn_name)
+    }
+
+    #[test]
+    fn run_with_ctx() {
+        let program = compile_locked(AUTH_CODE, "Account", "setName").unwrap();
+        let this_json = serde_json::json!({ "id": "1", "pk": pk(), "name": "" }).to_string();
+        let ctx_json = serde_json::json!({ "publicKey": pk() }).to_string();
+
+        let output = program
+            .run_inner(
+                &this_json,
+                r#"["test"]"#,
+                false,
+                Some(&ctx_json),
+                None,
+                None,
+            )
+            .unwrap();
+
+        let Ok(abi::Value::StructValue(fields)) = output.output.this(&output.abi) else {
+            panic!("expected this to be a struct");
+        };
+        assert_eq!(
+            fields.iter().find(|(k, _)| k == "name").unwrap().1,
+            abi::Value::String("test".to_owned())
+        );
+
+        let err = program
+            .run_inner(&this_json, r#"["test"]"#, false, None, None, None)
+            .err()
+            .unwrap();
+        assert!(err
+            .to_string()
+            .contains("You are not authorized to call this function"));
+    }
+
+    #[test]
+    fn run_with_salts() {
+        let program = compile_locked(AUTH_CODE, "Account", "setName").unwrap();
+        let this_json = serde_json::json!({ "id": "1", "pk": pk(), "name": "" }).to_string();
+        let ctx_json = serde_json::json!({ "publicKey": pk() }).to_string();
+
+        let run = |salts| {
+            program
+                .run_inner(
+                    &this_json,
+                    r#"["test"]"#,
+                    false,
+                    Some(&ctx_json),
+                    None,
+                    salts,
+                )
+                .unwrap()
+                .output
+                .hashes()
+                .unwrap()
+        };
+
+        let unsalted = run(None);
+        let salted = run(Some(vec![1, 2, 3]));
+        assert!(!unsalted.is_empty());
+        assert_ne!(unsalted, salted);
+        assert_eq!(run(Some(vec![0, 0, 0])), unsalted);
+
+        let err = program
+            .run_inner(
+                &this_json,
+                r#"["test"]"#,
+                false,
+                Some(&ctx_json),
+                None,
+                Some(vec![1]),
+            )
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "expected 3 salts, one per field, got 1");
+    }
+
+    #[test]
+    fn verify_proof_bundle() {
+        let program = compile_locked(AUTH_CODE, "Account", "setName").unwrap();
+        let this_json = serde_json::json!({ "id": "1", "pk": pk(), "name": "" }).to_string();
+        let ctx_json = serde_json::json!({ "publicKey": pk() }).to_string();
+
+        let output = program
+            .run_inner(&this_json, r#"["test"]"#, true, Some(&ctx_json), None, None)
+            .unwrap();
+
+        let to_strings = |values: &[u64]| values.iter().map(|v| v.to_string()).collect();
+        let bundle = ProofBundle {
+            proof: output.proof.clone(),
+            program_info: base64::engine::general_purpose::STANDARD
+                .encode(output.info.clone().to_bytes()),
+            stack_inputs: output
+                .output
+                .stack_inputs
+                .clone()
+                .into_iter()
+                .map(|h| h.to_string())
+                .collect(),
+            output_stack: to_strings(&output.output.stack),
+            overflow_addrs: to_strings(output.output_stack.as_ref().unwrap().overflow_addrs()),
+        };
+
+        let bundle_json = serde_json::to_string(&bundle).unwrap();
+        assert!(verify_inner(&serde_json::from_str(&bundle_json).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn compile_error_has_span() {
+        let code = "contract Account { id: string; f() { assert(true); } }";
+        let err = WasmError::from(compile_locked(code, "Account", "f").err().unwrap());
+
+        let err = serde_json::to_value(&err).unwrap();
+        assert_eq!(err["kind"], "ArgumentsCount");
+        assert_eq!(
+            err["message"],
+            "incorrect number of arguments 1 but expected 2"
+        );
+        let start = err["span"]["start"].as_u64().unwrap() as usize;
+        let end = err["span"]["end"].as_u64().unwrap() as usize;
+        assert!(end > start);
+        assert!(code[start..].starts_with("assert(true)"));
+    }
+
+    #[test]
+    fn abi_matches_contract() {
+        let program = compile_locked(AUTH_CODE, "Account", "setName").unwrap();
+
+        let abi = program
+            .serialize_abi(serde_json::value::Serializer)
+            .unwrap();
+        let fields = abi["this_type"]["Struct"]["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|field| field[0].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(fields, ["id", "pk", "name"]);
+        assert_eq!(abi["param_types"], serde_json::json!(["String"]));
+        assert_eq!(abi["param_names"], serde_json::json!(["name"]));
+    }
+}